@@ -8,7 +8,39 @@
  * closeness of scalar values, vectors, arrays, and nested containers. The class
  * is designed to facilitate automated testing by providing expect_near methods
  * that compare actual and expected results within a specified tolerance,
- * reporting failures and tracking test status.
+ * reporting failures and tracking test status. The comparison semantics
+ * themselves live in the side-effect-free functions of
+ * MCAP_tester_comparison.hpp; the class only layers reporting on top of them.
+ * Every assertion is also recorded, so the outcome of a test executable can
+ * be written to a JSON report (see MCAP_tester_report.hpp).
+ *
+ * For validation directly on an embedded target, two macros strip the
+ * dependencies such targets lack. MCAP_TESTER_NO_STDIO removes iostreams and
+ * file I/O: results are printed only through the function set with
+ * set_output_function, the methods reading or writing files (fixtures,
+ * golden files, snapshots, runtime baselines, error maps, reports and
+ * session archives) are unavailable, and emit_report delivers the JSON
+ * report through the output function instead. MCAP_TESTER_NO_EXCEPTIONS
+ * removes throw_error_if_test_failed, fail-fast and FailureGuard; failures are
+ * then queried with get_test_failed_flag and the assertion records. The
+ * comparison methods and the structured records are the same in every
+ * mode. The optional headers other than MCAP_tester_sparse.hpp require the
+ * full standard library.
+ *
+ * CI can adjust a tester without recompiling through environment variables
+ * read at construction, which take precedence over the programmatic
+ * configuration:
+ *
+ *   MCAP_TOL_SCALE=2         Multiplies every comparison tolerance, e.g.
+ *                            for different FP hardware.
+ *   MCAP_VERBOSITY=verbose   quiet, normal or verbose.
+ *   MCAP_FAIL_FAST=1         1 enables and 0 disables fail-fast mode.
+ *
+ * Active overrides are recorded as a passing "Environment overrides"
+ * assertion, so they are visible in the report; invalid values are ignored
+ * and noted there. A tester built with MCAPTesterBuilder::ignore_environment
+ * reads none of these variables, nor MCAP_UPDATE_SNAPSHOTS and
+ * MCAP_UPDATE_BASELINES.
  *
  * @class Tester::MCAPTester
  * @brief A template class for performing near-equality checks on scalars and
@@ -24,17 +56,80 @@
 #define MCAP_TESTER_HPP
 
 #include <array>
+#include <chrono>
 #include <cmath>
-#include <iostream>
-#include <stdexcept>
+#include <complex>
+#include <cstdint>
+#include <cstdlib>
+#include <functional>
+#include <map>
 #include <string>
+#include <type_traits>
+#include <utility>
 #include <vector>
 
+#include "MCAP_tester_comparison.hpp"
+#include "MCAP_tester_error_map.hpp"
+#include "MCAP_tester_flat_array.hpp"
+#include "MCAP_tester_html.hpp"
+#include "MCAP_tester_json.hpp"
+#include "MCAP_tester_reference.hpp"
+#include "MCAP_tester_report.hpp"
+#include "MCAP_tester_session.hpp"
+
+#ifdef MCAP_TESTER_NO_STDIO
+#include <iosfwd>
+#else
+#include <iostream>
+#endif
+
+#ifndef MCAP_TESTER_NO_EXCEPTIONS
+#include <exception>
+#include <stdexcept>
+#endif
+
+#ifdef MCAP_TESTER_ENABLE_MATFILE
+#ifdef MCAP_TESTER_NO_STDIO
+#error "MCAP_TESTER_ENABLE_MATFILE requires file I/O."
+#endif
+#include "MCAP_tester_matfile.hpp"
+#endif
+
+#ifdef MCAP_TESTER_ENABLE_ERROR_MAP_PNG
+#ifdef MCAP_TESTER_NO_STDIO
+#error "MCAP_TESTER_ENABLE_ERROR_MAP_PNG requires file I/O."
+#endif
+#endif
+
+#ifdef MCAP_TESTER_ENABLE_SPARSE
+#include "MCAP_tester_sparse.hpp"
+#endif
+
 namespace Tester {
 
+/**
+ * @brief How much a tester prints: nothing, warnings and failures (the
+ * default), or additionally every passing assertion.
+ */
+enum class Verbosity { Quiet, Normal, Verbose };
+
+template <typename T> class MCAPTesterBuilder;
+template <typename T> class FailureGuard;
+
 template <typename T> class MCAPTester {
 public:
-  MCAPTester() : _test_failed_flag(false) {}
+  MCAPTester() : MCAPTester(true) {}
+
+  /**
+   * @brief Returns a builder that configures a tester in one expression.
+   *
+   * Example:
+   *   MCAPTester<double> tester = MCAPTester<double>::builder()
+   *                                   .default_tolerance(1e-9, 1e-6)
+   *                                   .fail_fast()
+   *                                   .build();
+   */
+  static MCAPTesterBuilder<T> builder();
 
   /**
    * @brief Checks if the actual value is within a specified tolerance of the
@@ -54,13 +149,270 @@ public:
    */
   void expect_near(T actual, T expected, T tolerance,
                    const std::string &message) {
-    if (std::abs(actual - expected) <= tolerance) {
-      /* Do Nothing. */
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    ComparisonResult<T> result =
+        this->try_expect_near_scalar(actual, expected, tolerance);
+    this->_record_near(make_flat_scalar(actual), make_flat_scalar(expected),
+                       tolerance, tolerance, ShapePolicy::Exact, result,
+                       message);
+    this->_report_result(result, message, "", tolerance);
+  }
+
+  /**
+   * @brief Compares two scalars without reporting the outcome.
+   *
+   * The try_expect_near family performs the same comparisons as the
+   * corresponding expect_near overloads but neither prints, records nor
+   * touches the test failed flag, so library code can branch on the
+   * structured result instead. The degenerate array policies only apply to
+   * the reporting overloads.
+   *
+   * @param actual The actual value to check.
+   * @param expected The expected value to compare against.
+   * @param tolerance The maximum allowed difference.
+   * @return The structured comparison result; `passed()` tells the verdict.
+   */
+  ComparisonResult<T> try_expect_near_scalar(T actual, T expected,
+                                             T tolerance) const {
+    return Comparison::compare_scalar(actual, expected, tolerance);
+  }
+
+  /**
+   * @brief Compares two scalars against separate warning and failure
+   * tolerances without reporting the outcome.
+   */
+  ComparisonResult<T> try_expect_near_scalar(T actual, T expected,
+                                             T warning_tolerance,
+                                             T failure_tolerance) const {
+    return Comparison::compare_scalar(actual, expected, warning_tolerance,
+                                      failure_tolerance);
+  }
+
+  /**
+   * @brief Compares two flattened arrays without reporting the outcome.
+   */
+  ComparisonResult<T> try_expect_near(const FlatArray<T> &actual,
+                                      const FlatArray<T> &expected,
+                                      T tolerance) const {
+    return Comparison::compare_arrays(actual, expected, tolerance);
+  }
+
+  /**
+   * @brief Compares two flattened arrays against separate warning and
+   * failure tolerances without reporting the outcome.
+   */
+  ComparisonResult<T> try_expect_near(const FlatArray<T> &actual,
+                                      const FlatArray<T> &expected,
+                                      T warning_tolerance,
+                                      T failure_tolerance) const {
+    return Comparison::compare_arrays(actual, expected, warning_tolerance,
+                                      failure_tolerance);
+  }
+
+  /**
+   * @brief Compares two flattened arrays under a shape policy without
+   * reporting the outcome.
+   */
+  ComparisonResult<T> try_expect_near(const FlatArray<T> &actual,
+                                      const FlatArray<T> &expected,
+                                      T tolerance, ShapePolicy policy) const {
+    return Comparison::compare_arrays(actual, expected, tolerance, policy);
+  }
+
+  /**
+   * @brief Compares two containers without reporting the outcome.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   */
+  template <typename Container>
+  ComparisonResult<T> try_expect_near(const Container &actual,
+                                      const Container &expected,
+                                      T tolerance) const {
+    return this->try_expect_near(make_flat_array(actual),
+                                 make_flat_array(expected), tolerance);
+  }
+
+  /**
+   * @brief Compares two containers against separate warning and failure
+   * tolerances without reporting the outcome.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   */
+  template <typename Container>
+  ComparisonResult<T> try_expect_near(const Container &actual,
+                                      const Container &expected,
+                                      T warning_tolerance,
+                                      T failure_tolerance) const {
+    return this->try_expect_near(make_flat_array(actual),
+                                 make_flat_array(expected),
+                                 warning_tolerance, failure_tolerance);
+  }
+
+  /**
+   * @brief Checks a scalar against the configured default tolerances.
+   *
+   * The check passes if |actual - expected| <= absolute + relative *
+   * |expected|, with the tolerances set by set_default_tolerance, and treats
+   * NaN according to set_nan_policy.
+   *
+   * @param actual The actual value to check.
+   * @param expected The expected value to compare against.
+   * @param message The message to display if the check fails.
+   */
+  void expect_near_default(T actual, T expected, const std::string &message) {
+    this->expect_near_default(make_flat_scalar(actual),
+                              make_flat_scalar(expected), message);
+  }
+
+  /**
+   * @brief Checks two flattened arrays against the configured default
+   * tolerances.
+   *
+   * @param actual The flattened array of actual values to check.
+   * @param expected The flattened array of expected values to compare against.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near_default(const FlatArray<T> &actual,
+                           const FlatArray<T> &expected,
+                           const std::string &message) {
+    if (!this->_accept_degenerate(actual, expected, message)) {
+      return;
+    }
+
+    if (!this->_accept_tolerance(this->_default_absolute_tolerance,
+                                 message) ||
+        !this->_accept_tolerance(this->_default_relative_tolerance,
+                                 message)) {
+      return;
+    }
+    T absolute = this->_scaled(this->_default_absolute_tolerance);
+    T relative = this->_scaled(this->_default_relative_tolerance);
+    ComparisonResult<T> result = Comparison::compare_arrays_combined(
+        actual, expected, absolute, relative, this->_nan_policy);
+    std::vector<double> parameters;
+    parameters.push_back(static_cast<double>(absolute));
+    parameters.push_back(static_cast<double>(relative));
+    parameters.push_back(
+        static_cast<double>(static_cast<int>(this->_nan_policy)));
+    this->_record_entry("combined", actual, expected, parameters,
+                        result.status, message);
+    std::string detail = result.describe();
+    if (result.kind == MismatchKind::ElementMismatch) {
+      detail += " " + std::to_string(result.mismatch_count) +
+                " element(s) outside " + format_number(absolute) + " + " +
+                format_number(relative) +
+                " * |expected|, first " +
+                this->_format_position(actual.shape, result.index) + " " +
+                format_number(result.actual_value) + " vs " +
+                format_number(result.expected_value) + ".";
+    }
+    this->_report_result(result, message, detail, absolute);
+  }
+
+  /**
+   * @brief Checks two containers against the configured default tolerances.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   */
+  template <typename Container>
+  void expect_near_default(const Container &actual, const Container &expected,
+                           const std::string &message) {
+    this->expect_near_default(make_flat_array(actual),
+                              make_flat_array(expected), message);
+  }
+
+  /**
+   * @brief Checks that a condition holds.
+   *
+   * This is the building block for checks computed outside of the tester,
+   * e.g. by helpers built on top of it.
+   *
+   * @param condition The outcome of the check.
+   * @param message The message to display if the condition is false.
+   * @param detail Additional information displayed after the message.
+   * @param category The category recorded for a failure, e.g.
+   * FailureCategory::Resource for an exceeded stack budget.
+   */
+  void expect_true(bool condition, const std::string &message,
+                   const std::string &detail = std::string(),
+                   FailureCategory category = FailureCategory::Assertion) {
+    if (condition) {
+      this->_report_pass(message);
     } else {
-      std::cout << "FAILURE: " << message << std::endl;
-      std::cout << std::endl;
-      this->_test_failed_flag = true;
+      this->_report_failure(message, detail, category);
+    }
+  }
+
+  /**
+   * @brief Checks that two integer arrays are exactly equal, e.g. the index
+   * maps, active-set flags or iteration counts produced by generated code.
+   *
+   * The failure lists every differing element with its position and both
+   * values, not only the worst ones as the tolerance comparisons do.
+   *
+   * @tparam Container Any container of an integer type accepted by
+   * make_flat_array, e.g. std::vector<std::vector<std::int64_t>>.
+   * @param actual The actual array.
+   * @param expected The expected array.
+   * @param message The message to display in case of failure.
+   */
+  template <typename Container>
+  void expect_eq_array_int(const Container &actual, const Container &expected,
+                           const std::string &message) {
+    this->_check_integer_arrays(make_flat_array(actual),
+                                make_flat_array(expected), message);
+  }
+
+  /**
+   * @brief Checks two bundles of named signals, e.g. logged outputs keyed by
+   * signal name, matching the signals by name regardless of their order.
+   *
+   * The check fails if an expected signal is missing, if a signal is not
+   * expected, or if a signal differs by more than `tolerance`. The detail
+   * lists the missing and extra signals and the worst error of every
+   * compared signal, ordered by name.
+   *
+   * @tparam Map A map from std::string to any container accepted by
+   * make_flat_array, e.g. std::map<std::string, std::vector<double>> or
+   * std::unordered_map<std::string, std::vector<double>>.
+   * @param actual The actual signals by name.
+   * @param expected The expected signals by name.
+   * @param tolerance The tolerance corresponding samples must be within; a
+   * number is an absolute tolerance.
+   * @param message The message to display in case of failure.
+   */
+  template <typename Map>
+  void expect_near_signals(const Map &actual, const Map &expected,
+                           const Tolerance<T> &tolerance,
+                           const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    Tolerance<T> scaled = tolerance.scaled(this->_tolerance_scale);
+    std::map<std::string, FlatArray<T>> flat_actual;
+    std::map<std::string, FlatArray<T>> flat_expected;
+    for (typename Map::const_iterator it = actual.begin(); it != actual.end();
+         ++it) {
+      flat_actual[it->first] = make_flat_array(it->second);
+    }
+    for (typename Map::const_iterator it = expected.begin();
+         it != expected.end(); ++it) {
+      flat_expected[it->first] = make_flat_array(it->second);
     }
+
+    SignalBundleResult<T> result =
+        Comparison::compare_signals(flat_actual, flat_expected, scaled);
+    for (std::size_t i = 0; i < result.signals.size(); i++) {
+      const std::string &name = result.signals[i].name;
+      this->_record_entry("tolerance", flat_actual[name], flat_expected[name],
+                          this->_tolerance_parameters(scaled),
+                          result.signals[i].result.status,
+                          message + ": " + name);
+    }
+    this->_report_signals(result, scaled.absolute_part(), message);
   }
 
   /**
@@ -82,24 +434,8 @@ public:
    */
   void expect_near(const std::vector<T> actual, const std::vector<T> expected,
                    T tolerance, const std::string &message) {
-    if (actual.size() != expected.size()) {
-      std::cout << "FAILURE: " << message << " Size mismatch." << std::endl;
-      std::cout << std::endl;
-      this->_test_failed_flag = true;
-      return;
-    }
-
-    for (size_t i = 0; i < actual.size(); i++) {
-      if (std::abs(actual[i] - expected[i]) <= tolerance) {
-        /* Do Nothing. */
-      } else {
-        std::cout << "FAILURE: " << message << " Element mismatch."
-                  << std::endl;
-        std::cout << std::endl;
-        this->_test_failed_flag = true;
-        return;
-      }
-    }
+    this->expect_near(make_flat_array(actual), make_flat_array(expected),
+                      tolerance, message);
   }
 
   /**
@@ -123,17 +459,8 @@ public:
   void expect_near(const std::array<T, N> &actual,
                    const std::array<T, N> &expected, T tolerance,
                    const std::string &message) {
-    for (std::size_t i = 0; i < N; ++i) {
-      if (std::abs(actual[i] - expected[i]) <= tolerance) {
-        /* Do Nothing. */
-      } else {
-        std::cout << "FAILURE: " << message << " Element mismatch."
-                  << std::endl;
-        std::cout << std::endl;
-        this->_test_failed_flag = true;
-        return;
-      }
-    }
+    this->expect_near(make_flat_array(actual), make_flat_array(expected),
+                      tolerance, message);
   }
 
   /**
@@ -156,81 +483,4236 @@ public:
   void expect_near(const std::vector<std::vector<T>> actual,
                    const std::vector<std::vector<T>> expected, T tolerance,
                    const std::string &message) {
-    if (actual.size() != expected.size()) {
-      std::cout << "FAILURE: " << message << " Size mismatch." << std::endl;
-      std::cout << std::endl;
-      this->_test_failed_flag = true;
+    this->expect_near(make_flat_array(actual), make_flat_array(expected),
+                      tolerance, message);
+  }
+
+  template <std::size_t M, std::size_t N>
+  void expect_near(const std::array<std::array<T, N>, M> &actual,
+                   const std::array<std::array<T, N>, M> &expected, T tolerance,
+                   const std::string &message) {
+    this->expect_near(make_flat_array(actual), make_flat_array(expected),
+                      tolerance, message);
+  }
+
+  /**
+   * @brief Checks if two flattened arrays have the same shape and are
+   * element-wise equal within a given tolerance.
+   *
+   * This overload accepts the output of make_flat_array, make_flat_scalar or
+   * a loader such as load_flat_array, so values of any supported shape can be
+   * compared through a single entry point.
+   *
+   * @param actual The flattened array of actual values to check.
+   * @param expected The flattened array of expected values to compare against.
+   * @param tolerance The maximum allowed difference between corresponding
+   * elements.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near(const FlatArray<T> &actual, const FlatArray<T> &expected,
+                   T tolerance, const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    if (!this->_accept_degenerate(actual, expected, message)) {
+      return;
+    }
+
+    ComparisonResult<T> result =
+        this->try_expect_near(actual, expected, tolerance);
+    this->_record_near(actual, expected, tolerance, tolerance,
+                       ShapePolicy::Exact, result, message);
+    this->_report_result(
+        result, message,
+        this->_describe_mismatch(actual, expected, tolerance, tolerance,
+                                 result) +
+            this->_write_error_map(actual, expected, result, message),
+        tolerance);
+  }
+
+  /**
+   * @brief Checks two scalars within a combined absolute, relative and ULP
+   * tolerance.
+   *
+   * @param actual The actual value to check.
+   * @param expected The expected value to compare against.
+   * @param tolerance The tolerance, e.g. Tolerance<T>::relative(1e-9).
+   * @param message The message to display if the check fails.
+   */
+  void expect_near(T actual, T expected, const Tolerance<T> &tolerance,
+                   const std::string &message) {
+    if (tolerance.is_absolute()) {
+      this->expect_near(actual, expected, tolerance.absolute_part(), message);
       return;
     }
+    this->expect_near(make_flat_scalar(actual), make_flat_scalar(expected),
+                      tolerance, message);
+  }
 
-    for (size_t i = 0; i < actual.size(); i++) {
-      if (actual[i].size() != expected[i].size()) {
-        std::cout << "FAILURE: " << message << " Size mismatch. " << std::endl;
-        std::cout << std::endl;
-        this->_test_failed_flag = true;
-        return;
-      }
+  /**
+   * @brief Checks two vectors element-wise within a combined absolute,
+   * relative and ULP tolerance.
+   */
+  void expect_near(const std::vector<T> &actual,
+                   const std::vector<T> &expected,
+                   const Tolerance<T> &tolerance, const std::string &message) {
+    this->expect_near(make_flat_array(actual), make_flat_array(expected),
+                      tolerance, message);
+  }
 
-      for (size_t j = 0; j < actual[i].size(); j++) {
-        if (std::abs(actual[i][j] - expected[i][j]) <= tolerance) {
-          /* Do Nothing. */
-        } else {
-          std::cout << "FAILURE: " << message << " Element mismatch."
-                    << std::endl;
-          std::cout << std::endl;
-          this->_test_failed_flag = true;
-          return;
-        }
-      }
+  /**
+   * @brief Checks two flattened arrays element-wise within a combined
+   * absolute, relative and ULP tolerance.
+   *
+   * An absolute tolerance is checked exactly like the overload taking a
+   * number. Otherwise the failure message states the tolerance, the number
+   * of elements outside it and the first of them.
+   *
+   * @param actual The flattened array of actual values to check.
+   * @param expected The flattened array of expected values to compare against.
+   * @param tolerance The tolerance every element must be within.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near(const FlatArray<T> &actual, const FlatArray<T> &expected,
+                   const Tolerance<T> &tolerance, const std::string &message) {
+    if (tolerance.is_absolute()) {
+      this->expect_near(actual, expected, tolerance.absolute_part(), message);
+      return;
+    }
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
     }
+    Tolerance<T> scaled = tolerance.scaled(this->_tolerance_scale);
+    if (!this->_accept_degenerate(actual, expected, message)) {
+      return;
+    }
+
+    ComparisonResult<T> result =
+        Comparison::compare_arrays(actual, expected, scaled);
+    this->_record_entry("tolerance", actual, expected,
+                        this->_tolerance_parameters(scaled), result.status,
+                        message);
+
+    std::string detail = result.describe();
+    if (result.kind == MismatchKind::ElementMismatch) {
+      detail += " " + std::to_string(result.mismatch_count) +
+                " element(s) outside " + scaled.describe() + ", first " +
+                this->_format_position(actual.shape, result.index) + " " +
+                format_number(result.actual_value) + " vs " +
+                format_number(result.expected_value) + ".";
+    }
+    detail += this->_write_error_map(actual, expected, result, message);
+    this->_report_result(result, message, detail, scaled.absolute_part());
   }
 
-  template <std::size_t M, std::size_t N>
-  void expect_near(const std::array<std::array<T, N>, M> &actual,
-                   const std::array<std::array<T, N>, M> &expected, T tolerance,
-                   const std::string &message) {
-    for (std::size_t i = 0; i < M; ++i) {
-      for (std::size_t j = 0; j < N; ++j) {
-        if (std::abs(actual[i][j] - expected[i][j]) <= tolerance) {
-          /* Do Nothing. */
-        } else {
-          std::cout << "FAILURE: " << message << " Element mismatch."
-                    << std::endl;
-          std::cout << std::endl;
-          this->_test_failed_flag = true;
-          return;
-        }
+  /**
+   * @brief Checks two arrays element-wise within a tolerance under an
+   * explicit shape policy.
+   *
+   * With ShapePolicy::Broadcast, the expected array is broadcast onto the
+   * shape of the actual array as NumPy would, so generated broadcast
+   * semantics can be validated against NumPy references, e.g. a row vector
+   * (shape {N} or {1, N}) against every row of an M x N matrix. With
+   * ShapePolicy::Strict, the shapes and the source memory layouts recorded in
+   * the FlatArray objects must both match. ShapePolicy::Exact behaves like the
+   * overloads without a policy.
+   *
+   * @tparam ActualContainer Any container accepted by make_flat_array.
+   * @tparam ExpectedContainer Any container accepted by make_flat_array; use
+   * make_flat_scalar to broadcast a single value.
+   * @param actual The actual values to check.
+   * @param expected The expected values to compare against.
+   * @param tolerance The maximum allowed difference between corresponding
+   * elements.
+   * @param message The message to display in case of failure.
+   * @param policy How the shapes of both arrays must agree.
+   */
+  template <typename ActualContainer, typename ExpectedContainer>
+  void expect_near(const ActualContainer &actual,
+                   const ExpectedContainer &expected, T tolerance,
+                   const std::string &message, ShapePolicy policy) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    FlatArray<T> flat_actual = make_flat_array(actual);
+    FlatArray<T> flat_expected = make_flat_array(expected);
+    if (policy != ShapePolicy::Broadcast &&
+        !this->_accept_degenerate(flat_actual, flat_expected, message)) {
+      return;
+    }
+
+    ComparisonResult<T> result =
+        this->try_expect_near(flat_actual, flat_expected, tolerance, policy);
+    this->_record_near(flat_actual, flat_expected, tolerance, tolerance,
+                       policy, result, message);
+
+    FlatArray<T> broadcast;
+    if (policy == ShapePolicy::Broadcast &&
+        Comparison::broadcast_to(flat_expected, flat_actual.shape,
+                                 broadcast)) {
+      flat_expected = broadcast;
+    }
+    this->_report_result(
+        result, message,
+        this->_describe_mismatch(flat_actual, flat_expected, tolerance,
+                                 tolerance, result) +
+            this->_write_error_map(flat_actual, flat_expected, result,
+                                   message),
+        tolerance);
+  }
+
+#ifdef MCAP_TESTER_ENABLE_MATFILE
+  /**
+   * @brief Compares actual values against a variable stored in a Level-5 MAT
+   * file.
+   *
+   * Singleton dimensions are removed from both sides before comparing, so a
+   * std::vector can be checked against a MATLAB row or column vector. A
+   * missing file or variable is reported as a failure.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param actual The container of actual values to check.
+   * @param path The MAT file holding the expected values.
+   * @param variable_name The name of the expected variable in the MAT file.
+   * @param tolerance The maximum allowed difference between corresponding
+   * elements.
+   * @param message The message to display in case of failure.
+   */
+  template <typename Container>
+  void expect_near_matfile(const Container &actual, const std::string &path,
+                           const std::string &variable_name, T tolerance,
+                           const std::string &message) {
+    FlatArray<T> expected;
+    std::string error;
+    if (!MatFile::load_variable(path, variable_name, expected, error)) {
+      this->_report_failure(message, error, FailureCategory::Infrastructure);
+      return;
+    }
+
+    this->expect_near(squeeze_flat_array(make_flat_array(actual)),
+                      squeeze_flat_array(expected), tolerance, message);
+  }
+#endif
+
+#ifdef MCAP_TESTER_ENABLE_SPARSE
+  /**
+   * @brief Checks the sparsity pattern and the nonzero values of a sparse
+   * matrix.
+   *
+   * The check fails if the dimensions differ, if a nonzero is stored on one
+   * side only, or if a stored value differs by more than `tolerance`. When
+   * the patterns differ, the (row, column) positions of the missing and
+   * extra nonzeros are listed.
+   *
+   * @param actual The actual sparse matrix to check.
+   * @param expected The expected sparse matrix to compare against.
+   * @param tolerance The maximum allowed difference between values stored at
+   * the same position.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near_sparse(const SparseMatrix<T> &actual,
+                          const SparseMatrix<T> &expected, T tolerance,
+                          const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    this->_warn_unrecorded(message);
+    SparseComparisonResult<T> result =
+        Comparison::compare_sparse(actual, expected, tolerance);
+    if (result.passed()) {
+      this->_report_pass(message);
+      return;
+    }
+
+    std::string detail;
+    if (result.kind == MismatchKind::SizeMismatch) {
+      detail = "Size mismatch: actual is " + std::to_string(actual.rows) +
+               " x " + std::to_string(actual.columns) + ", expected is " +
+               std::to_string(expected.rows) + " x " +
+               std::to_string(expected.columns) +
+               " (or a matrix is malformed).";
+    } else {
+      if (!result.missing.empty()) {
+        detail += "Missing nonzeros " +
+                  this->_describe_positions(result.missing) + ". ";
+      }
+      if (!result.extra.empty()) {
+        detail += "Extra nonzeros " + this->_describe_positions(result.extra) +
+                  ". ";
+      }
+      if (result.mismatch_count > 0) {
+        detail += std::to_string(result.mismatch_count) +
+                  " value mismatch(es), first at (" +
+                  std::to_string(result.mismatch_position.first) + ", " +
+                  std::to_string(result.mismatch_position.second) +
+                  "): actual " + format_number(result.actual_value) +
+                  ", expected " + format_number(result.expected_value) + ".";
+      }
+      if (!detail.empty() && detail[detail.size() - 1] == ' ') {
+        detail.erase(detail.size() - 1);
       }
     }
+    this->_report_failure(message, detail,
+                          result.kind == MismatchKind::SizeMismatch
+                              ? FailureCategory::Structural
+                              : FailureCategory::Tolerance);
   }
+#endif
 
   /**
-   * @brief Throws a std::runtime_error if the test has failed.
+   * @brief Checks a scalar against separate warning and failure tolerances.
    *
-   * This method checks the internal _test_failed_flag. If the flag is set to
-   * true, it throws a std::runtime_error with the message "Test failed.".
+   * If the absolute difference exceeds `failure_tolerance`, the check fails as
+   * with expect_near. If it only exceeds `warning_tolerance`, a warning is
+   * printed and counted, but the test failed flag is left untouched, which
+   * separates "investigate" results from "reject" results.
    *
-   * @throws std::runtime_error if the test has failed.
+   * @param actual The actual value to check.
+   * @param expected The expected value to compare against.
+   * @param warning_tolerance The difference above which a warning is issued.
+   * @param failure_tolerance The difference above which the check fails.
+   * @param message The message to display on warning or failure.
    */
-  void throw_error_if_test_failed() {
-    if (this->_test_failed_flag) {
-      throw std::runtime_error("Test failed.");
+  void expect_near(T actual, T expected, T warning_tolerance,
+                   T failure_tolerance, const std::string &message) {
+    if (!this->_accept_tolerance(warning_tolerance, message) ||
+        !this->_accept_tolerance(failure_tolerance, message)) {
+      return;
     }
+    warning_tolerance = this->_scaled(warning_tolerance);
+    failure_tolerance = this->_scaled(failure_tolerance);
+    ComparisonResult<T> result = this->try_expect_near_scalar(
+        actual, expected, warning_tolerance, failure_tolerance);
+    this->_record_near(make_flat_scalar(actual), make_flat_scalar(expected),
+                       warning_tolerance, failure_tolerance,
+                       ShapePolicy::Exact, result, message);
+    this->_report_result(result, message, "", failure_tolerance);
   }
 
   /**
-   * @brief Resets the test failed flag to indicate that no test failure has
-   * occurred.
+   * @brief Checks two flattened arrays against separate warning and failure
+   * tolerances.
    *
-   * This method sets the internal _test_failed_flag to false, clearing any
-   * previous test failure state.
+   * A shape mismatch or any element beyond `failure_tolerance` fails the
+   * check. Otherwise, if any element is beyond `warning_tolerance`, a single
+   * warning is printed and counted.
+   *
+   * @param actual The flattened array of actual values to check.
+   * @param expected The flattened array of expected values to compare against.
+   * @param warning_tolerance The difference above which a warning is issued.
+   * @param failure_tolerance The difference above which the check fails.
+   * @param message The message to display on warning or failure.
    */
-  void reset_test_failed_flag() { this->_test_failed_flag = false; }
+  void expect_near(const FlatArray<T> &actual, const FlatArray<T> &expected,
+                   T warning_tolerance, T failure_tolerance,
+                   const std::string &message) {
+    if (!this->_accept_tolerance(warning_tolerance, message) ||
+        !this->_accept_tolerance(failure_tolerance, message)) {
+      return;
+    }
+    warning_tolerance = this->_scaled(warning_tolerance);
+    failure_tolerance = this->_scaled(failure_tolerance);
+    if (!this->_accept_degenerate(actual, expected, message)) {
+      return;
+    }
 
-protected:
-  bool _test_failed_flag = false;
-};
+    ComparisonResult<T> result = this->try_expect_near(
+        actual, expected, warning_tolerance, failure_tolerance);
+    this->_record_near(actual, expected, warning_tolerance, failure_tolerance,
+                       ShapePolicy::Exact, result, message);
+    this->_report_result(
+        result, message,
+        this->_describe_mismatch(actual, expected, warning_tolerance,
+                                 failure_tolerance, result) +
+            this->_write_error_map(actual, expected, result, message),
+        failure_tolerance);
+  }
 
-} // namespace Tester
+  /**
+   * @brief Checks two containers against separate warning and failure
+   * tolerances.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param actual The container of actual values to check.
+   * @param expected The container of expected values to compare against.
+   * @param warning_tolerance The difference above which a warning is issued.
+   * @param failure_tolerance The difference above which the check fails.
+   * @param message The message to display on warning or failure.
+   */
+  template <typename Container,
+            typename = typename std::enable_if<
+                !std::is_arithmetic<Container>::value>::type>
+  void expect_near(const Container &actual, const Container &expected,
+                   T warning_tolerance, T failure_tolerance,
+                   const std::string &message) {
+    this->expect_near(make_flat_array(actual), make_flat_array(expected),
+                      warning_tolerance, failure_tolerance, message);
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Runs every entry of a JSON fixture file against actual values.
+   *
+   * The fixture describes the expected scalars and arrays of a generated
+   * module together with their individual tolerances:
+   *
+   * @code{.json}
+   * {
+   *   "tolerance": 1.0e-9,
+   *   "entries": [
+   *     { "name": "gain", "expected": 0.5 },
+   *     { "name": "K", "expected": [[1.0, 2.0], [3.0, 4.0]],
+   *       "tolerance": 1.0e-6 },
+   *     { "name": "flux", "expected": [1.0e-3, 2.5e4],
+   *       "tolerance": { "relative": 1.0e-9, "ulps": 4 } }
+   *   ]
+   * }
+   * @endcode
+   *
+   * A tolerance is either a number, which is absolute, or an object combining
+   * "absolute", "relative" and "ulps" bounds as in Tolerance. The top-level
+   * "tolerance" is used for entries without their own. An entry
+   * may also carry a "rationale" string explaining its tolerance, which is
+   * registered with set_tolerance_rationale. For each
+   * entry, `actual_value` is called with the entry name and its result is
+   * compared with expect_near, using the entry name as the failure message.
+   * An unreadable fixture or malformed entry is reported as a failure.
+   *
+   * @param path Path of the JSON fixture file.
+   * @param actual_value Returns the actual value for a fixture entry name,
+   * built with make_flat_array or make_flat_scalar.
+   */
+  void run_fixture(
+      const std::string &path,
+      const std::function<FlatArray<T>(const std::string &)> &actual_value) {
+    JSON::Value fixture;
+    std::string error;
+    if (!JSON::load_file(path, fixture, error)) {
+      this->_report_failure(path, "Fixture not loaded. " + error,
+                            FailureCategory::Infrastructure);
+      return;
+    }
+
+    const JSON::Value &entries = fixture.get("entries");
+    if (!entries.is_array()) {
+      this->_report_failure(path, "Fixture has no entries array.",
+                            FailureCategory::Infrastructure);
+      return;
+    }
+
+    for (std::size_t i = 0; i < entries.size(); i++) {
+      const JSON::Value &entry = entries[i];
+      const JSON::Value &tolerance = entry.has("tolerance")
+                                         ? entry.get("tolerance")
+                                         : fixture.get("tolerance");
+
+      FlatArray<T> expected;
+      Tolerance<T> bound;
+      if (!entry.get("name").is_string() ||
+          !json_to_tolerance(tolerance, bound) ||
+          !json_to_flat_array(entry.get("expected"), expected)) {
+        this->_report_failure(path, "Malformed fixture entry " +
+                                        std::to_string(i) + ".",
+                              FailureCategory::Infrastructure);
+        continue;
+      }
+
+      const std::string &name = entry.get("name").as_string();
+      if (entry.get("rationale").is_string()) {
+        this->set_tolerance_rationale(name,
+                                      entry.get("rationale").as_string());
+      }
+      this->expect_near(actual_value(name), expected, bound, name);
+    }
+  }
+#endif
+
+  /**
+   * @brief Compares two trajectories whose rows are time samples and whose
+   * columns are signals.
+   *
+   * Instead of reporting only that some element differs, a failure reports
+   * the worst error with its sample and signal index, the range of samples
+   * that exceed the tolerance and how many elements do. Leading samples can
+   * be excluded with `settling_samples` so that initial transients do not
+   * count. Use Comparison::compare_trajectory to obtain the full error
+   * envelope over time.
+   *
+   * @tparam Container Any two-dimensional container accepted by
+   * make_flat_array.
+   * @param actual The actual trajectory, one row per time sample.
+   * @param expected The expected trajectory with the same shape.
+   * @param tolerance The tolerance per element; a number is an absolute
+   * tolerance.
+   * @param message The message to display in case of failure.
+   * @param settling_samples The number of leading samples to exclude.
+   */
+  template <typename Container>
+  void expect_near_trajectory(const Container &actual,
+                              const Container &expected,
+                              const Tolerance<T> &tolerance,
+                              const std::string &message,
+                              std::size_t settling_samples = 0) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    Tolerance<T> scaled = tolerance.scaled(this->_tolerance_scale);
+    FlatArray<T> flat_actual = make_flat_array(actual);
+    FlatArray<T> flat_expected = make_flat_array(expected);
+    if (!this->_accept_degenerate(flat_actual, flat_expected, message)) {
+      return;
+    }
+
+    TrajectoryResult<T> result = Comparison::compare_trajectory(
+        flat_actual, flat_expected, scaled, settling_samples);
+    std::vector<double> parameters = this->_tolerance_parameters(scaled);
+    parameters.push_back(static_cast<double>(settling_samples));
+    this->_record_entry("trajectory", flat_actual, flat_expected, parameters,
+                        result.status, message);
+
+    this->_report_trajectory(result, message, "", scaled.absolute_part());
+  }
+
+  /**
+   * @brief Compares two time series sampled on different time bases, e.g. a
+   * reference simulated at 1 kHz and generated code running at 500 Hz.
+   *
+   * The expected series is linearly interpolated at the time stamps of the
+   * actual series with Comparison::compare_resampled; actual samples outside
+   * the time range of the expected series are not compared. A failure
+   * reports the time stamp of the worst error and of the first sample
+   * outside the tolerance.
+   *
+   * @param actual The actual samples.
+   * @param actual_times The strictly increasing time stamps of the actual
+   * samples.
+   * @param expected The expected samples; at least two.
+   * @param expected_times The strictly increasing time stamps of the
+   * expected samples.
+   * @param tolerance The tolerance every compared sample must be within.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near_resampled(const std::vector<T> &actual,
+                             const std::vector<T> &actual_times,
+                             const std::vector<T> &expected,
+                             const std::vector<T> &expected_times,
+                             const Tolerance<T> &tolerance,
+                             const std::string &message) {
+    if (!Comparison::valid_time_base(actual, actual_times) ||
+        !Comparison::valid_time_base(expected, expected_times)) {
+      this->_report_failure(
+          message, "Invalid time base: every series needs one time stamp per "
+                   "sample, strictly increasing.",
+          FailureCategory::Structural);
+      return;
+    }
+    if (expected.size() < 2) {
+      this->_report_failure(message,
+                            "At least 2 expected samples are required.",
+                            FailureCategory::Structural);
+      return;
+    }
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+
+    Tolerance<T> scaled = tolerance.scaled(this->_tolerance_scale);
+    ResampledResult<T> result = Comparison::compare_resampled(
+        actual, actual_times, expected, expected_times, scaled);
+    if (result.samples_compared == 0) {
+      this->_report_failure(message, "No actual sample lies within the time "
+                                     "range of the expected series.",
+                            FailureCategory::Structural);
+      return;
+    }
+    this->_record_entry("resampled", this->_time_series(actual_times, actual),
+                        this->_time_series(expected_times, expected),
+                        this->_tolerance_parameters(scaled), result.status,
+                        message);
+
+    std::string detail;
+    if (result.kind == MismatchKind::ElementMismatch) {
+      detail = "Element mismatch. " + std::to_string(result.mismatch_count) +
+               " of " + std::to_string(result.samples_compared) +
+               " samples outside " + scaled.describe() + ", first at t = " +
+               format_number(actual_times[result.index]) + " (" +
+               format_number(result.actual_value) +
+               " vs interpolated " + format_number(result.expected_value) +
+               "). Worst error " + format_number(result.max_error) +
+               " at t = " + format_number(result.worst_time) + ".";
+    }
+    this->_report_result(result, message, detail, scaled.absolute_part());
+  }
+
+  /**
+   * @brief Checks that the fraction of samples within tolerance reaches a
+   * minimum in every channel.
+   *
+   * This matches requirements for noisy signals that are written as "99.9 %
+   * of the samples within tolerance" rather than "every sample within
+   * tolerance". Use Comparison::compute_pass_rates to obtain the rates
+   * themselves.
+   *
+   * @tparam Container Any one- or two-dimensional container accepted by
+   * make_flat_array; rows are samples and columns are channels.
+   * @param actual The actual samples.
+   * @param expected The expected samples with the same shape.
+   * @param tolerance The tolerance a sample must be within to pass; a number
+   * is an absolute tolerance.
+   * @param minimum_rate The smallest acceptable pass rate, e.g. 0.999.
+   * @param message The message to display in case of failure; every channel
+   * below the minimum is appended with its rate.
+   */
+  template <typename Container>
+  void expect_pass_rate_above(const Container &actual,
+                              const Container &expected,
+                              const Tolerance<T> &tolerance,
+                              double minimum_rate,
+                              const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    Tolerance<T> scaled = tolerance.scaled(this->_tolerance_scale);
+    FlatArray<T> flat_actual = make_flat_array(actual);
+    FlatArray<T> flat_expected = make_flat_array(expected);
+    PassRates rates =
+        Comparison::compute_pass_rates(flat_actual, flat_expected, scaled);
+    if (!rates.shape_matches) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    std::string detail;
+    for (std::size_t j = 0; j < rates.pass_rates.size(); j++) {
+      if (rates.pass_rates[j] >= minimum_rate) {
+        continue;
+      }
+      detail += std::string(detail.empty() ? "" : " ") + "Channel " +
+                std::to_string(j) + " pass rate " +
+                format_number(rates.pass_rates[j]) + " below " +
+                format_number(minimum_rate) + " (" +
+                std::to_string(rates.samples - rates.passed_samples[j]) +
+                " of " + std::to_string(rates.samples) +
+                " samples outside tolerance).";
+    }
+
+    /* The element-wise comparison supplies the errors; the verdict is the
+     * pass rate's. */
+    ComparisonResult<T> result =
+        Comparison::compare_arrays(flat_actual, flat_expected, scaled);
+    result.status =
+        detail.empty() ? ComparisonStatus::Pass : ComparisonStatus::Failure;
+    std::vector<double> parameters = this->_tolerance_parameters(scaled);
+    parameters.push_back(minimum_rate);
+    this->_record_entry("pass_rate", flat_actual, flat_expected, parameters,
+                        result.status, message);
+    this->_report_result(result, message, detail, scaled.absolute_part());
+  }
+
+  /**
+   * @brief Runs a deterministic scenario several times and checks that its
+   * outputs do not vary between runs.
+   *
+   * Generated code that reads uninitialized memory usually shows up only as
+   * sporadic failures; repeating the same scenario exposes it as run-to-run
+   * variation. The check fails if the outputs have different lengths or if
+   * the standard deviation of any element across the runs exceeds
+   * `max_sigma`.
+   *
+   * @param run Executes the scenario once and returns its outputs.
+   * @param runs The number of runs; at least two.
+   * @param max_sigma The largest allowed standard deviation per element.
+   * @param message The message to display in case of failure.
+   */
+  void expect_run_to_run_variance_below(
+      const std::function<std::vector<T>()> &run, std::size_t runs,
+      T max_sigma, const std::string &message) {
+    if (!this->_accept_tolerance(max_sigma, message)) {
+      return;
+    }
+    max_sigma = this->_scaled(max_sigma);
+    if (runs < 2) {
+      this->_report_failure(message, "At least two runs are required.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    std::vector<std::vector<T>> outputs;
+    for (std::size_t r = 0; r < runs; r++) {
+      outputs.push_back(run());
+    }
+
+    RunSpread<T> spread = Comparison::compute_run_spread(outputs);
+    if (!spread.size_matches) {
+      this->_report_failure(message, "Output length differs between runs.",
+                            FailureCategory::Structural);
+    } else if (spread.max_sigma <= max_sigma) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(
+          message, "Element " + std::to_string(spread.worst_index) +
+                       " varies between runs with standard deviation " +
+                       format_number(spread.max_sigma) + " (allowed " +
+                       format_number(max_sigma) + ").");
+    }
+  }
+
+  /**
+   * @brief Checks that the reset entry point of generated code restores its
+   * initial state.
+   *
+   * The scenario is run, the code is reset, and the scenario is run again;
+   * both runs must produce exactly the same outputs. Any output that differs
+   * reveals state that leaked across the reset, and the first differing
+   * outputs are listed with their values from both runs.
+   *
+   * @param run Executes the scenario once on the initialized code and returns
+   * its outputs, e.g. every step output of every signal.
+   * @param reset Calls the reset or initialize entry point of the code.
+   * @param message The message to display in case of failure.
+   */
+  void expect_reset_restores_state(const std::function<std::vector<T>()> &run,
+                                   const std::function<void()> &reset,
+                                   const std::string &message) {
+    std::vector<T> first = run();
+    reset();
+    std::vector<T> second = run();
+
+    if (first.size() != second.size()) {
+      this->_report_failure(message,
+                            "Output length changed after reset from " +
+                                std::to_string(first.size()) + " to " +
+                                std::to_string(second.size()) + ".",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    std::vector<std::size_t> leaked = Comparison::find_unequal(first, second);
+    if (leaked.empty()) {
+      this->_report_pass(message);
+      return;
+    }
+
+    const std::size_t listed = 10;
+    std::string detail = std::to_string(leaked.size()) +
+                         " output(s) differ after reset:";
+    for (std::size_t k = 0; k < leaked.size() && k < listed; k++) {
+      std::size_t i = leaked[k];
+      detail += std::string(k == 0 ? " " : ", ") + "[" + std::to_string(i) +
+                "] " + format_number(first[i]) + " before, " +
+                format_number(second[i]) + " after";
+    }
+    detail += (leaked.size() > listed) ? ", ..." : ".";
+    this->_report_failure(message, detail);
+  }
+
+  /**
+   * @brief Compares a function against its reference at and next to the
+   * declared bounds of every input.
+   *
+   * Most defects of generated code live at range boundaries, so every input
+   * is evaluated exactly at each of its bounds and one representable value
+   * inside and outside of it, with the other inputs at the middle of their
+   * ranges (see Comparison::boundary_probes). All probes are reported as one
+   * check that lists every probe whose outputs differ from the reference.
+   *
+   * @param function The function under test, e.g. a wrapper of a generated
+   * step function.
+   * @param input_ranges The declared range of every input.
+   * @param reference The reference implementation with the same signature.
+   * @param tolerance The maximum allowed difference per output.
+   * @param message The message to display in case of failure.
+   */
+  void expect_behavior_at_bounds(
+      const std::function<std::vector<T>(const std::vector<T> &)> &function,
+      const std::vector<InputRange<T>> &input_ranges,
+      const std::function<std::vector<T>(const std::vector<T> &)> &reference,
+      T tolerance, const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    std::vector<BoundaryProbe<T>> probes =
+        Comparison::boundary_probes(input_ranges);
+
+    ComparisonResult<T> combined;
+    bool structural = false;
+    std::string detail;
+    for (std::size_t k = 0; k < probes.size(); k++) {
+      const BoundaryProbe<T> &probe = probes[k];
+      FlatArray<T> outputs = make_flat_array(function(probe.inputs));
+      FlatArray<T> references = make_flat_array(reference(probe.inputs));
+      ComparisonResult<T> result =
+          Comparison::compare_arrays(outputs, references, tolerance);
+      this->_record_near(outputs, references, tolerance, tolerance,
+                         ShapePolicy::Exact, result,
+                         message + ": input " +
+                             std::to_string(probe.input_index) + " " +
+                             probe.label);
+      if (result.kind != MismatchKind::SizeMismatch) {
+        Comparison::Detail::merge_range(combined, result);
+      }
+      if (result.passed()) {
+        continue;
+      }
+
+      detail += std::string(detail.empty() ? "" : " ") + "Input " +
+                std::to_string(probe.input_index) + " " + probe.label +
+                " (" + format_number(probe.inputs[probe.input_index]) + "): ";
+      if (result.kind == MismatchKind::SizeMismatch) {
+        structural = true;
+        detail += "size mismatch.";
+      } else {
+        detail += "output " + std::to_string(result.index) + " is " +
+                  format_number(result.actual_value) + ", expected " +
+                  format_number(result.expected_value) + ".";
+      }
+    }
+    if (structural) {
+      combined.status = ComparisonStatus::Failure;
+      combined.kind = MismatchKind::SizeMismatch;
+    }
+
+    this->_report_result(combined, message, detail, tolerance);
+  }
+
+  /**
+   * @brief Compares two very long trajectories adaptively.
+   *
+   * A coarse subsample of every `stride`-th sample is compared first, and
+   * only the neighbourhood of coarse samples whose error exceeds half the
+   * tolerance is compared at full resolution, which cuts the comparison time
+   * for multi-hour logs while a divergence is still located exactly. See
+   * Comparison::compare_trajectory_adaptive for the limits of this approach.
+   *
+   * @tparam Container Any two-dimensional container accepted by
+   * make_flat_array.
+   * @param actual The actual trajectory, one row per time sample.
+   * @param expected The expected trajectory with the same shape.
+   * @param tolerance The tolerance per element; a number is an absolute
+   * tolerance.
+   * @param message The message to display in case of failure.
+   * @param stride The distance between two coarse samples.
+   * @param settling_samples The number of leading samples to exclude.
+   */
+  template <typename Container>
+  void expect_near_trajectory_adaptive(const Container &actual,
+                                       const Container &expected,
+                                       const Tolerance<T> &tolerance,
+                                       const std::string &message,
+                                       std::size_t stride,
+                                       std::size_t settling_samples = 0) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    Tolerance<T> scaled = tolerance.scaled(this->_tolerance_scale);
+    FlatArray<T> flat_actual = make_flat_array(actual);
+    FlatArray<T> flat_expected = make_flat_array(expected);
+    if (!this->_accept_degenerate(flat_actual, flat_expected, message)) {
+      return;
+    }
+
+    AdaptiveTrajectoryResult<T> result =
+        Comparison::compare_trajectory_adaptive(
+            flat_actual, flat_expected, scaled, settling_samples, stride,
+            scaled.scaled(0.5));
+    std::vector<double> parameters = this->_tolerance_parameters(scaled);
+    parameters.push_back(static_cast<double>(settling_samples));
+    parameters.push_back(static_cast<double>(stride));
+    this->_record_entry("adaptive_trajectory", flat_actual, flat_expected,
+                        parameters, result.status, message);
+    this->_report_trajectory(
+        result, message,
+        " " + std::to_string(result.samples_compared) + " of " +
+            std::to_string(flat_actual.shape.empty() ? 0
+                                                     : flat_actual.shape[0]) +
+            " samples compared.",
+        scaled.absolute_part());
+  }
+
+  /**
+   * @brief Compares the magnitude spectra of two signals over a frequency
+   * band.
+   *
+   * Per-sample comparisons flag two implementations that differ only by a
+   * tiny time shift, although they are functionally equivalent. This check
+   * compares the normalized one-sided magnitude spectra instead; see
+   * Comparison::compare_spectrum for the exact definition.
+   *
+   * @param actual The actual signal.
+   * @param expected The expected signal with the same length.
+   * @param sample_rate The sampling rate of both signals.
+   * @param min_frequency The lower edge of the compared band.
+   * @param max_frequency The upper edge of the compared band.
+   * @param magnitude_tolerance The maximum allowed magnitude difference.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near_spectrum(const std::vector<T> &actual,
+                            const std::vector<T> &expected, T sample_rate,
+                            T min_frequency, T max_frequency,
+                            T magnitude_tolerance,
+                            const std::string &message) {
+    if (!this->_accept_tolerance(magnitude_tolerance, message)) {
+      return;
+    }
+    magnitude_tolerance = this->_scaled(magnitude_tolerance);
+    this->_report_spectrum(
+        Comparison::compare_spectrum(actual, expected, sample_rate,
+                                     min_frequency, max_frequency,
+                                     magnitude_tolerance, false,
+                                     static_cast<T>(0)),
+        message);
+  }
+
+  /**
+   * @brief Compares the magnitude and phase spectra of two signals over a
+   * frequency band.
+   *
+   * @param actual The actual signal.
+   * @param expected The expected signal with the same length.
+   * @param sample_rate The sampling rate of both signals.
+   * @param min_frequency The lower edge of the compared band.
+   * @param max_frequency The upper edge of the compared band.
+   * @param magnitude_tolerance The maximum allowed magnitude difference.
+   * @param phase_tolerance The maximum allowed phase difference in radians,
+   * checked for bins whose expected magnitude exceeds `magnitude_tolerance`.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near_spectrum(const std::vector<T> &actual,
+                            const std::vector<T> &expected, T sample_rate,
+                            T min_frequency, T max_frequency,
+                            T magnitude_tolerance, T phase_tolerance,
+                            const std::string &message) {
+    if (!this->_accept_tolerance(magnitude_tolerance, message) ||
+        !this->_accept_tolerance(phase_tolerance, message)) {
+      return;
+    }
+    magnitude_tolerance = this->_scaled(magnitude_tolerance);
+    phase_tolerance = this->_scaled(phase_tolerance);
+    this->_report_spectrum(
+        Comparison::compare_spectrum(actual, expected, sample_rate,
+                                     min_frequency, max_frequency,
+                                     magnitude_tolerance, true,
+                                     phase_tolerance),
+        message);
+  }
+
+  /**
+   * @brief Checks that the root-mean-square error between two arrays is
+   * below a threshold.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param actual The actual values, a sequence or a two-dimensional array.
+   * @param expected The expected values with the same shape.
+   * @param threshold The largest acceptable RMSE; a relative part is
+   * relative to the RMS value of the expected values.
+   * @param message The message to display in case of failure; the computed
+   * RMSE is appended.
+   */
+  template <typename Container>
+  void expect_rmse_below(const Container &actual, const Container &expected,
+                         const Tolerance<T> &threshold,
+                         const std::string &message) {
+    this->_check_metric(make_flat_array(actual), make_flat_array(expected),
+                        &ErrorMetrics<T>::rmse, "RMSE", threshold, message);
+  }
+
+  /**
+   * @brief Checks that the mean absolute error between two arrays is below a
+   * threshold.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param actual The actual values, a sequence or a two-dimensional array.
+   * @param expected The expected values with the same shape.
+   * @param threshold The largest acceptable MAE; a relative part is relative
+   * to the mean magnitude of the expected values.
+   * @param message The message to display in case of failure; the computed
+   * MAE is appended.
+   */
+  template <typename Container>
+  void expect_mae_below(const Container &actual, const Container &expected,
+                        const Tolerance<T> &threshold,
+                        const std::string &message) {
+    this->_check_metric(make_flat_array(actual), make_flat_array(expected),
+                        &ErrorMetrics<T>::mae, "MAE", threshold, message);
+  }
+
+  /**
+   * @brief Checks that the maximum absolute error between two arrays is below
+   * a threshold.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param actual The actual values, a sequence or a two-dimensional array.
+   * @param expected The expected values with the same shape.
+   * @param threshold The largest acceptable maximum error; a relative part
+   * is relative to the largest magnitude of the expected values.
+   * @param message The message to display in case of failure; the computed
+   * maximum error is appended.
+   */
+  template <typename Container>
+  void expect_max_error_below(const Container &actual,
+                              const Container &expected,
+                              const Tolerance<T> &threshold,
+                              const std::string &message) {
+    this->_check_metric(make_flat_array(actual), make_flat_array(expected),
+                        &ErrorMetrics<T>::max_error, "Max error", threshold,
+                        message);
+  }
+
+  /**
+   * @brief Checks that a signal is quiet: its root-mean-square value is
+   * below a threshold.
+   *
+   * Pass the samples of an idle phase of a scenario, where the output must
+   * stay within its noise floor. The RMS is taken about zero, so remove a
+   * non-zero operating point from the samples first.
+   *
+   * @param segment The samples of the idle phase.
+   * @param max_rms The largest acceptable RMS value.
+   * @param message The message to display in case of failure; the computed
+   * RMS value is appended.
+   */
+  void expect_rms_below(const std::vector<T> &segment, T max_rms,
+                        const std::string &message) {
+    this->_check_quietness(segment, Comparison::compute_rms(segment), "RMS",
+                           max_rms, message);
+  }
+
+  /**
+   * @brief Checks that a signal is quiet: the difference between its largest
+   * and smallest sample is below a threshold.
+   *
+   * @param segment The samples of the idle phase.
+   * @param max_peak_to_peak The largest acceptable peak-to-peak range.
+   * @param message The message to display in case of failure; the computed
+   * range is appended.
+   */
+  void expect_peak_to_peak_below(const std::vector<T> &segment,
+                                 T max_peak_to_peak,
+                                 const std::string &message) {
+    this->_check_quietness(segment, Comparison::compute_peak_to_peak(segment),
+                           "Peak-to-peak", max_peak_to_peak, message);
+  }
+
+  /**
+   * @brief Checks that the total actuator effort of a logged command signal
+   * is within a budget.
+   *
+   * @param command The logged commands of the controller.
+   * @param dt The sample period.
+   * @param budget The largest acceptable effort.
+   * @param message The message to display in case of failure; the computed
+   * effort is appended.
+   * @param measure Whether the integral of |u| (default) or of u^2 is
+   * checked.
+   */
+  void expect_total_effort_below(
+      const std::vector<T> &command, T dt, T budget,
+      const std::string &message,
+      EffortMeasure measure = EffortMeasure::Absolute) {
+    T effort = Comparison::compute_effort(command, dt, measure);
+    std::string detail =
+        std::string(measure == EffortMeasure::Absolute ? "Integral of |u| "
+                                                       : "Integral of u^2 ") +
+        format_number(effort) + ", budget " + format_number(budget) + ".";
+    if (effort <= budget) {
+      this->_report_pass(message, detail);
+    } else {
+      this->_report_failure(message, detail);
+    }
+  }
+
+  /**
+   * @brief Checks that metric B is not significantly worse than metric A over
+   * repeated paired runs, where lower values are better.
+   *
+   * Noisy metrics such as run times or per-run errors cannot be compared
+   * sample by sample. The samples are instead paired by index, e.g. both
+   * implementations run on the same inputs, and a one-sided test decides
+   * whether B is greater than A. The check fails if the p-value is below
+   * `alpha`, i.e. if a slowdown at least this large would be this unlikely
+   * by chance. Negate metrics for which higher values are better.
+   *
+   * @param a_samples The samples of the baseline A.
+   * @param b_samples The samples of the candidate B, as many as of A.
+   * @param alpha The significance level, e.g. 0.05.
+   * @param message The message to display in case of failure; the test
+   * statistic and p-value are appended.
+   * @param test The paired test; the Wilcoxon signed-rank test by default.
+   */
+  void expect_not_significantly_worse(
+      const std::vector<T> &a_samples, const std::vector<T> &b_samples,
+      double alpha, const std::string &message,
+      SignificanceTest test = SignificanceTest::Wilcoxon) {
+    if (!(alpha > 0.0 && alpha < 1.0)) {
+      this->_report_failure(message, "Configuration error: alpha " +
+                                         format_number(alpha) +
+                                         " is not in (0, 1).",
+                            FailureCategory::Structural);
+      return;
+    }
+    if (a_samples.size() != b_samples.size()) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    SignificanceResult result =
+        (test == SignificanceTest::PairedT)
+            ? Comparison::paired_t_test(a_samples, b_samples)
+            : Comparison::wilcoxon_signed_rank_test(a_samples, b_samples);
+    if (!result.valid) {
+      this->_report_failure(message,
+                            "At least 2 paired samples are required.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    std::string detail =
+        std::string(test == SignificanceTest::PairedT ? "Paired t-test"
+                                                      : "Wilcoxon test") +
+        " over " + std::to_string(result.samples) +
+        " pairs: mean difference B - A " +
+        format_number(result.mean_difference) +
+        (test == SignificanceTest::PairedT ? ", t " : ", W+ ") +
+        format_number(result.statistic) + ", p " +
+        format_number(result.p_value) + " (alpha " + format_number(alpha) +
+        ").";
+    if (result.p_value >= alpha) {
+      this->_report_pass(message, detail);
+    } else {
+      this->_report_failure(message, detail);
+    }
+  }
+
+  /**
+   * @brief Checks the sample mean and the unbiased sample variance of a set
+   * of samples against the moments of the expected distribution.
+   *
+   * Randomized tests of noise models and estimators should size their
+   * tolerances from the sampling error: the sample mean has a standard error
+   * of sqrt(variance / n), and the sample variance of a normal distribution
+   * one of variance sqrt(2 / (n - 1)). Four standard errors fail a correct
+   * model about once in 15000 runs.
+   *
+   * @param samples The samples, e.g. outputs of a Monte-Carlo run.
+   * @param expected_mean The mean of the expected distribution.
+   * @param expected_variance The variance of the expected distribution.
+   * @param mean_tolerance The tolerance of the sample mean.
+   * @param variance_tolerance The tolerance of the sample variance.
+   * @param message The message to display in case of failure; the sample
+   * moments and tolerances are appended.
+   */
+  void expect_near_mean_var(const std::vector<T> &samples, T expected_mean,
+                            T expected_variance,
+                            const Tolerance<T> &mean_tolerance,
+                            const Tolerance<T> &variance_tolerance,
+                            const std::string &message) {
+    if (!this->_accept_tolerance(mean_tolerance, message) ||
+        !this->_accept_tolerance(variance_tolerance, message)) {
+      return;
+    }
+    Tolerance<T> mean_bound = mean_tolerance.scaled(this->_tolerance_scale);
+    Tolerance<T> variance_bound =
+        variance_tolerance.scaled(this->_tolerance_scale);
+    SampleMoments<T> moments = Comparison::compute_moments(samples);
+    if (!moments.valid) {
+      this->_report_failure(message, "At least 2 samples are required.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    std::string detail =
+        "Over " + std::to_string(moments.samples) + " samples: mean " +
+        format_number(moments.mean) + " (expected " +
+        format_number(expected_mean) + ", tolerance " +
+        mean_bound.describe() + "), variance " +
+        format_number(moments.variance) + " (expected " +
+        format_number(expected_variance) + ", tolerance " +
+        variance_bound.describe() + ").";
+    if (mean_bound.accepts(moments.mean, expected_mean) &&
+        variance_bound.accepts(moments.variance, expected_variance)) {
+      this->_report_pass(message, detail);
+    } else {
+      this->_report_failure(message, detail);
+    }
+  }
+
+  /**
+   * @brief Checks with the two-sample Kolmogorov-Smirnov test that two sets
+   * of samples come from the same distribution.
+   *
+   * The check fails if the largest distance D between the empirical
+   * distribution functions exceeds `max_statistic`. A principled threshold is
+   * Comparison::kolmogorov_smirnov_critical_value(n, m, alpha), which fails
+   * samples of the same distribution with a probability of about `alpha`.
+   * The statistic and threshold are recorded as the error and tolerance of
+   * the assertion.
+   *
+   * @param actual_samples The samples under test, e.g. outputs of a noise
+   * model.
+   * @param expected_samples Samples of the expected distribution; their
+   * number may differ from that of `actual_samples`.
+   * @param max_statistic The largest acceptable distance, in [0, 1].
+   * @param message The message to display in case of failure; the statistic
+   * and p-value are appended.
+   */
+  void expect_same_distribution(const std::vector<T> &actual_samples,
+                                const std::vector<T> &expected_samples,
+                                double max_statistic,
+                                const std::string &message) {
+    if (!(max_statistic >= 0.0 && max_statistic <= 1.0)) {
+      this->_report_failure(message, "Configuration error: threshold " +
+                                         format_number(max_statistic) +
+                                         " is not in [0, 1].",
+                            FailureCategory::Structural);
+      return;
+    }
+    KolmogorovSmirnovResult<T> result =
+        Comparison::kolmogorov_smirnov_test(actual_samples, expected_samples);
+    if (!result.valid) {
+      this->_report_failure(message,
+                            "Both samples must be non-empty and free of NaN.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    std::string detail =
+        "Kolmogorov-Smirnov D " + format_number(result.statistic) + " at " +
+        format_number(result.location) + " over " +
+        std::to_string(actual_samples.size()) + " and " +
+        std::to_string(expected_samples.size()) + " samples, p " +
+        format_number(result.p_value) + " (allowed D " +
+        format_number(max_statistic) + ").";
+    this->_pending_measurement = true;
+    this->_pending_error = result.statistic;
+    this->_pending_tolerance = max_statistic;
+    if (result.statistic <= max_statistic) {
+      this->_report_pass(message, detail);
+    } else {
+      this->_report_failure(message, detail);
+    }
+  }
+
+  /**
+   * @brief Checks that the relative norm error ||actual - expected|| /
+   * ||expected|| of two arrays is within a tolerance.
+   *
+   * For large matrices an element-wise tolerance is often the wrong
+   * criterion, since the relative error of a single tiny element can
+   * dominate; a norm-based comparison judges the matrix as a whole.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param actual The actual array.
+   * @param expected The expected array with the same shape.
+   * @param relative_tolerance The largest allowed relative norm error.
+   * @param message The message to display in case of failure; the computed
+   * relative error is appended.
+   * @param norm The Frobenius norm (default) or the spectral norm.
+   */
+  template <typename Container>
+  void expect_near_norm(const Container &actual, const Container &expected,
+                        T relative_tolerance, const std::string &message,
+                        MatrixNorm norm = MatrixNorm::Frobenius) {
+    if (!this->_accept_tolerance(relative_tolerance, message)) {
+      return;
+    }
+    relative_tolerance = this->_scaled(relative_tolerance);
+    this->_warn_unrecorded(message);
+    T error = static_cast<T>(0);
+    if (!Comparison::relative_norm_error(make_flat_array(actual),
+                                         make_flat_array(expected), norm,
+                                         error)) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+    } else if (error <= relative_tolerance) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(
+          message, std::string(norm == MatrixNorm::Frobenius ? "Frobenius"
+                                                             : "Spectral") +
+                       " relative error " + format_number(error) +
+                       " exceeds " + format_number(relative_tolerance) + ".");
+    }
+  }
+
+  /**
+   * @brief Checks an accumulated sum with a tolerance derived from its
+   * forward-error bound.
+   *
+   * Instead of a guessed constant, the tolerance is n * epsilon * sum |terms|
+   * (see Comparison::accumulation_tolerance), which any correct evaluation
+   * order of the generated code satisfies. The expected value should be
+   * computed more accurately than in T, e.g. in higher precision or with
+   * compensated summation, so that its own rounding error is negligible.
+   *
+   * @param actual The sum computed by the code under test.
+   * @param expected The accurate value of the sum.
+   * @param n_terms The number of accumulated terms.
+   * @param absolute_sum The sum of the absolute values of the terms.
+   * @param message The message to display in case of failure; the derived
+   * tolerance is appended.
+   */
+  void expect_near_accumulation(T actual, T expected, std::size_t n_terms,
+                                T absolute_sum, const std::string &message) {
+    T tolerance = this->_scaled(
+        Comparison::accumulation_tolerance(n_terms, absolute_sum));
+    ComparisonResult<T> result =
+        Comparison::compare_scalar(actual, expected, tolerance);
+    this->_record_near(make_flat_scalar(actual), make_flat_scalar(expected),
+                       tolerance, tolerance, ShapePolicy::Exact, result,
+                       message);
+    this->_report_result(result, message,
+                         "Error " + format_number(result.error) +
+                             " exceeds the accumulation bound " +
+                             format_number(tolerance) + " for " +
+                             std::to_string(n_terms) + " terms.",
+                         tolerance);
+  }
+
+  /**
+   * @brief Checks a dot product computed by the code under test with a
+   * tolerance derived from its forward-error bound.
+   *
+   * @param actual The dot product computed by the code under test.
+   * @param expected The accurate value of the dot product.
+   * @param x The first operand.
+   * @param y The second operand, of the same length.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near_accumulation(T actual, T expected, const std::vector<T> &x,
+                                const std::vector<T> &y,
+                                const std::string &message) {
+    if (x.size() != y.size()) {
+      this->_report_failure(message, "Operand size mismatch.",
+                            FailureCategory::Structural);
+      return;
+    }
+    this->expect_near_accumulation(actual, expected, x.size(),
+                                   Comparison::dot_absolute_sum(x, y),
+                                   message);
+  }
+
+  /**
+   * @brief Checks that the output of generated code matches a declared
+   * integration method.
+   *
+   * The reference output is synthesized with Reference::integrate from the
+   * same input. If the check fails and the output matches another method
+   * instead, the failure names that method, which catches a code generator
+   * that silently used a different discretization.
+   *
+   * @param actual The output of the generated code, one sample per input
+   * sample; sample 0 is the initial state.
+   * @param input The input samples.
+   * @param dt The sample time in seconds.
+   * @param method The declared integration method.
+   * @param tolerance The maximum allowed difference per sample.
+   * @param message The message to display in case of failure.
+   * @param initial_state The initial state.
+   * @param derivative The right-hand side x' = f(x, u) of the state equation;
+   * a pure integrator x' = u by default.
+   */
+  void expect_matches_integration(
+      const std::vector<T> &actual, const std::vector<T> &input, T dt,
+      Reference::IntegrationMethod method, T tolerance,
+      const std::string &message, T initial_state = static_cast<T>(0),
+      const std::function<T(T, T)> &derivative = std::function<T(T, T)>()) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    std::function<T(T, T)> f = derivative;
+    if (!f) {
+      f = [](T, T u) { return u; };
+    }
+
+    FlatArray<T> flat_actual = make_flat_array(actual);
+    FlatArray<T> flat_reference = make_flat_array(
+        Reference::integrate(input, dt, method, initial_state, f));
+    ComparisonResult<T> result =
+        Comparison::compare_arrays(flat_actual, flat_reference, tolerance);
+    this->_record_near(flat_actual, flat_reference, tolerance, tolerance,
+                       ShapePolicy::Exact, result, message);
+    if (result.passed()) {
+      this->_report_result(result, message, "", tolerance);
+      return;
+    }
+
+    std::string detail = result.describe();
+    if (result.kind == MismatchKind::ElementMismatch) {
+      detail += " Sample " + std::to_string(result.index) + " is " +
+                format_number(result.actual_value) + ", " +
+                Reference::integration_method_name(method) + " gives " +
+                format_number(result.expected_value) + ".";
+
+      const Reference::IntegrationMethod methods[] = {
+          Reference::IntegrationMethod::ForwardEuler,
+          Reference::IntegrationMethod::BackwardEuler,
+          Reference::IntegrationMethod::Tustin,
+          Reference::IntegrationMethod::RK4};
+      for (std::size_t i = 0; i < 4; i++) {
+        if (methods[i] != method &&
+            Comparison::compare_arrays(
+                flat_actual,
+                make_flat_array(Reference::integrate(input, dt, methods[i],
+                                                     initial_state, f)),
+                tolerance)
+                .passed()) {
+          detail += " The output matches " +
+                    Reference::integration_method_name(methods[i]) +
+                    " instead.";
+          break;
+        }
+      }
+    }
+    this->_report_result(result, message, detail, tolerance);
+  }
+
+  /**
+   * @brief Checks that a signal settles and that its settled value is near
+   * an expected value.
+   *
+   * The settling point is detected with Comparison::find_steady_state, using
+   * `tolerance` as the largest standard deviation of a settled window. A
+   * signal that never settles fails with its own message, distinct from a
+   * settled value outside tolerance.
+   *
+   * @param signal The samples of the signal.
+   * @param expected_value The expected settled value.
+   * @param tolerance The maximum allowed difference between the settled and
+   * the expected value.
+   * @param settle_window The number of samples per window; at least two.
+   * @param message The message to display in case of failure.
+   */
+  void expect_steady_state_near(const std::vector<T> &signal,
+                                T expected_value, T tolerance,
+                                std::size_t settle_window,
+                                const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    if (settle_window < 2 || signal.size() < settle_window) {
+      this->_report_failure(message,
+                            "The signal is shorter than the settle window "
+                            "or the window has fewer than two samples.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    SteadyState<T> steady =
+        Comparison::find_steady_state(signal, settle_window, tolerance);
+    if (!steady.settled) {
+      this->_report_failure(
+          message, "The signal never settles: the standard deviation of its "
+                   "last window is " +
+                       format_number(steady.window_sigma) + " (allowed " +
+                       format_number(tolerance) + ").");
+      return;
+    }
+
+    T error = std::abs(steady.value - expected_value);
+    std::string detail = "Settled from sample " +
+                         std::to_string(steady.index) + " at " +
+                         format_number(steady.value) + ", expected " +
+                         format_number(expected_value) + " (error " +
+                         format_number(error) + ").";
+    if (error <= tolerance) {
+      this->_report_pass(message, detail);
+    } else {
+      this->_report_failure(message, detail);
+    }
+  }
+
+  /**
+   * @brief Checks that a supposedly settled signal does not keep oscillating
+   * in a limit cycle.
+   *
+   * The second half of the signal is examined with
+   * Comparison::find_limit_cycle. Oscillations with an amplitude below
+   * `min_amplitude` are tolerated.
+   *
+   * @param signal The samples of the signal.
+   * @param min_amplitude The smallest amplitude of an offending oscillation.
+   * @param message The message to display in case of failure.
+   */
+  void expect_no_limit_cycle(const std::vector<T> &signal, T min_amplitude,
+                             const std::string &message) {
+    if (!this->_accept_tolerance(min_amplitude, message)) {
+      return;
+    }
+    min_amplitude = this->_scaled(min_amplitude);
+    LimitCycle<T> cycle = Comparison::find_limit_cycle(signal, min_amplitude);
+    if (cycle.detected) {
+      this->_report_failure(
+          message, "Limit cycle with a period of " +
+                       std::to_string(cycle.period) + " samples and an "
+                       "amplitude of " +
+                       format_number(cycle.amplitude) + " (autocorrelation " +
+                       format_number(cycle.correlation) + ").");
+      return;
+    }
+    this->_report_pass(message);
+  }
+
+  /**
+   * @brief Checks that an input/output pair is consistent with a declared
+   * saturation block.
+   *
+   * Unlike a tolerance check against a reference run, the output is judged
+   * sample by sample against the block semantics, and the first violating
+   * sample is reported.
+   *
+   * @param input The input samples of the block.
+   * @param output The output samples of the block.
+   * @param lower The declared lower limit.
+   * @param upper The declared upper limit.
+   * @param tolerance The maximum allowed difference per sample.
+   * @param message The message to display in case of failure.
+   */
+  void expect_saturation_consistent(const std::vector<T> &input,
+                                    const std::vector<T> &output, T lower,
+                                    T upper, T tolerance,
+                                    const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    if (input.size() != output.size()) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    T expected = static_cast<T>(0);
+    std::size_t k = Comparison::find_saturation_violation(
+        input, output, lower, upper, tolerance, expected);
+    this->_report_block_violation(input, output, k, expected,
+                                  "saturation to [" + format_number(lower) +
+                                      ", " + format_number(upper) + "]",
+                                  message);
+  }
+
+  /**
+   * @brief Checks that an input/output pair is consistent with a declared
+   * rate limiter block.
+   *
+   * See Comparison::find_rate_limiter_violation for how the samples are
+   * judged.
+   *
+   * @param input The input samples of the block.
+   * @param output The output samples of the block.
+   * @param rising_rate The declared rising slew rate per second.
+   * @param falling_rate The declared falling slew rate per second; negative,
+   * as in Simulink.
+   * @param dt The sample time in seconds.
+   * @param initial_output The output before the first sample.
+   * @param tolerance The maximum allowed difference per sample.
+   * @param message The message to display in case of failure.
+   */
+  void expect_rate_limiter_consistent(const std::vector<T> &input,
+                                      const std::vector<T> &output,
+                                      T rising_rate, T falling_rate, T dt,
+                                      T initial_output, T tolerance,
+                                      const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    if (input.size() != output.size()) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    T expected = static_cast<T>(0);
+    std::size_t k = Comparison::find_rate_limiter_violation(
+        input, output, rising_rate, falling_rate, dt, initial_output,
+        tolerance, expected);
+    this->_report_block_violation(input, output, k, expected,
+                                  "rate limits [" +
+                                      format_number(falling_rate) + ", " +
+                                      format_number(rising_rate) + "] per s",
+                                  message);
+  }
+
+  /**
+   * @brief Checks from the logged error and command of a PI or PID
+   * controller that its integrator did not wind up during actuator
+   * saturation.
+   *
+   * Windup bugs do not show in steady-state checks, so the log is searched
+   * for saturation episodes instead; see Comparison::find_worst_windup for
+   * how the windup of an episode is measured. The worst event is reported.
+   *
+   * @param error The logged control error.
+   * @param command The logged actuator command.
+   * @param lower The lower actuator limit.
+   * @param upper The upper actuator limit.
+   * @param integral_gain The integral gain of the controller.
+   * @param dt The sample time in seconds.
+   * @param max_windup The largest allowed windup, in the unit of the command;
+   * must be non-negative.
+   * @param message The message to display in case of failure.
+   */
+  void expect_no_integrator_windup(const std::vector<T> &error,
+                                   const std::vector<T> &command, T lower,
+                                   T upper, T integral_gain, T dt,
+                                   T max_windup, const std::string &message) {
+    if (!this->_accept_tolerance(max_windup, message)) {
+      return;
+    }
+    if (error.size() != command.size()) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    WindupEvent<T> worst = Comparison::find_worst_windup(
+        error, command, lower, upper, integral_gain, dt);
+    if (!worst.found || worst.windup <= max_windup) {
+      this->_report_pass(message);
+      return;
+    }
+    this->_report_failure(
+        message, "Integrator windup of " + format_number(worst.windup) +
+                     " during the saturation from sample " +
+                     std::to_string(worst.start) + " to " +
+                     std::to_string(worst.end) + " (command " +
+                     format_number(command[worst.start]) +
+                     "). Allowed: " + format_number(max_windup) + ".");
+  }
+
+  /**
+   * @brief Checks that a sequence never decreases by more than a tolerance
+   * from one element to the next.
+   *
+   * @param values The sequence to check, e.g. the iterates of an optimizer.
+   * @param tolerance The largest allowed decrease between two consecutive
+   * elements.
+   * @param message The message to display in case of failure; the first
+   * violating index and value are appended.
+   */
+  void expect_monotonic_increasing(const std::vector<T> &values, T tolerance,
+                                   const std::string &message) {
+    this->_check_monotonic(values, tolerance, true, message);
+  }
+
+  /**
+   * @brief Checks that a sequence never increases by more than a tolerance
+   * from one element to the next.
+   *
+   * @param values The sequence to check, e.g. the cost of an optimizer.
+   * @param tolerance The largest allowed increase between two consecutive
+   * elements.
+   * @param message The message to display in case of failure; the first
+   * violating index and value are appended.
+   */
+  void expect_monotonic_decreasing(const std::vector<T> &values, T tolerance,
+                                   const std::string &message) {
+    this->_check_monotonic(values, tolerance, false, message);
+  }
+
+  /**
+   * @brief Checks that every element of an array lies in the closed interval
+   * [low, high].
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param values The array to check.
+   * @param low The smallest allowed value.
+   * @param high The largest allowed value.
+   * @param message The message to display in case of failure; the first
+   * violating index and value are appended.
+   */
+  template <typename Container>
+  void expect_all_in_range(const Container &values, T low, T high,
+                           const std::string &message) {
+    FlatArray<T> flat = make_flat_array(values);
+    std::size_t index = Comparison::find_out_of_range(flat, low, high);
+    if (index == flat.data.size()) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(message, "Element " + std::to_string(index) +
+                                         " is " +
+                                         format_number(flat.data[index]) +
+                                         ", outside [" + format_number(low) +
+                                         ", " + format_number(high) + "].");
+    }
+  }
+
+  /**
+   * @brief Checks an aggregate of every row or column of a matrix against
+   * expected values.
+   *
+   * This is useful for large generated outputs where a full golden array is
+   * impractical but sums, means or other aggregates are known.
+   *
+   * @tparam Container Any two-dimensional container accepted by
+   * make_flat_array.
+   * @param actual The matrix to reduce.
+   * @param axis Whether to reduce each row or each column.
+   * @param reducer Maps the elements of one row or column to a single value,
+   * e.g. Comparison::sum_of<T>.
+   * @param expected One expected value per row or column.
+   * @param tolerance The maximum allowed difference between corresponding
+   * reduced values.
+   * @param message The message to display in case of failure.
+   */
+  template <typename Container>
+  void
+  expect_reduced_near(const Container &actual, ReductionAxis axis,
+                      const std::function<T(const std::vector<T> &)> &reducer,
+                      const std::vector<T> &expected, T tolerance,
+                      const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    FlatArray<T> reduced;
+    if (!Comparison::reduce_axis(make_flat_array(actual), axis, reducer,
+                                 reduced)) {
+      this->_report_failure(message, "Not a two-dimensional array.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    FlatArray<T> flat_expected = make_flat_array(expected);
+    ComparisonResult<T> result =
+        Comparison::compare_arrays(reduced, flat_expected, tolerance);
+    this->_record_near(reduced, flat_expected, tolerance, tolerance,
+                       ShapePolicy::Exact, result, message);
+    this->_report_result(
+        result, message,
+        this->_describe_mismatch(reduced, flat_expected, tolerance, tolerance,
+                                 result),
+        tolerance);
+  }
+
+  /**
+   * @brief Checks the sum of every row of a matrix.
+   *
+   * @param expected One expected sum per row.
+   */
+  template <typename Container>
+  void expect_row_sums_near(const Container &actual,
+                            const std::vector<T> &expected, T tolerance,
+                            const std::string &message) {
+    this->expect_reduced_near(actual, ReductionAxis::Rows,
+                              &Comparison::sum_of<T>, expected, tolerance,
+                              message);
+  }
+
+  /**
+   * @brief Checks the sum of every column of a matrix.
+   *
+   * @param expected One expected sum per column.
+   */
+  template <typename Container>
+  void expect_col_sums_near(const Container &actual,
+                            const std::vector<T> &expected, T tolerance,
+                            const std::string &message) {
+    this->expect_reduced_near(actual, ReductionAxis::Columns,
+                              &Comparison::sum_of<T>, expected, tolerance,
+                              message);
+  }
+
+  /**
+   * @brief Checks the mean of every row of a matrix.
+   *
+   * @param expected One expected mean per row.
+   */
+  template <typename Container>
+  void expect_row_means_near(const Container &actual,
+                             const std::vector<T> &expected, T tolerance,
+                             const std::string &message) {
+    this->expect_reduced_near(actual, ReductionAxis::Rows,
+                              &Comparison::mean_of<T>, expected, tolerance,
+                              message);
+  }
+
+  /**
+   * @brief Checks the mean of every column of a matrix.
+   *
+   * @param expected One expected mean per column.
+   */
+  template <typename Container>
+  void expect_col_means_near(const Container &actual,
+                             const std::vector<T> &expected, T tolerance,
+                             const std::string &message) {
+    this->expect_reduced_near(actual, ReductionAxis::Columns,
+                              &Comparison::mean_of<T>, expected, tolerance,
+                              message);
+  }
+
+  /**
+   * @brief Checks that a square matrix is symmetric within a tolerance.
+   *
+   * @tparam Container Any two-dimensional container accepted by
+   * make_flat_array.
+   * @param matrix The matrix to check.
+   * @param tolerance The largest allowed |a_ij - a_ji|.
+   * @param message The message to display in case of failure; the entry with
+   * the largest asymmetry is appended.
+   */
+  template <typename Container>
+  void expect_symmetric(const Container &matrix, T tolerance,
+                        const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    PropertyResult<T> result =
+        Comparison::check_symmetric(make_flat_array(matrix), tolerance);
+    if (!result.satisfied) {
+      this->_report_failure(message, this->_describe_symmetry(result));
+    } else {
+      this->_report_pass(message);
+    }
+  }
+
+  /**
+   * @brief Checks that a square matrix is symmetric and positive definite.
+   *
+   * @tparam Container Any two-dimensional container accepted by
+   * make_flat_array.
+   * @param matrix The matrix to check, e.g. a covariance matrix.
+   * @param tolerance The symmetry tolerance and the value every eigenvalue
+   * must exceed.
+   * @param message The message to display in case of failure; the smallest
+   * eigenvalue is appended.
+   */
+  template <typename Container>
+  void expect_positive_definite(const Container &matrix, T tolerance,
+                                const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    FlatArray<T> flat = make_flat_array(matrix);
+    PropertyResult<T> symmetry = Comparison::check_symmetric(flat, tolerance);
+    if (!symmetry.satisfied) {
+      this->_report_failure(message, this->_describe_symmetry(symmetry));
+      return;
+    }
+
+    PropertyResult<T> result =
+        Comparison::check_positive_definite(flat, tolerance);
+    if (!result.satisfied) {
+      this->_report_failure(
+          message, "Not positive definite. Eigenvalue " +
+                       std::to_string(result.row) + " is " +
+                       format_number(result.value) + ", not above " +
+                       format_number(result.bound) + ".");
+    } else {
+      this->_report_pass(message);
+    }
+  }
+
+  /**
+   * @brief Checks that a covariance matrix is symmetric, positive
+   * semi-definite and bounded.
+   *
+   * All three conditions are checked in one call, and every violated
+   * condition is reported together with the amount by which it is violated.
+   * The eigenvalues are computed from the symmetric part of the matrix.
+   *
+   * @tparam Container Any two-dimensional container accepted by
+   * make_flat_array.
+   * @param covariance The covariance matrix to check, e.g. a Kalman filter's
+   * P.
+   * @param symmetry_tolerance The largest allowed |P_ij - P_ji|.
+   * @param min_eigenvalue The smallest allowed eigenvalue, typically 0 or a
+   * small negative value allowing for rounding.
+   * @param max_trace The largest allowed trace.
+   * @param message The message to display in case of failure.
+   */
+  template <typename Container>
+  void expect_valid_covariance(const Container &covariance,
+                               T symmetry_tolerance, T min_eigenvalue,
+                               T max_trace, const std::string &message) {
+    if (!this->_accept_tolerance(symmetry_tolerance, message)) {
+      return;
+    }
+    symmetry_tolerance = this->_scaled(symmetry_tolerance);
+    FlatArray<T> flat = make_flat_array(covariance);
+    PropertyResult<T> symmetry =
+        Comparison::check_symmetric(flat, symmetry_tolerance);
+    if (!symmetry.square) {
+      this->_report_failure(message, "Not a square matrix.",
+                            FailureCategory::Structural);
+      return;
+    }
+
+    std::string detail;
+    if (!symmetry.satisfied) {
+      detail += this->_describe_symmetry(symmetry);
+    }
+
+    std::vector<T> eigenvalues = Comparison::symmetric_part_eigenvalues(flat);
+    if (!eigenvalues.empty() && !(eigenvalues[0] >= min_eigenvalue)) {
+      detail += std::string(detail.empty() ? "" : " ") +
+                "Smallest eigenvalue " + format_number(eigenvalues[0]) +
+                " is below " + format_number(min_eigenvalue) + " by " +
+                format_number(min_eigenvalue - eigenvalues[0]) + ".";
+    }
+
+    T trace = static_cast<T>(0);
+    for (std::size_t i = 0; i < flat.shape[0]; i++) {
+      trace += flat.data[i * flat.shape[0] + i];
+    }
+    if (!(trace <= max_trace)) {
+      detail += std::string(detail.empty() ? "" : " ") + "Trace " +
+                format_number(trace) + " exceeds " + format_number(max_trace) +
+                " by " + format_number(trace - max_trace) + ".";
+    }
+
+    if (detail.empty()) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(message, detail);
+    }
+  }
+
+  /**
+   * @brief Checks that a square matrix Q is orthogonal, i.e. that Q^T Q is
+   * the identity within a tolerance.
+   *
+   * @tparam Container Any two-dimensional container accepted by
+   * make_flat_array.
+   * @param matrix The matrix to check, e.g. a rotation matrix.
+   * @param tolerance The largest allowed deviation of Q^T Q from the identity.
+   * @param message The message to display in case of failure; the entry of
+   * Q^T Q with the largest deviation is appended.
+   */
+  template <typename Container>
+  void expect_orthogonal(const Container &matrix, T tolerance,
+                         const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    PropertyResult<T> result =
+        Comparison::check_orthogonal(make_flat_array(matrix), tolerance);
+    if (!result.square) {
+      this->_report_failure(message, "Not a square matrix.",
+                            FailureCategory::Structural);
+    } else if (!result.satisfied) {
+      this->_report_failure(message,
+                            "Not orthogonal. Entry (" +
+                                std::to_string(result.row) + ", " +
+                                std::to_string(result.column) +
+                                ") of Q^T Q deviates from the identity by " +
+                                format_number(result.value) + ".");
+    } else {
+      this->_report_pass(message);
+    }
+  }
+
+  /**
+   * @brief Checks that a square matrix is row-wise diagonally dominant.
+   *
+   * @tparam Container Any two-dimensional container accepted by
+   * make_flat_array.
+   * @param matrix The matrix to check.
+   * @param tolerance The amount by which the off-diagonal sum of a row may
+   * exceed the magnitude of its diagonal entry.
+   * @param message The message to display in case of failure; the first
+   * violating row is appended.
+   */
+  template <typename Container>
+  void expect_diagonally_dominant(const Container &matrix, T tolerance,
+                                  const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    PropertyResult<T> result = Comparison::check_diagonally_dominant(
+        make_flat_array(matrix), tolerance);
+    if (!result.square) {
+      this->_report_failure(message, "Not a square matrix.",
+                            FailureCategory::Structural);
+    } else if (!result.satisfied) {
+      this->_report_failure(
+          message, "Not diagonally dominant. Row " +
+                       std::to_string(result.row) + " has |diagonal| " +
+                       format_number(result.value) +
+                       " below off-diagonal sum " +
+                       format_number(result.bound) + ".");
+    } else {
+      this->_report_pass(message);
+    }
+  }
+
+  /**
+   * @brief Checks that two sets of real values agree within a tolerance,
+   * regardless of their order.
+   *
+   * This is meant for results whose order is implementation-defined, such as
+   * eigenvalues. Values are paired by a tolerance-aware matching and the
+   * values left without a partner are reported.
+   *
+   * @param actual The actual values, in any order.
+   * @param expected The expected values, in any order.
+   * @param tolerance The largest difference between two paired values.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near_unordered(const std::vector<T> &actual,
+                             const std::vector<T> &expected, T tolerance,
+                             const std::string &message) {
+    std::vector<std::complex<T>> complex_actual(actual.begin(), actual.end());
+    std::vector<std::complex<T>> complex_expected(expected.begin(),
+                                                  expected.end());
+    this->expect_near_unordered(complex_actual, complex_expected, tolerance,
+                                message);
+  }
+
+  /**
+   * @brief Checks that two sets of complex values agree within a tolerance,
+   * regardless of their order.
+   *
+   * @param actual The actual values, in any order.
+   * @param expected The expected values, in any order.
+   * @param tolerance The largest distance in the complex plane between two
+   * paired values.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near_unordered(const std::vector<std::complex<T>> &actual,
+                             const std::vector<std::complex<T>> &expected,
+                             T tolerance, const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    this->_warn_unrecorded(message);
+    UnorderedMatchResult<T> result =
+        Comparison::match_unordered(actual, expected, tolerance);
+    if (result.passed()) {
+      this->_report_pass(message);
+      return;
+    }
+
+    std::string detail = result.size_matches ? "Unmatched values."
+                                             : "Size mismatch.";
+    detail += " Actual: " + this->_describe_values(actual,
+                                                    result.unmatched_actual);
+    detail += "; expected: " +
+              this->_describe_values(expected, result.unmatched_expected) +
+              ".";
+    this->_report_failure(message, detail);
+  }
+
+  /**
+   * @brief Checks that two angles agree within a tolerance on the circle.
+   *
+   * Unlike expect_near, angles that differ by whole turns are considered
+   * equal, so -pi and pi match.
+   *
+   * @param actual The actual angle.
+   * @param expected The expected angle.
+   * @param tolerance The maximum allowed angular difference.
+   * @param message The message to display in case of failure.
+   * @param unit The unit of the angles and of the tolerance.
+   */
+  void expect_near_angle_scalar(T actual, T expected, T tolerance,
+                                const std::string &message,
+                                AngleUnit unit = AngleUnit::Radians) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    FlatArray<T> flat_actual = make_flat_scalar(actual);
+    FlatArray<T> flat_expected = make_flat_scalar(expected);
+    ComparisonResult<T> result = Comparison::compare_angles(
+        flat_actual, flat_expected, tolerance, unit);
+    this->_record_angle(flat_actual, flat_expected, tolerance, unit, result,
+                        message);
+    this->_report_result(result, message, "", tolerance);
+  }
+
+  /**
+   * @brief Checks that two arrays of angles agree element-wise within a
+   * tolerance on the circle.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param actual The actual angles.
+   * @param expected The expected angles with the same shape.
+   * @param tolerance The maximum allowed angular difference per element.
+   * @param message The message to display in case of failure.
+   * @param unit The unit of the angles and of the tolerance.
+   */
+  template <typename Container>
+  void expect_near_angle(const Container &actual, const Container &expected,
+                         T tolerance, const std::string &message,
+                         AngleUnit unit = AngleUnit::Radians) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    FlatArray<T> flat_actual = make_flat_array(actual);
+    FlatArray<T> flat_expected = make_flat_array(expected);
+    if (!this->_accept_degenerate(flat_actual, flat_expected, message)) {
+      return;
+    }
+
+    ComparisonResult<T> result = Comparison::compare_angles(
+        flat_actual, flat_expected, tolerance, unit);
+    this->_record_angle(flat_actual, flat_expected, tolerance, unit, result,
+                        message);
+    this->_report_result(result, message, result.describe(), tolerance);
+  }
+
+  /**
+   * @brief Checks that two quaternions describe the same rotation within an
+   * angular tolerance.
+   *
+   * Since q and -q describe the same rotation, the sign of the quaternions is
+   * ignored, and both are normalized before the rotation angle between them
+   * is computed.
+   *
+   * @tparam Container Any container of 4 elements accepted by
+   * make_flat_array.
+   * @param actual The actual quaternion.
+   * @param expected The expected quaternion in the same component order.
+   * @param tolerance The largest allowed rotation angle between both, in
+   * radians.
+   * @param message The message to display in case of failure; the rotation
+   * angle is appended.
+   */
+  template <typename Container>
+  void expect_near_quaternion(const Container &actual,
+                              const Container &expected, T tolerance,
+                              const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    this->_warn_unrecorded(message);
+    T angle = static_cast<T>(0);
+    if (!Comparison::quaternion_angle(make_flat_array(actual),
+                                      make_flat_array(expected), angle)) {
+      this->_report_failure(message, "Not a quaternion.",
+                            FailureCategory::Structural);
+    } else if (angle <= tolerance) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(message, "Rotation differs by " +
+                                         format_number(angle) + " rad.");
+    }
+  }
+
+  /**
+   * @brief Checks that two rotation matrices agree within an angular
+   * tolerance.
+   *
+   * The matrices are compared by the geodesic angle of their relative
+   * rotation rather than element by element.
+   *
+   * @tparam Container Any 3 x 3 container accepted by make_flat_array.
+   * @param actual The actual rotation matrix.
+   * @param expected The expected rotation matrix.
+   * @param tolerance The largest allowed relative rotation angle, in radians.
+   * @param message The message to display in case of failure; the relative
+   * rotation angle is appended.
+   */
+  template <typename Container>
+  void expect_near_rotation(const Container &actual, const Container &expected,
+                            T tolerance, const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    this->_warn_unrecorded(message);
+    T angle = static_cast<T>(0);
+    if (!Comparison::rotation_angle(make_flat_array(actual),
+                                    make_flat_array(expected), angle)) {
+      this->_report_failure(message, "Not a 3 x 3 matrix.",
+                            FailureCategory::Structural);
+    } else if (angle <= tolerance) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(message, "Rotation differs by " +
+                                         format_number(angle) + " rad.");
+    }
+  }
+
+  /**
+   * @brief Checks two sequences of phasors against independent magnitude and
+   * phase tolerances.
+   *
+   * This matches requirements that bound the amplitude and the angle of a
+   * phasor separately, instead of using a single tolerance on the complex
+   * difference. The phase is not checked where the expected magnitude is
+   * within the magnitude tolerance of zero.
+   *
+   * @param actual The actual phasors.
+   * @param expected The expected phasors.
+   * @param magnitude_tolerance The largest allowed magnitude difference.
+   * @param phase_tolerance_degrees The largest allowed phase difference in
+   * degrees.
+   * @param message The message to display in case of failure.
+   */
+  void expect_near_phasor(const std::vector<std::complex<T>> &actual,
+                          const std::vector<std::complex<T>> &expected,
+                          T magnitude_tolerance, T phase_tolerance_degrees,
+                          const std::string &message) {
+    if (!this->_accept_tolerance(magnitude_tolerance, message) ||
+        !this->_accept_tolerance(phase_tolerance_degrees, message)) {
+      return;
+    }
+    magnitude_tolerance = this->_scaled(magnitude_tolerance);
+    phase_tolerance_degrees = this->_scaled(phase_tolerance_degrees);
+    this->_warn_unrecorded(message);
+    PhasorResult<T> result = Comparison::compare_phasors(
+        actual, expected, magnitude_tolerance, phase_tolerance_degrees);
+    if (result.kind == MismatchKind::SizeMismatch) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+    } else if (!result.passed()) {
+      this->_report_failure(
+          message, std::string(result.phase_failed ? "Phase" : "Magnitude") +
+                       " mismatch at phasor " + std::to_string(result.index) +
+                       ". Magnitude error " +
+                       format_number(result.magnitude_error) +
+                       ", phase error " + format_number(result.phase_error) +
+                       " deg.");
+    } else {
+      this->_report_pass(message);
+    }
+  }
+
+  /**
+   * @brief Checks a single phasor against independent magnitude and phase
+   * tolerances.
+   */
+  void expect_near_phasor(const std::complex<T> &actual,
+                          const std::complex<T> &expected,
+                          T magnitude_tolerance, T phase_tolerance_degrees,
+                          const std::string &message) {
+    this->expect_near_phasor(std::vector<std::complex<T>>(1, actual),
+                             std::vector<std::complex<T>>(1, expected),
+                             magnitude_tolerance, phase_tolerance_degrees,
+                             message);
+  }
+
+  /**
+   * @brief Checks that all poles of a continuous-time system are stable, i.e.
+   * that every real part is below -margin.
+   *
+   * @param poles The real poles or eigenvalues to check.
+   * @param margin The required distance from the imaginary axis.
+   * @param message The message to display in case of failure; the violating
+   * poles are appended.
+   */
+  void expect_stable_continuous(const std::vector<T> &poles, T margin,
+                                const std::string &message) {
+    this->_check_stability(
+        std::vector<std::complex<T>>(poles.begin(), poles.end()), margin,
+        StabilityDomain::Continuous, message);
+  }
+
+  /**
+   * @brief Checks that all complex poles of a continuous-time system are
+   * stable, i.e. that every real part is below -margin.
+   */
+  void expect_stable_continuous(const std::vector<std::complex<T>> &poles,
+                                T margin, const std::string &message) {
+    this->_check_stability(poles, margin, StabilityDomain::Continuous,
+                           message);
+  }
+
+  /**
+   * @brief Checks that all poles of a discrete-time system are stable, i.e.
+   * that every magnitude is below 1 - margin.
+   *
+   * @param poles The real poles or eigenvalues to check.
+   * @param margin The required distance from the unit circle.
+   * @param message The message to display in case of failure; the violating
+   * poles are appended.
+   */
+  void expect_stable_discrete(const std::vector<T> &poles, T margin,
+                              const std::string &message) {
+    this->_check_stability(
+        std::vector<std::complex<T>>(poles.begin(), poles.end()), margin,
+        StabilityDomain::Discrete, message);
+  }
+
+  /**
+   * @brief Checks that all complex poles of a discrete-time system are
+   * stable, i.e. that every magnitude is below 1 - margin.
+   */
+  void expect_stable_discrete(const std::vector<std::complex<T>> &poles,
+                              T margin, const std::string &message) {
+    this->_check_stability(poles, margin, StabilityDomain::Discrete, message);
+  }
+
+  /**
+   * @brief Checks that the tolerance-quantized fingerprint of an array matches
+   * the expected fingerprint.
+   *
+   * This allows large intermediate arrays to be regression-checked by storing
+   * only a 64-bit hash instead of the full golden data. On mismatch, the
+   * actual fingerprint is printed so that it can be copied into the test.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param actual The array to fingerprint.
+   * @param expected_fingerprint The fingerprint recorded from a trusted run.
+   * @param quantum The quantization step used for hashing.
+   * @param message The message to display if the check fails.
+   */
+  template <typename Container>
+  void expect_fingerprint_matches(const Container &actual,
+                                  std::uint64_t expected_fingerprint,
+                                  T quantum, const std::string &message) {
+    std::uint64_t actual_fingerprint =
+        compute_fingerprint(make_flat_array(actual), quantum);
+
+    if (actual_fingerprint == expected_fingerprint) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(message,
+                            "Fingerprint mismatch. Actual: " +
+                                fingerprint_to_string(actual_fingerprint));
+    }
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Checks the fingerprint of an array and falls back to a full
+   * comparison against a golden file when the fingerprints differ.
+   *
+   * Because values within `quantum` of each other may still round to
+   * different bins, a fingerprint mismatch is confirmed by comparing every
+   * element against the golden file (written with save_flat_array) using
+   * `quantum` as the tolerance. The check only fails if the golden file is
+   * missing or the full comparison also fails.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param actual The array to fingerprint.
+   * @param expected_fingerprint The fingerprint recorded from a trusted run.
+   * @param quantum The quantization step and fallback comparison tolerance.
+   * @param golden_path Path of the golden file used for the full comparison.
+   * @param message The message to display if the check fails.
+   */
+  template <typename Container>
+  void expect_fingerprint_matches(const Container &actual,
+                                  std::uint64_t expected_fingerprint,
+                                  T quantum, const std::string &golden_path,
+                                  const std::string &message) {
+    FlatArray<T> flat_actual = make_flat_array(actual);
+    std::uint64_t actual_fingerprint =
+        compute_fingerprint(flat_actual, quantum);
+
+    if (actual_fingerprint == expected_fingerprint) {
+      this->_report_pass(message);
+      return;
+    }
+
+    FlatArray<T> golden;
+    if (!load_flat_array(golden_path, golden)) {
+      this->_report_failure(message,
+                            "Fingerprint mismatch. Actual: " +
+                                fingerprint_to_string(actual_fingerprint) +
+                                " (golden file not available: " +
+                                golden_path + ")",
+                            FailureCategory::Infrastructure);
+      return;
+    }
+
+    ComparisonResult<T> result =
+        Comparison::compare_arrays(flat_actual, golden, quantum);
+    this->_record_near(flat_actual, golden, quantum, quantum,
+                       ShapePolicy::Exact, result, message);
+    if (!result.passed()) {
+      this->_report_failure(message, "Fingerprint mismatch. " +
+                                         result.describe() +
+                                         " (against golden file)");
+    } else {
+      this->_report_pass(message);
+    }
+  }
+#endif
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Compares an array against a stored snapshot, recording the
+   * snapshot if it does not exist yet.
+   *
+   * Snapshots are stored with save_flat_array as `<directory>/<name>.txt`,
+   * where characters other than letters, digits, '-' and '_' in the name are
+   * replaced by '_'. If the snapshot file is missing, or the environment
+   * variable MCAP_UPDATE_SNAPSHOTS is set to 1, the actual values are written
+   * and the check passes. Otherwise the actual values are compared with the
+   * snapshot using the given tolerance.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param name The snapshot name, also used as the failure message.
+   * @param actual The container of actual values to check.
+   * @param tolerance The maximum allowed difference between corresponding
+   * elements.
+   */
+  template <typename Container>
+  void expect_matches_snapshot(const std::string &name,
+                               const Container &actual, T tolerance) {
+    std::string path =
+        this->_snapshot_directory + "/" + this->_file_name(name) + ".txt";
+
+    FlatArray<T> flat_actual = make_flat_array(actual);
+    FlatArray<T> snapshot;
+
+    const char *update = this->_use_environment
+                             ? std::getenv("MCAP_UPDATE_SNAPSHOTS")
+                             : nullptr;
+    bool update_requested = (update != nullptr && std::string(update) == "1");
+
+    if (update_requested || !load_flat_array(path, snapshot)) {
+      if (!create_directories(this->_snapshot_directory) ||
+          !save_flat_array(path, flat_actual)) {
+        this->_report_failure(name, "Cannot write snapshot " + path + ".",
+                              FailureCategory::Infrastructure);
+      } else {
+        if (this->_verbosity != Verbosity::Quiet) {
+          this->_emit("SNAPSHOT: " + name + " recorded to " + path +
+                      ".\n\n");
+        }
+        this->_report_pass(name);
+      }
+      return;
+    }
+
+    this->expect_near(flat_actual, snapshot, tolerance, name);
+  }
+#endif
+
+  /**
+   * @brief Benchmarks a function and checks that it meets a timing budget.
+   *
+   * The function is called `warmup_iterations` times without measuring, then
+   * `iterations` times, each call timed with std::chrono::steady_clock. The
+   * statistics are stored in the detail of the assertion record, also when
+   * the check passes. If a baseline directory is set (see
+   * set_runtime_baseline_directory), the mean is additionally compared with
+   * the stored baseline of the same message.
+   *
+   * @param iterations The number of measured calls.
+   * @param max_mean The largest allowed mean duration in seconds.
+   * @param max_p99 The largest allowed 99th percentile duration in seconds.
+   * @param message The message to display in case of failure; it also names
+   * the baseline.
+   * @param function The code to benchmark, e.g. one step of a generated
+   * controller.
+   * @param warmup_iterations The number of calls made before measuring.
+   */
+  void expect_runtime_below(std::size_t iterations, double max_mean,
+                            double max_p99, const std::string &message,
+                            const std::function<void()> &function,
+                            std::size_t warmup_iterations = 10) {
+    for (std::size_t i = 0; i < warmup_iterations; i++) {
+      function();
+    }
+
+    std::vector<double> durations;
+    durations.reserve(iterations);
+    for (std::size_t i = 0; i < iterations; i++) {
+      std::chrono::steady_clock::time_point start =
+          std::chrono::steady_clock::now();
+      function();
+      std::chrono::duration<double> duration =
+          std::chrono::steady_clock::now() - start;
+      durations.push_back(duration.count());
+    }
+
+    RuntimeStatistics statistics =
+        Comparison::compute_runtime_statistics(durations);
+    std::string detail =
+        "Mean " + format_number(statistics.mean) + " s, p99 " +
+        format_number(statistics.p99) + " s, min " +
+        format_number(statistics.minimum) + " s, max " +
+        format_number(statistics.maximum) + " s over " +
+        std::to_string(statistics.count) + " iterations.";
+
+    if (statistics.count == 0) {
+      this->_report_failure(message, "No iterations were measured.",
+                            FailureCategory::Timing);
+      return;
+    }
+    if (statistics.mean > max_mean || statistics.p99 > max_p99) {
+      this->_report_failure(message, detail + " Budget: mean " +
+                                         format_number(max_mean) + " s, p99 " +
+                                         format_number(max_p99) + " s.",
+                            FailureCategory::Timing);
+      return;
+    }
+
+#ifndef MCAP_TESTER_NO_STDIO
+    if (!this->_runtime_baseline_directory.empty()) {
+      this->_check_runtime_baseline(statistics, detail, message);
+      return;
+    }
+#endif
+    this->_report_pass(message, detail);
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Enables the comparison of expect_runtime_below against stored
+   * baselines.
+   *
+   * The baseline of a check is stored with save_flat_array as
+   * `<directory>/<message>.txt`, with the file name derived as for snapshots,
+   * and holds the mean and p99 duration. A missing baseline is recorded and
+   * the check passes; so does every check while the environment variable
+   * MCAP_UPDATE_BASELINES is set to 1, which rewrites the baselines.
+   *
+   * @param directory The baseline directory; an empty string disables
+   * baselines, which is the default.
+   * @param max_regression The largest allowed relative increase of the mean
+   * over the baseline, e.g. 0.1 for 10 %. Defaults to 0.1.
+   */
+  void set_runtime_baseline_directory(const std::string &directory,
+                                      double max_regression = 0.1) {
+    this->_runtime_baseline_directory = directory;
+    this->_runtime_max_regression = max_regression;
+  }
+#endif
+
+  /**
+   * @brief Sets the directory in which snapshots are stored.
+   *
+   * @param directory The snapshot directory. Defaults to "snapshots".
+   */
+  void set_snapshot_directory(const std::string &directory) {
+    this->_snapshot_directory = directory;
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Writes the error map |actual - expected| of every failed
+   * element-wise array comparison to an artifacts directory, so the spatial
+   * pattern of the error can be inspected.
+   *
+   * The map of a comparison is written to <directory>/<message>.csv and,
+   * with MCAP_TESTER_ENABLE_ERROR_MAP_PNG, as a heatmap to <message>.png,
+   * with every character of the message other than letters, digits, '-' and
+   * '_' replaced by '_'. The written paths are appended to the failure
+   * detail. See MCAP_tester_error_map.hpp for the layout.
+   *
+   * @param directory The artifacts directory; empty, the default, disables
+   * the maps.
+   */
+  void set_error_map_directory(const std::string &directory) {
+    this->_error_map_directory = directory;
+  }
+#endif
+
+  /**
+   * @brief Sets how array checks treat arrays without any element.
+   *
+   * This covers empty vectors as well as 0xN and Nx0 shapes. Comparing two
+   * empty arrays passes silently by default, which can hide "no data
+   * produced" bugs; DegenerateArrayPolicy::Warn or DegenerateArrayPolicy::Fail
+   * report them explicitly instead.
+   *
+   * @param policy The policy applied when both arrays are empty.
+   */
+  void set_empty_array_policy(DegenerateArrayPolicy policy) {
+    this->_empty_array_policy = policy;
+  }
+
+  /**
+   * @brief Sets how array checks treat arrays holding exactly one element.
+   *
+   * Scalars compared through the scalar overloads are not affected. With
+   * DegenerateArrayPolicy::Warn the comparison still takes place after the
+   * warning is reported; with DegenerateArrayPolicy::Fail it does not.
+   *
+   * @param policy The policy applied when both arrays hold a single element.
+   */
+  void set_single_element_policy(DegenerateArrayPolicy policy) {
+    this->_single_element_policy = policy;
+  }
+
+  /**
+   * @brief Sets the tolerances used by the expect_near_default overloads.
+   *
+   * @param absolute The absolute tolerance.
+   * @param relative The tolerance relative to the magnitude of the expected
+   * value.
+   */
+  void set_default_tolerance(T absolute, T relative = static_cast<T>(0)) {
+    this->_default_absolute_tolerance = absolute;
+    this->_default_relative_tolerance = relative;
+  }
+
+  /**
+   * @brief Sets whether NaN matches NaN in the expect_near_default overloads.
+   *
+   * The other checks always fail on NaN.
+   *
+   * @param policy NanPolicy::Fail (default) or NanPolicy::MatchNan.
+   */
+  void set_nan_policy(NanPolicy policy) { this->_nan_policy = policy; }
+
+  /**
+   * @brief Sets how much the tester prints.
+   *
+   * Assertions are recorded regardless of the verbosity. Has no effect if
+   * MCAP_VERBOSITY is set.
+   *
+   * @param verbosity The verbosity; Verbosity::Normal by default.
+   */
+  void set_verbosity(Verbosity verbosity) {
+    if (!this->_verbosity_overridden) {
+      this->_verbosity = verbosity;
+    }
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Sets the stream that receives the printed results.
+   *
+   * @param output The output stream, std::cout by default; it must outlive
+   * the tester.
+   */
+  void set_output(std::ostream &output) { this->_output = &output; }
+#endif
+
+  /**
+   * @brief Sends the printed results to a function instead of the output
+   * stream, e.g. one writing to a UART on an embedded target.
+   *
+   * The function receives the printed text in pieces ending with a
+   * newline. An empty function restores the output stream.
+   *
+   * @param output The function that receives the printed text.
+   */
+  void set_output_function(
+      const std::function<void(const std::string &)> &output) {
+    this->_output_function = output;
+  }
+
+#ifndef MCAP_TESTER_NO_EXCEPTIONS
+  /**
+   * @brief Enables or disables fail-fast mode.
+   *
+   * In fail-fast mode the first failing assertion throws std::runtime_error
+   * right after it is reported and recorded. Has no effect if
+   * MCAP_FAIL_FAST is set.
+   *
+   * @param enabled true to stop at the first failure.
+   */
+  void set_fail_fast(bool enabled) {
+    if (!this->_fail_fast_overridden) {
+      this->_fail_fast = enabled;
+    }
+  }
+#endif
+
+  /**
+   * @brief Registers a function that is called every time an assertion
+   * fails.
+   *
+   * The callback receives the record of the failed assertion right after it
+   * is printed and recorded, e.g. to dump intermediate model state, save a
+   * plot or notify a dashboard. Callbacks run in registration order and must
+   * not make assertions on the same tester.
+   *
+   * @param callback The function to call with the failure record.
+   */
+  void
+  on_failure(const std::function<void(const AssertionRecord &)> &callback) {
+    this->_failure_callbacks.push_back(callback);
+  }
+
+  /**
+   * @brief Sets how many of the worst elements an array mismatch lists.
+   *
+   * Every array check scans all elements, and a mismatch reports how many
+   * of them are outside tolerance together with the elements with the
+   * largest errors. The count is capped at 50 so the output stays bounded; 0
+   * lists no elements but still reports the total.
+   *
+   * @param count The number of worst elements to list; 5 by default.
+   */
+  void set_worst_element_count(std::size_t count) {
+    const std::size_t cap = 50;
+    this->_worst_element_count = (count < cap) ? count : cap;
+  }
+
+  /**
+   * @brief Returns the policy for arrays without any element.
+   */
+  DegenerateArrayPolicy get_empty_array_policy() const {
+    return this->_empty_array_policy;
+  }
+
+  /**
+   * @brief Returns the policy for arrays holding exactly one element.
+   */
+  DegenerateArrayPolicy get_single_element_policy() const {
+    return this->_single_element_policy;
+  }
+
+  /**
+   * @brief Records why the tolerance of a check was chosen.
+   *
+   * The rationale (free text or a link to a requirement or analysis) is
+   * associated with the message of the check and is printed together with
+   * every warning or failure reported under that message, so the reasoning
+   * behind each tolerance value travels with the results.
+   *
+   * @param message The message of the check the tolerance belongs to.
+   * @param rationale The explanation or reference for the tolerance value.
+   */
+  void set_tolerance_rationale(const std::string &message,
+                               const std::string &rationale) {
+    this->_tolerance_rationales[message] = rationale;
+  }
+
+  /**
+   * @brief Returns the tolerance rationale recorded for a message, or an
+   * empty string if none was recorded.
+   */
+  std::string get_tolerance_rationale(const std::string &message) const {
+    std::map<std::string, std::string>::const_iterator it =
+        this->_tolerance_rationales.find(message);
+    return (it == this->_tolerance_rationales.end()) ? std::string()
+                                                     : it->second;
+  }
+
+  /**
+   * @brief Attaches a piece of information about the run to the reports,
+   * e.g. the model, the target or the commit under test.
+   *
+   * The entries are written to the "metadata" object of the JSON reports
+   * produced by write_report, emit_report and save_results.
+   *
+   * @param key The name of the entry; an existing entry is replaced.
+   * @param value The value of the entry.
+   */
+  void set_metadata(const std::string &key, const std::string &value) {
+    this->_metadata[key] = value;
+  }
+
+  /**
+   * @brief Returns the metadata attached to the reports.
+   */
+  const std::map<std::string, std::string> &get_metadata() const {
+    return this->_metadata;
+  }
+
+#ifndef MCAP_TESTER_NO_EXCEPTIONS
+  /**
+   * @brief Throws a std::runtime_error if the test has failed.
+   *
+   * This method checks the internal _test_failed_flag. If the flag is set to
+   * true, it throws a std::runtime_error whose message starts with "Test
+   * failed." and lists the recorded failures.
+   *
+   * @throws std::runtime_error if the test has failed.
+   */
+  void throw_error_if_test_failed() {
+    if (this->_test_failed_flag) {
+      this->_failure_handled = true;
+      throw std::runtime_error(this->_failure_summary());
+    }
+  }
+#endif
+
+  /**
+   * @brief Resets the test failed flag to indicate that no test failure has
+   * occurred.
+   *
+   * This method sets the internal _test_failed_flag to false, clearing any
+   * previous test failure state.
+   */
+  void reset_test_failed_flag() {
+    this->_test_failed_flag = false;
+    this->_failure_handled = true;
+  }
+
+  /**
+   * @brief Returns whether an assertion failed since the tester was created
+   * or the flag was last reset.
+   */
+  bool get_test_failed_flag() const { return this->_test_failed_flag; }
+
+  /**
+   * @brief Returns the number of warnings issued by dual-threshold checks.
+   */
+  std::size_t get_warning_count() const { return this->_warning_count; }
+
+  /**
+   * @brief Returns the active environment overrides, e.g.
+   * "MCAP_TOL_SCALE=2 MCAP_VERBOSITY=verbose", or an empty string.
+   */
+  std::string get_environment_overrides() const {
+    return this->_environment_overrides;
+  }
+
+  /**
+   * @brief Resets the warning count to zero.
+   */
+  void reset_warning_count() { this->_warning_count = 0; }
+
+  /**
+   * @brief Returns the records of all assertions made so far, in order.
+   */
+  const std::vector<AssertionRecord> &get_records() const {
+    return this->_records;
+  }
+
+  /**
+   * @brief Discards all assertion records.
+   */
+  void clear_records() { this->_records.clear(); }
+
+  /**
+   * @brief Sets the source location attached to the following assertions.
+   *
+   * The location is printed with every warning or failure and stored in the
+   * assertion records until clear_call_site is called. The MCAP_CHECK and
+   * MCAP_EXPECT_NEAR macros set and clear it around a single assertion.
+   *
+   * @param file The source file of the assertion, usually __FILE__.
+   * @param line The line of the assertion, usually __LINE__.
+   */
+  void set_call_site(const std::string &file, int line) {
+    this->_call_site = file + ":" + std::to_string(line);
+  }
+
+  /**
+   * @brief Stops attaching a source location to assertions.
+   */
+  void clear_call_site() { this->_call_site.clear(); }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Writes the recorded assertions to a JSON report file.
+   *
+   * Reports written by several test executables can be combined with
+   * Report::merge_reports.
+   *
+   * @param path The report file to write.
+   * @param name The name of the report, e.g. the test executable or model.
+   * @return true on success, false if the file cannot be written.
+   */
+  bool write_report(const std::string &path, const std::string &name) const {
+    return Report::save_report(path, name, this->_records, this->_metadata);
+  }
+
+  /**
+   * @brief Writes the recorded assertions to a self-contained HTML page, with
+   * the measured error against the tolerance of every comparison and the
+   * details of every failure; see Report::to_html.
+   *
+   * @param path The HTML file to write.
+   * @param name The title of the page, e.g. the test executable or model.
+   * @return true on success, false if the file cannot be written.
+   */
+  bool write_html_report(const std::string &path,
+                         const std::string &name = "results") const {
+    std::vector<Report::HtmlCase> cases(1,
+                                        Report::HtmlCase(name, this->_records));
+    return Report::save_html_report(path, name, cases);
+  }
+#endif
+
+  /**
+   * @brief Prints the JSON report of the recorded assertions, with the
+   * layout written by write_report, regardless of the verbosity.
+   *
+   * Without file I/O this is how the results leave the target: the report
+   * is delivered through the output function (see set_output_function).
+   *
+   * @param name The name of the report, e.g. the test executable or model.
+   */
+  void emit_report(const std::string &name) const {
+    this->_emit(JSON::serialize(Report::to_json(name, this->_records,
+                                                this->_metadata)) +
+                "\n");
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Writes the recorded assertions, including the measured error and
+   * tolerance of every tolerance comparison, for archiving as a CI
+   * artifact.
+   *
+   * The file is a report named "results"; read it back with
+   * Report::load_results.
+   *
+   * @param path The file to write.
+   * @return true on success, false if the file cannot be written.
+   */
+  bool save_results(const std::string &path) const {
+    return Report::save_report(path, "results", this->_records,
+                               this->_metadata);
+  }
+#endif
+
+  /**
+   * @brief Fails if the error of a tolerance comparison grew against the
+   * results of a previous run by more than a threshold.
+   *
+   * The comparisons are matched with Report::find_error_drift. Every drifted
+   * comparison is listed in the detail of one assertion, named "Error
+   * drift", so drift is caught even while the errors are within tolerance.
+   *
+   * @param baseline The records of the previous run, e.g. read with
+   * Report::load_results.
+   * @param max_growth The allowed relative growth of an error.
+   * @return The drifted comparisons.
+   */
+  std::vector<ErrorDrift>
+  compare_with_previous(const std::vector<AssertionRecord> &baseline,
+                        double max_growth = 0.1) {
+    std::vector<ErrorDrift> drifts =
+        Report::find_error_drift(this->_records, baseline, max_growth);
+    if (drifts.empty()) {
+      this->_report_pass("Error drift");
+      return drifts;
+    }
+
+    std::string detail = std::to_string(drifts.size()) +
+                         " comparison(s) grew by more than " +
+                         format_number(max_growth * 100.0) + "%:";
+    for (std::size_t i = 0; i < drifts.size(); i++) {
+      detail += " " + drifts[i].message + " (" +
+                format_number(drifts[i].previous_error) + " -> " +
+                format_number(drifts[i].current_error) + ")";
+      detail += i + 1 < drifts.size() ? ";" : ".";
+    }
+    this->_report_failure("Error drift", detail);
+    return drifts;
+  }
+
+  /**
+   * @brief Declares how much the error of a tolerance comparison may grow,
+   * e.g. by 5% per release and by 15% since the baseline release.
+   *
+   * @param message The message of the comparison.
+   * @param max_release_growth The allowed growth against the previous
+   * release, as a fraction.
+   * @param max_cumulative_growth The allowed growth against the baseline
+   * release, as a fraction.
+   */
+  void set_regression_budget(const std::string &message,
+                             double max_release_growth,
+                             double max_cumulative_growth) {
+    for (std::size_t i = 0; i < this->_regression_budgets.size(); i++) {
+      if (this->_regression_budgets[i].message == message) {
+        this->_regression_budgets[i] = RegressionBudget(
+            message, max_release_growth, max_cumulative_growth);
+        return;
+      }
+    }
+    this->_regression_budgets.push_back(
+        RegressionBudget(message, max_release_growth, max_cumulative_growth));
+  }
+
+  /**
+   * @brief Checks the declared regression budgets against the results
+   * stored for earlier releases.
+   *
+   * The budgets are checked with Report::check_regression_budgets and
+   * reported as one assertion, named "Regression budget", which fails with
+   * the ledger of every budget if one is exhausted.
+   *
+   * @param history The records of earlier releases, oldest first, e.g. read
+   * with Report::load_history.
+   * @return The ledger.
+   */
+  std::vector<LedgerEntry> check_regression_budgets(
+      const std::vector<std::vector<AssertionRecord>> &history) {
+    std::vector<LedgerEntry> ledger = Report::check_regression_budgets(
+        history, this->_records, this->_regression_budgets);
+    std::size_t exhausted = 0;
+    for (std::size_t i = 0; i < ledger.size(); i++) {
+      exhausted += ledger[i].exhausted ? 1 : 0;
+    }
+
+    if (exhausted == 0) {
+      this->_report_pass("Regression budget");
+    } else {
+      std::string table = Report::format_ledger(ledger);
+      table.erase(table.find_last_not_of('\n') + 1);
+      this->_report_failure("Regression budget",
+                            std::to_string(exhausted) +
+                                " budget(s) exhausted.\n" + table);
+    }
+    return ledger;
+  }
+
+  /**
+   * @brief Declares a discrete mode or branch of the generated logic that the
+   * test data is expected to exercise.
+   *
+   * Together with record_mode and expect_all_modes_exercised this provides
+   * evidence that no declared mode is dead code for the test data, e.g. every
+   * state of a generated state machine or every saturation branch.
+   *
+   * @param mode The name of the mode.
+   */
+  void declare_mode(const std::string &mode) {
+    this->_mode_counts.insert(std::make_pair(mode, std::size_t(0)));
+  }
+
+  /**
+   * @brief Declares several modes at once.
+   *
+   * @param modes The names of the modes.
+   */
+  void declare_modes(const std::vector<std::string> &modes) {
+    for (std::size_t i = 0; i < modes.size(); i++) {
+      this->declare_mode(modes[i]);
+    }
+  }
+
+  /**
+   * @brief Records that a mode appeared in the test data.
+   *
+   * Modes that were not declared are counted as well but are not required
+   * by expect_all_modes_exercised.
+   *
+   * @param mode The name of the mode.
+   */
+  void record_mode(const std::string &mode) { this->_mode_counts[mode]++; }
+
+  /**
+   * @brief Returns how often every declared or recorded mode appeared.
+   */
+  const std::map<std::string, std::size_t> &get_mode_counts() const {
+    return this->_mode_counts;
+  }
+
+  /**
+   * @brief Checks that every declared mode was recorded at least once.
+   *
+   * @param message The message to display in case of failure; the modes that
+   * never appeared are listed.
+   */
+  void expect_all_modes_exercised(const std::string &message) {
+    std::string missing;
+    for (std::map<std::string, std::size_t>::const_iterator it =
+             this->_mode_counts.begin();
+         it != this->_mode_counts.end(); ++it) {
+      if (it->second == 0) {
+        missing += (missing.empty() ? "" : ", ") + it->first;
+      }
+    }
+
+    if (missing.empty()) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(message, "Modes never exercised: " + missing +
+                                         ".");
+    }
+  }
+
+  /**
+   * @brief Starts or stops recording the inputs of every element-wise
+   * comparison for a session archive.
+   *
+   * Recording keeps copies of all compared arrays in memory until
+   * save_session is called. Checks that cannot be replayed from two arrays,
+   * such as norms, spectra, metrics and geometric comparisons, report a
+   * warning instead; MCAP_tester_session.hpp lists them.
+   *
+   * @param enabled true to record subsequent comparisons.
+   */
+  void set_session_recording(bool enabled) {
+    this->_session_recording = enabled;
+  }
+
+  /**
+   * @brief Returns the comparisons recorded since session recording was
+   * enabled.
+   */
+  const std::vector<SessionEntry<T>> &get_session_entries() const {
+    return this->_session_entries;
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Writes the recorded comparisons to a session archive.
+   *
+   * @param path The archive file to write.
+   * @param storage Whether the arrays are embedded in the archive or written
+   * to separate files next to it.
+   * @return true on success, false if a file cannot be written.
+   */
+  bool save_session(const std::string &path,
+                    SessionStorage storage = SessionStorage::Inline) const {
+    return Session::save_session(path, this->_session_entries, storage);
+  }
+#endif
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Replays a session archive and fails for every verdict that is not
+   * reproduced.
+   *
+   * @param path The archive file written by save_session.
+   */
+  void replay_session(const std::string &path) {
+    SessionReplay replay = Session::replay_session<T>(path);
+    for (std::size_t i = 0; i < replay.errors.size(); i++) {
+      this->_report_failure(path, replay.errors[i],
+                            FailureCategory::Infrastructure);
+    }
+    for (std::size_t i = 0; i < replay.mismatches.size(); i++) {
+      this->_report_failure(path, replay.mismatches[i]);
+    }
+    if (replay.reproduced()) {
+      this->_report_pass(path);
+    }
+  }
+#endif
+
+protected:
+  friend class MCAPTesterBuilder<T>;
+  friend class FailureGuard<T>;
+
+  /* Without the environment, the MCAP_* variables are never read; see
+   * MCAPTesterBuilder::ignore_environment. */
+  explicit MCAPTester(bool use_environment)
+      : _test_failed_flag(false), _warning_count(0),
+        _snapshot_directory("snapshots"),
+        _empty_array_policy(DegenerateArrayPolicy::Pass),
+        _single_element_policy(DegenerateArrayPolicy::Pass),
+        _worst_element_count(5), _records(), _session_recording(false),
+        _session_entries(), _mode_counts(), _call_site(),
+        _default_absolute_tolerance(static_cast<T>(0)),
+        _default_relative_tolerance(static_cast<T>(0)),
+        _nan_policy(NanPolicy::Fail), _verbosity(Verbosity::Normal),
+        _output(_default_output()), _fail_fast(false), _failure_callbacks(),
+        _failure_handled(true), _runtime_baseline_directory(),
+        _runtime_max_regression(0.1), _output_function(),
+        _pending_measurement(false), _pending_error(0.0),
+        _pending_tolerance(0.0), _tolerance_scale(1.0),
+        _verbosity_overridden(false), _fail_fast_overridden(false),
+        _environment_overrides(), _regression_budgets(),
+        _error_map_directory(),
+        _pending_relative_error(std::numeric_limits<double>::quiet_NaN()),
+        _metadata(), _use_environment(use_environment) {
+    if (use_environment) {
+      this->_apply_environment();
+    }
+  }
+
+  void _apply_environment() {
+    std::string overrides;
+    const char *scale = std::getenv("MCAP_TOL_SCALE");
+    if (scale != nullptr) {
+      char *end = nullptr;
+      double value = std::strtod(scale, &end);
+      bool valid = *scale != '\0' && *end == '\0' && value > 0.0 &&
+                   std::isfinite(value);
+      if (valid) {
+        this->_tolerance_scale = value;
+      }
+      overrides += std::string(" MCAP_TOL_SCALE=") + scale +
+                   (valid ? "" : " (ignored)");
+    }
+
+    const char *verbosity = std::getenv("MCAP_VERBOSITY");
+    if (verbosity != nullptr) {
+      std::string text = verbosity;
+      bool valid = text == "quiet" || text == "normal" || text == "verbose";
+      if (valid) {
+        this->_verbosity = text == "quiet"
+                               ? Verbosity::Quiet
+                               : (text == "normal" ? Verbosity::Normal
+                                                   : Verbosity::Verbose);
+        this->_verbosity_overridden = true;
+      }
+      overrides += " MCAP_VERBOSITY=" + text + (valid ? "" : " (ignored)");
+    }
+
+    const char *fail_fast = std::getenv("MCAP_FAIL_FAST");
+    if (fail_fast != nullptr) {
+      std::string text = fail_fast;
+#ifdef MCAP_TESTER_NO_EXCEPTIONS
+      bool valid = false;
+#else
+      bool valid = text == "0" || text == "1";
+#endif
+      if (valid) {
+        this->_fail_fast = text == "1";
+        this->_fail_fast_overridden = true;
+      }
+      overrides += " MCAP_FAIL_FAST=" + text + (valid ? "" : " (ignored)");
+    }
+
+    if (!overrides.empty()) {
+      this->_environment_overrides = overrides.substr(1);
+      this->_push_record("Environment overrides", ComparisonStatus::Pass,
+                         this->_environment_overrides);
+    }
+  }
+
+  /* A negative or NaN tolerance would make every comparison fail or pass
+   * silently, so it is reported as a configuration error instead. */
+  bool _accept_tolerance(T tolerance, const std::string &message) {
+    if (tolerance >= static_cast<T>(0)) {
+      return true;
+    }
+    this->_report_failure(message, "Configuration error: invalid tolerance " +
+                                       format_number(tolerance) +
+                                       "; tolerances must be non-negative.",
+                          FailureCategory::Structural);
+    return false;
+  }
+
+  bool _accept_tolerance(const Tolerance<T> &tolerance,
+                         const std::string &message) {
+    if (tolerance.valid()) {
+      return true;
+    }
+    this->_report_failure(message, "Configuration error: invalid tolerance " +
+                                       tolerance.describe() +
+                                       "; tolerances must be non-negative.",
+                          FailureCategory::Structural);
+    return false;
+  }
+
+  T _scaled(T tolerance) const {
+    return static_cast<T>(static_cast<double>(tolerance) *
+                          this->_tolerance_scale);
+  }
+
+  /* Writes the error map of a failed array comparison if an artifacts
+   * directory is set, and returns the sentence naming the written files. */
+  std::string _write_error_map(const FlatArray<T> &actual,
+                               const FlatArray<T> &expected,
+                               const ComparisonResult<T> &result,
+                               const std::string &message) const {
+#ifndef MCAP_TESTER_NO_STDIO
+    ErrorMap map;
+    if (this->_error_map_directory.empty() ||
+        result.status != ComparisonStatus::Failure ||
+        !make_error_map(actual, expected, map)) {
+      return std::string();
+    }
+    std::string base =
+        this->_error_map_directory + "/" + this->_file_name(message);
+    if (!create_directories(this->_error_map_directory) ||
+        !save_error_map_csv(base + ".csv", map)) {
+      return " Cannot write error map " + base + ".csv.";
+    }
+    std::string files = base + ".csv";
+#ifdef MCAP_TESTER_ENABLE_ERROR_MAP_PNG
+    if (save_error_map_png(base + ".png", map)) {
+      files += " and " + base + ".png";
+    }
+#endif
+    return " Error map: " + files + ".";
+#else
+    (void)actual;
+    (void)expected;
+    (void)result;
+    (void)message;
+    return std::string();
+#endif
+  }
+
+  static std::string _file_name(const std::string &name) {
+    std::string file_name = name;
+    for (std::size_t i = 0; i < file_name.size(); i++) {
+      char c = file_name[i];
+      if (!((c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') ||
+            (c >= '0' && c <= '9') || c == '-' || c == '_')) {
+        file_name[i] = '_';
+      }
+    }
+    return file_name;
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  void _check_runtime_baseline(const RuntimeStatistics &statistics,
+                               const std::string &detail,
+                               const std::string &message) {
+    std::string path = this->_runtime_baseline_directory + "/" +
+                       this->_file_name(message) + ".txt";
+    FlatArray<double> baseline;
+
+    const char *update = this->_use_environment
+                             ? std::getenv("MCAP_UPDATE_BASELINES")
+                             : nullptr;
+    bool update_requested = (update != nullptr && std::string(update) == "1");
+
+    if (update_requested || !load_flat_array(path, baseline) ||
+        baseline.data.size() != 2) {
+      if (!create_directories(this->_runtime_baseline_directory) ||
+          !save_flat_array(path, make_flat_array(std::vector<double>{
+                                     statistics.mean, statistics.p99}))) {
+        this->_report_failure(message, "Cannot write baseline " + path + ".",
+                              FailureCategory::Infrastructure);
+      } else {
+        this->_report_pass(message, detail + " Baseline recorded to " + path +
+                                        ".");
+      }
+      return;
+    }
+
+    double limit = baseline.data[0] * (1.0 + this->_runtime_max_regression);
+    std::string comparison = " Baseline mean " +
+                             format_number(baseline.data[0]) + " s, allowed " +
+                             format_number(limit) + " s.";
+    if (statistics.mean > limit) {
+      this->_report_failure(message, detail + comparison,
+                            FailureCategory::Timing);
+      return;
+    }
+    this->_report_pass(message, detail + comparison);
+  }
+#endif
+
+  void _print_result(const char *label, const std::string &message,
+                     const std::string &detail) const {
+    if (this->_verbosity == Verbosity::Quiet) {
+      return;
+    }
+
+    std::string text = label + message;
+    if (!detail.empty()) {
+      text += " " + detail;
+    }
+    text += "\n";
+
+    std::string rationale = this->get_tolerance_rationale(message);
+    if (!rationale.empty()) {
+      text += "  Tolerance rationale: " + rationale + "\n";
+    }
+    if (!this->_call_site.empty()) {
+      text += "  At: " + this->_call_site + "\n";
+    }
+    this->_emit(text + "\n");
+  }
+
+  void _emit(const std::string &text) const {
+    if (this->_output_function) {
+      this->_output_function(text);
+      return;
+    }
+#ifndef MCAP_TESTER_NO_STDIO
+    *this->_output << text << std::flush;
+#endif
+  }
+
+  static std::ostream *_default_output() {
+#ifdef MCAP_TESTER_NO_STDIO
+    return nullptr;
+#else
+    return &std::cout;
+#endif
+  }
+
+  void _record_entry(const std::string &kind, const FlatArray<T> &actual,
+                     const FlatArray<T> &expected,
+                     const std::vector<double> &parameters,
+                     ComparisonStatus status, const std::string &message) {
+    if (!this->_session_recording) {
+      return;
+    }
+
+    SessionEntry<T> entry;
+    entry.kind = kind;
+    entry.message = message;
+    entry.parameters = parameters;
+    entry.actual = actual;
+    entry.expected = expected;
+    entry.status = status;
+    this->_session_entries.push_back(entry);
+  }
+
+  void _record_near(const FlatArray<T> &actual, const FlatArray<T> &expected,
+                    T warning_tolerance, T failure_tolerance,
+                    ShapePolicy policy, const ComparisonResult<T> &result,
+                    const std::string &message) {
+    std::vector<double> parameters;
+    parameters.push_back(static_cast<double>(warning_tolerance));
+    parameters.push_back(static_cast<double>(failure_tolerance));
+    parameters.push_back(static_cast<double>(static_cast<int>(policy)));
+    this->_record_entry("near", actual, expected, parameters, result.status,
+                        message);
+  }
+
+  void _record_angle(const FlatArray<T> &actual, const FlatArray<T> &expected,
+                     T tolerance, AngleUnit unit,
+                     const ComparisonResult<T> &result,
+                     const std::string &message) {
+    std::vector<double> parameters;
+    parameters.push_back(static_cast<double>(tolerance));
+    parameters.push_back(static_cast<double>(static_cast<int>(unit)));
+    this->_record_entry("angle", actual, expected, parameters, result.status,
+                        message);
+  }
+
+  /* The parameters of the session kinds taking a Tolerance start with its
+   * absolute, relative and ULP bound. */
+  /* A time series as one {time, value} row per sample. */
+  static FlatArray<T> _time_series(const std::vector<T> &times,
+                                   const std::vector<T> &values) {
+    FlatArray<T> series;
+    series.shape.push_back(values.size());
+    series.shape.push_back(2);
+    for (std::size_t k = 0; k < values.size(); k++) {
+      series.data.push_back(times[k]);
+      series.data.push_back(values[k]);
+    }
+    return series;
+  }
+
+  static std::vector<double>
+  _tolerance_parameters(const Tolerance<T> &tolerance) {
+    std::vector<double> parameters;
+    parameters.push_back(static_cast<double>(tolerance.absolute_part()));
+    parameters.push_back(static_cast<double>(tolerance.relative_part()));
+    parameters.push_back(static_cast<double>(tolerance.ulp_count()));
+    return parameters;
+  }
+
+  /* Checks of aggregates or geometric quantities cannot be replayed from
+   * two arrays, so a recorded session would silently miss them. */
+  void _warn_unrecorded(const std::string &message) {
+    if (this->_session_recording) {
+      this->_report_warning(message, "Not recorded for session replay.");
+    }
+  }
+
+  void _report_block_violation(const std::vector<T> &input,
+                               const std::vector<T> &output, std::size_t k,
+                               T expected, const std::string &semantics,
+                               const std::string &message) {
+    if (k == input.size()) {
+      this->_report_pass(message);
+      return;
+    }
+    this->_report_failure(message, "Sample " + std::to_string(k) +
+                                       " violates " + semantics + ": input " +
+                                       format_number(input[k]) + ", output " +
+                                       format_number(output[k]) +
+                                       ", expected " +
+                                       format_number(expected) + ".");
+  }
+
+  std::string _failure_summary() const {
+    const std::size_t listed = 20;
+    std::size_t count = 0;
+    std::string lines;
+    for (std::size_t i = 0; i < this->_records.size(); i++) {
+      const AssertionRecord &record = this->_records[i];
+      if (record.status != ComparisonStatus::Failure) {
+        continue;
+      }
+      if (++count <= listed) {
+        lines += "\n  " + record.message;
+        lines += record.detail.empty() ? "" : " " + record.detail;
+        lines += record.location.empty() ? "" : " (" + record.location + ")";
+      }
+    }
+    if (count > listed) {
+      lines += "\n  ... and " + std::to_string(count - listed) + " more.";
+    }
+
+    if (count == 0) {
+      return "Test failed.";
+    }
+    return "Test failed. " + std::to_string(count) + " failure(s) (" +
+           Report::format_category_summary(this->_records) + "):" + lines;
+  }
+
+  void _report_pass(const std::string &message,
+                    const std::string &detail = std::string()) {
+    if (this->_verbosity == Verbosity::Verbose) {
+      this->_print_result("PASS: ", message, detail);
+    }
+    this->_push_record(message, ComparisonStatus::Pass, detail);
+  }
+
+  void _report_failure(
+      const std::string &message, const std::string &detail,
+      FailureCategory category = FailureCategory::Tolerance) {
+    this->_print_result("FAILURE: ", message, detail);
+    this->_test_failed_flag = true;
+    this->_failure_handled = false;
+    this->_push_record(message, ComparisonStatus::Failure, detail, category);
+    for (std::size_t i = 0; i < this->_failure_callbacks.size(); i++) {
+      this->_failure_callbacks[i](this->_records.back());
+    }
+#ifndef MCAP_TESTER_NO_EXCEPTIONS
+    if (this->_fail_fast) {
+      throw std::runtime_error("Test failed: " + message);
+    }
+#endif
+  }
+
+  void _report_warning(const std::string &message, const std::string &detail) {
+    this->_print_result("WARNING: ", message, detail);
+    this->_warning_count++;
+    this->_push_record(message, ComparisonStatus::Warning, detail);
+  }
+
+  void _check_quietness(const std::vector<T> &segment, T value,
+                        const std::string &metric_name, T threshold,
+                        const std::string &message) {
+    if (segment.empty()) {
+      this->_report_failure(message, "Empty signal segment.",
+                            FailureCategory::Structural);
+    } else if (value <= threshold) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(message, metric_name + " " + format_number(value) +
+                                         " exceeds " +
+                                         format_number(threshold) + ".");
+    }
+  }
+
+  /* The relative part of the threshold is relative to the same metric of
+   * the expected values against zero; a ULP bound does not apply. */
+  void _check_metric(const FlatArray<T> &actual, const FlatArray<T> &expected,
+                     T ErrorMetrics<T>::*metric, const std::string &metric_name,
+                     const Tolerance<T> &threshold,
+                     const std::string &message) {
+    if (!this->_accept_tolerance(threshold, message)) {
+      return;
+    }
+    if (!this->_accept_degenerate(actual, expected, message)) {
+      return;
+    }
+    this->_warn_unrecorded(message);
+
+    Tolerance<T> scaled = threshold.scaled(this->_tolerance_scale);
+    ErrorMetrics<T> metrics =
+        Comparison::compute_error_metrics(actual, expected);
+    T value = metrics.*metric;
+    T bound = scaled.absolute_part();
+    if (metrics.shape_matches && scaled.relative_part() != static_cast<T>(0)) {
+      FlatArray<T> zeros = expected;
+      zeros.data.assign(expected.data.size(), static_cast<T>(0));
+      bound += scaled.relative_part() *
+               Comparison::compute_error_metrics(expected, zeros).*metric;
+    }
+
+    if (!metrics.shape_matches) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+    } else if (value <= bound) {
+      this->_report_pass(message);
+    } else {
+      this->_report_failure(message, metric_name + " " + format_number(value) +
+                                         " exceeds " + format_number(bound) +
+                                         ".");
+    }
+  }
+
+  /**
+   * @brief Applies the degenerate array policies before an array check.
+   *
+   * @return true if the comparison should go ahead, false if the policy
+   * already decided the outcome.
+   */
+  bool _accept_degenerate(const FlatArray<T> &actual,
+                          const FlatArray<T> &expected,
+                          const std::string &message) {
+    if (!Comparison::same_shape(actual, expected)) {
+      return true;
+    }
+
+    ArrayShapeClass shape_class = Comparison::classify_shape(actual);
+    if (shape_class == ArrayShapeClass::Empty) {
+      if (this->_empty_array_policy == DegenerateArrayPolicy::Warn) {
+        this->_report_warning(message, "Empty arrays compared.");
+      } else if (this->_empty_array_policy == DegenerateArrayPolicy::Fail) {
+        this->_report_failure(message, "Empty arrays compared.",
+                              FailureCategory::Structural);
+      } else {
+        this->_report_pass(message);
+      }
+      return false;
+    } else if (shape_class == ArrayShapeClass::SingleElement) {
+      if (this->_single_element_policy == DegenerateArrayPolicy::Warn) {
+        this->_report_warning(message, "Single-element arrays compared.");
+      } else if (this->_single_element_policy == DegenerateArrayPolicy::Fail) {
+        this->_report_failure(message, "Single-element arrays compared.",
+                              FailureCategory::Structural);
+        return false;
+      }
+    }
+    return true;
+  }
+
+  void _check_monotonic(const std::vector<T> &values, T tolerance,
+                        bool increasing, const std::string &message) {
+    if (!this->_accept_tolerance(tolerance, message)) {
+      return;
+    }
+    tolerance = this->_scaled(tolerance);
+    std::size_t index =
+        Comparison::find_monotonic_violation(values, tolerance, increasing);
+    if (index == values.size()) {
+      this->_report_pass(message);
+    } else if (index == 0) {
+      this->_report_failure(message, "Element 0 is " +
+                                         format_number(values[0]) + ".");
+    } else {
+      std::string direction = increasing ? "increasing" : "decreasing";
+      this->_report_failure(message, "Not " + direction + " at element " +
+                                         std::to_string(index) + ": " +
+                                         format_number(values[index]) +
+                                         " after " +
+                                         format_number(values[index - 1]) +
+                                         ".");
+    }
+  }
+
+  void _check_stability(const std::vector<std::complex<T>> &poles, T margin,
+                        StabilityDomain domain, const std::string &message) {
+    std::vector<std::size_t> unstable =
+        Comparison::find_unstable_poles(poles, margin, domain);
+    if (unstable.empty()) {
+      this->_report_pass(message);
+      return;
+    }
+
+    std::string bound =
+        (domain == StabilityDomain::Continuous)
+            ? "real part below " + format_number(-margin)
+            : "magnitude below " + format_number(static_cast<T>(1) - margin);
+    this->_report_failure(message, "Unstable poles: " +
+                                       this->_describe_values(poles, unstable) +
+                                       " (required " + bound + ").");
+  }
+
+  std::string
+  _describe_values(const std::vector<std::complex<T>> &values,
+                   const std::vector<std::size_t> &indices) const {
+    if (indices.empty()) {
+      return "none";
+    }
+
+    std::string text;
+    for (std::size_t k = 0; k < indices.size(); k++) {
+      const std::complex<T> &value = values[indices[k]];
+      text += (k == 0) ? "" : ", ";
+      text += format_number(value.real());
+      if (value.imag() != static_cast<T>(0)) {
+        text += (value.imag() < static_cast<T>(0) ? "-" : "+") +
+                format_number(std::abs(value.imag())) + "i";
+      }
+      text += " [" + std::to_string(indices[k]) + "]";
+    }
+    return text;
+  }
+
+  std::string _describe_mismatch(const FlatArray<T> &actual,
+                                const FlatArray<T> &expected,
+                                T warning_tolerance, T failure_tolerance,
+                                const ComparisonResult<T> &result) const {
+    if (result.kind != MismatchKind::ElementMismatch) {
+      return result.describe();
+    }
+
+    T tolerance = (result.status == ComparisonStatus::Failure)
+                      ? failure_tolerance
+                      : warning_tolerance;
+    WorstElements<T> worst = Comparison::find_worst_elements(
+        actual, expected, tolerance, this->_worst_element_count);
+
+    std::string detail = result.describe() + " " +
+                         std::to_string(worst.count) + " of " +
+                         std::to_string(actual.data.size()) +
+                         " elements outside tolerance.";
+    for (std::size_t k = 0; k < worst.elements.size(); k++) {
+      const ElementError<T> &element = worst.elements[k];
+      detail += std::string(k == 0 ? " Worst: " : ", ") +
+                this->_format_position(actual.shape, element.index) + " " +
+                format_number(element.actual_value) + " vs " +
+                format_number(element.expected_value) + " (error " +
+                format_number(element.error) + ")";
+    }
+    if (!worst.elements.empty()) {
+      detail += (worst.count > worst.elements.size()) ? ", ..." : ".";
+    }
+    return detail;
+  }
+
+  /* Reports a signal bundle as one comparison over all signals; a missing,
+   * extra or differently sized signal makes it a structural failure. */
+  void _report_signals(const SignalBundleResult<T> &result, T tolerance,
+                       const std::string &message) {
+    ComparisonResult<T> combined;
+    bool structural = !result.missing.empty() || !result.extra.empty();
+    std::size_t failed = 0;
+    std::string errors;
+    for (std::size_t i = 0; i < result.signals.size(); i++) {
+      const SignalComparison<T> &signal = result.signals[i];
+      errors += (i == 0 ? " " : ", ") + signal.name + " ";
+      if (signal.result.kind == MismatchKind::SizeMismatch) {
+        structural = true;
+        failed++;
+        errors += "size mismatch";
+        continue;
+      }
+      Comparison::Detail::merge_range(combined, signal.result);
+      errors += format_number(signal.result.max_error);
+      if (!signal.result.passed()) {
+        failed++;
+        errors += " (" + std::to_string(signal.result.mismatch_count) +
+                  " sample(s) outside)";
+      }
+    }
+    if (structural) {
+      combined.status = ComparisonStatus::Failure;
+      combined.kind = MismatchKind::SizeMismatch;
+    }
+
+    std::string detail;
+    if (!result.missing.empty()) {
+      detail += "Missing signals:";
+      for (std::size_t i = 0; i < result.missing.size(); i++) {
+        detail += (i == 0 ? " " : ", ") + result.missing[i];
+      }
+      detail += ". ";
+    }
+    if (!result.extra.empty()) {
+      detail += "Extra signals:";
+      for (std::size_t i = 0; i < result.extra.size(); i++) {
+        detail += (i == 0 ? " " : ", ") + result.extra[i];
+      }
+      detail += ". ";
+    }
+    if (!result.passed()) {
+      detail += std::to_string(failed) + " of " +
+                std::to_string(result.signals.size()) +
+                " signals outside tolerance.";
+      if (!result.signals.empty()) {
+        detail += " Worst error per signal:" + errors + ".";
+      }
+    }
+    this->_report_result(combined, message, detail, tolerance);
+  }
+
+  template <typename I>
+  void _check_integer_arrays(const FlatArray<I> &actual,
+                             const FlatArray<I> &expected,
+                             const std::string &message) {
+    this->_warn_unrecorded(message);
+    IntegerComparisonResult result =
+        Comparison::compare_integer_arrays(actual, expected);
+    if (!result.shape_matches) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+      return;
+    } else if (result.passed()) {
+      this->_report_pass(message);
+      return;
+    }
+
+    std::string detail = std::to_string(result.mismatches.size()) + " of " +
+                         std::to_string(actual.data.size()) +
+                         " elements differ:";
+    for (std::size_t k = 0; k < result.mismatches.size(); k++) {
+      std::size_t i = result.mismatches[k];
+      detail += std::string(k == 0 ? " " : ", ") +
+                this->_format_position(actual.shape, i) + " " +
+                std::to_string(actual.data[i]) + " vs " +
+                std::to_string(expected.data[i]);
+    }
+    this->_report_failure(message, detail + ".");
+  }
+
+  std::string _format_position(const std::vector<std::size_t> &shape,
+                               std::size_t index) const {
+    std::vector<std::size_t> position(shape.size(), 0);
+    for (std::size_t d = shape.size(); d > 0; d--) {
+      if (shape[d - 1] > 0) {
+        position[d - 1] = index % shape[d - 1];
+        index /= shape[d - 1];
+      }
+    }
+
+    std::string text = "(";
+    for (std::size_t d = 0; d < position.size(); d++) {
+      text += (d == 0 ? "" : ", ") + std::to_string(position[d]);
+    }
+    return text + ")";
+  }
+
+  std::string _describe_positions(
+      const std::vector<std::pair<std::size_t, std::size_t>> &positions)
+      const {
+    std::string text;
+    for (std::size_t k = 0; k < positions.size(); k++) {
+      text += (k == 0) ? "" : ", ";
+      text += "(" + std::to_string(positions[k].first) + ", " +
+              std::to_string(positions[k].second) + ")";
+    }
+    return text;
+  }
+
+  std::string _describe_symmetry(const PropertyResult<T> &result) const {
+    if (!result.square) {
+      return "Not a square matrix.";
+    }
+    return "Not symmetric. Entry (" + std::to_string(result.row) + ", " +
+           std::to_string(result.column) + ") differs from its transpose by " +
+           format_number(result.value) + ".";
+  }
+
+  void _report_trajectory(const TrajectoryResult<T> &result,
+                          const std::string &message,
+                          const std::string &suffix, T tolerance) {
+    if (result.kind == MismatchKind::SizeMismatch) {
+      this->_report_result(result, message, result.describe());
+      return;
+    }
+
+    std::string detail;
+    if (!result.passed()) {
+      detail = "Trajectory mismatch. Worst error " +
+               format_number(result.max_error) + " at sample " +
+               std::to_string(result.worst_sample) + ", signal " +
+               std::to_string(result.worst_signal) + "; samples " +
+               std::to_string(result.first_violation_sample) + " to " +
+               std::to_string(result.last_violation_sample) +
+               " exceed the tolerance (" +
+               std::to_string(result.mismatch_count) + " elements)." + suffix;
+    }
+    this->_report_result(result, message, detail, tolerance);
+  }
+
+  void _report_spectrum(const SpectrumResult<T> &result,
+                        const std::string &message) {
+    this->_warn_unrecorded(message);
+    if (result.kind == MismatchKind::SizeMismatch) {
+      this->_report_failure(message, "Size mismatch.",
+                            FailureCategory::Structural);
+    } else if (!result.passed()) {
+      this->_report_failure(
+          message, std::string(result.phase_failed ? "Phase" : "Magnitude") +
+                       " mismatch at " +
+                       format_number(result.failed_frequency) +
+                       " Hz. Worst magnitude error " +
+                       format_number(result.magnitude_error) + " at " +
+                       format_number(result.worst_frequency) +
+                       " Hz, worst phase error " +
+                       format_number(result.phase_error) + " rad.");
+    } else {
+      this->_report_pass(message);
+    }
+  }
+
+  void _push_record(const std::string &message, ComparisonStatus status,
+                    const std::string &detail,
+                    FailureCategory category = FailureCategory::None) {
+    AssertionRecord record(message, status, detail,
+                           this->get_tolerance_rationale(message),
+                           this->_call_site);
+    record.category = category;
+    record.measured = this->_pending_measurement;
+    record.error = this->_pending_error;
+    record.tolerance = this->_pending_tolerance;
+    record.relative_error = this->_pending_relative_error;
+    this->_pending_measurement = false;
+    this->_pending_relative_error = std::numeric_limits<double>::quiet_NaN();
+    this->_records.push_back(record);
+  }
+
+  /* Reports a tolerance comparison and records its error and tolerance. A
+   * failure also names the smallest tolerances it would have passed with. */
+  void _report_result(const ComparisonResult<T> &result,
+                      const std::string &message, const std::string &detail,
+                      T tolerance) {
+    if (result.kind == MismatchKind::SizeMismatch ||
+        result.kind == MismatchKind::LayoutMismatch) {
+      this->_report_result(result, message, detail);
+      return;
+    }
+    this->_pending_measurement = true;
+    this->_pending_error = static_cast<double>(result.max_error);
+    this->_pending_tolerance = static_cast<double>(tolerance);
+    this->_pending_relative_error = result.max_relative_error;
+
+    std::string passing;
+    if (result.status == ComparisonStatus::Failure &&
+        !std::isnan(result.max_error)) {
+      passing = "Smallest passing tolerance: " +
+                format_number(result.max_error) + " absolute";
+      if (std::isinf(result.max_relative_error)) {
+        passing += "; no relative tolerance passes.";
+      } else if (!std::isnan(result.max_relative_error)) {
+        passing += " or " + format_number(result.max_relative_error) +
+                   " relative.";
+      } else {
+        passing += ".";
+      }
+    }
+    this->_report_result(result, message,
+                         detail.empty() || passing.empty()
+                             ? detail + passing
+                             : detail + " " + passing);
+  }
+
+  void _report_result(const ComparisonResult<T> &result,
+                      const std::string &message, const std::string &detail) {
+    if (result.status == ComparisonStatus::Failure) {
+      bool structural = result.kind == MismatchKind::SizeMismatch ||
+                        result.kind == MismatchKind::LayoutMismatch;
+      this->_report_failure(message, detail,
+                            structural ? FailureCategory::Structural
+                                       : FailureCategory::Tolerance);
+    } else if (result.status == ComparisonStatus::Warning) {
+      this->_report_warning(message, detail);
+    } else {
+      this->_report_pass(message);
+    }
+  }
+
+  bool _test_failed_flag = false;
+  std::size_t _warning_count = 0;
+  std::string _snapshot_directory;
+  std::map<std::string, std::string> _tolerance_rationales;
+  DegenerateArrayPolicy _empty_array_policy;
+  DegenerateArrayPolicy _single_element_policy;
+  std::size_t _worst_element_count;
+  std::vector<AssertionRecord> _records;
+  bool _session_recording;
+  std::vector<SessionEntry<T>> _session_entries;
+  std::map<std::string, std::size_t> _mode_counts;
+  std::string _call_site;
+  T _default_absolute_tolerance;
+  T _default_relative_tolerance;
+  NanPolicy _nan_policy;
+  Verbosity _verbosity;
+  std::ostream *_output;
+  bool _fail_fast;
+  std::vector<std::function<void(const AssertionRecord &)>> _failure_callbacks;
+  bool _failure_handled;
+  std::string _runtime_baseline_directory;
+  double _runtime_max_regression;
+  std::function<void(const std::string &)> _output_function;
+  bool _pending_measurement;
+  double _pending_error;
+  double _pending_tolerance;
+  double _tolerance_scale;
+  bool _verbosity_overridden;
+  bool _fail_fast_overridden;
+  std::string _environment_overrides;
+  std::vector<RegressionBudget> _regression_budgets;
+  std::string _error_map_directory;
+  double _pending_relative_error;
+  std::map<std::string, std::string> _metadata;
+  bool _use_environment;
+};
+
+#ifndef MCAP_TESTER_NO_EXCEPTIONS
+/**
+ * @brief Throws the unhandled failures of a tester at the end of a scope.
+ *
+ * A forgotten throw_error_if_test_failed silently lets a failing test pass.
+ * A guard created next to the tester catches that: if its tester has
+ * failures that were neither thrown by throw_error_if_test_failed nor
+ * cleared by reset_test_failed_flag when the guard is destroyed, the guard
+ * throws the same std::runtime_error, so the enclosing test fails with the
+ * collected failure messages. Nothing is thrown while an exception raised
+ * after the guard was created is propagating. Before C++17 only whether any
+ * exception is propagating can be detected, so the guard never throws while
+ * one is, even if it was already propagating when the guard was created. The
+ * tester must outlive its guard.
+ *
+ * Example:
+ *   MCAPTester<double> tester;
+ *   FailureGuard<double> guard(tester);
+ *   tester.expect_near(y, y_ref, 1e-9, "y");
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> class FailureGuard {
+public:
+  explicit FailureGuard(const MCAPTester<T> &tester)
+      : _tester(tester), _exceptions(_uncaught_exceptions()) {}
+
+  FailureGuard(const FailureGuard &) = delete;
+  FailureGuard &operator=(const FailureGuard &) = delete;
+
+  ~FailureGuard() noexcept(false) {
+    if (this->_tester._test_failed_flag && !this->_tester._failure_handled &&
+        !this->_unwinding()) {
+      throw std::runtime_error(this->_tester._failure_summary());
+    }
+  }
+
+protected:
+  /* Before C++17, only whether any exception is propagating is known, and
+   * throwing during unwinding would call std::terminate. */
+  bool _unwinding() const {
+#if __cplusplus >= 201703L
+    return _uncaught_exceptions() > this->_exceptions;
+#else
+    return std::uncaught_exception();
+#endif
+  }
+
+  static int _uncaught_exceptions() {
+#if __cplusplus >= 201703L
+    return std::uncaught_exceptions();
+#else
+    return 0;
+#endif
+  }
+
+  const MCAPTester<T> &_tester;
+  int _exceptions;
+};
+#endif
+
+/**
+ * @brief Configures an MCAPTester step by step; obtained from
+ * MCAPTester<T>::builder() or constructed directly as TesterBuilder<T>.
+ *
+ * Every setting of the tester can be made here, so a framework embedding the
+ * tester as its comparison engine configures it in one place, typically
+ * with ignore_environment and an output function or stream of its own.
+ * Each built tester holds its own copy of the settings; testers share no
+ * state.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> class MCAPTesterBuilder {
+public:
+  MCAPTesterBuilder() : _tester(false), _use_environment(true) {}
+
+  /**
+   * @brief Sets the tolerances used by expect_near_default.
+   */
+  MCAPTesterBuilder &default_tolerance(T absolute,
+                                       T relative = static_cast<T>(0)) {
+    this->_tester.set_default_tolerance(absolute, relative);
+    return *this;
+  }
+
+  /**
+   * @brief Records why the tolerance of a check was chosen; see
+   * MCAPTester::set_tolerance_rationale.
+   */
+  MCAPTesterBuilder &tolerance_rationale(const std::string &message,
+                                         const std::string &rationale) {
+    this->_tester.set_tolerance_rationale(message, rationale);
+    return *this;
+  }
+
+  /**
+   * @brief Declares how much the error of a comparison may grow; see
+   * MCAPTester::set_regression_budget.
+   */
+  MCAPTesterBuilder &regression_budget(const std::string &message,
+                                       double max_release_growth,
+                                       double max_cumulative_growth) {
+    this->_tester.set_regression_budget(message, max_release_growth,
+                                        max_cumulative_growth);
+    return *this;
+  }
+
+  /**
+   * @brief Sets whether NaN matches NaN in expect_near_default.
+   */
+  MCAPTesterBuilder &nan_policy(NanPolicy policy) {
+    this->_tester.set_nan_policy(policy);
+    return *this;
+  }
+
+  /**
+   * @brief Sets how array checks treat arrays without any element.
+   */
+  MCAPTesterBuilder &empty_array_policy(DegenerateArrayPolicy policy) {
+    this->_tester.set_empty_array_policy(policy);
+    return *this;
+  }
+
+  /**
+   * @brief Sets how array checks treat arrays holding exactly one element.
+   */
+  MCAPTesterBuilder &single_element_policy(DegenerateArrayPolicy policy) {
+    this->_tester.set_single_element_policy(policy);
+    return *this;
+  }
+
+  /**
+   * @brief Sets how many of the worst elements an array mismatch lists.
+   */
+  MCAPTesterBuilder &worst_element_count(std::size_t count) {
+    this->_tester.set_worst_element_count(count);
+    return *this;
+  }
+
+  /**
+   * @brief Sets how much the tester prints.
+   */
+  MCAPTesterBuilder &verbosity(Verbosity verbosity) {
+    this->_tester.set_verbosity(verbosity);
+    return *this;
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Sets the stream that receives the printed results; it must
+   * outlive the tester.
+   */
+  MCAPTesterBuilder &output(std::ostream &output) {
+    this->_tester.set_output(output);
+    return *this;
+  }
+#endif
+
+  /**
+   * @brief Sets the function that receives the printed results.
+   */
+  MCAPTesterBuilder &
+  output_function(const std::function<void(const std::string &)> &output) {
+    this->_tester.set_output_function(output);
+    return *this;
+  }
+
+  /**
+   * @brief Registers a function called with the record of every failed
+   * assertion, e.g. to forward it to the reporter of the embedding
+   * framework.
+   */
+  MCAPTesterBuilder &
+  on_failure(const std::function<void(const AssertionRecord &)> &callback) {
+    this->_tester.on_failure(callback);
+    return *this;
+  }
+
+  /**
+   * @brief Attaches a piece of information about the run to the reports.
+   */
+  MCAPTesterBuilder &metadata(const std::string &key,
+                              const std::string &value) {
+    this->_tester.set_metadata(key, value);
+    return *this;
+  }
+
+  /**
+   * @brief Sets the directory in which snapshots are stored.
+   */
+  MCAPTesterBuilder &snapshot_directory(const std::string &directory) {
+    this->_tester.set_snapshot_directory(directory);
+    return *this;
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Enables the comparison of runtimes against stored baselines; see
+   * MCAPTester::set_runtime_baseline_directory.
+   */
+  MCAPTesterBuilder &runtime_baseline_directory(const std::string &directory,
+                                                double max_regression = 0.1) {
+    this->_tester.set_runtime_baseline_directory(directory, max_regression);
+    return *this;
+  }
+
+  /**
+   * @brief Writes the error maps of failed array comparisons to a
+   * directory.
+   */
+  MCAPTesterBuilder &error_map_directory(const std::string &directory) {
+    this->_tester.set_error_map_directory(directory);
+    return *this;
+  }
+#endif
+
+  /**
+   * @brief Records the inputs of every element-wise comparison for a session
+   * archive.
+   */
+  MCAPTesterBuilder &session_recording(bool enabled = true) {
+    this->_tester.set_session_recording(enabled);
+    return *this;
+  }
+
+#ifndef MCAP_TESTER_NO_EXCEPTIONS
+  /**
+   * @brief Makes the first failing assertion throw std::runtime_error.
+   */
+  MCAPTesterBuilder &fail_fast(bool enabled = true) {
+    this->_tester.set_fail_fast(enabled);
+    return *this;
+  }
+#endif
+
+  /**
+   * @brief Makes the built testers ignore the MCAP_* environment variables,
+   * so their behavior depends on this configuration only.
+   *
+   * Otherwise the variables are read by build() and take precedence over
+   * the configured settings, as for a constructed tester.
+   */
+  MCAPTesterBuilder &ignore_environment(bool ignored = true) {
+    this->_use_environment = !ignored;
+    return *this;
+  }
+
+  /**
+   * @brief Returns a tester with the configured settings.
+   */
+  MCAPTester<T> build() const {
+    MCAPTester<T> tester = this->_tester;
+    if (this->_use_environment) {
+      tester._use_environment = true;
+      tester._apply_environment();
+    }
+    return tester;
+  }
+
+protected:
+  MCAPTester<T> _tester;
+  bool _use_environment;
+};
+
+/**
+ * @brief The builder of an MCAPTester under a framework-neutral name.
+ */
+template <typename T> using TesterBuilder = MCAPTesterBuilder<T>;
+
+template <typename T> inline MCAPTesterBuilder<T> MCAPTester<T>::builder() {
+  return MCAPTesterBuilder<T>();
+}
+
+} // namespace Tester
+
+/**
+ * @brief Calls an assertion method of a tester and records the file and line
+ * of the call.
+ *
+ * Example: MCAP_CHECK(tester, expect_near(gain, 2.0, 1e-6, "controller gain"))
+ * prints "At: test_controller.cpp:42" below a failure. `tester` is evaluated
+ * more than once and must not have side effects. If the assertion throws,
+ * the location stays set until the next clear_call_site.
+ */
+#define MCAP_CHECK(tester, ...)                                                \
+  ((tester).set_call_site(__FILE__, __LINE__), (tester).__VA_ARGS__,           \
+   (tester).clear_call_site())
+
+/**
+ * @brief Calls expect_near on a tester and records the file and line of the
+ * call; the remaining arguments are those of expect_near.
+ */
+#define MCAP_EXPECT_NEAR(tester, ...)                                          \
+  MCAP_CHECK(tester, expect_near(__VA_ARGS__))
+
+/**
+ * @brief Calls expect_true on a tester and records the file and line of the
+ * call; the remaining arguments are those of expect_true.
+ */
+#define MCAP_EXPECT_TRUE(tester, ...)                                          \
+  MCAP_CHECK(tester, expect_true(__VA_ARGS__))
 
 #endif // MCAP_TESTER_HPP
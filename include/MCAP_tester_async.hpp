@@ -0,0 +1,247 @@
+/**
+ * @file MCAP_tester_async.hpp
+ * @brief Provides assertions for asynchronous code that never block a
+ * thread while they wait.
+ *
+ * Comparisons of data acquired with asynchronous I/O must wait until the
+ * data has arrived. Polling in a loop with sleeps blocks the thread that the
+ * I/O needs. Async::expect_eventually instead evaluates its predicate as a
+ * task of a runtime and, while the predicate does not hold, schedules the
+ * next evaluation after the poll interval, so the runtime keeps serving I/O
+ * in between.
+ *
+ * A runtime is represented by a Scheduler, a function that runs a task once
+ * after a delay, e.g. an adapter to the timer of the application's event
+ * loop. EventLoop is a minimal runtime for programs that have none:
+ *
+ *   Async::EventLoop loop;
+ *   start_acquisition(loop.scheduler());
+ *   Async::expect_eventually(loop.scheduler(), tester,
+ *                            [&]() { return buffer.size() >= 1000; },
+ *                            std::chrono::milliseconds(10),
+ *                            std::chrono::seconds(5), "acquisition");
+ *   loop.run();
+ *
+ * The tester is only used from the tasks of the runtime. With a runtime that
+ * runs tasks on several threads, concurrent assertions on one tester must be
+ * serialized, e.g. by a SharedMCAPTester or a strand. Programs using this
+ * header must be built with thread support, e.g. -pthread.
+ */
+
+#ifndef MCAP_TESTER_ASYNC_HPP
+#define MCAP_TESTER_ASYNC_HPP
+
+#include <algorithm>
+#include <chrono>
+#include <condition_variable>
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <future>
+#include <memory>
+#include <mutex>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+namespace Tester {
+
+namespace Async {
+
+typedef std::chrono::steady_clock Clock;
+
+/**
+ * @brief Runs a task once, no earlier than `delay` from now, without
+ * blocking the caller.
+ */
+typedef std::function<void(const std::function<void()> &task,
+                           Clock::duration delay)>
+    Scheduler;
+
+/**
+ * @brief A single-threaded runtime: tasks run in the order of their due
+ * time on the thread that calls run.
+ *
+ * Tasks may be scheduled from any thread, including from running tasks.
+ */
+class EventLoop {
+public:
+  EventLoop() : _mutex(), _changed(), _tasks(), _sequence(0) {}
+
+  /**
+   * @brief Returns a scheduler that adds tasks to this loop; the loop must
+   * outlive it.
+   */
+  Scheduler scheduler() {
+    EventLoop *loop = this;
+    return [loop](const std::function<void()> &task, Clock::duration delay) {
+      loop->schedule(task, delay);
+    };
+  }
+
+  /**
+   * @brief Adds a task that becomes due after `delay`.
+   */
+  void schedule(const std::function<void()> &task,
+                Clock::duration delay = Clock::duration::zero()) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tasks.push_back(Task(Clock::now() + delay, this->_sequence++, task));
+    std::push_heap(this->_tasks.begin(), this->_tasks.end(), Later());
+    this->_changed.notify_one();
+  }
+
+  /**
+   * @brief Waits until the earliest task is due and runs it.
+   *
+   * @return false if no task was scheduled.
+   */
+  bool run_one() {
+    std::function<void()> task;
+    {
+      std::unique_lock<std::mutex> lock(this->_mutex);
+      if (this->_tasks.empty()) {
+        return false;
+      }
+      /* A task scheduled meanwhile from another thread may be due earlier. */
+      while (Clock::now() < this->_tasks.front().due) {
+        this->_changed.wait_until(lock, this->_tasks.front().due);
+      }
+      std::pop_heap(this->_tasks.begin(), this->_tasks.end(), Later());
+      task = this->_tasks.back().task;
+      this->_tasks.pop_back();
+    }
+    task();
+    return true;
+  }
+
+  /**
+   * @brief Runs tasks until none is left.
+   */
+  void run() {
+    while (this->run_one()) {
+    }
+  }
+
+  /**
+   * @brief Returns the number of tasks that have not run yet.
+   */
+  std::size_t pending() const {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    return this->_tasks.size();
+  }
+
+protected:
+  struct Task {
+    Task(Clock::time_point due, std::uint64_t sequence,
+         const std::function<void()> &task)
+        : due(due), sequence(sequence), task(task) {}
+
+    Clock::time_point due;
+    std::uint64_t sequence;
+    std::function<void()> task;
+  };
+
+  /* Orders the heap so that the earliest task, and among equally due tasks
+   * the first scheduled, is on top. */
+  struct Later {
+    bool operator()(const Task &a, const Task &b) const {
+      return a.due != b.due ? a.due > b.due : a.sequence > b.sequence;
+    }
+  };
+
+  mutable std::mutex _mutex;
+  std::condition_variable _changed;
+  std::vector<Task> _tasks;
+  std::uint64_t _sequence;
+};
+
+namespace Detail {
+
+/* The state of one expect_eventually, shared by its poll tasks. */
+template <typename T> struct Poll {
+  Poll(const Scheduler &scheduler, MCAPTester<T> &tester,
+       const std::function<bool()> &predicate, Clock::duration interval,
+       Clock::duration timeout, const std::string &message,
+       const std::function<void(bool)> &then)
+      : scheduler(scheduler), tester(tester), predicate(predicate),
+        interval(interval), start(Clock::now()), timeout(timeout),
+        message(message), then(then), polls(0), verdict() {}
+
+  Scheduler scheduler;
+  MCAPTester<T> &tester;
+  std::function<bool()> predicate;
+  Clock::duration interval;
+  Clock::time_point start;
+  Clock::duration timeout;
+  std::string message;
+  std::function<void(bool)> then;
+  std::size_t polls;
+  std::promise<bool> verdict;
+};
+
+template <typename T> inline void poll(const std::shared_ptr<Poll<T>> &state) {
+  state->polls++;
+  bool held = state->predicate();
+  Clock::duration elapsed = Clock::now() - state->start;
+  if (!held && elapsed < state->timeout) {
+    state->scheduler([state]() { poll(state); }, state->interval);
+    return;
+  }
+
+  std::string detail =
+      (held ? "Held after " : "Did not hold within ") +
+      format_number(std::chrono::duration<double>(elapsed).count()) + " s (" +
+      std::to_string(state->polls) + " polls, timeout " +
+      format_number(std::chrono::duration<double>(state->timeout).count()) +
+      " s).";
+  state->tester.expect_true(held, state->message, detail,
+                            FailureCategory::Timing);
+  state->verdict.set_value(held);
+  if (state->then) {
+    state->then(held);
+  }
+}
+
+} // namespace Detail
+
+/**
+ * @brief Checks that a predicate holds within a timeout, polling it as tasks
+ * of a runtime.
+ *
+ * The predicate is first evaluated as soon as the runtime runs the first
+ * task, then after every poll interval until it holds or the timeout has
+ * elapsed. The verdict is reported to the tester once, with the elapsed
+ * time and the number of polls; a timeout is a timing failure.
+ *
+ * @param scheduler The runtime that runs the polls.
+ * @param tester The tester that receives the verdict; it must outlive the
+ * polls.
+ * @param predicate The condition to wait for; it must not block.
+ * @param poll_interval The delay between two evaluations.
+ * @param timeout The time after which the check fails.
+ * @param message The message to display in case of failure.
+ * @param then Called with the verdict after it was reported, e.g. to
+ * continue the test or to signal its completion.
+ * @return The verdict. Waiting for it on a thread of the runtime blocks the
+ * polls; continue with `then` there instead.
+ */
+template <typename T>
+inline std::future<bool>
+expect_eventually(const Scheduler &scheduler, MCAPTester<T> &tester,
+                  const std::function<bool()> &predicate,
+                  Clock::duration poll_interval, Clock::duration timeout,
+                  const std::string &message,
+                  const std::function<void(bool)> &then = nullptr) {
+  std::shared_ptr<Detail::Poll<T>> state = std::make_shared<Detail::Poll<T>>(
+      scheduler, tester, predicate, poll_interval, timeout, message, then);
+  std::future<bool> verdict = state->verdict.get_future();
+  scheduler([state]() { Detail::poll(state); }, Clock::duration::zero());
+  return verdict;
+}
+
+} // namespace Async
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_ASYNC_HPP
@@ -0,0 +1,3451 @@
+/**
+ * @file MCAP_tester_comparison.hpp
+ * @brief Provides the side-effect-free comparison core used by
+ * Tester::MCAPTester.
+ *
+ * The functions in the Tester::Comparison namespace never print and never
+ * touch any tester state. They return a ComparisonResult that describes the
+ * verdict and where and by how much the values differ. MCAPTester layers its
+ * reporting on top of these functions, so code that needs to branch on the
+ * exact comparison semantics can call them directly instead of duplicating
+ * them.
+ *
+ * Defining MCAP_TESTER_ENABLE_PARALLEL makes compare_arrays split large
+ * arrays across std::thread workers; the program must then be built with
+ * thread support, e.g. -pthread.
+ */
+
+#ifndef MCAP_TESTER_COMPARISON_HPP
+#define MCAP_TESTER_COMPARISON_HPP
+
+#include <algorithm>
+#include <cmath>
+#include <complex>
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <limits>
+#include <map>
+#include <string>
+#include <type_traits>
+#include <utility>
+#include <vector>
+
+#ifdef MCAP_TESTER_ENABLE_PARALLEL
+#include <thread>
+#endif
+
+#include "MCAP_tester_flat_array.hpp"
+#include "MCAP_tester_numerics.hpp"
+#include "MCAP_tester_reference.hpp"
+
+#ifndef MCAP_TESTER_PARALLEL_MIN_ELEMENTS
+#define MCAP_TESTER_PARALLEL_MIN_ELEMENTS 65536
+#endif
+
+namespace Tester {
+
+enum class ComparisonStatus { Pass, Warning, Failure };
+
+enum class MismatchKind {
+  None,
+  SizeMismatch,
+  LayoutMismatch,
+  ElementMismatch
+};
+
+/**
+ * @brief How strictly the shapes of two arrays must agree.
+ *
+ * Exact requires identical shapes. Broadcast stretches the expected array
+ * onto the shape of the actual array following NumPy's broadcasting rules, so
+ * a row, a column or a scalar can be compared against every row, column or
+ * element. Strict requires identical shapes and identical source memory
+ * layouts.
+ */
+enum class ShapePolicy { Exact, Broadcast, Strict };
+
+/**
+ * @brief The axis along which a two-dimensional array is reduced.
+ *
+ * Rows reduces every row to a single value, giving one result per row.
+ * Columns reduces every column to a single value, giving one result per
+ * column.
+ */
+enum class ReductionAxis { Rows, Columns };
+
+/**
+ * @brief The matrix norm used by norm-based comparisons.
+ *
+ * Frobenius is the square root of the sum of all squared elements. Spectral
+ * is the largest singular value; for one-dimensional arrays both equal the
+ * Euclidean norm.
+ */
+enum class MatrixNorm { Frobenius, Spectral };
+
+/**
+ * @brief The unit in which angles are given.
+ */
+enum class AngleUnit { Radians, Degrees };
+
+/**
+ * @brief Whether poles belong to a continuous-time or a discrete-time system.
+ */
+enum class StabilityDomain { Continuous, Discrete };
+
+enum class ArrayShapeClass { Scalar, Empty, SingleElement, Regular };
+
+/**
+ * @brief Outcome applied to degenerate (empty or single-element) arrays.
+ */
+enum class DegenerateArrayPolicy { Pass, Warn, Fail };
+
+/**
+ * @brief Whether a NaN compares equal to a NaN at the same position.
+ */
+enum class NanPolicy { Fail, MatchNan };
+
+/**
+ * @brief The actuator effort integrated over a command signal: the integral
+ * of |u| (e.g. fuel or charge) or of u^2 (e.g. energy).
+ */
+enum class EffortMeasure { Absolute, Squared };
+
+/**
+ * @brief The paired test used to compare repeated-run metrics.
+ *
+ * PairedT is Student's t-test on the paired differences, which assumes they
+ * are roughly normal. Wilcoxon is the signed-rank test, which only assumes a
+ * symmetric distribution and is robust against the outliers typical of
+ * timing measurements.
+ */
+enum class SignificanceTest { PairedT, Wilcoxon };
+
+/**
+ * @brief A tolerance combining an absolute, a relative and a ULP bound.
+ *
+ * A value passes if |actual - expected| <= absolute + relative * |expected|,
+ * or if actual and expected are at most `ulps` representable values apart
+ * (see Numerics::ulp_distance). The relative bound suits values of widely
+ * varying magnitude, the ULP bound results that should be exact up to
+ * rounding; integers have no ULP bound. A plain number converts implicitly
+ * to an absolute tolerance, so every check taking a Tolerance also accepts
+ * the numbers used so far.
+ *
+ * Example:
+ *   tester.expect_near(y, y_ref, Tolerance<double>::relative(1e-9), "y");
+ *   tester.expect_near(z, z_ref, Tolerance<double>::ulps(4), "z");
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> class Tolerance {
+public:
+  Tolerance()
+      : _absolute(static_cast<T>(0)), _relative(static_cast<T>(0)), _ulps(0) {
+  }
+
+  Tolerance(T absolute)
+      : _absolute(absolute), _relative(static_cast<T>(0)), _ulps(0) {}
+
+  static Tolerance absolute(T tolerance) { return Tolerance(tolerance); }
+
+  static Tolerance relative(T tolerance) {
+    return absolute_relative(static_cast<T>(0), tolerance);
+  }
+
+  static Tolerance absolute_relative(T absolute, T relative) {
+    Tolerance tolerance(absolute);
+    tolerance._relative = relative;
+    return tolerance;
+  }
+
+  static Tolerance ulps(std::uint64_t count) {
+    Tolerance tolerance;
+    tolerance._ulps = count;
+    return tolerance;
+  }
+
+  /**
+   * @brief Returns a copy that additionally accepts values at most `count`
+   * ULP apart, e.g. Tolerance<double>::absolute(1e-12).or_ulps(4).
+   */
+  Tolerance or_ulps(std::uint64_t count) const {
+    Tolerance tolerance = *this;
+    tolerance._ulps = count;
+    return tolerance;
+  }
+
+  /**
+   * @brief Returns true if an actual value is within the tolerance of an
+   * expected value. NaN never is.
+   */
+  bool accepts(T actual, T expected) const {
+    T bound = this->_absolute;
+    if (this->_relative != static_cast<T>(0)) {
+      bound += this->_relative * std::abs(expected);
+    }
+    if (std::abs(actual - expected) <= bound) {
+      return true;
+    }
+    return this->_ulps > 0 &&
+           this->_within_ulps(actual, expected,
+                              std::is_floating_point<T>());
+  }
+
+  /**
+   * @brief Returns false if a bound is negative or NaN.
+   */
+  bool valid() const {
+    return this->_absolute >= static_cast<T>(0) &&
+           this->_relative >= static_cast<T>(0);
+  }
+
+  /**
+   * @brief Returns true if only the absolute bound is set.
+   */
+  bool is_absolute() const {
+    return this->_relative == static_cast<T>(0) && this->_ulps == 0;
+  }
+
+  /**
+   * @brief Returns a copy with every bound multiplied by a factor; the ULP
+   * count is rounded up.
+   */
+  Tolerance scaled(double factor) const {
+    Tolerance tolerance = *this;
+    tolerance._absolute =
+        static_cast<T>(static_cast<double>(this->_absolute) * factor);
+    tolerance._relative =
+        static_cast<T>(static_cast<double>(this->_relative) * factor);
+    tolerance._ulps = static_cast<std::uint64_t>(
+        std::ceil(static_cast<double>(this->_ulps) * factor));
+    return tolerance;
+  }
+
+  /**
+   * @brief Returns the tolerance as text, e.g. "1e-09 + 1e-06 * |expected|"
+   * or "4 ULP".
+   */
+  std::string describe() const {
+    std::string text;
+    if (this->_absolute != static_cast<T>(0) ||
+        (this->_relative == static_cast<T>(0) && this->_ulps == 0)) {
+      text = format_number(this->_absolute);
+    }
+    if (this->_relative != static_cast<T>(0)) {
+      text += (text.empty() ? "" : " + ") + format_number(this->_relative) +
+              " * |expected|";
+    }
+    if (this->_ulps > 0) {
+      text += (text.empty() ? "" : " or ") + std::to_string(this->_ulps) +
+              " ULP";
+    }
+    return text;
+  }
+
+  T absolute_part() const { return this->_absolute; }
+  T relative_part() const { return this->_relative; }
+  std::uint64_t ulp_count() const { return this->_ulps; }
+
+protected:
+  template <typename U>
+  bool _within_ulps(U actual, U expected, std::true_type) const {
+    return Numerics::ulp_distance(actual, expected) <= this->_ulps;
+  }
+
+  /* Integers have no ULP spacing; the ULP bound accepts nothing extra. */
+  template <typename U>
+  bool _within_ulps(U, U, std::false_type) const {
+    return false;
+  }
+
+  T _absolute;
+  T _relative;
+  std::uint64_t _ulps;
+};
+
+#ifndef MCAP_TESTER_NO_STDIO
+/**
+ * @brief Converts a JSON tolerance into a Tolerance.
+ *
+ * A number is an absolute tolerance; an object may combine the keys
+ * "absolute", "relative" and "ulps".
+ *
+ * @param value The JSON value to convert.
+ * @param tolerance Receives the converted tolerance.
+ * @return true if the value is a number or an object whose known keys hold
+ * non-negative numbers and which has at least one of them; "ulps" must be
+ * an integer below 2^64.
+ */
+template <typename T>
+inline bool json_to_tolerance(const JSON::Value &value,
+                              Tolerance<T> &tolerance) {
+  if (value.is_number()) {
+    tolerance = Tolerance<T>(static_cast<T>(value.as_number()));
+    return true;
+  }
+  if (!value.is_object()) {
+    return false;
+  }
+
+  const char *keys[] = {"absolute", "relative", "ulps"};
+  double bounds[] = {0.0, 0.0, 0.0};
+  bool found = false;
+  for (std::size_t k = 0; k < 3; k++) {
+    if (!value.has(keys[k])) {
+      continue;
+    }
+    const JSON::Value &bound = value.get(keys[k]);
+    if (!bound.is_number() || !(bound.as_number() >= 0.0)) {
+      return false;
+    }
+    /* A ULP count is cast to std::uint64_t, which requires an integer in
+     * range. */
+    if (k == 2 && !(std::floor(bound.as_number()) == bound.as_number() &&
+                    bound.as_number() < 18446744073709551616.0)) {
+      return false;
+    }
+    bounds[k] = bound.as_number();
+    found = true;
+  }
+  if (!found) {
+    return false;
+  }
+
+  tolerance = Tolerance<T>::absolute_relative(static_cast<T>(bounds[0]),
+                                              static_cast<T>(bounds[1]))
+                  .or_ulps(static_cast<std::uint64_t>(bounds[2]));
+  return true;
+}
+#endif
+
+/**
+ * @brief Structured outcome of a comparison.
+ *
+ * For element-wise comparisons, `index` is the row-major index of the first
+ * element beyond the failure tolerance (or beyond the warning tolerance, if
+ * no element failed), and `actual_value`, `expected_value` and `error` refer
+ * to that element. `max_error` is the largest absolute difference over all
+ * elements and `mismatch_count` the number of elements beyond the failure
+ * tolerance. `max_relative_error` is the largest absolute difference
+ * relative to the magnitude of the expected value, infinite if an expected
+ * zero is missed; it is NaN for comparisons without a relative measure, such
+ * as angles. It is a double so that integer comparisons keep the fraction.
+ * Thus `max_error` and `max_relative_error` are the smallest
+ * absolute and relative tolerances the comparison passes with. These fields
+ * are only meaningful when the shapes agree.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct ComparisonResult {
+  ComparisonResult()
+      : status(ComparisonStatus::Pass), kind(MismatchKind::None), index(0),
+        actual_value(static_cast<T>(0)), expected_value(static_cast<T>(0)),
+        error(static_cast<T>(0)), max_error(static_cast<T>(0)),
+        mismatch_count(0), max_relative_error(0.0) {}
+
+  /**
+   * @brief Returns true unless the comparison failed. Warnings pass.
+   */
+  bool passed() const { return this->status != ComparisonStatus::Failure; }
+
+  /**
+   * @brief Returns the short description printed after the failure message.
+   */
+  std::string describe() const {
+    if (this->kind == MismatchKind::SizeMismatch) {
+      return "Size mismatch.";
+    } else if (this->kind == MismatchKind::LayoutMismatch) {
+      return "Layout mismatch.";
+    } else if (this->status == ComparisonStatus::Failure) {
+      return "Element mismatch.";
+    } else if (this->status == ComparisonStatus::Warning) {
+      return "Element outside warning tolerance.";
+    } else {
+      return "";
+    }
+  }
+
+  ComparisonStatus status;
+  MismatchKind kind;
+  std::size_t index;
+  T actual_value;
+  T expected_value;
+  T error;
+  T max_error;
+  std::size_t mismatch_count;
+  double max_relative_error;
+};
+
+/**
+ * @brief Structured outcome of a trajectory comparison.
+ *
+ * Rows are time samples and columns are signals. The inherited fields
+ * describe the comparison over the samples after the settling window, with
+ * `index` being the row-major index of the first failing element. The worst
+ * element over that range is given by `worst_sample` and `worst_signal`, and
+ * `envelope` holds the maximum absolute error of every sample, including the
+ * settling window, so the error can be inspected over time.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct TrajectoryResult : public ComparisonResult<T> {
+  TrajectoryResult()
+      : ComparisonResult<T>(), worst_sample(0), worst_signal(0),
+        first_violation_sample(0), last_violation_sample(0), envelope() {}
+
+  std::size_t worst_sample;
+  std::size_t worst_signal;
+  std::size_t first_violation_sample;
+  std::size_t last_violation_sample;
+  std::vector<T> envelope;
+};
+
+/**
+ * @brief Structured outcome of an adaptive trajectory comparison.
+ *
+ * Only the samples that were actually compared contribute to the inherited
+ * fields; `envelope` is zero for samples that were skipped.
+ * `samples_compared` counts the compared samples and `refined_samples` those
+ * compared during the full-resolution pass.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T>
+struct AdaptiveTrajectoryResult : public TrajectoryResult<T> {
+  AdaptiveTrajectoryResult()
+      : TrajectoryResult<T>(), samples_compared(0), refined_samples(0) {}
+
+  std::size_t samples_compared;
+  std::size_t refined_samples;
+};
+
+/**
+ * @brief Structured outcome of a comparison of two time series sampled on
+ * different time bases.
+ *
+ * The inherited fields refer to the samples of the actual series, compared
+ * with the expected series interpolated at their time stamps: `index` is the
+ * first failing actual sample and `expected_value` the interpolated value
+ * there. `worst_time` is the time stamp of the largest error.
+ * `samples_compared` counts the actual samples within the time range of the
+ * expected series; samples outside it are not compared.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct ResampledResult : public ComparisonResult<T> {
+  ResampledResult()
+      : ComparisonResult<T>(), worst_time(static_cast<T>(0)),
+        samples_compared(0) {}
+
+  T worst_time;
+  std::size_t samples_compared;
+};
+
+/**
+ * @brief Aggregate error metrics between two arrays of equal shape.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct ErrorMetrics {
+  ErrorMetrics()
+      : shape_matches(false), count(0), rmse(static_cast<T>(0)),
+        mae(static_cast<T>(0)), max_error(static_cast<T>(0)) {}
+
+  bool shape_matches;
+  std::size_t count;
+  T rmse;
+  T mae;
+  T max_error;
+};
+
+/**
+ * @brief Outcome of a structural matrix property check.
+ *
+ * When the property is violated, `row` and `column` locate the offending
+ * entry (for eigenvalue-based checks, `row` is the index of the offending
+ * eigenvalue in ascending order), `value` is the measured quantity and
+ * `bound` the limit it should have respected.
+ *
+ * @tparam T Type of the matrix elements.
+ */
+template <typename T> struct PropertyResult {
+  PropertyResult()
+      : satisfied(true), square(true), row(0), column(0),
+        value(static_cast<T>(0)), bound(static_cast<T>(0)) {}
+
+  bool satisfied;
+  bool square;
+  std::size_t row;
+  std::size_t column;
+  T value;
+  T bound;
+};
+
+/**
+ * @brief Outcome of an order-insensitive comparison of two value sets.
+ *
+ * `assignment[i]` is the index of the expected value matched to actual value
+ * i, or `expected_count` if the actual value stayed unmatched. The unmatched
+ * indices of both sides are listed in ascending order.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct UnorderedMatchResult {
+  UnorderedMatchResult()
+      : size_matches(true), expected_count(0), assignment(),
+        unmatched_actual(), unmatched_expected() {}
+
+  bool passed() const {
+    return this->size_matches && this->unmatched_actual.empty() &&
+           this->unmatched_expected.empty();
+  }
+
+  bool size_matches;
+  std::size_t expected_count;
+  std::vector<std::size_t> assignment;
+  std::vector<std::size_t> unmatched_actual;
+  std::vector<std::size_t> unmatched_expected;
+};
+
+/**
+ * @brief Per-channel fraction of samples within tolerance.
+ *
+ * Rows are samples and columns are channels; a one-dimensional array is a
+ * single channel. `passed_samples[j]` counts the samples of channel j that
+ * are within tolerance and `pass_rates[j]` is that count divided by
+ * `samples`.
+ */
+struct PassRates {
+  PassRates()
+      : shape_matches(false), samples(0), passed_samples(), pass_rates() {}
+
+  bool shape_matches;
+  std::size_t samples;
+  std::vector<std::size_t> passed_samples;
+  std::vector<double> pass_rates;
+};
+
+/**
+ * @brief Statistics of measured durations in seconds.
+ *
+ * `p99` is the 99th percentile by the nearest-rank method, so with fewer
+ * than 100 samples it is the maximum.
+ */
+struct RuntimeStatistics {
+  RuntimeStatistics()
+      : count(0), mean(0.0), p99(0.0), minimum(0.0), maximum(0.0) {}
+
+  std::size_t count;
+  double mean;
+  double p99;
+  double minimum;
+  double maximum;
+};
+
+/**
+ * @brief One element that lies outside tolerance.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct ElementError {
+  ElementError()
+      : index(0), actual_value(static_cast<T>(0)),
+        expected_value(static_cast<T>(0)), error(static_cast<T>(0)) {}
+
+  std::size_t index;
+  T actual_value;
+  T expected_value;
+  T error;
+};
+
+/**
+ * @brief The elements with the largest errors of an array comparison.
+ *
+ * `count` is the total number of elements outside tolerance; `elements`
+ * holds at most the requested number of them, largest error first.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct WorstElements {
+  WorstElements() : count(0), elements() {}
+
+  std::size_t count;
+  std::vector<ElementError<T>> elements;
+};
+
+/**
+ * @brief The declared range of one input of a function under test.
+ *
+ * @tparam T Type of the input.
+ */
+template <typename T> struct InputRange {
+  InputRange() : lower(static_cast<T>(0)), upper(static_cast<T>(0)) {}
+
+  InputRange(T lower, T upper) : lower(lower), upper(upper) {}
+
+  T lower;
+  T upper;
+};
+
+/**
+ * @brief One evaluation point of a boundary sweep.
+ *
+ * `inputs` holds every input of the function; the input at `input_index` is
+ * placed at or next to one of its bounds, as described by `label`, and all
+ * other inputs are at the middle of their ranges.
+ *
+ * @tparam T Type of the inputs.
+ */
+template <typename T> struct BoundaryProbe {
+  BoundaryProbe() : input_index(0), label(), inputs() {}
+
+  std::size_t input_index;
+  std::string label;
+  std::vector<T> inputs;
+};
+
+/**
+ * @brief Where and at which value a signal settles.
+ *
+ * If `settled`, the signal stays settled from sample `index` to its end and
+ * `value` is the mean of those samples. `window_sigma` is the standard
+ * deviation of the last window that is not settled, or of the last window of
+ * the signal if it never settles.
+ *
+ * @tparam T Type of the signal.
+ */
+template <typename T> struct SteadyState {
+  SteadyState()
+      : settled(false), index(0), value(static_cast<T>(0)),
+        window_sigma(static_cast<T>(0)) {}
+
+  bool settled;
+  std::size_t index;
+  T value;
+  T window_sigma;
+};
+
+/**
+ * @brief A sustained oscillation found in the tail of a signal.
+ *
+ * `detected` is true if the tail is periodic with a period of `period`
+ * samples, at which lag its normalized autocorrelation is `correlation`.
+ * `amplitude` is half the peak-to-peak range of the tail, reported whether
+ * or not an oscillation was detected.
+ *
+ * @tparam T Type of the signal.
+ */
+template <typename T> struct LimitCycle {
+  LimitCycle()
+      : detected(false), period(0), amplitude(static_cast<T>(0)),
+        correlation(static_cast<T>(0)) {}
+
+  bool detected;
+  std::size_t period;
+  T amplitude;
+  T correlation;
+};
+
+/**
+ * @brief Element-wise spread of repeated runs of the same scenario.
+ *
+ * `sigmas[i]` is the population standard deviation of element i across all
+ * runs; `worst_index` is the element with the largest spread, which is
+ * `max_sigma`. `size_matches` is false if the runs produced outputs of
+ * different lengths.
+ *
+ * @tparam T Type of the outputs.
+ */
+template <typename T> struct RunSpread {
+  RunSpread()
+      : size_matches(false), sigmas(), max_sigma(static_cast<T>(0)),
+        worst_index(0) {}
+
+  bool size_matches;
+  std::vector<T> sigmas;
+  T max_sigma;
+  std::size_t worst_index;
+};
+
+/**
+ * @brief The worst integrator windup event found in a controller log.
+ *
+ * The event spans the samples `start` to `end` (inclusive) of one saturation
+ * episode; `windup` is the integrator excess, in the unit of the integrator
+ * output, that had to be unwound while the error already pushed the command
+ * back from the limit. `found` is false if the log contains no such event.
+ *
+ * @tparam T Type of the signals.
+ */
+template <typename T> struct WindupEvent {
+  WindupEvent()
+      : found(false), start(0), end(0), windup(static_cast<T>(0)) {}
+
+  bool found;
+  std::size_t start;
+  std::size_t end;
+  T windup;
+};
+
+/**
+ * @brief Structured outcome of a phasor comparison.
+ *
+ * If the comparison failed, `index` is the first offending phasor and
+ * `phase_failed` tells whether its phase rather than its magnitude was out of
+ * tolerance; `magnitude_error` and `phase_error` are the errors at that
+ * phasor, in the unit of the magnitude and in degrees.
+ *
+ * @tparam T Type of the real and imaginary parts.
+ */
+template <typename T> struct PhasorResult {
+  PhasorResult()
+      : status(ComparisonStatus::Pass), kind(MismatchKind::None), index(0),
+        magnitude_error(static_cast<T>(0)), phase_error(static_cast<T>(0)),
+        phase_failed(false), max_magnitude_error(static_cast<T>(0)),
+        max_phase_error(static_cast<T>(0)) {}
+
+  bool passed() const { return this->status != ComparisonStatus::Failure; }
+
+  ComparisonStatus status;
+  MismatchKind kind;
+  std::size_t index;
+  T magnitude_error;
+  T phase_error;
+  bool phase_failed;
+  T max_magnitude_error;
+  T max_phase_error;
+};
+
+/**
+ * @brief Structured outcome of a spectrum comparison.
+ *
+ * `worst_frequency` is the frequency of the bin with the largest magnitude
+ * error, `magnitude_error` that error and `phase_error` the largest wrapped
+ * phase difference in radians over the bins whose phase was compared. If the
+ * comparison failed, `failed_frequency` is the first offending bin and
+ * `phase_failed` tells whether its phase rather than its magnitude was out of
+ * tolerance.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct SpectrumResult {
+  SpectrumResult()
+      : status(ComparisonStatus::Pass), kind(MismatchKind::None),
+        bins_compared(0), worst_frequency(static_cast<T>(0)),
+        magnitude_error(static_cast<T>(0)), phase_error(static_cast<T>(0)),
+        failed_frequency(static_cast<T>(0)), phase_failed(false) {}
+
+  bool passed() const { return this->status != ComparisonStatus::Failure; }
+
+  ComparisonStatus status;
+  MismatchKind kind;
+  std::size_t bins_compared;
+  T worst_frequency;
+  T magnitude_error;
+  T phase_error;
+  T failed_frequency;
+  bool phase_failed;
+};
+
+/**
+ * @brief Outcome of a one-sided paired significance test of whether sample
+ * B is greater than sample A.
+ *
+ * `samples` is the number of pairs the test used (the Wilcoxon test drops
+ * pairs without difference), `mean_difference` the mean of B - A over all
+ * pairs and `statistic` the t statistic or the signed-rank sum W+ of the
+ * positive differences. `p_value` is the probability of a statistic at least
+ * as large if B and A are equal in distribution. `valid` is false if the
+ * samples have different lengths or fewer than two pairs.
+ */
+struct SignificanceResult {
+  SignificanceResult()
+      : valid(false), samples(0), mean_difference(0.0), statistic(0.0),
+        p_value(1.0) {}
+
+  bool valid;
+  std::size_t samples;
+  double mean_difference;
+  double statistic;
+  double p_value;
+};
+
+/**
+ * @brief The sample mean and unbiased sample variance of a set of samples.
+ *
+ * `valid` is false for fewer than two samples.
+ */
+template <typename T> struct SampleMoments {
+  SampleMoments()
+      : valid(false), samples(0), mean(static_cast<T>(0)),
+        variance(static_cast<T>(0)) {}
+
+  bool valid;
+  std::size_t samples;
+  T mean;
+  T variance;
+};
+
+/**
+ * @brief The outcome of a two-sample Kolmogorov-Smirnov test.
+ *
+ * `statistic` is the largest distance D between the empirical distribution
+ * functions of the samples, reached at the sample value `location`, and
+ * `p_value` the asymptotic probability of a distance at least as large if
+ * both samples come from the same distribution. `valid` is false if a sample
+ * is empty or contains NaN.
+ */
+template <typename T> struct KolmogorovSmirnovResult {
+  KolmogorovSmirnovResult()
+      : valid(false), statistic(0.0), location(static_cast<T>(0)),
+        p_value(1.0) {}
+
+  bool valid;
+  double statistic;
+  T location;
+  double p_value;
+};
+
+/**
+ * @brief The outcome of an exact comparison of integer arrays.
+ *
+ * `mismatches` holds the row-major index of every element that differs; it
+ * is only meaningful when the shapes agree.
+ */
+struct IntegerComparisonResult {
+  IntegerComparisonResult() : shape_matches(false), mismatches() {}
+
+  bool passed() const {
+    return this->shape_matches && this->mismatches.empty();
+  }
+
+  bool shape_matches;
+  std::vector<std::size_t> mismatches;
+};
+
+/**
+ * @brief The comparison of one signal of a named-signal bundle.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct SignalComparison {
+  SignalComparison() : name(), result() {}
+
+  std::string name;
+  ComparisonResult<T> result;
+};
+
+/**
+ * @brief Structured outcome of the comparison of two bundles of named
+ * signals.
+ *
+ * `missing` lists the expected signals the actual bundle lacks, `extra` the
+ * actual signals that are not expected, and `signals` the comparison of
+ * every signal in both bundles, all ordered by name.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct SignalBundleResult {
+  SignalBundleResult() : missing(), extra(), signals() {}
+
+  /**
+   * @brief Returns true if both bundles hold the same signals and every
+   * signal passed.
+   */
+  bool passed() const {
+    if (!this->missing.empty() || !this->extra.empty()) {
+      return false;
+    }
+    for (std::size_t i = 0; i < this->signals.size(); i++) {
+      if (!this->signals[i].result.passed()) {
+        return false;
+      }
+    }
+    return true;
+  }
+
+  std::vector<std::string> missing;
+  std::vector<std::string> extra;
+  std::vector<SignalComparison<T>> signals;
+};
+
+namespace Comparison {
+
+/**
+ * @brief Checks whether two flattened arrays have identical, non-ragged
+ * shapes.
+ */
+template <typename T>
+inline bool same_shape(const FlatArray<T> &actual,
+                       const FlatArray<T> &expected) {
+  return !actual.ragged && !expected.ragged &&
+         actual.shape == expected.shape &&
+         actual.data.size() == expected.data.size();
+}
+
+/**
+ * @brief Classifies a flattened array as a scalar, an empty array (including
+ * 0xN and Nx0 shapes), a single-element array or a regular array.
+ */
+template <typename T>
+inline ArrayShapeClass classify_shape(const FlatArray<T> &values) {
+  if (values.shape.empty()) {
+    return ArrayShapeClass::Scalar;
+  } else if (values.data.empty()) {
+    return ArrayShapeClass::Empty;
+  } else if (values.data.size() == 1) {
+    return ArrayShapeClass::SingleElement;
+  } else {
+    return ArrayShapeClass::Regular;
+  }
+}
+
+namespace Detail {
+
+/* Returns an absolute error relative to the magnitude of the expected value,
+ * infinite if only the expected value is zero. Computed in double, since an
+ * integer quotient would truncate to zero. */
+template <typename T> inline double relative_error(T error, T expected) {
+  double absolute = static_cast<double>(error);
+  if (std::isnan(absolute) || absolute == 0.0) {
+    return absolute;
+  }
+  double magnitude = std::abs(static_cast<double>(expected));
+  if (magnitude > 0.0) {
+    return absolute / magnitude;
+  }
+  return std::numeric_limits<double>::infinity();
+}
+
+/* Updates the largest relative error of a result; NaN sticks. */
+template <typename T>
+inline void track_relative_error(ComparisonResult<T> &result, T error,
+                                 T expected) {
+  double relative = relative_error(error, expected);
+  if (!std::isnan(result.max_relative_error) &&
+      (std::isnan(relative) || relative > result.max_relative_error)) {
+    result.max_relative_error = relative;
+  }
+}
+
+template <typename T>
+inline ComparisonResult<T>
+compare_range(const FlatArray<T> &actual, const FlatArray<T> &expected,
+              T warning_tolerance, T failure_tolerance, std::size_t begin,
+              std::size_t end) {
+  ComparisonResult<T> result;
+
+  for (std::size_t i = begin; i < end; i++) {
+    T error = std::abs(actual.data[i] - expected.data[i]);
+
+    if (std::isnan(error)) {
+      result.max_error = error;
+    } else if (error > result.max_error) {
+      result.max_error = error;
+    }
+    track_relative_error(result, error, expected.data[i]);
+
+    if (error <= warning_tolerance) {
+      continue;
+    } else if (error <= failure_tolerance) {
+      if (result.status != ComparisonStatus::Pass) {
+        continue;
+      }
+      result.status = ComparisonStatus::Warning;
+    } else {
+      result.mismatch_count++;
+      if (result.status == ComparisonStatus::Failure) {
+        continue;
+      }
+      result.status = ComparisonStatus::Failure;
+    }
+
+    result.kind = MismatchKind::ElementMismatch;
+    result.index = i;
+    result.actual_value = actual.data[i];
+    result.expected_value = expected.data[i];
+    result.error = error;
+  }
+
+  return result;
+}
+
+/* Merges the result of a later range into the result of the ranges before
+ * it, giving the same verdict as one pass over both. */
+template <typename T>
+inline void merge_range(ComparisonResult<T> &result,
+                        const ComparisonResult<T> &later) {
+  if (std::isnan(later.max_error) ||
+      (!std::isnan(result.max_error) && later.max_error > result.max_error)) {
+    result.max_error = later.max_error;
+  }
+  if (std::isnan(later.max_relative_error) ||
+      (!std::isnan(result.max_relative_error) &&
+       later.max_relative_error > result.max_relative_error)) {
+    result.max_relative_error = later.max_relative_error;
+  }
+  result.mismatch_count += later.mismatch_count;
+
+  if (result.status == ComparisonStatus::Failure ||
+      later.status == ComparisonStatus::Pass ||
+      (result.status == ComparisonStatus::Warning &&
+       later.status == ComparisonStatus::Warning)) {
+    return;
+  }
+
+  result.status = later.status;
+  result.kind = later.kind;
+  result.index = later.index;
+  result.actual_value = later.actual_value;
+  result.expected_value = later.expected_value;
+  result.error = later.error;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Compares two flattened arrays against separate warning and failure
+ * tolerances.
+ *
+ * An element fails if its absolute difference is not within
+ * `failure_tolerance` (so NaN differences always fail) and warns if it is
+ * within `failure_tolerance` but not within `warning_tolerance`. All elements
+ * are examined, so the result also carries the maximum error and the number
+ * of failing elements.
+ *
+ * When MCAP_TESTER_ENABLE_PARALLEL is defined, arrays with at least
+ * MCAP_TESTER_PARALLEL_MIN_ELEMENTS elements are split into contiguous
+ * ranges compared on separate threads. The ranges are merged in order, so
+ * the reported element is still the lowest-index mismatch and the result is
+ * identical to the single-threaded one.
+ *
+ * @tparam T Type of the array elements.
+ * @param actual The flattened array of actual values.
+ * @param expected The flattened array of expected values.
+ * @param warning_tolerance The difference above which an element warns.
+ * @param failure_tolerance The difference above which an element fails.
+ * @return The structured comparison result.
+ */
+template <typename T>
+inline ComparisonResult<T>
+compare_arrays(const FlatArray<T> &actual, const FlatArray<T> &expected,
+               T warning_tolerance, T failure_tolerance) {
+  if (!same_shape(actual, expected)) {
+    ComparisonResult<T> result;
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  std::size_t size = actual.data.size();
+#ifdef MCAP_TESTER_ENABLE_PARALLEL
+  std::size_t thread_count = std::thread::hardware_concurrency();
+  if (size >= MCAP_TESTER_PARALLEL_MIN_ELEMENTS && thread_count > 1) {
+    std::vector<ComparisonResult<T>> parts(thread_count);
+    std::vector<std::thread> threads;
+    std::size_t chunk = (size + thread_count - 1) / thread_count;
+    for (std::size_t t = 0; t < thread_count; t++) {
+      std::size_t begin = std::min(size, t * chunk);
+      std::size_t end = std::min(size, begin + chunk);
+      threads.push_back(std::thread([&, t, begin, end]() {
+        parts[t] = Detail::compare_range(actual, expected, warning_tolerance,
+                                         failure_tolerance, begin, end);
+      }));
+    }
+    for (std::size_t t = 0; t < thread_count; t++) {
+      threads[t].join();
+    }
+
+    ComparisonResult<T> result = parts[0];
+    for (std::size_t t = 1; t < thread_count; t++) {
+      Detail::merge_range(result, parts[t]);
+    }
+    return result;
+  }
+#endif
+
+  return Detail::compare_range(actual, expected, warning_tolerance,
+                               failure_tolerance, 0, size);
+}
+
+/**
+ * @brief Broadcasts an array onto a target shape following NumPy's rules.
+ *
+ * Shapes are aligned at their trailing dimension; each dimension of `values`
+ * must either equal the target dimension or be 1, and missing leading
+ * dimensions are treated as 1.
+ *
+ * @tparam T Type of the array elements.
+ * @param values The array to broadcast.
+ * @param shape The target shape.
+ * @param broadcast Receives the broadcast array.
+ * @return true if `values` can be broadcast to `shape`, false otherwise.
+ */
+template <typename T>
+inline bool broadcast_to(const FlatArray<T> &values,
+                         const std::vector<std::size_t> &shape,
+                         FlatArray<T> &broadcast) {
+  if (values.ragged || values.shape.size() > shape.size()) {
+    return false;
+  }
+
+  std::size_t offset = shape.size() - values.shape.size();
+  std::vector<std::size_t> source_shape(offset, 1);
+  source_shape.insert(source_shape.end(), values.shape.begin(),
+                      values.shape.end());
+  for (std::size_t d = 0; d < shape.size(); d++) {
+    if (source_shape[d] != shape[d] && source_shape[d] != 1) {
+      return false;
+    }
+  }
+
+  std::size_t count = 1;
+  for (std::size_t d = 0; d < shape.size(); d++) {
+    count *= shape[d];
+  }
+
+  FlatArray<T> result;
+  result.shape = shape;
+  result.layout = values.layout;
+  result.data.resize(count);
+
+  std::vector<std::size_t> index(shape.size(), 0);
+  for (std::size_t k = 0; k < count; k++) {
+    std::size_t source = 0;
+    for (std::size_t d = 0; d < shape.size(); d++) {
+      std::size_t source_index = (source_shape[d] == 1) ? 0 : index[d];
+      source = source * source_shape[d] + source_index;
+    }
+    result.data[k] = values.data[source];
+
+    for (std::size_t d = shape.size(); d-- > 0;) {
+      if (++index[d] < shape[d]) {
+        break;
+      }
+      index[d] = 0;
+    }
+  }
+
+  broadcast = result;
+  return true;
+}
+
+/**
+ * @brief Compares two flattened arrays element-wise within a tolerance
+ * under a shape policy.
+ *
+ * @tparam T Type of the array elements.
+ * @param actual The flattened array of actual values.
+ * @param expected The flattened array of expected values.
+ * @param tolerance The maximum allowed difference between corresponding
+ * elements.
+ * @param policy How the shapes of both arrays must agree.
+ * @return The structured comparison result.
+ */
+template <typename T>
+inline ComparisonResult<T>
+compare_arrays(const FlatArray<T> &actual, const FlatArray<T> &expected,
+               T tolerance, ShapePolicy policy) {
+  if (policy == ShapePolicy::Broadcast) {
+    FlatArray<T> broadcast;
+    if (actual.ragged || !broadcast_to(expected, actual.shape, broadcast)) {
+      ComparisonResult<T> result;
+      result.status = ComparisonStatus::Failure;
+      result.kind = MismatchKind::SizeMismatch;
+      return result;
+    }
+    return compare_arrays(actual, broadcast, tolerance, tolerance);
+  }
+
+  if (policy == ShapePolicy::Strict && same_shape(actual, expected) &&
+      actual.layout != expected.layout) {
+    ComparisonResult<T> result;
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::LayoutMismatch;
+    return result;
+  }
+
+  return compare_arrays(actual, expected, tolerance, tolerance);
+}
+
+/**
+ * @brief Compares two flattened arrays element-wise within a tolerance.
+ *
+ * @tparam T Type of the array elements.
+ * @param actual The flattened array of actual values.
+ * @param expected The flattened array of expected values.
+ * @param tolerance The maximum allowed difference between corresponding
+ * elements.
+ * @return The structured comparison result.
+ */
+template <typename T>
+inline ComparisonResult<T> compare_arrays(const FlatArray<T> &actual,
+                                          const FlatArray<T> &expected,
+                                          T tolerance) {
+  return compare_arrays(actual, expected, tolerance, tolerance);
+}
+
+/**
+ * @brief Compares two flattened arrays element-wise within a combined
+ * absolute, relative and ULP tolerance.
+ *
+ * @tparam T Type of the array elements.
+ * @param actual The flattened array of actual values.
+ * @param expected The flattened array of expected values.
+ * @param tolerance The tolerance every element must be within.
+ * @return The structured comparison result; `error` is the absolute
+ * difference of the first failing element.
+ */
+template <typename T>
+inline ComparisonResult<T> compare_arrays(const FlatArray<T> &actual,
+                                          const FlatArray<T> &expected,
+                                          const Tolerance<T> &tolerance) {
+  ComparisonResult<T> result;
+  if (!same_shape(actual, expected)) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  for (std::size_t i = 0; i < actual.data.size(); i++) {
+    T error = std::abs(actual.data[i] - expected.data[i]);
+    if (std::isnan(error) || error > result.max_error) {
+      result.max_error = error;
+    }
+    Detail::track_relative_error(result, error, expected.data[i]);
+    if (tolerance.accepts(actual.data[i], expected.data[i])) {
+      continue;
+    }
+
+    result.mismatch_count++;
+    if (result.status != ComparisonStatus::Failure) {
+      result.status = ComparisonStatus::Failure;
+      result.kind = MismatchKind::ElementMismatch;
+      result.index = i;
+      result.actual_value = actual.data[i];
+      result.expected_value = expected.data[i];
+      result.error = error;
+    }
+  }
+  return result;
+}
+
+/**
+ * @brief Compares two flattened arrays element-wise within a combined
+ * absolute, relative and ULP tolerance under a shape policy.
+ *
+ * @tparam T Type of the array elements.
+ * @param actual The flattened array of actual values.
+ * @param expected The flattened array of expected values.
+ * @param tolerance The tolerance every element must be within.
+ * @param policy How strictly the shapes must agree.
+ * @return The structured comparison result.
+ */
+template <typename T>
+inline ComparisonResult<T>
+compare_arrays(const FlatArray<T> &actual, const FlatArray<T> &expected,
+               const Tolerance<T> &tolerance, ShapePolicy policy) {
+  if (policy == ShapePolicy::Broadcast) {
+    FlatArray<T> broadcast;
+    if (actual.ragged || !broadcast_to(expected, actual.shape, broadcast)) {
+      ComparisonResult<T> result;
+      result.status = ComparisonStatus::Failure;
+      result.kind = MismatchKind::SizeMismatch;
+      return result;
+    }
+    return compare_arrays(actual, broadcast, tolerance);
+  }
+
+  if (policy == ShapePolicy::Strict && same_shape(actual, expected) &&
+      actual.layout != expected.layout) {
+    ComparisonResult<T> result;
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::LayoutMismatch;
+    return result;
+  }
+
+  return compare_arrays(actual, expected, tolerance);
+}
+
+/**
+ * @brief Compares two scalars against separate warning and failure
+ * tolerances.
+ *
+ * @tparam T Type of the values.
+ * @param actual The actual value.
+ * @param expected The expected value.
+ * @param warning_tolerance The difference above which the result warns.
+ * @param failure_tolerance The difference above which the result fails.
+ * @return The structured comparison result.
+ */
+template <typename T>
+inline ComparisonResult<T> compare_scalar(T actual, T expected,
+                                          T warning_tolerance,
+                                          T failure_tolerance) {
+  return compare_arrays(make_flat_scalar(actual), make_flat_scalar(expected),
+                        warning_tolerance, failure_tolerance);
+}
+
+/**
+ * @brief Compares two scalars within a tolerance.
+ *
+ * @tparam T Type of the values.
+ * @param actual The actual value.
+ * @param expected The expected value.
+ * @param tolerance The maximum allowed difference.
+ * @return The structured comparison result.
+ */
+template <typename T>
+inline ComparisonResult<T> compare_scalar(T actual, T expected, T tolerance) {
+  return compare_scalar(actual, expected, tolerance, tolerance);
+}
+
+/**
+ * @brief Compares two scalars within a combined absolute, relative and ULP
+ * tolerance.
+ *
+ * @tparam T Type of the values.
+ * @param actual The actual value.
+ * @param expected The expected value.
+ * @param tolerance The tolerance the value must be within.
+ * @return The structured comparison result.
+ */
+template <typename T>
+inline ComparisonResult<T> compare_scalar(T actual, T expected,
+                                          const Tolerance<T> &tolerance) {
+  return compare_arrays(make_flat_scalar(actual), make_flat_scalar(expected),
+                        tolerance);
+}
+
+/**
+ * @brief Returns the length of the shorter arc between two angles, in the
+ * unit of the angles.
+ *
+ * Angles that differ by whole turns, such as -pi and pi or 10 and 370
+ * degrees, have an angular difference of zero.
+ */
+template <typename T>
+inline T angular_difference(T actual, T expected, AngleUnit unit) {
+  double half_turn = (unit == AngleUnit::Degrees) ? 180.0 : Numerics::PI;
+  double difference = (static_cast<double>(actual) -
+                       static_cast<double>(expected)) *
+                      (Numerics::PI / half_turn);
+  return static_cast<T>(std::abs(Numerics::wrap_angle(difference)) *
+                        (half_turn / Numerics::PI));
+}
+
+/**
+ * @brief Computes the angle of the rotation between two quaternions.
+ *
+ * Both quaternions are normalized first, and since q and -q describe the
+ * same rotation the sign of either one does not matter. The component order
+ * is irrelevant as long as it is the same for both quaternions.
+ *
+ * @tparam T Type of the quaternion components.
+ * @param actual The actual quaternion with 4 elements.
+ * @param expected The expected quaternion with 4 elements.
+ * @param angle Receives the rotation angle in radians, in [0, pi]; NaN if a
+ * quaternion has zero norm or contains NaN.
+ * @return true on success, false if either array does not hold 4 elements.
+ */
+template <typename T>
+inline bool quaternion_angle(const FlatArray<T> &actual,
+                             const FlatArray<T> &expected, T &angle) {
+  if (actual.data.size() != 4 || expected.data.size() != 4) {
+    return false;
+  }
+
+  double dot = 0.0;
+  double actual_norm = 0.0;
+  double expected_norm = 0.0;
+  for (std::size_t i = 0; i < 4; i++) {
+    double a = static_cast<double>(actual.data[i]);
+    double e = static_cast<double>(expected.data[i]);
+    dot += a * e;
+    actual_norm += a * a;
+    expected_norm += e * e;
+  }
+
+  double cosine = std::abs(dot) / std::sqrt(actual_norm * expected_norm);
+  angle = static_cast<T>(2.0 * std::acos(std::min(cosine, 1.0)));
+  return true;
+}
+
+/**
+ * @brief Computes the geodesic angle between two rotation matrices, i.e. the
+ * angle of the relative rotation R_actual^T R_expected.
+ *
+ * @tparam T Type of the matrix elements.
+ * @param actual The actual 3 x 3 rotation matrix.
+ * @param expected The expected 3 x 3 rotation matrix.
+ * @param angle Receives the angle in radians, in [0, pi].
+ * @return true on success, false if either array is not 3 x 3.
+ */
+template <typename T>
+inline bool rotation_angle(const FlatArray<T> &actual,
+                           const FlatArray<T> &expected, T &angle) {
+  std::vector<std::size_t> shape(2, 3);
+  if (actual.ragged || expected.ragged || actual.shape != shape ||
+      expected.shape != shape) {
+    return false;
+  }
+
+  double trace = 0.0;
+  for (std::size_t i = 0; i < 3; i++) {
+    for (std::size_t k = 0; k < 3; k++) {
+      trace += static_cast<double>(actual.data[k * 3 + i]) *
+               static_cast<double>(expected.data[k * 3 + i]);
+    }
+  }
+
+  double cosine = std::max(-1.0, std::min(1.0, (trace - 1.0) / 2.0));
+  angle = std::isnan(trace) ? static_cast<T>(trace)
+                            : static_cast<T>(std::acos(cosine));
+  return true;
+}
+
+/**
+ * @brief Compares two flattened arrays of angles element-wise on the circle.
+ *
+ * Each element is compared by its angular_difference, so values that are
+ * equal modulo a full turn match. The `error` and `max_error` fields of the
+ * result hold angular differences in the unit of the angles.
+ *
+ * @tparam T Type of the array elements.
+ * @param actual The flattened array of actual angles.
+ * @param expected The flattened array of expected angles.
+ * @param tolerance The maximum allowed angular difference.
+ * @param unit The unit of the angles and of the tolerance.
+ * @return The structured comparison result.
+ */
+template <typename T>
+inline ComparisonResult<T> compare_angles(const FlatArray<T> &actual,
+                                          const FlatArray<T> &expected,
+                                          T tolerance, AngleUnit unit) {
+  ComparisonResult<T> result;
+
+  if (!same_shape(actual, expected)) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  result.max_relative_error = std::numeric_limits<double>::quiet_NaN();
+  for (std::size_t i = 0; i < actual.data.size(); i++) {
+    T error = angular_difference(actual.data[i], expected.data[i], unit);
+
+    if (std::isnan(error) || error > result.max_error) {
+      result.max_error = error;
+    }
+
+    if (error <= tolerance) {
+      continue;
+    }
+
+    result.mismatch_count++;
+    if (result.status == ComparisonStatus::Failure) {
+      continue;
+    }
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::ElementMismatch;
+    result.index = i;
+    result.actual_value = actual.data[i];
+    result.expected_value = expected.data[i];
+    result.error = error;
+  }
+
+  return result;
+}
+
+namespace Detail {
+
+template <typename T>
+inline void compare_trajectory_sample(const FlatArray<T> &actual,
+                                      const FlatArray<T> &expected,
+                                      std::size_t k,
+                                      const Tolerance<T> &tolerance,
+                                      std::size_t settling_samples,
+                                      TrajectoryResult<T> &result,
+                                      bool &worst_found) {
+  std::size_t signals = actual.shape[1];
+  bool violation = false;
+
+  for (std::size_t j = 0; j < signals; j++) {
+    std::size_t i = k * signals + j;
+    T error = std::abs(actual.data[i] - expected.data[i]);
+
+    if (std::isnan(error) || error > result.envelope[k]) {
+      result.envelope[k] = error;
+    }
+    if (k < settling_samples) {
+      continue;
+    }
+    track_relative_error(result, error, expected.data[i]);
+
+    if (!std::isnan(result.max_error) &&
+        (!worst_found || std::isnan(error) || error > result.max_error)) {
+      result.max_error = error;
+      result.worst_sample = k;
+      result.worst_signal = j;
+      worst_found = true;
+    }
+
+    if (!tolerance.accepts(actual.data[i], expected.data[i])) {
+      violation = true;
+      result.mismatch_count++;
+      if (result.status != ComparisonStatus::Failure) {
+        result.status = ComparisonStatus::Failure;
+        result.kind = MismatchKind::ElementMismatch;
+        result.index = i;
+        result.actual_value = actual.data[i];
+        result.expected_value = expected.data[i];
+        result.error = error;
+      }
+    }
+  }
+
+  if (violation) {
+    result.last_violation_sample = k;
+  }
+}
+
+} // namespace Detail
+
+/**
+ * @brief Compares two trajectories whose rows are time samples and whose
+ * columns are signals.
+ *
+ * Samples before `settling_samples` are excluded from the verdict, so initial
+ * transients can be ignored, but they still appear in the error envelope.
+ *
+ * @tparam T Type of the trajectory elements.
+ * @param actual The actual trajectory with shape {samples, signals}.
+ * @param expected The expected trajectory with the same shape.
+ * @param tolerance The tolerance every element must be within.
+ * @param settling_samples The number of leading samples to exclude.
+ * @return The structured trajectory comparison result.
+ */
+template <typename T>
+inline TrajectoryResult<T>
+compare_trajectory(const FlatArray<T> &actual, const FlatArray<T> &expected,
+                   const Tolerance<T> &tolerance,
+                   std::size_t settling_samples) {
+  TrajectoryResult<T> result;
+
+  if (!same_shape(actual, expected) || actual.shape.size() != 2) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  std::size_t samples = actual.shape[0];
+  std::size_t signals = actual.shape[1];
+  result.envelope.assign(samples, static_cast<T>(0));
+
+  bool worst_found = false;
+  for (std::size_t k = 0; k < samples; k++) {
+    Detail::compare_trajectory_sample(actual, expected, k, tolerance,
+                                      settling_samples, result, worst_found);
+  }
+
+  if (result.status == ComparisonStatus::Failure) {
+    result.first_violation_sample = result.index / signals;
+  }
+
+  return result;
+}
+
+/**
+ * @brief Compares two trajectories within an absolute tolerance per element.
+ */
+template <typename T>
+inline TrajectoryResult<T> compare_trajectory(const FlatArray<T> &actual,
+                                              const FlatArray<T> &expected,
+                                              T tolerance,
+                                              std::size_t settling_samples) {
+  return compare_trajectory(actual, expected, Tolerance<T>(tolerance),
+                            settling_samples);
+}
+
+/**
+ * @brief Compares two long trajectories by first comparing a coarse
+ * subsample and then only the neighbourhood of elevated coarse errors at full
+ * resolution.
+ *
+ * Every `stride`-th sample and the last sample are compared first. Wherever
+ * the largest error of such a coarse sample exceeds `refine_threshold` (or is
+ * NaN), all samples within `stride` of it are compared too, so a divergence
+ * is still pinpointed to the exact sample. An excursion that starts and ends
+ * between two coarse samples whose errors both stay below the threshold is
+ * not detected, so the stride must be shorter than the shortest event of
+ * interest. With a stride of 1 the result matches compare_trajectory.
+ *
+ * @tparam T Type of the trajectory elements.
+ * @param actual The actual trajectory with shape {samples, signals}.
+ * @param expected The expected trajectory with the same shape.
+ * @param tolerance The tolerance every element must be within.
+ * @param settling_samples The number of leading samples to exclude.
+ * @param stride The distance between two coarse samples; 0 is treated as 1.
+ * @param refine_threshold The tolerance a coarse sample must be within for
+ * its neighbourhood to be skipped, typically a fraction of the tolerance.
+ * @return The structured trajectory comparison result.
+ */
+template <typename T>
+inline AdaptiveTrajectoryResult<T>
+compare_trajectory_adaptive(const FlatArray<T> &actual,
+                            const FlatArray<T> &expected,
+                            const Tolerance<T> &tolerance,
+                            std::size_t settling_samples, std::size_t stride,
+                            const Tolerance<T> &refine_threshold) {
+  AdaptiveTrajectoryResult<T> result;
+
+  if (!same_shape(actual, expected) || actual.shape.size() != 2) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  std::size_t samples = actual.shape[0];
+  std::size_t signals = actual.shape[1];
+  if (stride == 0) {
+    stride = 1;
+  }
+  result.envelope.assign(samples, static_cast<T>(0));
+
+  std::vector<bool> coarse(samples, false);
+  std::vector<bool> refined(samples, false);
+  for (std::size_t k = 0; k < samples; k += stride) {
+    coarse[k] = true;
+  }
+  if (samples > 0) {
+    coarse[samples - 1] = true;
+  }
+
+  for (std::size_t k = 0; k < samples; k++) {
+    if (!coarse[k]) {
+      continue;
+    }
+
+    bool elevated = false;
+    for (std::size_t j = 0; j < signals && !elevated; j++) {
+      std::size_t i = k * signals + j;
+      elevated = !refine_threshold.accepts(actual.data[i], expected.data[i]);
+    }
+
+    if (elevated) {
+      std::size_t first = (k >= stride - 1) ? k - (stride - 1) : 0;
+      std::size_t last = std::min(samples - 1, k + (stride - 1));
+      for (std::size_t m = first; m <= last; m++) {
+        refined[m] = !coarse[m];
+      }
+    }
+  }
+
+  bool worst_found = false;
+  for (std::size_t k = 0; k < samples; k++) {
+    if (!coarse[k] && !refined[k]) {
+      continue;
+    }
+    result.samples_compared++;
+    result.refined_samples += refined[k] ? 1 : 0;
+    Detail::compare_trajectory_sample(actual, expected, k, tolerance,
+                                      settling_samples, result, worst_found);
+  }
+
+  if (result.status == ComparisonStatus::Failure) {
+    result.first_violation_sample = result.index / signals;
+  }
+
+  return result;
+}
+
+/**
+ * @brief Compares two long trajectories adaptively with absolute tolerances.
+ */
+template <typename T>
+inline AdaptiveTrajectoryResult<T> compare_trajectory_adaptive(
+    const FlatArray<T> &actual, const FlatArray<T> &expected, T tolerance,
+    std::size_t settling_samples, std::size_t stride, T refine_threshold) {
+  return compare_trajectory_adaptive(actual, expected,
+                                     Tolerance<T>(tolerance), settling_samples,
+                                     stride, Tolerance<T>(refine_threshold));
+}
+
+/**
+ * @brief Computes the root-mean-square, mean absolute and maximum absolute
+ * error between two arrays.
+ *
+ * If the shapes differ, `shape_matches` is false and the metrics are zero.
+ * Any NaN difference makes all metrics NaN.
+ *
+ * @tparam T Type of the array elements.
+ * @param actual The flattened array of actual values.
+ * @param expected The flattened array of expected values.
+ * @return The computed metrics.
+ */
+template <typename T>
+inline ErrorMetrics<T> compute_error_metrics(const FlatArray<T> &actual,
+                                             const FlatArray<T> &expected) {
+  ErrorMetrics<T> metrics;
+  if (!same_shape(actual, expected)) {
+    return metrics;
+  }
+
+  metrics.shape_matches = true;
+  metrics.count = actual.data.size();
+  if (metrics.count == 0) {
+    return metrics;
+  }
+
+  T sum_squared = static_cast<T>(0);
+  T sum_absolute = static_cast<T>(0);
+  for (std::size_t i = 0; i < actual.data.size(); i++) {
+    T error = std::abs(actual.data[i] - expected.data[i]);
+    sum_squared += error * error;
+    sum_absolute += error;
+    if (std::isnan(error) || error > metrics.max_error) {
+      metrics.max_error = error;
+    }
+  }
+
+  T count = static_cast<T>(metrics.count);
+  metrics.rmse = std::sqrt(sum_squared / count);
+  metrics.mae = sum_absolute / count;
+  if (std::isnan(sum_absolute)) {
+    metrics.max_error = sum_absolute;
+  }
+  return metrics;
+}
+
+/**
+ * @brief Compares the one-sided spectra of two equally long real signals over
+ * a frequency band.
+ *
+ * Both signals are transformed with Numerics::dft and the magnitudes are
+ * normalized by the signal length, so a sinusoid of amplitude A shows up with
+ * magnitude A/2 (A for the DC bin). Only bins with frequencies in
+ * [min_frequency, max_frequency] are compared. When `compare_phase` is set,
+ * the wrapped phase difference is also checked, but only for bins where the
+ * expected magnitude exceeds `magnitude_tolerance`, since the phase of a bin
+ * that holds only noise is meaningless.
+ *
+ * @tparam T Type of the signal samples.
+ * @param actual The actual signal.
+ * @param expected The expected signal with the same length.
+ * @param sample_rate The sampling rate of both signals.
+ * @param min_frequency The lower edge of the compared band.
+ * @param max_frequency The upper edge of the compared band.
+ * @param magnitude_tolerance The maximum allowed magnitude difference.
+ * @param compare_phase Whether phases are compared as well.
+ * @param phase_tolerance The maximum allowed phase difference in radians.
+ * @return The structured spectrum comparison result.
+ */
+template <typename T>
+inline SpectrumResult<T>
+compare_spectrum(const std::vector<T> &actual, const std::vector<T> &expected,
+                 T sample_rate, T min_frequency, T max_frequency,
+                 T magnitude_tolerance, bool compare_phase,
+                 T phase_tolerance) {
+  SpectrumResult<T> result;
+  if (actual.size() != expected.size() || actual.empty()) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  std::vector<std::complex<double>> actual_spectrum =
+      Numerics::dft_real(actual);
+  std::vector<std::complex<double>> expected_spectrum =
+      Numerics::dft_real(expected);
+
+  double length = static_cast<double>(actual.size());
+  for (std::size_t k = 0; k <= actual.size() / 2; k++) {
+    double frequency = static_cast<double>(k) *
+                       static_cast<double>(sample_rate) / length;
+    if (frequency < static_cast<double>(min_frequency) ||
+        frequency > static_cast<double>(max_frequency)) {
+      continue;
+    }
+    result.bins_compared++;
+
+    double scale = (k == 0 || 2 * k == actual.size()) ? length : length / 2.0;
+    double actual_magnitude = std::abs(actual_spectrum[k]) / scale;
+    double expected_magnitude = std::abs(expected_spectrum[k]) / scale;
+    T magnitude_error =
+        static_cast<T>(std::abs(actual_magnitude - expected_magnitude));
+
+    T phase_error = static_cast<T>(0);
+    bool phase_compared =
+        compare_phase && expected_magnitude > magnitude_tolerance;
+    if (phase_compared) {
+      phase_error = static_cast<T>(std::abs(Numerics::wrap_angle(
+          std::arg(actual_spectrum[k]) - std::arg(expected_spectrum[k]))));
+    }
+
+    if (std::isnan(magnitude_error) ||
+        magnitude_error > result.magnitude_error) {
+      result.magnitude_error = magnitude_error;
+      result.worst_frequency = static_cast<T>(frequency);
+    }
+    if (std::isnan(phase_error) || phase_error > result.phase_error) {
+      result.phase_error = phase_error;
+    }
+
+    bool magnitude_ok = (magnitude_error <= magnitude_tolerance);
+    bool phase_ok = !phase_compared || (phase_error <= phase_tolerance);
+    if ((!magnitude_ok || !phase_ok) &&
+        result.status != ComparisonStatus::Failure) {
+      result.status = ComparisonStatus::Failure;
+      result.kind = MismatchKind::ElementMismatch;
+      result.failed_frequency = static_cast<T>(frequency);
+      result.phase_failed = magnitude_ok;
+    }
+  }
+
+  return result;
+}
+
+/**
+ * @brief Sums a sequence of values, for use as a reducer.
+ */
+template <typename T> inline T sum_of(const std::vector<T> &values) {
+  T sum = static_cast<T>(0);
+  for (std::size_t i = 0; i < values.size(); i++) {
+    sum += values[i];
+  }
+  return sum;
+}
+
+/**
+ * @brief Averages a sequence of values, for use as a reducer. An empty
+ * sequence has a mean of zero.
+ */
+template <typename T> inline T mean_of(const std::vector<T> &values) {
+  if (values.empty()) {
+    return static_cast<T>(0);
+  }
+  return sum_of(values) / static_cast<T>(values.size());
+}
+
+/**
+ * @brief Reduces every row or every column of a two-dimensional array to a
+ * single value.
+ *
+ * @tparam T Type of the array elements.
+ * @param values The array to reduce; must have shape {rows, columns}.
+ * @param axis Whether to reduce each row or each column.
+ * @param reducer Maps the elements of one row or column to a single value.
+ * @param reduced Receives the one-dimensional array of reduced values.
+ * @return true on success, false if `values` is not a regular
+ * two-dimensional array.
+ */
+template <typename T>
+inline bool
+reduce_axis(const FlatArray<T> &values, ReductionAxis axis,
+            const std::function<T(const std::vector<T> &)> &reducer,
+            FlatArray<T> &reduced) {
+  if (values.ragged || values.shape.size() != 2) {
+    return false;
+  }
+
+  std::size_t rows = values.shape[0];
+  std::size_t columns = values.shape[1];
+  std::size_t count = (axis == ReductionAxis::Rows) ? rows : columns;
+  std::size_t length = (axis == ReductionAxis::Rows) ? columns : rows;
+
+  FlatArray<T> result;
+  result.shape.push_back(count);
+  result.data.resize(count);
+
+  std::vector<T> slice(length);
+  for (std::size_t k = 0; k < count; k++) {
+    for (std::size_t j = 0; j < length; j++) {
+      slice[j] = (axis == ReductionAxis::Rows) ? values.data[k * columns + j]
+                                               : values.data[j * columns + k];
+    }
+    result.data[k] = reducer(slice);
+  }
+
+  reduced = result;
+  return true;
+}
+
+/**
+ * @brief Checks that a square matrix equals its transpose within a
+ * tolerance. Reports the entry (row, column) with the largest asymmetry.
+ */
+template <typename T>
+inline PropertyResult<T> check_symmetric(const FlatArray<T> &matrix,
+                                         T tolerance) {
+  PropertyResult<T> result;
+  if (matrix.ragged || matrix.shape.size() != 2 ||
+      matrix.shape[0] != matrix.shape[1]) {
+    result.satisfied = false;
+    result.square = false;
+    return result;
+  }
+
+  std::size_t n = matrix.shape[0];
+  result.bound = tolerance;
+  for (std::size_t i = 0; i < n; i++) {
+    for (std::size_t j = i + 1; j < n; j++) {
+      T difference = std::abs(matrix.data[i * n + j] - matrix.data[j * n + i]);
+      if (std::isnan(difference) || difference > result.value) {
+        result.row = i;
+        result.column = j;
+        result.value = difference;
+      }
+      if (std::isnan(difference)) {
+        result.satisfied = false;
+        return result;
+      }
+    }
+  }
+
+  result.satisfied = (result.value <= tolerance);
+  return result;
+}
+
+/**
+ * @brief Computes the eigenvalues of the symmetric part (M + M^T) / 2 of a
+ * square matrix, in ascending order.
+ *
+ * The matrix must be square, e.g. as verified by check_symmetric.
+ */
+template <typename T>
+inline std::vector<T> symmetric_part_eigenvalues(const FlatArray<T> &matrix) {
+  std::size_t n = matrix.shape.empty() ? 0 : matrix.shape[0];
+  std::vector<double> values(n * n);
+  for (std::size_t i = 0; i < n; i++) {
+    for (std::size_t j = 0; j < n; j++) {
+      values[i * n + j] =
+          0.5 * (static_cast<double>(matrix.data[i * n + j]) +
+                 static_cast<double>(matrix.data[j * n + i]));
+    }
+  }
+
+  std::vector<double> eigenvalues = Numerics::symmetric_eigenvalues(values, n);
+  return std::vector<T>(eigenvalues.begin(), eigenvalues.end());
+}
+
+/**
+ * @brief Checks that a symmetric matrix is positive definite, i.e. that
+ * every eigenvalue exceeds the tolerance. Reports the smallest eigenvalue.
+ *
+ * The matrix must pass check_symmetric with the same tolerance first; if it
+ * does not, that result is returned instead.
+ */
+template <typename T>
+inline PropertyResult<T> check_positive_definite(const FlatArray<T> &matrix,
+                                                 T tolerance) {
+  PropertyResult<T> result = check_symmetric(matrix, tolerance);
+  if (!result.satisfied) {
+    return result;
+  }
+
+  result = PropertyResult<T>();
+  result.bound = tolerance;
+  std::vector<T> eigenvalues = symmetric_part_eigenvalues(matrix);
+  if (eigenvalues.empty()) {
+    return result;
+  }
+
+  result.row = 0;
+  result.value = eigenvalues[0];
+  result.satisfied = (result.value > tolerance);
+  return result;
+}
+
+/**
+ * @brief Checks that a square matrix Q is orthogonal, i.e. that Q^T Q equals
+ * the identity within a tolerance. Reports the entry of Q^T Q with the
+ * largest deviation from the identity.
+ */
+template <typename T>
+inline PropertyResult<T> check_orthogonal(const FlatArray<T> &matrix,
+                                          T tolerance) {
+  PropertyResult<T> result;
+  if (matrix.ragged || matrix.shape.size() != 2 ||
+      matrix.shape[0] != matrix.shape[1]) {
+    result.satisfied = false;
+    result.square = false;
+    return result;
+  }
+
+  std::size_t n = matrix.shape[0];
+  result.bound = tolerance;
+  for (std::size_t i = 0; i < n; i++) {
+    for (std::size_t j = i; j < n; j++) {
+      T dot = static_cast<T>(0);
+      for (std::size_t k = 0; k < n; k++) {
+        dot += matrix.data[k * n + i] * matrix.data[k * n + j];
+      }
+      T identity = static_cast<T>(i == j ? 1 : 0);
+      T deviation = std::abs(dot - identity);
+      if (std::isnan(deviation) || deviation > result.value) {
+        result.row = i;
+        result.column = j;
+        result.value = deviation;
+      }
+      if (std::isnan(deviation)) {
+        result.satisfied = false;
+        return result;
+      }
+    }
+  }
+
+  result.satisfied = (result.value <= tolerance);
+  return result;
+}
+
+/**
+ * @brief Checks that a square matrix is row-wise diagonally dominant:
+ * |a_ii| >= sum_{j != i} |a_ij| - tolerance for every row i.
+ *
+ * Reports the first violating row, with `value` holding |a_ii| and `bound`
+ * the off-diagonal sum of that row.
+ */
+template <typename T>
+inline PropertyResult<T>
+check_diagonally_dominant(const FlatArray<T> &matrix, T tolerance) {
+  PropertyResult<T> result;
+  if (matrix.ragged || matrix.shape.size() != 2 ||
+      matrix.shape[0] != matrix.shape[1]) {
+    result.satisfied = false;
+    result.square = false;
+    return result;
+  }
+
+  std::size_t n = matrix.shape[0];
+  for (std::size_t i = 0; i < n; i++) {
+    T diagonal = std::abs(matrix.data[i * n + i]);
+    T off_diagonal = static_cast<T>(0);
+    for (std::size_t j = 0; j < n; j++) {
+      if (j != i) {
+        off_diagonal += std::abs(matrix.data[i * n + j]);
+      }
+    }
+
+    if (!(diagonal >= off_diagonal - tolerance)) {
+      result.satisfied = false;
+      result.row = i;
+      result.column = i;
+      result.value = diagonal;
+      result.bound = off_diagonal;
+      return result;
+    }
+  }
+  return result;
+}
+
+namespace Detail {
+
+inline bool find_augmenting_path(const std::vector<std::vector<bool>> &close,
+                                 std::size_t actual_index,
+                                 std::vector<bool> &visited,
+                                 std::vector<std::size_t> &expected_owner) {
+  std::size_t none = close.size();
+  for (std::size_t j = 0; j < expected_owner.size(); j++) {
+    if (!close[actual_index][j] || visited[j]) {
+      continue;
+    }
+    visited[j] = true;
+    if (expected_owner[j] == none ||
+        find_augmenting_path(close, expected_owner[j], visited,
+                             expected_owner)) {
+      expected_owner[j] = actual_index;
+      return true;
+    }
+  }
+  return false;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Matches two sets of complex values regardless of their order.
+ *
+ * Two values can be matched when their distance in the complex plane is
+ * within the tolerance. A maximum bipartite matching is computed, so a value
+ * is only reported as unmatched if no assignment at all can pair it, e.g.
+ * when several eigenvalues are close together.
+ *
+ * @tparam T Type of the real and imaginary parts.
+ * @param actual The actual values, in any order.
+ * @param expected The expected values, in any order.
+ * @param tolerance The largest distance between two matched values.
+ * @return The matching and the unmatched values of either side.
+ */
+template <typename T>
+inline UnorderedMatchResult<T>
+match_unordered(const std::vector<std::complex<T>> &actual,
+                const std::vector<std::complex<T>> &expected, T tolerance) {
+  UnorderedMatchResult<T> result;
+  result.size_matches = (actual.size() == expected.size());
+  result.expected_count = expected.size();
+
+  std::vector<std::vector<bool>> close(
+      actual.size(), std::vector<bool>(expected.size(), false));
+  for (std::size_t i = 0; i < actual.size(); i++) {
+    for (std::size_t j = 0; j < expected.size(); j++) {
+      close[i][j] = (std::abs(actual[i] - expected[j]) <= tolerance);
+    }
+  }
+
+  std::vector<std::size_t> expected_owner(expected.size(), actual.size());
+  for (std::size_t i = 0; i < actual.size(); i++) {
+    std::vector<bool> visited(expected.size(), false);
+    Detail::find_augmenting_path(close, i, visited, expected_owner);
+  }
+
+  result.assignment.assign(actual.size(), expected.size());
+  for (std::size_t j = 0; j < expected.size(); j++) {
+    if (expected_owner[j] == actual.size()) {
+      result.unmatched_expected.push_back(j);
+    } else {
+      result.assignment[expected_owner[j]] = j;
+    }
+  }
+  for (std::size_t i = 0; i < actual.size(); i++) {
+    if (result.assignment[i] == expected.size()) {
+      result.unmatched_actual.push_back(i);
+    }
+  }
+  return result;
+}
+
+/**
+ * @brief Matches two sets of real values regardless of their order.
+ */
+template <typename T>
+inline UnorderedMatchResult<T> match_unordered(const std::vector<T> &actual,
+                                               const std::vector<T> &expected,
+                                               T tolerance) {
+  std::vector<std::complex<T>> complex_actual(actual.begin(), actual.end());
+  std::vector<std::complex<T>> complex_expected(expected.begin(),
+                                                expected.end());
+  return match_unordered(complex_actual, complex_expected, tolerance);
+}
+
+/**
+ * @brief Finds the poles that violate a stability margin.
+ *
+ * In the continuous domain every real part must be below -margin; in the
+ * discrete domain every magnitude must be below 1 - margin. NaN poles are
+ * always reported.
+ *
+ * @tparam T Type of the real and imaginary parts.
+ * @param poles The poles or eigenvalues to check.
+ * @param margin The required distance from the stability boundary.
+ * @param domain Whether the poles are continuous-time or discrete-time.
+ * @return The indices of the violating poles in ascending order.
+ */
+template <typename T>
+inline std::vector<std::size_t>
+find_unstable_poles(const std::vector<std::complex<T>> &poles, T margin,
+                    StabilityDomain domain) {
+  std::vector<std::size_t> unstable;
+  for (std::size_t i = 0; i < poles.size(); i++) {
+    bool stable = (domain == StabilityDomain::Continuous)
+                      ? (poles[i].real() < -margin)
+                      : (std::abs(poles[i]) < static_cast<T>(1) - margin);
+    if (!stable) {
+      unstable.push_back(i);
+    }
+  }
+  return unstable;
+}
+
+/**
+ * @brief Compares two sequences of phasors by magnitude and phase
+ * separately.
+ *
+ * The phase is only compared where the expected magnitude exceeds the
+ * magnitude tolerance, since the angle of a phasor that is numerically zero
+ * is meaningless.
+ *
+ * @tparam T Type of the real and imaginary parts.
+ * @param actual The actual phasors.
+ * @param expected The expected phasors.
+ * @param magnitude_tolerance The largest allowed magnitude difference.
+ * @param phase_tolerance_degrees The largest allowed phase difference in
+ * degrees.
+ * @return The structured phasor comparison result.
+ */
+template <typename T>
+inline PhasorResult<T>
+compare_phasors(const std::vector<std::complex<T>> &actual,
+                const std::vector<std::complex<T>> &expected,
+                T magnitude_tolerance, T phase_tolerance_degrees) {
+  PhasorResult<T> result;
+  if (actual.size() != expected.size()) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  for (std::size_t i = 0; i < actual.size(); i++) {
+    T magnitude_error = std::abs(std::abs(actual[i]) - std::abs(expected[i]));
+    T phase_error = static_cast<T>(0);
+    bool compare_phase = (std::abs(expected[i]) > magnitude_tolerance);
+    if (compare_phase) {
+      phase_error = angular_difference(
+          static_cast<T>(std::arg(actual[i]) * 180.0 / Numerics::PI),
+          static_cast<T>(std::arg(expected[i]) * 180.0 / Numerics::PI),
+          AngleUnit::Degrees);
+    }
+
+    if (std::isnan(magnitude_error) ||
+        magnitude_error > result.max_magnitude_error) {
+      result.max_magnitude_error = magnitude_error;
+    }
+    if (std::isnan(phase_error) || phase_error > result.max_phase_error) {
+      result.max_phase_error = phase_error;
+    }
+
+    bool magnitude_failed = !(magnitude_error <= magnitude_tolerance);
+    bool phase_failed =
+        compare_phase && !(phase_error <= phase_tolerance_degrees);
+    if ((magnitude_failed || phase_failed) &&
+        result.status != ComparisonStatus::Failure) {
+      result.status = ComparisonStatus::Failure;
+      result.kind = MismatchKind::ElementMismatch;
+      result.index = i;
+      result.magnitude_error = magnitude_error;
+      result.phase_error = phase_error;
+      result.phase_failed = !magnitude_failed;
+    }
+  }
+  return result;
+}
+
+/**
+ * @brief Computes the Frobenius or spectral norm of an array.
+ *
+ * Arrays with more than two dimensions use their last dimension as columns
+ * and all leading dimensions as rows.
+ */
+template <typename T>
+inline T matrix_norm(const FlatArray<T> &values, MatrixNorm norm) {
+  std::size_t columns = values.shape.empty() ? 1 : values.shape.back();
+  if (norm == MatrixNorm::Frobenius || values.shape.size() < 2 ||
+      columns == 0) {
+    double sum = 0.0;
+    for (std::size_t i = 0; i < values.data.size(); i++) {
+      double value = static_cast<double>(values.data[i]);
+      sum += value * value;
+    }
+    return static_cast<T>(std::sqrt(sum));
+  }
+
+  std::size_t rows = values.data.size() / columns;
+  std::vector<double> gram(columns * columns, 0.0);
+  for (std::size_t i = 0; i < columns; i++) {
+    for (std::size_t j = 0; j < columns; j++) {
+      double sum = 0.0;
+      for (std::size_t k = 0; k < rows; k++) {
+        sum += static_cast<double>(values.data[k * columns + i]) *
+               static_cast<double>(values.data[k * columns + j]);
+      }
+      gram[i * columns + j] = sum;
+    }
+  }
+
+  std::vector<double> eigenvalues =
+      Numerics::symmetric_eigenvalues(gram, columns);
+  return static_cast<T>(std::sqrt(std::max(0.0, eigenvalues.back())));
+}
+
+/**
+ * @brief Computes the relative error ||actual - expected|| / ||expected||.
+ *
+ * If the norm of `expected` is zero, the absolute error ||actual - expected||
+ * is returned instead, so an all-zero reference is only matched by an
+ * all-zero actual array.
+ *
+ * @tparam T Type of the array elements.
+ * @param actual The actual array.
+ * @param expected The expected array with the same shape.
+ * @param norm The norm to use.
+ * @param error Receives the relative error.
+ * @return true on success, false if the shapes differ.
+ */
+template <typename T>
+inline bool relative_norm_error(const FlatArray<T> &actual,
+                                const FlatArray<T> &expected, MatrixNorm norm,
+                                T &error) {
+  if (!same_shape(actual, expected)) {
+    return false;
+  }
+
+  FlatArray<T> difference = actual;
+  for (std::size_t i = 0; i < difference.data.size(); i++) {
+    difference.data[i] -= expected.data[i];
+  }
+
+  T difference_norm = matrix_norm(difference, norm);
+  T expected_norm = matrix_norm(expected, norm);
+  error = (expected_norm > static_cast<T>(0)) ? difference_norm / expected_norm
+                                              : difference_norm;
+  return true;
+}
+
+/**
+ * @brief Finds the first element that breaks the monotonicity of a sequence.
+ *
+ * A non-decreasing sequence may drop by at most `tolerance` from one element
+ * to the next and a non-increasing one may rise by at most `tolerance`. NaN
+ * elements always break monotonicity.
+ *
+ * @tparam T Type of the sequence elements.
+ * @param values The sequence to check.
+ * @param tolerance The largest allowed step in the wrong direction.
+ * @param increasing true for non-decreasing, false for non-increasing.
+ * @return The index of the first violating element, or values.size() if the
+ * sequence is monotonic.
+ */
+template <typename T>
+inline std::size_t find_monotonic_violation(const std::vector<T> &values,
+                                            T tolerance, bool increasing) {
+  for (std::size_t i = 0; i < values.size(); i++) {
+    if (std::isnan(values[i])) {
+      return i;
+    }
+    if (i == 0) {
+      continue;
+    }
+
+    T step = increasing ? values[i - 1] - values[i] : values[i] - values[i - 1];
+    if (!(step <= tolerance)) {
+      return i;
+    }
+  }
+  return values.size();
+}
+
+/**
+ * @brief Finds the first element outside the closed interval [low, high].
+ *
+ * @return The index of the first violating element, or values.data.size() if
+ * every element is in range. NaN elements are always out of range.
+ */
+template <typename T>
+inline std::size_t find_out_of_range(const FlatArray<T> &values, T low,
+                                     T high) {
+  for (std::size_t i = 0; i < values.data.size(); i++) {
+    if (!(values.data[i] >= low && values.data[i] <= high)) {
+      return i;
+    }
+  }
+  return values.data.size();
+}
+
+/**
+ * @brief Computes the fraction of samples within tolerance for every channel.
+ *
+ * A channel without samples has a pass rate of 1.
+ *
+ * @tparam T Type of the compared values.
+ * @param actual The actual samples, one row per sample and one column per
+ * channel, or a one-dimensional single channel.
+ * @param expected The expected samples with the same shape.
+ * @param tolerance The tolerance a sample must be within to pass.
+ * @return The per-channel pass counts and rates.
+ */
+template <typename T>
+inline PassRates compute_pass_rates(const FlatArray<T> &actual,
+                                    const FlatArray<T> &expected,
+                                    const Tolerance<T> &tolerance) {
+  PassRates rates;
+  if (!same_shape(actual, expected) || actual.shape.empty() ||
+      actual.shape.size() > 2) {
+    return rates;
+  }
+
+  rates.shape_matches = true;
+  rates.samples = actual.shape[0];
+  std::size_t channels = (actual.shape.size() == 2) ? actual.shape[1] : 1;
+  rates.passed_samples.assign(channels, 0);
+  for (std::size_t k = 0; k < rates.samples; k++) {
+    for (std::size_t j = 0; j < channels; j++) {
+      std::size_t i = k * channels + j;
+      if (tolerance.accepts(actual.data[i], expected.data[i])) {
+        rates.passed_samples[j]++;
+      }
+    }
+  }
+
+  rates.pass_rates.assign(channels, 1.0);
+  for (std::size_t j = 0; j < channels && rates.samples > 0; j++) {
+    rates.pass_rates[j] = static_cast<double>(rates.passed_samples[j]) /
+                          static_cast<double>(rates.samples);
+  }
+  return rates;
+}
+
+/**
+ * @brief Computes the fraction of samples within an absolute tolerance for
+ * every channel.
+ */
+template <typename T>
+inline PassRates compute_pass_rates(const FlatArray<T> &actual,
+                                    const FlatArray<T> &expected,
+                                    T tolerance) {
+  return compute_pass_rates(actual, expected, Tolerance<T>(tolerance));
+}
+
+/**
+ * @brief Computes the element-wise spread of the outputs of repeated runs.
+ *
+ * A NaN in any run makes the spread of that element NaN, which is reported
+ * as the worst element.
+ *
+ * @tparam T Type of the outputs.
+ * @param runs The outputs of every run, all of the same length.
+ * @return The per-element standard deviations and the largest of them.
+ */
+template <typename T>
+inline RunSpread<T>
+compute_run_spread(const std::vector<std::vector<T>> &runs) {
+  RunSpread<T> spread;
+  for (std::size_t r = 1; r < runs.size(); r++) {
+    if (runs[r].size() != runs[0].size()) {
+      return spread;
+    }
+  }
+
+  spread.size_matches = true;
+  if (runs.empty()) {
+    return spread;
+  }
+
+  std::size_t count = runs[0].size();
+  T run_count = static_cast<T>(runs.size());
+  spread.sigmas.assign(count, static_cast<T>(0));
+  for (std::size_t i = 0; i < count; i++) {
+    T mean = static_cast<T>(0);
+    for (std::size_t r = 0; r < runs.size(); r++) {
+      mean += runs[r][i];
+    }
+    mean /= run_count;
+
+    T variance = static_cast<T>(0);
+    for (std::size_t r = 0; r < runs.size(); r++) {
+      variance += (runs[r][i] - mean) * (runs[r][i] - mean);
+    }
+    spread.sigmas[i] = std::sqrt(variance / run_count);
+
+    if (std::isnan(spread.sigmas[i]) && !std::isnan(spread.max_sigma)) {
+      spread.max_sigma = spread.sigmas[i];
+      spread.worst_index = i;
+    } else if (spread.sigmas[i] > spread.max_sigma) {
+      spread.max_sigma = spread.sigmas[i];
+      spread.worst_index = i;
+    }
+  }
+  return spread;
+}
+
+/**
+ * @brief Finds the elements that differ between two outputs of equal length.
+ *
+ * Elements are compared exactly; NaN equals NaN, so deterministic NaN outputs
+ * do not count as differences.
+ *
+ * @tparam T Type of the outputs.
+ * @param first The first output.
+ * @param second The second output, of the same length.
+ * @return The indices of the differing elements in ascending order.
+ */
+template <typename T>
+inline std::vector<std::size_t> find_unequal(const std::vector<T> &first,
+                                             const std::vector<T> &second) {
+  std::vector<std::size_t> indices;
+  for (std::size_t i = 0; i < first.size() && i < second.size(); i++) {
+    bool both_nan = std::isnan(first[i]) && std::isnan(second[i]);
+    if (!both_nan && first[i] != second[i]) {
+      indices.push_back(i);
+    }
+  }
+  return indices;
+}
+
+/**
+ * @brief Finds the elements with the largest errors beyond a tolerance.
+ *
+ * Every element is examined, so the total number of elements outside
+ * tolerance is counted even if only a few are kept. NaN errors rank above
+ * every finite error, and equal errors are ordered by index.
+ *
+ * @tparam T Type of the compared values.
+ * @param actual The actual values.
+ * @param expected The expected values with the same shape.
+ * @param tolerance The maximum allowed difference per element.
+ * @param count The maximum number of elements to keep.
+ * @return The number of elements outside tolerance and the worst of them;
+ * empty if the shapes differ.
+ */
+template <typename T>
+inline WorstElements<T> find_worst_elements(const FlatArray<T> &actual,
+                                            const FlatArray<T> &expected,
+                                            T tolerance, std::size_t count) {
+  WorstElements<T> worst;
+  if (!same_shape(actual, expected)) {
+    return worst;
+  }
+
+  for (std::size_t i = 0; i < actual.data.size(); i++) {
+    T error = std::abs(actual.data[i] - expected.data[i]);
+    if (error <= tolerance) {
+      continue;
+    }
+    worst.count++;
+
+    std::size_t position = worst.elements.size();
+    while (position > 0) {
+      T previous = worst.elements[position - 1].error;
+      if (std::isnan(previous) || (!std::isnan(error) && previous >= error)) {
+        break;
+      }
+      position--;
+    }
+    if (position >= count) {
+      continue;
+    }
+
+    ElementError<T> element;
+    element.index = i;
+    element.actual_value = actual.data[i];
+    element.expected_value = expected.data[i];
+    element.error = error;
+    worst.elements.insert(worst.elements.begin() + position, element);
+    if (worst.elements.size() > count) {
+      worst.elements.pop_back();
+    }
+  }
+  return worst;
+}
+
+/**
+ * @brief Generates the evaluation points of a boundary sweep.
+ *
+ * For every input, six points are generated: exactly at the lower and the
+ * upper bound, one representable value inside each bound and one
+ * representable value outside each bound, so off-by-one comparisons such as
+ * `<` versus `<=` in saturation logic change the result.
+ *
+ * @tparam T Floating-point type of the inputs.
+ * @param ranges The declared range of every input.
+ * @return The evaluation points, grouped by input.
+ */
+template <typename T>
+inline std::vector<BoundaryProbe<T>>
+boundary_probes(const std::vector<InputRange<T>> &ranges) {
+  std::vector<T> nominal;
+  for (std::size_t i = 0; i < ranges.size(); i++) {
+    nominal.push_back(ranges[i].lower +
+                      (ranges[i].upper - ranges[i].lower) /
+                          static_cast<T>(2));
+  }
+
+  const T infinity = std::numeric_limits<T>::infinity();
+  std::vector<BoundaryProbe<T>> probes;
+  for (std::size_t i = 0; i < ranges.size(); i++) {
+    T lower = ranges[i].lower;
+    T upper = ranges[i].upper;
+    std::vector<std::pair<std::string, T>> points = {
+        {"below the lower bound", std::nextafter(lower, -infinity)},
+        {"at the lower bound", lower},
+        {"inside the lower bound", std::nextafter(lower, infinity)},
+        {"inside the upper bound", std::nextafter(upper, -infinity)},
+        {"at the upper bound", upper},
+        {"above the upper bound", std::nextafter(upper, infinity)}};
+
+    for (std::size_t k = 0; k < points.size(); k++) {
+      BoundaryProbe<T> probe;
+      probe.input_index = i;
+      probe.label = points[k].first;
+      probe.inputs = nominal;
+      probe.inputs[i] = points[k].second;
+      probes.push_back(probe);
+    }
+  }
+  return probes;
+}
+
+/**
+ * @brief Derives the tolerance of an accumulated sum from the standard
+ * forward-error bound.
+ *
+ * A sum of `n_terms` terms evaluated in floating point, in any order, differs
+ * from the exact sum by at most n * u / (1 - n * u) times the sum of the
+ * absolute values of the terms, where u = epsilon / 2 is the unit roundoff.
+ * The returned tolerance n * epsilon * absolute_sum covers this bound for
+ * every n * u below one half, and is also valid for dot products and matrix
+ * product elements, whose terms are the products of the operands.
+ *
+ * @tparam T Floating-point type of the accumulation.
+ * @param n_terms The number of accumulated terms.
+ * @param absolute_sum The sum of the absolute values of the terms.
+ * @return The tolerance for comparing the computed sum with the exact one.
+ */
+template <typename T>
+inline T accumulation_tolerance(std::size_t n_terms, T absolute_sum) {
+  return static_cast<T>(n_terms) * std::numeric_limits<T>::epsilon() *
+         absolute_sum;
+}
+
+/**
+ * @brief Computes the sum of |x_i * y_i|, the magnitude that bounds the
+ * rounding error of the dot product of x and y.
+ *
+ * @return The sum over the common length of both vectors.
+ */
+template <typename T>
+inline T dot_absolute_sum(const std::vector<T> &x, const std::vector<T> &y) {
+  T sum = static_cast<T>(0);
+  for (std::size_t i = 0; i < x.size() && i < y.size(); i++) {
+    sum += std::abs(x[i] * y[i]);
+  }
+  return sum;
+}
+
+/**
+ * @brief Compares two flattened arrays against a combined absolute and
+ * relative tolerance.
+ *
+ * An element passes if |actual - expected| <= absolute_tolerance +
+ * relative_tolerance * |expected|. With NanPolicy::MatchNan, a NaN passes if
+ * the other value at the same position is NaN as well; otherwise every NaN
+ * fails.
+ *
+ * @tparam T Type of the array elements.
+ * @param actual The flattened array of actual values.
+ * @param expected The flattened array of expected values.
+ * @param absolute_tolerance The absolute part of the tolerance.
+ * @param relative_tolerance The part of the tolerance proportional to the
+ * magnitude of the expected value.
+ * @param nan_policy How NaN values are treated.
+ * @return The structured comparison result; `error` is the absolute
+ * difference of the first failing element.
+ */
+template <typename T>
+inline ComparisonResult<T>
+compare_arrays_combined(const FlatArray<T> &actual,
+                        const FlatArray<T> &expected, T absolute_tolerance,
+                        T relative_tolerance, NanPolicy nan_policy) {
+  ComparisonResult<T> result;
+  if (!same_shape(actual, expected)) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  for (std::size_t i = 0; i < actual.data.size(); i++) {
+    if (nan_policy == NanPolicy::MatchNan && std::isnan(actual.data[i]) &&
+        std::isnan(expected.data[i])) {
+      continue;
+    }
+
+    T error = std::abs(actual.data[i] - expected.data[i]);
+    if (std::isnan(error) || error > result.max_error) {
+      result.max_error = error;
+    }
+    Detail::track_relative_error(result, error, expected.data[i]);
+    if (error <=
+        absolute_tolerance + relative_tolerance * std::abs(expected.data[i])) {
+      continue;
+    }
+
+    result.mismatch_count++;
+    if (result.status != ComparisonStatus::Failure) {
+      result.status = ComparisonStatus::Failure;
+      result.kind = MismatchKind::ElementMismatch;
+      result.index = i;
+      result.actual_value = actual.data[i];
+      result.expected_value = expected.data[i];
+      result.error = error;
+    }
+  }
+  return result;
+}
+
+/**
+ * @brief Finds the first sample at which an output is inconsistent with a
+ * saturation of its input.
+ *
+ * @tparam T Type of the signals.
+ * @param input The input samples of the block.
+ * @param output The output samples of the block, of the same length.
+ * @param lower The declared lower limit.
+ * @param upper The declared upper limit.
+ * @param tolerance The maximum allowed difference from the saturated input.
+ * @param expected Receives the saturated input at the violating sample.
+ * @return The index of the first violating sample, or input.size() if the
+ * output is consistent.
+ */
+template <typename T>
+inline std::size_t
+find_saturation_violation(const std::vector<T> &input,
+                          const std::vector<T> &output, T lower, T upper,
+                          T tolerance, T &expected) {
+  for (std::size_t k = 0; k < input.size(); k++) {
+    expected = Reference::saturate(input[k], lower, upper);
+    if (!(std::abs(output[k] - expected) <= tolerance)) {
+      return k;
+    }
+  }
+  return input.size();
+}
+
+/**
+ * @brief Finds the first sample at which an output is inconsistent with a
+ * rate limiter applied to its input.
+ *
+ * Every sample is judged against the rate limiter semantics of
+ * Reference::RateLimiter starting from the previous actual output, not from
+ * a re-simulated one, so a single deviation is reported where it happens
+ * instead of being carried into every later sample.
+ *
+ * @tparam T Type of the signals.
+ * @param input The input samples of the block.
+ * @param output The output samples of the block, of the same length.
+ * @param rising_rate The declared rising slew rate per second.
+ * @param falling_rate The declared falling slew rate per second (negative).
+ * @param dt The sample time in seconds.
+ * @param initial_output The output before the first sample.
+ * @param tolerance The maximum allowed difference from the rate limiter
+ * output.
+ * @param expected Receives the rate limiter output at the violating sample.
+ * @return The index of the first violating sample, or input.size() if the
+ * output is consistent.
+ */
+template <typename T>
+inline std::size_t find_rate_limiter_violation(
+    const std::vector<T> &input, const std::vector<T> &output, T rising_rate,
+    T falling_rate, T dt, T initial_output, T tolerance, T &expected) {
+  T previous = initial_output;
+  for (std::size_t k = 0; k < input.size(); k++) {
+    Reference::RateLimiter<T> block(rising_rate, falling_rate, dt, previous);
+    expected = block.step(input[k]);
+    if (!(std::abs(output[k] - expected) <= tolerance)) {
+      return k;
+    }
+    previous = output[k];
+  }
+  return input.size();
+}
+
+/**
+ * @brief Finds the worst integrator windup event in a controller log.
+ *
+ * A saturation episode is a run of samples at which the command is at or
+ * beyond one of the actuator limits. A controller with working anti-windup
+ * leaves the limit as soon as the error reverses; a wound-up integrator keeps
+ * the command saturated until the excess is integrated away. The windup of an
+ * episode is therefore ki * dt times the summed magnitude of the reversed
+ * error over the saturated samples. A positive error is assumed to raise the
+ * command.
+ *
+ * @tparam T Type of the signals.
+ * @param error The logged control error.
+ * @param command The logged actuator command, of the same length.
+ * @param lower The lower actuator limit.
+ * @param upper The upper actuator limit.
+ * @param integral_gain The integral gain of the controller.
+ * @param dt The sample time in seconds.
+ * @return The episode with the largest windup.
+ */
+template <typename T>
+inline WindupEvent<T>
+find_worst_windup(const std::vector<T> &error, const std::vector<T> &command,
+                  T lower, T upper, T integral_gain, T dt) {
+  WindupEvent<T> worst;
+  const T zero = static_cast<T>(0);
+  std::size_t k = 0;
+  while (k < command.size()) {
+    int side = command[k] >= upper ? 1 : (command[k] <= lower ? -1 : 0);
+    if (side == 0) {
+      k++;
+      continue;
+    }
+
+    WindupEvent<T> episode;
+    episode.start = k;
+    T reversed = zero;
+    while (k < command.size() &&
+           (side > 0 ? command[k] >= upper : command[k] <= lower)) {
+      T pushback = side > 0 ? -error[k] : error[k];
+      if (pushback > zero) {
+        reversed += pushback;
+      }
+      episode.end = k;
+      k++;
+    }
+
+    episode.windup = std::abs(integral_gain) * dt * reversed;
+    if (episode.windup > worst.windup) {
+      worst = episode;
+      worst.found = true;
+    }
+  }
+  return worst;
+}
+
+/**
+ * @brief Computes the statistics of measured durations.
+ *
+ * @param durations The duration of every iteration in seconds.
+ * @return The statistics; all zero if there are no durations.
+ */
+inline RuntimeStatistics
+compute_runtime_statistics(std::vector<double> durations) {
+  RuntimeStatistics statistics;
+  if (durations.empty()) {
+    return statistics;
+  }
+
+  std::sort(durations.begin(), durations.end());
+  double sum = 0.0;
+  for (std::size_t i = 0; i < durations.size(); i++) {
+    sum += durations[i];
+  }
+
+  std::size_t rank = (durations.size() * 99 + 99) / 100;
+  statistics.count = durations.size();
+  statistics.mean = sum / static_cast<double>(durations.size());
+  statistics.p99 = durations[rank - 1];
+  statistics.minimum = durations.front();
+  statistics.maximum = durations.back();
+  return statistics;
+}
+
+/**
+ * @brief Detects where a signal has settled by a windowed variance
+ * criterion.
+ *
+ * A window of `window` consecutive samples is settled if its population
+ * standard deviation is at most `max_sigma`. The signal settles at the first
+ * sample from which every window up to the end of the signal is settled.
+ *
+ * @tparam T Type of the signal.
+ * @param signal The samples of the signal.
+ * @param window The number of samples per window; at least two.
+ * @param max_sigma The largest standard deviation of a settled window.
+ * @return The settling point and the settled value.
+ */
+template <typename T>
+inline SteadyState<T> find_steady_state(const std::vector<T> &signal,
+                                        std::size_t window, T max_sigma) {
+  SteadyState<T> result;
+  if (window < 2 || signal.size() < window) {
+    return result;
+  }
+
+  /* Prefix sums of the samples relative to the last one, which is close to
+   * the settled value, keep the cancellation in the variance small. */
+  const T reference = signal.back();
+  std::vector<T> sums(signal.size() + 1, static_cast<T>(0));
+  std::vector<T> squares(signal.size() + 1, static_cast<T>(0));
+  for (std::size_t i = 0; i < signal.size(); i++) {
+    T x = signal[i] - reference;
+    sums[i + 1] = sums[i] + x;
+    squares[i + 1] = squares[i] + x * x;
+  }
+
+  const T count = static_cast<T>(window);
+  std::size_t start = signal.size() - window + 1;
+  while (start > 0) {
+    std::size_t i = start - 1;
+    T mean = (sums[i + window] - sums[i]) / count;
+    T variance = (squares[i + window] - squares[i]) / count - mean * mean;
+    T sigma = std::sqrt(variance > static_cast<T>(0) ? variance
+                                                     : static_cast<T>(0));
+    if (!(sigma <= max_sigma)) {
+      result.window_sigma = sigma;
+      break;
+    }
+    start = i;
+  }
+
+  if (start == signal.size() - window + 1) {
+    return result;
+  }
+  result.settled = true;
+  result.index = start;
+  result.value = reference + (sums[signal.size()] - sums[start]) /
+                                 static_cast<T>(signal.size() - start);
+  return result;
+}
+
+/**
+ * @brief Detects a sustained periodic oscillation in the second half of a
+ * signal, e.g. a quantization-induced limit cycle of a fixed-point
+ * controller.
+ *
+ * The mean of the tail is removed and its normalized autocorrelation is
+ * computed for lags up to half the tail, so at least two periods are seen.
+ * Past the first negative value, the autocorrelation peaks at the period;
+ * the tail is periodic if the peak reaches `min_correlation`, and peaks at
+ * multiples of the period are not considered once one does. Tails with a
+ * half peak-to-peak range below `min_amplitude` are not reported, so
+ * settling noise well below the resolution of interest is ignored.
+ *
+ * @tparam T Type of the signal.
+ * @param signal The samples of the signal.
+ * @param min_amplitude The smallest amplitude of a reported oscillation.
+ * @param min_correlation The autocorrelation an oscillation must reach at
+ * its period, between 0 and 1.
+ * @return The oscillation, if any.
+ */
+template <typename T>
+inline LimitCycle<T> find_limit_cycle(const std::vector<T> &signal,
+                                      T min_amplitude,
+                                      T min_correlation = static_cast<T>(0.5)) {
+  LimitCycle<T> result;
+  std::vector<T> tail(signal.begin() + signal.size() / 2, signal.end());
+  if (tail.size() < 4) {
+    return result;
+  }
+
+  T low = tail[0];
+  T high = tail[0];
+  T mean = static_cast<T>(0);
+  for (std::size_t i = 0; i < tail.size(); i++) {
+    low = tail[i] < low ? tail[i] : low;
+    high = tail[i] > high ? tail[i] : high;
+    mean += tail[i];
+  }
+  mean /= static_cast<T>(tail.size());
+  result.amplitude = (high - low) / static_cast<T>(2);
+
+  T energy = static_cast<T>(0);
+  for (std::size_t i = 0; i < tail.size(); i++) {
+    tail[i] -= mean;
+    energy += tail[i] * tail[i];
+  }
+  if (!(energy > static_cast<T>(0)) || result.amplitude < min_amplitude) {
+    return result;
+  }
+
+  bool crossed = false;
+  for (std::size_t lag = 1; lag <= tail.size() / 2; lag++) {
+    T sum = static_cast<T>(0);
+    for (std::size_t i = 0; i + lag < tail.size(); i++) {
+      sum += tail[i] * tail[i + lag];
+    }
+    /* Normalizing by the overlap keeps long lags comparable with short. */
+    T correlation = sum / energy * static_cast<T>(tail.size()) /
+                    static_cast<T>(tail.size() - lag);
+    if (correlation < static_cast<T>(0)) {
+      if (result.period > 0 && result.correlation >= min_correlation) {
+        break;
+      }
+      crossed = true;
+    } else if (crossed && correlation > result.correlation) {
+      result.correlation = correlation;
+      result.period = lag;
+    }
+  }
+
+  result.detected = result.period > 0 && result.correlation >= min_correlation;
+  return result;
+}
+
+/**
+ * @brief Returns the root-mean-square value of a signal; 0 for an empty
+ * signal.
+ */
+template <typename T> inline T compute_rms(const std::vector<T> &signal) {
+  if (signal.empty()) {
+    return static_cast<T>(0);
+  }
+  T sum_squared = static_cast<T>(0);
+  for (std::size_t i = 0; i < signal.size(); i++) {
+    sum_squared += signal[i] * signal[i];
+  }
+  return std::sqrt(sum_squared / static_cast<T>(signal.size()));
+}
+
+/**
+ * @brief Returns the difference between the largest and the smallest sample
+ * of a signal; 0 for an empty signal.
+ */
+template <typename T>
+inline T compute_peak_to_peak(const std::vector<T> &signal) {
+  if (signal.empty()) {
+    return static_cast<T>(0);
+  }
+  T low = signal[0];
+  T high = signal[0];
+  for (std::size_t i = 1; i < signal.size(); i++) {
+    low = signal[i] < low ? signal[i] : low;
+    high = signal[i] > high ? signal[i] : high;
+  }
+  return high - low;
+}
+
+/**
+ * @brief Integrates the actuator effort of a logged command signal.
+ *
+ * Every command is held for one sample period, as by a zero-order hold, so
+ * the effort is dt times the sum of |u[k]| or u[k]^2.
+ *
+ * @param command The logged commands.
+ * @param dt The sample period.
+ * @param measure Whether |u| or u^2 is integrated.
+ * @return The total effort.
+ */
+template <typename T>
+inline T compute_effort(const std::vector<T> &command, T dt,
+                        EffortMeasure measure) {
+  T sum = static_cast<T>(0);
+  for (std::size_t i = 0; i < command.size(); i++) {
+    sum += (measure == EffortMeasure::Absolute) ? std::abs(command[i])
+                                                : command[i] * command[i];
+  }
+  return sum * dt;
+}
+
+namespace Detail {
+
+/* Returns twice the average rank of every magnitude, so tied ranks stay
+ * integers. */
+inline std::vector<std::size_t>
+doubled_ranks(const std::vector<double> &magnitudes) {
+  std::vector<std::size_t> order(magnitudes.size());
+  for (std::size_t i = 0; i < order.size(); i++) {
+    order[i] = i;
+  }
+  std::sort(order.begin(), order.end(),
+            [&magnitudes](std::size_t x, std::size_t y) {
+              return magnitudes[x] < magnitudes[y];
+            });
+
+  std::vector<std::size_t> ranks(magnitudes.size());
+  for (std::size_t i = 0; i < order.size();) {
+    std::size_t j = i;
+    while (j + 1 < order.size() &&
+           magnitudes[order[j + 1]] == magnitudes[order[i]]) {
+      j++;
+    }
+    for (std::size_t k = i; k <= j; k++) {
+      ranks[order[k]] = i + j + 2;
+    }
+    i = j + 1;
+  }
+  return ranks;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Tests with a one-sided paired t-test whether B is greater than A.
+ *
+ * @param a The samples of A, e.g. run times of the current implementation.
+ * @param b The samples of B, paired with those of A by index.
+ * @return The test outcome.
+ */
+template <typename T>
+inline SignificanceResult paired_t_test(const std::vector<T> &a,
+                                        const std::vector<T> &b) {
+  SignificanceResult result;
+  if (a.size() != b.size() || a.size() < 2) {
+    return result;
+  }
+
+  std::size_t n = a.size();
+  double mean = 0.0;
+  for (std::size_t i = 0; i < n; i++) {
+    mean += static_cast<double>(b[i]) - static_cast<double>(a[i]);
+  }
+  mean /= static_cast<double>(n);
+  double variance = 0.0;
+  for (std::size_t i = 0; i < n; i++) {
+    double deviation =
+        static_cast<double>(b[i]) - static_cast<double>(a[i]) - mean;
+    variance += deviation * deviation;
+  }
+  variance /= static_cast<double>(n - 1);
+
+  result.valid = true;
+  result.samples = n;
+  result.mean_difference = mean;
+  if (variance == 0.0) {
+    const double infinity = std::numeric_limits<double>::infinity();
+    result.statistic =
+        mean > 0.0 ? infinity : (mean < 0.0 ? -infinity : 0.0);
+    result.p_value = mean > 0.0 ? 0.0 : 1.0;
+    return result;
+  }
+  result.statistic = mean / std::sqrt(variance / static_cast<double>(n));
+  result.p_value = 1.0 - Numerics::student_t_cdf(result.statistic,
+                                                 static_cast<double>(n - 1));
+  return result;
+}
+
+/**
+ * @brief Tests with a one-sided Wilcoxon signed-rank test whether B is
+ * greater than A.
+ *
+ * Pairs without difference are dropped and tied magnitudes share their
+ * average rank. Up to 50 remaining pairs, the p-value is exact, computed
+ * from the distribution of the rank sum over all sign assignments; beyond
+ * that the tie-corrected normal approximation is used.
+ *
+ * @param a The samples of A, e.g. run times of the current implementation.
+ * @param b The samples of B, paired with those of A by index.
+ * @return The test outcome.
+ */
+template <typename T>
+inline SignificanceResult wilcoxon_signed_rank_test(const std::vector<T> &a,
+                                                    const std::vector<T> &b) {
+  SignificanceResult result;
+  if (a.size() != b.size() || a.size() < 2) {
+    return result;
+  }
+
+  std::vector<double> magnitudes;
+  std::vector<bool> positive;
+  for (std::size_t i = 0; i < a.size(); i++) {
+    double difference = static_cast<double>(b[i]) - static_cast<double>(a[i]);
+    result.mean_difference += difference;
+    if (difference != 0.0) {
+      magnitudes.push_back(std::abs(difference));
+      positive.push_back(difference > 0.0);
+    }
+  }
+  result.valid = true;
+  result.mean_difference /= static_cast<double>(a.size());
+  result.samples = magnitudes.size();
+  if (magnitudes.empty()) {
+    return result;
+  }
+
+  std::vector<std::size_t> ranks = Detail::doubled_ranks(magnitudes);
+  std::size_t observed = 0;
+  std::size_t total = 0;
+  for (std::size_t i = 0; i < ranks.size(); i++) {
+    observed += positive[i] ? ranks[i] : 0;
+    total += ranks[i];
+  }
+  result.statistic = static_cast<double>(observed) / 2.0;
+
+  std::size_t n = ranks.size();
+  if (n <= 50) {
+    std::vector<double> probability(total + 1, 0.0);
+    probability[0] = 1.0;
+    std::size_t reached = 0;
+    for (std::size_t i = 0; i < n; i++) {
+      reached += ranks[i];
+      for (std::size_t s = reached; s >= ranks[i]; s--) {
+        probability[s] = 0.5 * (probability[s] + probability[s - ranks[i]]);
+      }
+      for (std::size_t s = ranks[i]; s-- > 0;) {
+        probability[s] *= 0.5;
+      }
+    }
+    double p_value = 0.0;
+    for (std::size_t s = observed; s <= total; s++) {
+      p_value += probability[s];
+    }
+    result.p_value = std::min(1.0, p_value);
+    return result;
+  }
+
+  std::vector<double> sorted = magnitudes;
+  std::sort(sorted.begin(), sorted.end());
+  double tie_correction = 0.0;
+  for (std::size_t i = 0; i < sorted.size();) {
+    std::size_t j = i;
+    while (j < sorted.size() && sorted[j] == sorted[i]) {
+      j++;
+    }
+    double t = static_cast<double>(j - i);
+    tie_correction += t * t * t - t;
+    i = j;
+  }
+  double count = static_cast<double>(n);
+  double mean = count * (count + 1.0) / 4.0;
+  double variance = count * (count + 1.0) * (2.0 * count + 1.0) / 24.0 -
+                    tie_correction / 48.0;
+  double z = (result.statistic - mean - 0.5) / std::sqrt(variance);
+  result.p_value = 1.0 - Numerics::normal_cdf(z);
+  return result;
+}
+
+/**
+ * @brief Returns true if a time base has as many stamps as the series has
+ * samples and the stamps are strictly increasing.
+ */
+template <typename T>
+inline bool valid_time_base(const std::vector<T> &values,
+                            const std::vector<T> &times) {
+  if (values.size() != times.size()) {
+    return false;
+  }
+  for (std::size_t i = 1; i < times.size(); i++) {
+    if (!(times[i] > times[i - 1])) {
+      return false;
+    }
+  }
+  return true;
+}
+
+/**
+ * @brief Compares two time series with different time bases by linearly
+ * interpolating the expected series at the time stamps of the actual one.
+ *
+ * This suits a reference simulated at a higher rate than the generated code,
+ * e.g. 1 kHz against 500 Hz. Actual samples outside the time range of the
+ * expected series are not compared.
+ *
+ * @tparam T Type of the samples and time stamps.
+ * @param actual The actual samples.
+ * @param actual_times The time stamps of the actual samples.
+ * @param expected The expected samples; at least two.
+ * @param expected_times The time stamps of the expected samples.
+ * @param tolerance The tolerance every compared sample must be within.
+ * @return The structured comparison result; a size mismatch if a time base
+ * is invalid (see valid_time_base).
+ */
+template <typename T>
+inline ResampledResult<T>
+compare_resampled(const std::vector<T> &actual,
+                  const std::vector<T> &actual_times,
+                  const std::vector<T> &expected,
+                  const std::vector<T> &expected_times,
+                  const Tolerance<T> &tolerance) {
+  ResampledResult<T> result;
+  if (!valid_time_base(actual, actual_times) ||
+      !valid_time_base(expected, expected_times) || expected.size() < 2) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  std::size_t j = 0;
+  for (std::size_t i = 0; i < actual.size(); i++) {
+    T t = actual_times[i];
+    if (t < expected_times.front() || t > expected_times.back()) {
+      continue;
+    }
+    while (j + 2 < expected_times.size() && expected_times[j + 1] < t) {
+      j++;
+    }
+    T weight =
+        (t - expected_times[j]) / (expected_times[j + 1] - expected_times[j]);
+    T value = expected[j] + weight * (expected[j + 1] - expected[j]);
+    T error = std::abs(actual[i] - value);
+    result.samples_compared++;
+
+    if (!std::isnan(result.max_error) &&
+        (result.samples_compared == 1 || std::isnan(error) ||
+         error > result.max_error)) {
+      result.max_error = error;
+      result.worst_time = t;
+    }
+    Detail::track_relative_error<T>(result, error, value);
+    if (tolerance.accepts(actual[i], value)) {
+      continue;
+    }
+
+    result.mismatch_count++;
+    if (result.status != ComparisonStatus::Failure) {
+      result.status = ComparisonStatus::Failure;
+      result.kind = MismatchKind::ElementMismatch;
+      result.index = i;
+      result.actual_value = actual[i];
+      result.expected_value = value;
+      result.error = error;
+    }
+  }
+  return result;
+}
+
+/**
+ * @brief Computes the sample mean and the unbiased sample variance of a set
+ * of samples.
+ */
+template <typename T>
+inline SampleMoments<T> compute_moments(const std::vector<T> &samples) {
+  SampleMoments<T> moments;
+  moments.samples = samples.size();
+  if (samples.size() < 2) {
+    return moments;
+  }
+
+  moments.valid = true;
+  moments.mean = mean_of(samples);
+  T sum_of_squares = static_cast<T>(0);
+  for (std::size_t i = 0; i < samples.size(); i++) {
+    T deviation = samples[i] - moments.mean;
+    sum_of_squares += deviation * deviation;
+  }
+  moments.variance = sum_of_squares / static_cast<T>(samples.size() - 1);
+  return moments;
+}
+
+/**
+ * @brief Tests with the two-sample Kolmogorov-Smirnov test whether two sets
+ * of samples come from the same distribution.
+ *
+ * The p-value uses the asymptotic Kolmogorov distribution with the effective
+ * sample size n m / (n + m), which is accurate from a few dozen samples on.
+ *
+ * @param a The first set of samples, e.g. outputs of a noise model.
+ * @param b The second set of samples, e.g. draws from the reference
+ * distribution; its size may differ from that of `a`.
+ * @return The test outcome.
+ */
+template <typename T>
+inline KolmogorovSmirnovResult<T>
+kolmogorov_smirnov_test(const std::vector<T> &a, const std::vector<T> &b) {
+  KolmogorovSmirnovResult<T> result;
+  if (a.empty() || b.empty()) {
+    return result;
+  }
+  for (std::size_t i = 0; i < a.size(); i++) {
+    if (std::isnan(a[i])) {
+      return result;
+    }
+  }
+  for (std::size_t i = 0; i < b.size(); i++) {
+    if (std::isnan(b[i])) {
+      return result;
+    }
+  }
+
+  std::vector<T> sorted_a = a;
+  std::vector<T> sorted_b = b;
+  std::sort(sorted_a.begin(), sorted_a.end());
+  std::sort(sorted_b.begin(), sorted_b.end());
+  const double n = static_cast<double>(a.size());
+  const double m = static_cast<double>(b.size());
+
+  /* Once one sample is exhausted the distance only shrinks, so the walk can
+   * stop there. */
+  std::size_t i = 0;
+  std::size_t j = 0;
+  while (i < sorted_a.size() && j < sorted_b.size()) {
+    T x = sorted_a[i] < sorted_b[j] ? sorted_a[i] : sorted_b[j];
+    while (i < sorted_a.size() && sorted_a[i] <= x) {
+      i++;
+    }
+    while (j < sorted_b.size() && sorted_b[j] <= x) {
+      j++;
+    }
+    double distance =
+        std::abs(static_cast<double>(i) / n - static_cast<double>(j) / m);
+    if (distance > result.statistic) {
+      result.statistic = distance;
+      result.location = x;
+    }
+  }
+
+  double root = std::sqrt(n * m / (n + m));
+  result.valid = true;
+  result.p_value = Numerics::kolmogorov_survival(
+      (root + 0.12 + 0.11 / root) * result.statistic);
+  return result;
+}
+
+/**
+ * @brief Returns the asymptotic critical value of the two-sample
+ * Kolmogorov-Smirnov statistic for sample sizes n and m at significance
+ * level `alpha`, sqrt(-ln(alpha / 2) / 2) sqrt((n + m) / (n m)).
+ *
+ * Samples from the same distribution exceed it with a probability of about
+ * `alpha`.
+ */
+inline double kolmogorov_smirnov_critical_value(std::size_t n, std::size_t m,
+                                                double alpha) {
+  double sizes = static_cast<double>(n) * static_cast<double>(m);
+  return std::sqrt(-0.5 * std::log(0.5 * alpha)) *
+         std::sqrt((static_cast<double>(n) + static_cast<double>(m)) / sizes);
+}
+
+/**
+ * @brief Compares two flattened integer arrays for exact equality, e.g.
+ * index maps, active-set flags or iteration counts.
+ *
+ * @tparam I Integer type of the array elements.
+ * @param actual The flattened array of actual values.
+ * @param expected The flattened array of expected values.
+ * @return The shape check and the position of every differing element.
+ */
+template <typename I>
+inline IntegerComparisonResult
+compare_integer_arrays(const FlatArray<I> &actual,
+                       const FlatArray<I> &expected) {
+  static_assert(std::is_integral<I>::value,
+                "compare_integer_arrays requires an integer element type.");
+  IntegerComparisonResult result;
+  result.shape_matches = same_shape(actual, expected);
+  if (!result.shape_matches) {
+    return result;
+  }
+  for (std::size_t i = 0; i < actual.data.size(); i++) {
+    if (actual.data[i] != expected.data[i]) {
+      result.mismatches.push_back(i);
+    }
+  }
+  return result;
+}
+
+/**
+ * @brief Compares two bundles of named signals, matching the signals by
+ * name regardless of their order.
+ *
+ * @tparam T Type of the signal values.
+ * @param actual The actual signals by name.
+ * @param expected The expected signals by name.
+ * @param tolerance The tolerance corresponding samples must be within.
+ * @return The missing and extra signals and the comparison of every signal
+ * in both bundles.
+ */
+template <typename T>
+inline SignalBundleResult<T>
+compare_signals(const std::map<std::string, FlatArray<T>> &actual,
+                const std::map<std::string, FlatArray<T>> &expected,
+                const Tolerance<T> &tolerance) {
+  SignalBundleResult<T> result;
+  typedef typename std::map<std::string, FlatArray<T>>::const_iterator
+      Iterator;
+  for (Iterator it = expected.begin(); it != expected.end(); ++it) {
+    Iterator match = actual.find(it->first);
+    if (match == actual.end()) {
+      result.missing.push_back(it->first);
+      continue;
+    }
+    SignalComparison<T> signal;
+    signal.name = it->first;
+    signal.result = compare_arrays(match->second, it->second, tolerance);
+    result.signals.push_back(signal);
+  }
+  for (Iterator it = actual.begin(); it != actual.end(); ++it) {
+    if (expected.find(it->first) == expected.end()) {
+      result.extra.push_back(it->first);
+    }
+  }
+  return result;
+}
+
+/**
+ * @brief Compares two bundles of named signals within an absolute
+ * tolerance.
+ */
+template <typename T>
+inline SignalBundleResult<T>
+compare_signals(const std::map<std::string, FlatArray<T>> &actual,
+                const std::map<std::string, FlatArray<T>> &expected,
+                T tolerance) {
+  return compare_signals(actual, expected, Tolerance<T>(tolerance));
+}
+
+} // namespace Comparison
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_COMPARISON_HPP
@@ -0,0 +1,254 @@
+/**
+ * @file MCAP_tester_conformance.hpp
+ * @brief Provides a conformance suite that verifies the behaviour of a
+ * configured Tester::MCAPTester on known comparison cases.
+ *
+ * Before trusting green results, an integrator can run the suite against the
+ * same configuration that the real tests use. Every case runs on a fresh
+ * tester prepared by the integrator's configuration function and checks that
+ * the observed verdict matches the documented one, covering edge-case
+ * floating-point values, shape handling, NaN handling and the degenerate
+ * array policies. The expected verdict of the policy-dependent cases is
+ * derived from the configured policies.
+ */
+
+#ifndef MCAP_TESTER_CONFORMANCE_HPP
+#define MCAP_TESTER_CONFORMANCE_HPP
+
+#include <cstddef>
+#include <functional>
+#include <limits>
+#include <stdexcept>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+namespace Tester {
+
+/**
+ * @brief The outcome of one conformance case.
+ */
+struct ConformanceCase {
+  ConformanceCase()
+      : name(), expected(ComparisonStatus::Pass),
+        observed(ComparisonStatus::Pass) {}
+
+  bool passed() const { return this->expected == this->observed; }
+
+  std::string name;
+  ComparisonStatus expected;
+  ComparisonStatus observed;
+};
+
+/**
+ * @brief The outcomes of all conformance cases.
+ */
+struct ConformanceResult {
+  ConformanceResult() : cases() {}
+
+  bool passed() const { return this->failure_count() == 0; }
+
+  std::size_t failure_count() const {
+    std::size_t count = 0;
+    for (std::size_t i = 0; i < this->cases.size(); i++) {
+      count += this->cases[i].passed() ? 0 : 1;
+    }
+    return count;
+  }
+
+  std::vector<ConformanceCase> cases;
+};
+
+namespace Conformance {
+
+namespace Detail {
+
+inline ComparisonStatus
+worst_status(const std::vector<AssertionRecord> &records) {
+  ComparisonStatus worst = ComparisonStatus::Pass;
+  for (std::size_t i = 0; i < records.size(); i++) {
+    if (records[i].status == ComparisonStatus::Failure) {
+      return ComparisonStatus::Failure;
+    } else if (records[i].status == ComparisonStatus::Warning) {
+      worst = ComparisonStatus::Warning;
+    }
+  }
+  return worst;
+}
+
+inline ComparisonStatus policy_status(DegenerateArrayPolicy policy) {
+  if (policy == DegenerateArrayPolicy::Fail) {
+    return ComparisonStatus::Failure;
+  } else if (policy == DegenerateArrayPolicy::Warn) {
+    return ComparisonStatus::Warning;
+  }
+  return ComparisonStatus::Pass;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Runs the conformance suite against a tester configuration.
+ *
+ * The testers of the cases run quietly, and a fail-fast configuration is
+ * supported.
+ *
+ * @tparam T The element type the real tests use.
+ * @param configure Applies the configuration under test to a fresh tester,
+ * e.g. the degenerate array policies.
+ * @return The expected and observed verdict of every case.
+ */
+template <typename T>
+inline ConformanceResult
+run(const std::function<void(MCAPTester<T> &)> &configure) {
+  typedef std::function<void(MCAPTester<T> &)> Body;
+  struct Case {
+    std::string name;
+    ComparisonStatus expected;
+    Body body;
+  };
+
+  MCAPTester<T> probe;
+  configure(probe);
+  ComparisonStatus empty_status =
+      Detail::policy_status(probe.get_empty_array_policy());
+  ComparisonStatus single_status =
+      Detail::policy_status(probe.get_single_element_policy());
+
+  const T zero = static_cast<T>(0);
+  const T one = static_cast<T>(1);
+  const T half = static_cast<T>(0.5);
+  const T nan = std::numeric_limits<T>::quiet_NaN();
+  const T infinity = std::numeric_limits<T>::infinity();
+  const T largest = std::numeric_limits<T>::max();
+  const T denormal = std::numeric_limits<T>::denorm_min();
+  const T epsilon = std::numeric_limits<T>::epsilon();
+
+  const ComparisonStatus pass = ComparisonStatus::Pass;
+  const ComparisonStatus warning = ComparisonStatus::Warning;
+  const ComparisonStatus failure = ComparisonStatus::Failure;
+
+  std::vector<Case> cases = {
+      {"Identical scalars pass", pass,
+       [=](MCAPTester<T> &t) { t.expect_near(one, one, zero, "case"); }},
+      {"Difference equal to the tolerance passes", pass,
+       [=](MCAPTester<T> &t) { t.expect_near(one, one + half, half, "case"); }},
+      {"Difference above the tolerance fails", failure,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(one, one + half, half / 2, "case");
+       }},
+      {"Negative zero equals zero", pass,
+       [=](MCAPTester<T> &t) { t.expect_near(-zero, zero, zero, "case"); }},
+      {"Denormal values within epsilon of zero pass", pass,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(denormal, zero, epsilon, "case");
+       }},
+      {"Largest finite values compare equal", pass,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(largest, largest, zero, "case");
+       }},
+      {"Opposite infinities fail", failure,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(infinity, -infinity, largest, "case");
+       }},
+      {"NaN actual value fails", failure,
+       [=](MCAPTester<T> &t) { t.expect_near(nan, one, largest, "case"); }},
+      {"NaN never matches NaN", failure,
+       [=](MCAPTester<T> &t) { t.expect_near(nan, nan, largest, "case"); }},
+      {"NaN in the last element of an array fails", failure,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(std::vector<T>{one, one, nan},
+                       std::vector<T>{one, one, one}, largest, "case");
+       }},
+      {"Mismatch in the last element of an array fails", failure,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(std::vector<T>{one, one, one},
+                       std::vector<T>{one, one, zero}, half, "case");
+       }},
+      {"Vectors of different length fail", failure,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(std::vector<T>{one, one}, std::vector<T>{one}, largest,
+                       "case");
+       }},
+      {"Transposed shapes with equal elements fail", failure,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(std::vector<std::vector<T>>{{one, one, one},
+                                                   {one, one, one}},
+                       std::vector<std::vector<T>>{
+                           {one, one}, {one, one}, {one, one}},
+                       largest, "case");
+       }},
+      {"Ragged nested vectors fail", failure,
+       [=](MCAPTester<T> &t) {
+         std::vector<std::vector<T>> ragged = {{one, one}, {one}};
+         t.expect_near(ragged, ragged, largest, "case");
+       }},
+      {"Difference between the warning and failure tolerance warns", warning,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(one, one + half, half / 2, one, "case");
+       }},
+      {"Empty arrays follow the empty array policy", empty_status,
+       [=](MCAPTester<T> &t) {
+         t.expect_near(std::vector<T>(), std::vector<T>(), zero, "case");
+       }},
+      {"Single-element arrays follow the single element policy",
+       single_status, [=](MCAPTester<T> &t) {
+         t.expect_near(std::vector<T>{one}, std::vector<T>{one}, zero, "case");
+       }}};
+
+  ConformanceResult result;
+  for (std::size_t i = 0; i < cases.size(); i++) {
+    MCAPTester<T> tester;
+    configure(tester);
+    tester.set_verbosity(Verbosity::Quiet);
+    try {
+      cases[i].body(tester);
+    } catch (const std::runtime_error &) {
+      /* A fail-fast tester throws after recording the failure. */
+    }
+
+    ConformanceCase outcome;
+    outcome.name = cases[i].name;
+    outcome.expected = cases[i].expected;
+    outcome.observed = Detail::worst_status(tester.get_records());
+    result.cases.push_back(outcome);
+  }
+
+  return result;
+}
+
+/**
+ * @brief Runs the conformance suite against the default configuration.
+ */
+template <typename T> inline ConformanceResult run() {
+  return run<T>([](MCAPTester<T> &) {});
+}
+
+/**
+ * @brief Runs the conformance suite and reports every case through a
+ * tester, under the message "Conformance: <case>".
+ *
+ * @param tester The tester that receives the outcome of every case.
+ * @param configure Applies the configuration under test to a fresh tester.
+ */
+template <typename T>
+inline void check(MCAPTester<T> &tester,
+                  const std::function<void(MCAPTester<T> &)> &configure) {
+  ConformanceResult result = run<T>(configure);
+  for (std::size_t i = 0; i < result.cases.size(); i++) {
+    const ConformanceCase &outcome = result.cases[i];
+    std::string detail = "Expected " +
+                         Report::status_to_string(outcome.expected) +
+                         ", observed " +
+                         Report::status_to_string(outcome.observed) + ".";
+    tester.expect_true(outcome.passed(), "Conformance: " + outcome.name,
+                       detail);
+  }
+}
+
+} // namespace Conformance
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_CONFORMANCE_HPP
@@ -0,0 +1,232 @@
+/**
+ * @file MCAP_tester_error_map.hpp
+ * @brief Provides element-wise error maps of array comparisons, written as
+ * CSV tables or PNG heatmaps.
+ *
+ * The worst element of a failed 500 x 500 matrix comparison rarely tells an
+ * indexing bug from accumulated round-off; the spatial pattern of the error
+ * does. An ErrorMap holds |actual - expected| for every element, arranged as
+ * a matrix with one row per index of the leading dimensions and one column
+ * per index of the last dimension. save_error_map_csv writes it as a table.
+ * With MCAP_TESTER_ENABLE_ERROR_MAP_PNG defined, save_error_map_png writes a
+ * heatmap from black (no error) through red and yellow to white (the largest
+ * error); NaN errors are drawn blue. The PNG encoder is self-contained and
+ * stores the image uncompressed.
+ *
+ * MCAPTester writes the maps of failed array comparisons automatically once
+ * an artifacts directory is set with set_error_map_directory.
+ */
+
+#ifndef MCAP_TESTER_ERROR_MAP_HPP
+#define MCAP_TESTER_ERROR_MAP_HPP
+
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester_comparison.hpp"
+#include "MCAP_tester_flat_array.hpp"
+
+#ifndef MCAP_TESTER_NO_STDIO
+#include <fstream>
+#include <iomanip>
+#include <limits>
+#endif
+
+namespace Tester {
+
+/**
+ * @brief The absolute element-wise error of an array comparison, stored row
+ * by row.
+ */
+struct ErrorMap {
+  ErrorMap() : rows(0), columns(0), errors() {}
+
+  /**
+   * @brief Returns the largest error that is not NaN, or 0 if there is none.
+   */
+  double max_error() const {
+    double largest = 0.0;
+    for (std::size_t i = 0; i < this->errors.size(); i++) {
+      if (this->errors[i] > largest) {
+        largest = this->errors[i];
+      }
+    }
+    return largest;
+  }
+
+  std::size_t rows;
+  std::size_t columns;
+  std::vector<double> errors;
+};
+
+/**
+ * @brief Computes the error map of two arrays of the same shape.
+ *
+ * @param actual The actual array.
+ * @param expected The expected array.
+ * @param map Receives |actual - expected| for every element.
+ * @return false if the shapes differ or the arrays are empty.
+ */
+template <typename T>
+inline bool make_error_map(const FlatArray<T> &actual,
+                           const FlatArray<T> &expected, ErrorMap &map) {
+  if (!Comparison::same_shape(actual, expected) || actual.data.empty()) {
+    return false;
+  }
+  map.columns = actual.shape.empty() ? 1 : actual.shape.back();
+  map.rows = actual.data.size() / map.columns;
+  map.errors.resize(actual.data.size());
+  for (std::size_t i = 0; i < actual.data.size(); i++) {
+    map.errors[i] = std::abs(static_cast<double>(actual.data[i]) -
+                             static_cast<double>(expected.data[i]));
+  }
+  return true;
+}
+
+#ifndef MCAP_TESTER_NO_STDIO
+/**
+ * @brief Writes an error map as a CSV table with one line per row and full
+ * precision.
+ *
+ * @return true if the file was written successfully, false otherwise.
+ */
+inline bool save_error_map_csv(const std::string &path, const ErrorMap &map) {
+  std::ofstream file(path.c_str());
+  if (!file) {
+    return false;
+  }
+  file << std::setprecision(std::numeric_limits<double>::max_digits10);
+  for (std::size_t r = 0; r < map.rows; r++) {
+    for (std::size_t c = 0; c < map.columns; c++) {
+      file << (c == 0 ? "" : ",") << map.errors[r * map.columns + c];
+    }
+    file << "\n";
+  }
+  return static_cast<bool>(file);
+}
+
+#ifdef MCAP_TESTER_ENABLE_ERROR_MAP_PNG
+namespace Detail {
+
+inline std::uint32_t png_crc(const std::string &bytes) {
+  std::uint32_t crc = 0xFFFFFFFFu;
+  for (std::size_t i = 0; i < bytes.size(); i++) {
+    crc ^= static_cast<unsigned char>(bytes[i]);
+    for (int k = 0; k < 8; k++) {
+      crc = (crc & 1u) ? (crc >> 1) ^ 0xEDB88320u : crc >> 1;
+    }
+  }
+  return crc ^ 0xFFFFFFFFu;
+}
+
+inline void append_be32(std::string &bytes, std::uint32_t value) {
+  bytes += static_cast<char>((value >> 24) & 0xFFu);
+  bytes += static_cast<char>((value >> 16) & 0xFFu);
+  bytes += static_cast<char>((value >> 8) & 0xFFu);
+  bytes += static_cast<char>(value & 0xFFu);
+}
+
+inline void append_png_chunk(std::string &png, const std::string &type,
+                             const std::string &data) {
+  append_be32(png, static_cast<std::uint32_t>(data.size()));
+  png += type + data;
+  append_be32(png, png_crc(type + data));
+}
+
+/* Wraps raw bytes into a zlib stream of uncompressed deflate blocks. */
+inline std::string zlib_store(const std::string &raw) {
+  std::string stream("\x78\x01", 2);
+  std::size_t offset = 0;
+  do {
+    std::size_t length = raw.size() - offset;
+    length = length > 65535 ? 65535 : length;
+    bool last = offset + length == raw.size();
+    stream += static_cast<char>(last ? 1 : 0);
+    stream += static_cast<char>(length & 0xFFu);
+    stream += static_cast<char>((length >> 8) & 0xFFu);
+    stream += static_cast<char>(~length & 0xFFu);
+    stream += static_cast<char>((~length >> 8) & 0xFFu);
+    stream += raw.substr(offset, length);
+    offset += length;
+  } while (offset < raw.size());
+
+  std::uint32_t a = 1;
+  std::uint32_t b = 0;
+  for (std::size_t i = 0; i < raw.size(); i++) {
+    a = (a + static_cast<unsigned char>(raw[i])) % 65521u;
+    b = (b + a) % 65521u;
+  }
+  append_be32(stream, (b << 16) | a);
+  return stream;
+}
+
+/* Maps a share of the largest error in [0, 1] to the black-red-yellow-white
+ * heat scale. */
+inline void heat_color(double share, unsigned char rgb[3]) {
+  double level = share * 3.0;
+  for (int channel = 0; channel < 3; channel++) {
+    double value = level - channel;
+    value = value < 0.0 ? 0.0 : (value > 1.0 ? 1.0 : value);
+    rgb[channel] = static_cast<unsigned char>(value * 255.0 + 0.5);
+  }
+}
+
+} // namespace Detail
+
+/**
+ * @brief Writes an error map as a PNG heatmap.
+ *
+ * Small maps are enlarged so that the longer side has at least 256 pixels.
+ *
+ * @return true if the file was written successfully, false otherwise.
+ */
+inline bool save_error_map_png(const std::string &path, const ErrorMap &map) {
+  if (map.rows == 0 || map.columns == 0) {
+    return false;
+  }
+  std::size_t longer = map.rows > map.columns ? map.rows : map.columns;
+  std::size_t scale = longer < 256 ? (256 + longer - 1) / longer : 1;
+  std::size_t width = map.columns * scale;
+  std::size_t height = map.rows * scale;
+  double largest = map.max_error();
+
+  std::string raw;
+  raw.reserve(height * (1 + 3 * width));
+  for (std::size_t y = 0; y < height; y++) {
+    raw += '\0';
+    for (std::size_t x = 0; x < width; x++) {
+      double error = map.errors[(y / scale) * map.columns + x / scale];
+      unsigned char rgb[3] = {0, 0, 255};
+      if (!std::isnan(error)) {
+        Detail::heat_color(largest > 0.0 ? error / largest : 0.0, rgb);
+      }
+      raw.append(reinterpret_cast<const char *>(rgb), 3);
+    }
+  }
+
+  std::string header;
+  Detail::append_be32(header, static_cast<std::uint32_t>(width));
+  Detail::append_be32(header, static_cast<std::uint32_t>(height));
+  header += std::string("\x08\x02\x00\x00\x00", 5);
+
+  std::string png("\x89PNG\r\n\x1a\n", 8);
+  Detail::append_png_chunk(png, "IHDR", header);
+  Detail::append_png_chunk(png, "IDAT", Detail::zlib_store(raw));
+  Detail::append_png_chunk(png, "IEND", std::string());
+
+  std::ofstream file(path.c_str(), std::ios::binary);
+  if (!file) {
+    return false;
+  }
+  file.write(png.data(), static_cast<std::streamsize>(png.size()));
+  return static_cast<bool>(file);
+}
+#endif
+#endif
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_ERROR_MAP_HPP
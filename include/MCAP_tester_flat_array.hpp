@@ -0,0 +1,451 @@
+/**
+ * @file MCAP_tester_flat_array.hpp
+ * @brief Provides the Tester::FlatArray container shared by the MCAP_tester
+ * headers, with conversions from standard containers and JSON, fingerprinting
+ * and plain text storage.
+ *
+ * Every supported input (std::vector, std::array, their nested forms, JSON
+ * fixture values and MAT file variables) is flattened into row-major order
+ * together with its shape, so that the comparison functions only need to be
+ * written once.
+ */
+
+#ifndef MCAP_TESTER_FLAT_ARRAY_HPP
+#define MCAP_TESTER_FLAT_ARRAY_HPP
+
+#include <array>
+#include <cerrno>
+#include <cmath>
+#include <cstdint>
+#include <cstdio>
+#include <cstdlib>
+#include <cstring>
+#include <limits>
+#include <string>
+#include <type_traits>
+#include <vector>
+
+#include "MCAP_tester_json.hpp"
+
+#ifndef MCAP_TESTER_NO_STDIO
+#include <fstream>
+#include <iomanip>
+#include <sstream>
+
+#ifdef _WIN32
+#include <direct.h>
+#else
+#include <sys/stat.h>
+#include <sys/types.h>
+#endif
+#endif
+
+namespace Tester {
+
+/**
+ * @brief Memory order of the buffer a FlatArray was created from.
+ */
+enum class ArrayLayout { RowMajor, ColumnMajor };
+
+/**
+ * @brief Row-major flattened copy of a vector, array or nested container.
+ *
+ * The shape holds the extent of every dimension, so a std::vector of length N
+ * becomes shape {N} and a nested container with M rows of N columns becomes
+ * shape {M, N}. Nested std::vector objects whose rows differ in length cannot
+ * be described by a shape; they are marked as ragged instead.
+ *
+ * The data is always stored in row-major order. The layout records the order
+ * of the source buffer, which only matters for strict shape checks.
+ *
+ * @tparam T Type of the stored elements.
+ */
+template <typename T> struct FlatArray {
+  FlatArray()
+      : shape(), data(), ragged(false), layout(ArrayLayout::RowMajor) {}
+
+  std::vector<std::size_t> shape;
+  std::vector<T> data;
+  bool ragged;
+  ArrayLayout layout;
+};
+
+template <typename T>
+inline FlatArray<T> make_flat_array(const std::vector<T> &values) {
+  FlatArray<T> flat;
+  flat.shape.push_back(values.size());
+  flat.data = values;
+  return flat;
+}
+
+template <typename T, std::size_t N>
+inline FlatArray<T> make_flat_array(const std::array<T, N> &values) {
+  FlatArray<T> flat;
+  flat.shape.push_back(N);
+  flat.data.assign(values.begin(), values.end());
+  return flat;
+}
+
+template <typename T>
+inline FlatArray<T>
+make_flat_array(const std::vector<std::vector<T>> &values) {
+  FlatArray<T> flat;
+  std::size_t columns = values.empty() ? 0 : values[0].size();
+  flat.shape.push_back(values.size());
+  flat.shape.push_back(columns);
+
+  for (std::size_t i = 0; i < values.size(); i++) {
+    if (values[i].size() != columns) {
+      flat.ragged = true;
+    }
+    flat.data.insert(flat.data.end(), values[i].begin(), values[i].end());
+  }
+
+  return flat;
+}
+
+template <typename T, std::size_t M, std::size_t N>
+inline FlatArray<T>
+make_flat_array(const std::array<std::array<T, N>, M> &values) {
+  FlatArray<T> flat;
+  flat.shape.push_back(M);
+  flat.shape.push_back(N);
+  for (std::size_t i = 0; i < M; ++i) {
+    flat.data.insert(flat.data.end(), values[i].begin(), values[i].end());
+  }
+  return flat;
+}
+
+template <typename T>
+inline FlatArray<T> make_flat_array(const FlatArray<T> &values) {
+  return values;
+}
+
+/**
+ * @brief Copies a contiguous matrix buffer into a FlatArray.
+ *
+ * This is meant for generated code that exposes its matrices as raw arrays.
+ * Elements are read according to `layout` and stored in row-major order, and
+ * the layout is recorded so strict checks can verify the memory order.
+ *
+ * @tparam T Type of the elements.
+ * @param buffer Pointer to rows * columns contiguous elements.
+ * @param rows The number of rows.
+ * @param columns The number of columns.
+ * @param layout The memory order of the buffer.
+ * @return The flattened matrix with shape {rows, columns}.
+ */
+template <typename T>
+inline FlatArray<T> make_flat_array_from_buffer(const T *buffer,
+                                                std::size_t rows,
+                                                std::size_t columns,
+                                                ArrayLayout layout) {
+  FlatArray<T> flat;
+  flat.shape.push_back(rows);
+  flat.shape.push_back(columns);
+  flat.layout = layout;
+  flat.data.resize(rows * columns);
+
+  for (std::size_t i = 0; i < rows; i++) {
+    for (std::size_t j = 0; j < columns; j++) {
+      flat.data[i * columns + j] = (layout == ArrayLayout::RowMajor)
+                                       ? buffer[i * columns + j]
+                                       : buffer[j * rows + i];
+    }
+  }
+  return flat;
+}
+
+/**
+ * @brief Wraps a single scalar as a zero-dimensional FlatArray.
+ */
+template <typename T> inline FlatArray<T> make_flat_scalar(T value) {
+  FlatArray<T> flat;
+  flat.data.push_back(value);
+  return flat;
+}
+
+/**
+ * @brief Removes all dimensions of extent one from a FlatArray.
+ *
+ * This lets a MATLAB 1xN or Nx1 matrix be compared with a vector of length N.
+ */
+template <typename T>
+inline FlatArray<T> squeeze_flat_array(FlatArray<T> flat) {
+  std::vector<std::size_t> shape;
+  for (std::size_t i = 0; i < flat.shape.size(); i++) {
+    if (flat.shape[i] != 1) {
+      shape.push_back(flat.shape[i]);
+    }
+  }
+  flat.shape = shape;
+  return flat;
+}
+
+/**
+ * @brief Converts a JSON number or (nested) array of numbers into a
+ * FlatArray.
+ *
+ * A number becomes a zero-dimensional array, an array of numbers becomes shape
+ * {N} and an array of arrays becomes shape {M, N}. Rows of unequal length mark
+ * the result as ragged.
+ *
+ * @tparam T Type of the array elements.
+ * @param value The JSON value to convert.
+ * @param flat Receives the converted array.
+ * @return true if the value only contains numbers nested at most two deep.
+ */
+template <typename T>
+inline bool json_to_flat_array(const JSON::Value &value, FlatArray<T> &flat) {
+  FlatArray<T> converted;
+
+  if (value.is_number()) {
+    converted.data.push_back(static_cast<T>(value.as_number()));
+  } else if (value.is_array() && (value.size() == 0 || value[0].is_number())) {
+    converted.shape.push_back(value.size());
+    for (std::size_t i = 0; i < value.size(); i++) {
+      if (!value[i].is_number()) {
+        return false;
+      }
+      converted.data.push_back(static_cast<T>(value[i].as_number()));
+    }
+  } else if (value.is_array()) {
+    std::size_t columns = value[0].size();
+    converted.shape.push_back(value.size());
+    converted.shape.push_back(columns);
+    for (std::size_t i = 0; i < value.size(); i++) {
+      if (!value[i].is_array()) {
+        return false;
+      }
+      if (value[i].size() != columns) {
+        converted.ragged = true;
+      }
+      for (std::size_t j = 0; j < value[i].size(); j++) {
+        if (!value[i][j].is_number()) {
+          return false;
+        }
+        converted.data.push_back(static_cast<T>(value[i][j].as_number()));
+      }
+    }
+  } else {
+    return false;
+  }
+
+  flat = converted;
+  return true;
+}
+
+/**
+ * @brief Computes a canonical, tolerance-quantized 64-bit fingerprint of an
+ * array.
+ *
+ * Every element is rounded to the nearest multiple of `quantum` before it is
+ * hashed (FNV-1a) together with the shape, so arrays that differ only by noise
+ * well below `quantum` share the same fingerprint. Negative zero hashes like
+ * zero, and all NaN payloads hash alike.
+ *
+ * Note that two values closer than `quantum` can still fall on different
+ * sides of a rounding boundary; a fingerprint mismatch is therefore a hint to
+ * compare in full, not a proof that the arrays differ.
+ *
+ * @tparam T Type of the array elements.
+ * @param values The flattened array to fingerprint.
+ * @param quantum The quantization step, typically the comparison tolerance.
+ * @return The 64-bit fingerprint.
+ */
+template <typename T>
+inline std::uint64_t compute_fingerprint(const FlatArray<T> &values,
+                                         T quantum) {
+  const std::uint64_t fnv_prime = 1099511628211ULL;
+  std::uint64_t hash = 14695981039346656037ULL;
+
+  auto mix = [&hash, fnv_prime](std::uint64_t word) {
+    for (int byte = 0; byte < 8; byte++) {
+      hash ^= (word >> (byte * 8)) & 0xFFULL;
+      hash *= fnv_prime;
+    }
+  };
+
+  mix(static_cast<std::uint64_t>(values.shape.size()));
+  for (std::size_t i = 0; i < values.shape.size(); i++) {
+    mix(static_cast<std::uint64_t>(values.shape[i]));
+  }
+
+  for (std::size_t i = 0; i < values.data.size(); i++) {
+    double element = static_cast<double>(values.data[i]);
+    double step = static_cast<double>(quantum);
+    double quantized = 0.0;
+
+    if (std::isnan(element)) {
+      quantized = std::numeric_limits<double>::quiet_NaN();
+    } else if (std::isinf(element) || !(step > 0.0)) {
+      quantized = element;
+    } else {
+      quantized = std::floor(element / step + 0.5);
+    }
+
+    std::uint64_t word = 0;
+    if (std::isnan(quantized)) {
+      word = 0x7FF8000000000000ULL;
+    } else if (quantized == 0.0) {
+      word = 0;
+    } else {
+      std::memcpy(&word, &quantized, sizeof(word));
+    }
+    mix(word);
+  }
+
+  return hash;
+}
+
+/**
+ * @brief Formats a number for failure messages with six significant digits.
+ */
+template <typename T> inline std::string format_number(T value) {
+#ifdef MCAP_TESTER_NO_STDIO
+  if (std::is_integral<T>::value) {
+    return std::to_string(value);
+  }
+  char buffer[32];
+  std::snprintf(buffer, sizeof(buffer), "%.6g", static_cast<double>(value));
+  return buffer;
+#else
+  std::ostringstream stream;
+  stream << std::setprecision(6) << value;
+  return stream.str();
+#endif
+}
+
+/**
+ * @brief Formats a fingerprint as a zero-padded hexadecimal literal.
+ *
+ * @param fingerprint The fingerprint to format.
+ * @return The fingerprint as a string such as "0x0123456789abcdef".
+ */
+inline std::string fingerprint_to_string(std::uint64_t fingerprint) {
+  char buffer[32];
+  std::snprintf(buffer, sizeof(buffer), "0x%016llx",
+                static_cast<unsigned long long>(fingerprint));
+  return buffer;
+}
+
+#ifndef MCAP_TESTER_NO_STDIO
+/**
+ * @brief Writes a flattened array to a plain text file.
+ *
+ * The first line holds the number of dimensions followed by the extent of
+ * each dimension. The elements follow in row-major order, one row of the last
+ * dimension per line, written with enough digits to round-trip exactly.
+ *
+ * @tparam T Type of the array elements.
+ * @param path The file to write.
+ * @param values The array to store.
+ * @return true if the file was written successfully, false otherwise.
+ */
+template <typename T>
+inline bool save_flat_array(const std::string &path,
+                            const FlatArray<T> &values) {
+  std::ofstream file(path.c_str());
+  if (!file) {
+    return false;
+  }
+
+  file << values.shape.size();
+  for (std::size_t i = 0; i < values.shape.size(); i++) {
+    file << " " << values.shape[i];
+  }
+  file << "\n";
+
+  std::size_t row_length = values.shape.empty() ? 1 : values.shape.back();
+  file << std::setprecision(std::numeric_limits<T>::max_digits10);
+  for (std::size_t i = 0; i < values.data.size(); i++) {
+    file << values.data[i];
+    if (row_length == 0 || (i + 1) % row_length == 0) {
+      file << "\n";
+    } else {
+      file << " ";
+    }
+  }
+
+  return static_cast<bool>(file);
+}
+
+/**
+ * @brief Creates a directory and all of its missing parents.
+ *
+ * @param path The directory to create, using '/' or '\\' as separators.
+ * @return true if the directory exists afterwards, false otherwise.
+ */
+inline bool create_directories(const std::string &path) {
+  for (std::size_t i = 1; i <= path.size(); i++) {
+    if (i != path.size() && path[i] != '/' && path[i] != '\\') {
+      continue;
+    }
+    std::string parent = path.substr(0, i);
+#ifdef _WIN32
+    int result = _mkdir(parent.c_str());
+#else
+    int result = mkdir(parent.c_str(), 0755);
+#endif
+    if (result != 0 && errno != EEXIST) {
+      return false;
+    }
+  }
+  return true;
+}
+
+/**
+ * @brief Reads a flattened array written by save_flat_array.
+ *
+ * @tparam T Type of the array elements.
+ * @param path The file to read.
+ * @param values Receives the loaded array.
+ * @return true if the file exists and is well formed, false otherwise.
+ */
+template <typename T>
+inline bool load_flat_array(const std::string &path, FlatArray<T> &values) {
+  std::ifstream file(path.c_str());
+  if (!file) {
+    return false;
+  }
+
+  std::size_t dimensions = 0;
+  if (!(file >> dimensions)) {
+    return false;
+  }
+
+  FlatArray<T> loaded;
+  std::size_t element_count = 1;
+  for (std::size_t i = 0; i < dimensions; i++) {
+    std::size_t extent = 0;
+    if (!(file >> extent)) {
+      return false;
+    }
+    loaded.shape.push_back(extent);
+    element_count *= extent;
+  }
+
+  /* Tokens are parsed with strtod so that the "nan" and "inf" written by the
+   * stream for non-finite values are read back as well. */
+  loaded.data.resize(element_count);
+  for (std::size_t i = 0; i < element_count; i++) {
+    std::string token;
+    if (!(file >> token)) {
+      return false;
+    }
+    char *end = nullptr;
+    double value = std::strtod(token.c_str(), &end);
+    if (end == token.c_str() || *end != '\0') {
+      return false;
+    }
+    loaded.data[i] = static_cast<T>(value);
+  }
+
+  values = loaded;
+  return true;
+}
+#endif
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_FLAT_ARRAY_HPP
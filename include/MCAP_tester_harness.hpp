@@ -0,0 +1,405 @@
+/**
+ * @file MCAP_tester_harness.hpp
+ * @brief Provides the Tester::ClosedLoopHarness class template for
+ * simulating a generated controller against a plant model.
+ *
+ * Every controller test used to contain the same loop: evaluate the
+ * reference and the disturbance, step the controller, step the plant and
+ * store the signals. The harness owns that loop. The user registers the
+ * generated controller step function and a plant model, optionally with
+ * reference and disturbance profiles, and receives the simulated
+ * trajectories, which are nested vectors of [step][channel] and can be passed
+ * directly to the assertion methods of Tester::MCAPTester, e.g.
+ * expect_near_trajectory.
+ *
+ * Robustness scenarios are declared as injections instead of being coded
+ * into the plant: additive disturbances and sensor dropouts over a time
+ * window, and parameter changes at a point in time. The injections applied
+ * during a run are part of its trajectories and can be recorded in the
+ * report of a tester with record_injections.
+ */
+
+#ifndef MCAP_TESTER_HARNESS_HPP
+#define MCAP_TESTER_HARNESS_HPP
+
+#include <cstddef>
+#include <functional>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+namespace Tester {
+
+/**
+ * @brief The kind of a harness injection.
+ */
+enum class InjectionKind { Disturbance, SensorDropout, ParameterChange };
+
+/**
+ * @brief An injection that was applied during a run, from `first_step` to
+ * `last_step` (inclusive); `description` states what was injected.
+ */
+struct AppliedInjection {
+  AppliedInjection()
+      : name(), kind(InjectionKind::Disturbance), first_step(0), last_step(0),
+        description() {}
+
+  std::string name;
+  InjectionKind kind;
+  std::size_t first_step;
+  std::size_t last_step;
+  std::string description;
+};
+
+/**
+ * @brief The signals of a simulated loop.
+ *
+ * Element k of every signal belongs to step k, at time `time[k]`. At step k
+ * the controller computes `command[k]` from `reference[k]` and the
+ * measurement of the previous step, and `measurement[k]` is the plant output
+ * after applying `command[k]` and `disturbance[k]`, as seen by the controller,
+ * i.e. after sensor dropouts. `disturbance` includes the injected
+ * disturbances, and `injections` lists the injections in the order in which
+ * they first became active.
+ *
+ * @tparam T Type of the signals.
+ */
+template <typename T> struct Trajectories {
+  Trajectories()
+      : time(), reference(), disturbance(), command(), measurement(),
+        injections() {}
+
+  std::vector<T> time;
+  std::vector<std::vector<T>> reference;
+  std::vector<std::vector<T>> disturbance;
+  std::vector<std::vector<T>> command;
+  std::vector<std::vector<T>> measurement;
+  std::vector<AppliedInjection> injections;
+};
+
+/**
+ * @brief Extracts one channel of a signal of a trajectory.
+ *
+ * @param signal A signal of Trajectories, indexed by [step][channel].
+ * @param channel The channel to extract.
+ * @return The channel over all steps; 0 at steps where it is missing.
+ */
+template <typename T>
+inline std::vector<T> channel(const std::vector<std::vector<T>> &signal,
+                              std::size_t channel) {
+  std::vector<T> values(signal.size(), static_cast<T>(0));
+  for (std::size_t k = 0; k < signal.size(); k++) {
+    if (channel < signal[k].size()) {
+      values[k] = signal[k][channel];
+    }
+  }
+  return values;
+}
+
+/**
+ * @brief Records the injections of a run in the report of a tester, as
+ * passing assertions named "Injection: <name>" whose detail describes the
+ * injection.
+ *
+ * @param tester The tester whose report receives the injections.
+ * @param trajectories The trajectories of the run.
+ */
+template <typename T>
+inline void record_injections(MCAPTester<T> &tester,
+                              const Trajectories<T> &trajectories) {
+  for (std::size_t i = 0; i < trajectories.injections.size(); i++) {
+    const AppliedInjection &injection = trajectories.injections[i];
+    tester.expect_true(true, "Injection: " + injection.name,
+                       injection.description + " Steps " +
+                           std::to_string(injection.first_step) + " to " +
+                           std::to_string(injection.last_step) + ".");
+  }
+}
+
+/**
+ * @brief Simulates a controller and a plant in closed or open loop.
+ *
+ * The controller and the plant are called as given, so stateful closures
+ * carry their state from one run to the next and must be reset by the caller
+ * between runs.
+ *
+ * @tparam T Type of the signals.
+ */
+template <typename T> class ClosedLoopHarness {
+public:
+  typedef std::vector<T> Signal;
+
+  /** @brief Computes the command from the reference and the measurement. */
+  typedef std::function<Signal(const Signal &reference,
+                               const Signal &measurement)>
+      Controller;
+
+  /** @brief Advances the plant by one step and returns its measurement. */
+  typedef std::function<Signal(const Signal &command,
+                               const Signal &disturbance)>
+      Plant;
+
+  /** @brief Returns the value of a reference or disturbance at a time. */
+  typedef std::function<Signal(T time)> Profile;
+
+  /**
+   * @brief Creates a harness without reference and disturbance; both are
+   * empty signals until a profile is set.
+   *
+   * @param dt The sample time in seconds.
+   * @param controller The step function of the controller under test.
+   * @param plant The step function of the plant model.
+   */
+  ClosedLoopHarness(T dt, const Controller &controller, const Plant &plant)
+      : _dt(dt), _controller(controller), _plant(plant),
+        _reference([](T) { return Signal(); }),
+        _disturbance([](T) { return Signal(); }), _initial_measurement(),
+        _injections() {}
+
+  /**
+   * @brief Sets the reference profile passed to the controller.
+   */
+  void set_reference(const Profile &reference) {
+    this->_reference = reference;
+  }
+
+  /**
+   * @brief Sets the disturbance profile passed to the plant.
+   */
+  void set_disturbance(const Profile &disturbance) {
+    this->_disturbance = disturbance;
+  }
+
+  /**
+   * @brief Sets the measurement the controller receives at the first step;
+   * empty by default.
+   */
+  void set_initial_measurement(const Signal &measurement) {
+    this->_initial_measurement = measurement;
+  }
+
+  /**
+   * @brief Adds a disturbance to the disturbance profile while
+   * start_time <= time < end_time.
+   *
+   * @param name The name under which the injection is recorded.
+   * @param start_time The time at which the disturbance starts.
+   * @param end_time The time at which the disturbance ends.
+   * @param offset The value added to the disturbance, channel by channel;
+   * the disturbance signal is extended with zeros if it is shorter.
+   */
+  void add_disturbance(const std::string &name, T start_time, T end_time,
+                       const Signal &offset) {
+    Injection injection(name, InjectionKind::Disturbance, start_time,
+                        end_time);
+    injection.offset = offset;
+    injection.description = "Disturbance of " + this->_describe(offset) +
+                            " from " + format_number(start_time) + " s to " +
+                            format_number(end_time) + " s.";
+    this->_injections.push_back(injection);
+  }
+
+  /**
+   * @brief Drops out a sensor while start_time <= time < end_time: the
+   * controller keeps receiving the last value of the channel measured before
+   * the dropout.
+   *
+   * @param name The name under which the injection is recorded.
+   * @param start_time The time at which the dropout starts.
+   * @param end_time The time at which the sensor recovers.
+   * @param channel The measurement channel that drops out.
+   */
+  void add_sensor_dropout(const std::string &name, T start_time, T end_time,
+                          std::size_t channel) {
+    Injection injection(name, InjectionKind::SensorDropout, start_time,
+                        end_time);
+    injection.channel = channel;
+    injection.description = "Dropout of measurement channel " +
+                            std::to_string(channel) + " from " +
+                            format_number(start_time) + " s to " +
+                            format_number(end_time) + " s.";
+    this->_injections.push_back(injection);
+  }
+
+  /**
+   * @brief Changes a parameter of the controller or the plant once per run,
+   * before the first step at or after a time.
+   *
+   * @param name The name under which the injection is recorded.
+   * @param time The time of the change.
+   * @param change Applies the change, e.g. by modifying a gain captured by
+   * the plant closure. Since the change is applied again in every run, the
+   * caller must restore the parameter between runs.
+   */
+  void add_parameter_change(const std::string &name, T time,
+                            const std::function<void()> &change) {
+    Injection injection(name, InjectionKind::ParameterChange, time, time);
+    injection.change = change;
+    injection.description =
+        "Parameter change at " + format_number(time) + " s.";
+    this->_injections.push_back(injection);
+  }
+
+  /**
+   * @brief Returns the sample time in seconds.
+   */
+  T get_dt() const { return this->_dt; }
+
+  /**
+   * @brief Simulates the closed loop.
+   *
+   * @param steps The number of steps to simulate.
+   * @return The signals of every step.
+   */
+  Trajectories<T> run_closed_loop(std::size_t steps) const {
+    Trajectories<T> trajectories;
+    std::vector<std::size_t> applied(this->_injections.size(), _none());
+    Signal measurement = this->_initial_measurement;
+    for (std::size_t k = 0; k < steps; k++) {
+      T time = static_cast<T>(k) * this->_dt;
+      this->_change_parameters(trajectories, applied, k, time);
+      Signal reference = this->_reference(time);
+      Signal command = this->_controller(reference, measurement);
+      measurement = this->_step_plant(trajectories, applied, k, time,
+                                      reference, command, measurement);
+    }
+    return trajectories;
+  }
+
+  /**
+   * @brief Simulates the plant alone, driven by a command profile; the
+   * controller is not called and the reference is recorded only.
+   *
+   * @param steps The number of steps to simulate.
+   * @param command The command applied to the plant at every time.
+   * @return The signals of every step.
+   */
+  Trajectories<T> run_open_loop(std::size_t steps,
+                                const Profile &command) const {
+    Trajectories<T> trajectories;
+    std::vector<std::size_t> applied(this->_injections.size(), _none());
+    Signal measurement = this->_initial_measurement;
+    for (std::size_t k = 0; k < steps; k++) {
+      T time = static_cast<T>(k) * this->_dt;
+      this->_change_parameters(trajectories, applied, k, time);
+      measurement =
+          this->_step_plant(trajectories, applied, k, time,
+                            this->_reference(time), command(time), measurement);
+    }
+    return trajectories;
+  }
+
+protected:
+  struct Injection {
+    Injection(const std::string &name, InjectionKind kind, T start_time,
+              T end_time)
+        : name(name), kind(kind), start_time(start_time), end_time(end_time),
+          offset(), channel(0), change(), description() {}
+
+    bool active(T time) const {
+      return this->start_time <= time && time < this->end_time;
+    }
+
+    std::string name;
+    InjectionKind kind;
+    T start_time;
+    T end_time;
+    Signal offset;
+    std::size_t channel;
+    std::function<void()> change;
+    std::string description;
+  };
+
+  static std::size_t _none() { return static_cast<std::size_t>(-1); }
+
+  static std::string _describe(const Signal &values) {
+    std::string text = "[";
+    for (std::size_t i = 0; i < values.size(); i++) {
+      text += (i == 0 ? "" : ", ") + format_number(values[i]);
+    }
+    return text + "]";
+  }
+
+  /* Records that an injection is active at step k; `applied` holds the index
+   * of its entry in the trajectories, or _none() before its first step. */
+  void _mark_applied(Trajectories<T> &trajectories,
+                     std::vector<std::size_t> &applied, std::size_t i,
+                     std::size_t k) const {
+    if (applied[i] == _none()) {
+      applied[i] = trajectories.injections.size();
+      AppliedInjection entry;
+      entry.name = this->_injections[i].name;
+      entry.kind = this->_injections[i].kind;
+      entry.first_step = k;
+      entry.description = this->_injections[i].description;
+      trajectories.injections.push_back(entry);
+    }
+    trajectories.injections[applied[i]].last_step = k;
+  }
+
+  void _change_parameters(Trajectories<T> &trajectories,
+                          std::vector<std::size_t> &applied, std::size_t k,
+                          T time) const {
+    for (std::size_t i = 0; i < this->_injections.size(); i++) {
+      const Injection &injection = this->_injections[i];
+      if (injection.kind == InjectionKind::ParameterChange &&
+          applied[i] == _none() && injection.start_time <= time) {
+        injection.change();
+        this->_mark_applied(trajectories, applied, i, k);
+      }
+    }
+  }
+
+  Signal _step_plant(Trajectories<T> &trajectories,
+                     std::vector<std::size_t> &applied, std::size_t k, T time,
+                     const Signal &reference, const Signal &command,
+                     const Signal &previous_measurement) const {
+    Signal disturbance = this->_disturbance(time);
+    for (std::size_t i = 0; i < this->_injections.size(); i++) {
+      const Injection &injection = this->_injections[i];
+      if (injection.kind == InjectionKind::Disturbance &&
+          injection.active(time)) {
+        if (disturbance.size() < injection.offset.size()) {
+          disturbance.resize(injection.offset.size(), static_cast<T>(0));
+        }
+        for (std::size_t j = 0; j < injection.offset.size(); j++) {
+          disturbance[j] += injection.offset[j];
+        }
+        this->_mark_applied(trajectories, applied, i, k);
+      }
+    }
+
+    Signal measurement = this->_plant(command, disturbance);
+    for (std::size_t i = 0; i < this->_injections.size(); i++) {
+      const Injection &injection = this->_injections[i];
+      if (injection.kind == InjectionKind::SensorDropout &&
+          injection.active(time) && injection.channel < measurement.size()) {
+        measurement[injection.channel] =
+            injection.channel < previous_measurement.size()
+                ? previous_measurement[injection.channel]
+                : static_cast<T>(0);
+        this->_mark_applied(trajectories, applied, i, k);
+      }
+    }
+
+    trajectories.time.push_back(time);
+    trajectories.reference.push_back(reference);
+    trajectories.disturbance.push_back(disturbance);
+    trajectories.command.push_back(command);
+    trajectories.measurement.push_back(measurement);
+    return measurement;
+  }
+
+  T _dt;
+  Controller _controller;
+  Plant _plant;
+  Profile _reference;
+  Profile _disturbance;
+  Signal _initial_measurement;
+  std::vector<Injection> _injections;
+};
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_HARNESS_HPP
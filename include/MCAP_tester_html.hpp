@@ -0,0 +1,237 @@
+/**
+ * @file MCAP_tester_html.hpp
+ * @brief Renders assertion records as a self-contained HTML result page.
+ *
+ * Not everyone who needs the results reads terminal logs. Report::to_html
+ * renders the test cases of a run into a single static page: a summary with
+ * the failures per category, an overview of every case with its run time,
+ * and for each case a table of its assertions with the measured error, the
+ * tolerance and the share of the tolerance used. The details of failures,
+ * including the error tables of trajectory and array comparisons, are
+ * embedded verbatim. The page has no external scripts, styles or images, so
+ * it can be archived as a single CI artifact and opened in any browser.
+ */
+
+#ifndef MCAP_TESTER_HTML_HPP
+#define MCAP_TESTER_HTML_HPP
+
+#include <cstddef>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester_report.hpp"
+
+#ifndef MCAP_TESTER_NO_STDIO
+#include <fstream>
+#endif
+
+namespace Tester {
+
+namespace Report {
+
+/**
+ * @brief The assertions of one test case and its run time.
+ *
+ * `seconds` is negative if the run time was not measured.
+ */
+struct HtmlCase {
+  HtmlCase() : name(), records(), seconds(-1.0) {}
+
+  HtmlCase(const std::string &name, const std::vector<AssertionRecord> &records,
+           double seconds = -1.0)
+      : name(name), records(records), seconds(seconds) {}
+
+  std::string name;
+  std::vector<AssertionRecord> records;
+  double seconds;
+};
+
+namespace Detail {
+
+inline std::string escape_html(const std::string &text) {
+  std::string escaped;
+  for (std::size_t i = 0; i < text.size(); i++) {
+    switch (text[i]) {
+    case '&':
+      escaped += "&amp;";
+      break;
+    case '<':
+      escaped += "&lt;";
+      break;
+    case '>':
+      escaped += "&gt;";
+      break;
+    case '"':
+      escaped += "&quot;";
+      break;
+    default:
+      escaped += text[i];
+    }
+  }
+  return escaped;
+}
+
+inline std::size_t count_status(const std::vector<AssertionRecord> &records,
+                                ComparisonStatus status) {
+  std::size_t count = 0;
+  for (std::size_t i = 0; i < records.size(); i++) {
+    count += records[i].status == status ? 1 : 0;
+  }
+  return count;
+}
+
+inline ComparisonStatus
+case_status(const std::vector<AssertionRecord> &records) {
+  ComparisonStatus worst = ComparisonStatus::Pass;
+  for (std::size_t i = 0; i < records.size(); i++) {
+    if (status_rank(records[i].status) > status_rank(worst)) {
+      worst = records[i].status;
+    }
+  }
+  return worst;
+}
+
+inline std::string status_cell(ComparisonStatus status) {
+  std::string text = status_to_string(status);
+  return "<td class=\"" + text + "\">" + text + "</td>";
+}
+
+/* The share of the tolerance used by the error, as a bar that is full at
+ * the tolerance. */
+inline std::string margin_cell(const AssertionRecord &record) {
+  if (!record.measured || !(record.tolerance > 0.0)) {
+    return "<td></td>";
+  }
+  double share = record.error / record.tolerance;
+  double width = share < 1.0 ? (share > 0.0 ? share * 100.0 : 0.0) : 100.0;
+  return "<td><div class=\"bar\"><div class=\"" +
+         std::string(share > 1.0 ? "over" : "used") +
+         "\" style=\"width:" + format_number(width) + "%\"></div></div>" +
+         format_number(share * 100.0) + "%</td>";
+}
+
+} // namespace Detail
+
+/**
+ * @brief Renders the test cases of a run as a self-contained HTML page.
+ *
+ * @param title The title of the page, e.g. the test executable or model.
+ * @param cases The test cases, in the order to show them.
+ * @return The HTML document.
+ */
+inline std::string to_html(const std::string &title,
+                           const std::vector<HtmlCase> &cases) {
+  std::vector<AssertionRecord> all;
+  for (std::size_t c = 0; c < cases.size(); c++) {
+    all.insert(all.end(), cases[c].records.begin(), cases[c].records.end());
+  }
+  std::size_t failures = Detail::count_status(all, ComparisonStatus::Failure);
+  std::size_t warnings = Detail::count_status(all, ComparisonStatus::Warning);
+  std::string categories = format_category_summary(all);
+
+  std::string html =
+      "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>" +
+      Detail::escape_html(title) +
+      "</title>\n<style>\n"
+      "body{font-family:sans-serif;margin:2em;color:#222}\n"
+      "table{border-collapse:collapse;margin-bottom:2em}\n"
+      "th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;"
+      "vertical-align:top}\n"
+      "th{background:#f0f0f0}\n"
+      "td.pass{color:#1a7f37}td.warning{color:#9a6700}"
+      "td.failure{color:#cf222e;font-weight:bold}\n"
+      "pre{margin:0;font-size:90%;white-space:pre-wrap}\n"
+      ".bar{display:inline-block;width:80px;height:10px;background:#eee;"
+      "margin-right:6px}\n"
+      ".used{height:100%;background:#2da44e}"
+      ".over{height:100%;background:#cf222e}\n"
+      "</style>\n</head>\n<body>\n<h1>" +
+      Detail::escape_html(title) + "</h1>\n<p>" +
+      (failures == 0 ? "Passed" : "Failed") + ": " +
+      std::to_string(cases.size()) + " case(s), " +
+      std::to_string(all.size()) + " assertion(s), " +
+      std::to_string(failures) + " failure(s), " + std::to_string(warnings) +
+      " warning(s)" +
+      (categories.empty() ? std::string()
+                          : " (" + Detail::escape_html(categories) + ")") +
+      ".</p>\n";
+
+  html += "<table>\n<tr><th>case</th><th>status</th><th>assertions</th>"
+          "<th>failures</th><th>time (s)</th></tr>\n";
+  for (std::size_t c = 0; c < cases.size(); c++) {
+    const HtmlCase &entry = cases[c];
+    html += "<tr><td><a href=\"#case-" + std::to_string(c) + "\">" +
+            Detail::escape_html(entry.name) + "</a></td>" +
+            Detail::status_cell(Detail::case_status(entry.records)) + "<td>" +
+            std::to_string(entry.records.size()) + "</td><td>" +
+            std::to_string(Detail::count_status(entry.records,
+                                                ComparisonStatus::Failure)) +
+            "</td><td>" +
+            (entry.seconds < 0.0 ? std::string()
+                                 : format_number(entry.seconds)) +
+            "</td></tr>\n";
+  }
+  html += "</table>\n";
+
+  for (std::size_t c = 0; c < cases.size(); c++) {
+    const HtmlCase &entry = cases[c];
+    html += "<h2 id=\"case-" + std::to_string(c) + "\">" +
+            Detail::escape_html(entry.name) + "</h2>\n";
+    html += "<table>\n<tr><th>status</th><th>assertion</th><th>error</th>"
+            "<th>tolerance</th><th>tolerance used</th><th>category</th>"
+            "<th>location</th><th>detail</th></tr>\n";
+    for (std::size_t i = 0; i < entry.records.size(); i++) {
+      const AssertionRecord &record = entry.records[i];
+      html += "<tr>" + Detail::status_cell(record.status) + "<td>" +
+              Detail::escape_html(record.message) + "</td><td>" +
+              (record.measured ? format_number(record.error) : std::string()) +
+              "</td><td>" +
+              (record.measured ? format_number(record.tolerance)
+                               : std::string()) +
+              "</td>" + Detail::margin_cell(record) + "<td>" +
+              (record.category == FailureCategory::None
+                   ? std::string()
+                   : category_to_string(record.category)) +
+              "</td><td>" + Detail::escape_html(record.location) +
+              "</td><td><pre>" + Detail::escape_html(record.detail) +
+              (record.rationale.empty()
+                   ? std::string()
+                   : "\nRationale: " + Detail::escape_html(record.rationale)) +
+              "</pre></td></tr>\n";
+    }
+    html += "</table>\n";
+  }
+  return html + "</body>\n</html>\n";
+}
+
+/**
+ * @brief Renders the records of a single tester as a self-contained HTML
+ * page with one case.
+ */
+inline std::string to_html(const std::string &title,
+                           const std::vector<AssertionRecord> &records) {
+  return to_html(title, std::vector<HtmlCase>(1, HtmlCase(title, records)));
+}
+
+#ifndef MCAP_TESTER_NO_STDIO
+/**
+ * @brief Writes the HTML page of the test cases of a run to a file.
+ *
+ * @return true on success, false if the file cannot be written.
+ */
+inline bool save_html_report(const std::string &path, const std::string &title,
+                             const std::vector<HtmlCase> &cases) {
+  std::ofstream file(path.c_str());
+  if (!file) {
+    return false;
+  }
+  file << to_html(title, cases);
+  return static_cast<bool>(file);
+}
+#endif
+
+} // namespace Report
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_HTML_HPP
@@ -0,0 +1,610 @@
+/**
+ * @file MCAP_tester_json.hpp
+ * @brief Provides a minimal JSON document model, parser and writer used by
+ * MCAP_tester for fixtures and reports.
+ *
+ * The Tester::JSON namespace defines the Value class, which holds null,
+ * boolean, number, string, array and object values. Object members keep their
+ * insertion order so that written files are stable and easy to diff. Only the
+ * subset of JSON needed by the tester is supported: numbers are stored as
+ * double, and non-finite numbers are written as null.
+ */
+
+#ifndef MCAP_TESTER_JSON_HPP
+#define MCAP_TESTER_JSON_HPP
+
+#include <cmath>
+#include <cstdio>
+#include <cstdlib>
+#include <string>
+#include <utility>
+#include <vector>
+
+#ifndef MCAP_TESTER_NO_STDIO
+#include <fstream>
+#include <sstream>
+#endif
+
+namespace Tester {
+
+namespace JSON {
+
+enum class Type { Null, Boolean, Number, String, Array, Object };
+
+class Value {
+public:
+  Value() : _type(Type::Null), _boolean(false), _number(0.0) {}
+
+  Value(bool boolean) : _type(Type::Boolean), _boolean(boolean), _number(0.0) {}
+
+  Value(double number)
+      : _type(Type::Number), _boolean(false), _number(number) {}
+
+  Value(int number)
+      : _type(Type::Number), _boolean(false),
+        _number(static_cast<double>(number)) {}
+
+  Value(std::size_t number)
+      : _type(Type::Number), _boolean(false),
+        _number(static_cast<double>(number)) {}
+
+  Value(const std::string &text)
+      : _type(Type::String), _boolean(false), _number(0.0), _string(text) {}
+
+  Value(const char *text)
+      : _type(Type::String), _boolean(false), _number(0.0), _string(text) {}
+
+  static Value array() {
+    Value value;
+    value._type = Type::Array;
+    return value;
+  }
+
+  static Value object() {
+    Value value;
+    value._type = Type::Object;
+    return value;
+  }
+
+  Type type() const { return this->_type; }
+
+  bool is_null() const { return this->_type == Type::Null; }
+  bool is_boolean() const { return this->_type == Type::Boolean; }
+  bool is_number() const { return this->_type == Type::Number; }
+  bool is_string() const { return this->_type == Type::String; }
+  bool is_array() const { return this->_type == Type::Array; }
+  bool is_object() const { return this->_type == Type::Object; }
+
+  bool as_boolean() const { return this->_boolean; }
+  double as_number() const { return this->_number; }
+  const std::string &as_string() const { return this->_string; }
+
+  /**
+   * @brief Returns the number of array elements or object members.
+   */
+  std::size_t size() const {
+    if (this->_type == Type::Array) {
+      return this->_array.size();
+    } else if (this->_type == Type::Object) {
+      return this->_object.size();
+    } else {
+      return 0;
+    }
+  }
+
+  const Value &operator[](std::size_t index) const {
+    return this->_array[index];
+  }
+
+  Value &operator[](std::size_t index) { return this->_array[index]; }
+
+  /**
+   * @brief Appends an element, converting a null value into an array first.
+   */
+  void push_back(const Value &element) {
+    if (this->_type == Type::Null) {
+      this->_type = Type::Array;
+    }
+    this->_array.push_back(element);
+  }
+
+  /**
+   * @brief Checks whether an object contains the given member.
+   */
+  bool has(const std::string &key) const {
+    for (std::size_t i = 0; i < this->_object.size(); i++) {
+      if (this->_object[i].first == key) {
+        return true;
+      }
+    }
+    return false;
+  }
+
+  /**
+   * @brief Returns the member with the given key, or a null value if the
+   * member does not exist.
+   */
+  const Value &get(const std::string &key) const {
+    static const Value null_value;
+    for (std::size_t i = 0; i < this->_object.size(); i++) {
+      if (this->_object[i].first == key) {
+        return this->_object[i].second;
+      }
+    }
+    return null_value;
+  }
+
+  /**
+   * @brief Sets a member, converting a null value into an object first and
+   * replacing any existing member with the same key.
+   */
+  void set(const std::string &key, const Value &member) {
+    if (this->_type == Type::Null) {
+      this->_type = Type::Object;
+    }
+    for (std::size_t i = 0; i < this->_object.size(); i++) {
+      if (this->_object[i].first == key) {
+        this->_object[i].second = member;
+        return;
+      }
+    }
+    this->_object.push_back(std::make_pair(key, member));
+  }
+
+  const std::vector<std::pair<std::string, Value>> &members() const {
+    return this->_object;
+  }
+
+protected:
+  Type _type;
+  bool _boolean;
+  double _number;
+  std::string _string;
+  std::vector<Value> _array;
+  std::vector<std::pair<std::string, Value>> _object;
+};
+
+namespace Detail {
+
+class Parser {
+public:
+  explicit Parser(const std::string &text) : _text(text), _position(0) {}
+
+  bool parse_document(Value &value, std::string &error) {
+    if (!this->_parse_value(value, error)) {
+      return false;
+    }
+    this->_skip_whitespace();
+    if (this->_position != this->_text.size()) {
+      error = this->_error_at("Unexpected trailing characters");
+      return false;
+    }
+    return true;
+  }
+
+protected:
+  std::string _error_at(const std::string &what) const {
+    return what + " at offset " + std::to_string(this->_position) + ".";
+  }
+
+  void _skip_whitespace() {
+    while (this->_position < this->_text.size()) {
+      char c = this->_text[this->_position];
+      if (c == ' ' || c == '\t' || c == '\n' || c == '\r') {
+        this->_position++;
+      } else {
+        break;
+      }
+    }
+  }
+
+  bool _consume_literal(const char *literal) {
+    std::size_t length = std::string(literal).size();
+    if (this->_text.compare(this->_position, length, literal) == 0) {
+      this->_position += length;
+      return true;
+    }
+    return false;
+  }
+
+  bool _parse_value(Value &value, std::string &error) {
+    this->_skip_whitespace();
+    if (this->_position >= this->_text.size()) {
+      error = this->_error_at("Unexpected end of input");
+      return false;
+    }
+
+    char c = this->_text[this->_position];
+    if (c == '{') {
+      return this->_parse_object(value, error);
+    } else if (c == '[') {
+      return this->_parse_array(value, error);
+    } else if (c == '"') {
+      std::string text;
+      if (!this->_parse_string(text, error)) {
+        return false;
+      }
+      value = Value(text);
+      return true;
+    } else if (this->_consume_literal("true")) {
+      value = Value(true);
+      return true;
+    } else if (this->_consume_literal("false")) {
+      value = Value(false);
+      return true;
+    } else if (this->_consume_literal("null")) {
+      value = Value();
+      return true;
+    } else {
+      return this->_parse_number(value, error);
+    }
+  }
+
+  bool _parse_number(Value &value, std::string &error) {
+    const char *begin = this->_text.c_str() + this->_position;
+    char *end = nullptr;
+    double number = std::strtod(begin, &end);
+    if (end == begin) {
+      error = this->_error_at("Invalid value");
+      return false;
+    }
+    this->_position += static_cast<std::size_t>(end - begin);
+    value = Value(number);
+    return true;
+  }
+
+  static void _append_utf8(std::string &text, unsigned long code_point) {
+    if (code_point < 0x80) {
+      text += static_cast<char>(code_point);
+    } else if (code_point < 0x800) {
+      text += static_cast<char>(0xC0 | (code_point >> 6));
+      text += static_cast<char>(0x80 | (code_point & 0x3F));
+    } else if (code_point < 0x10000) {
+      text += static_cast<char>(0xE0 | (code_point >> 12));
+      text += static_cast<char>(0x80 | ((code_point >> 6) & 0x3F));
+      text += static_cast<char>(0x80 | (code_point & 0x3F));
+    } else {
+      text += static_cast<char>(0xF0 | (code_point >> 18));
+      text += static_cast<char>(0x80 | ((code_point >> 12) & 0x3F));
+      text += static_cast<char>(0x80 | ((code_point >> 6) & 0x3F));
+      text += static_cast<char>(0x80 | (code_point & 0x3F));
+    }
+  }
+
+  bool _parse_hex4(unsigned long &code_point, std::string &error) {
+    if (this->_position + 4 > this->_text.size()) {
+      error = this->_error_at("Truncated unicode escape");
+      return false;
+    }
+    std::string digits = this->_text.substr(this->_position, 4);
+    char *end = nullptr;
+    code_point = std::strtoul(digits.c_str(), &end, 16);
+    if (end != digits.c_str() + 4) {
+      error = this->_error_at("Invalid unicode escape");
+      return false;
+    }
+    this->_position += 4;
+    return true;
+  }
+
+  bool _parse_string(std::string &text, std::string &error) {
+    this->_position++; /* opening quote */
+    while (this->_position < this->_text.size()) {
+      char c = this->_text[this->_position++];
+      if (c == '"') {
+        return true;
+      } else if (c != '\\') {
+        text += c;
+        continue;
+      }
+
+      if (this->_position >= this->_text.size()) {
+        break;
+      }
+      char escaped = this->_text[this->_position++];
+      switch (escaped) {
+      case '"':
+        text += '"';
+        break;
+      case '\\':
+        text += '\\';
+        break;
+      case '/':
+        text += '/';
+        break;
+      case 'b':
+        text += '\b';
+        break;
+      case 'f':
+        text += '\f';
+        break;
+      case 'n':
+        text += '\n';
+        break;
+      case 'r':
+        text += '\r';
+        break;
+      case 't':
+        text += '\t';
+        break;
+      case 'u': {
+        unsigned long code_point = 0;
+        if (!this->_parse_hex4(code_point, error)) {
+          return false;
+        }
+        if (code_point >= 0xD800 && code_point < 0xDC00 &&
+            this->_consume_literal("\\u")) {
+          unsigned long low = 0;
+          if (!this->_parse_hex4(low, error)) {
+            return false;
+          }
+          code_point = 0x10000 + ((code_point - 0xD800) << 10) + (low - 0xDC00);
+        }
+        _append_utf8(text, code_point);
+        break;
+      }
+      default:
+        error = this->_error_at("Invalid escape sequence");
+        return false;
+      }
+    }
+
+    error = this->_error_at("Unterminated string");
+    return false;
+  }
+
+  bool _parse_array(Value &value, std::string &error) {
+    this->_position++; /* '[' */
+    value = Value::array();
+
+    this->_skip_whitespace();
+    if (this->_position < this->_text.size() &&
+        this->_text[this->_position] == ']') {
+      this->_position++;
+      return true;
+    }
+
+    while (true) {
+      Value element;
+      if (!this->_parse_value(element, error)) {
+        return false;
+      }
+      value.push_back(element);
+
+      this->_skip_whitespace();
+      if (this->_position >= this->_text.size()) {
+        error = this->_error_at("Unterminated array");
+        return false;
+      }
+      char c = this->_text[this->_position++];
+      if (c == ']') {
+        return true;
+      } else if (c != ',') {
+        error = this->_error_at("Expected ',' or ']'");
+        return false;
+      }
+    }
+  }
+
+  bool _parse_object(Value &value, std::string &error) {
+    this->_position++; /* '{' */
+    value = Value::object();
+
+    this->_skip_whitespace();
+    if (this->_position < this->_text.size() &&
+        this->_text[this->_position] == '}') {
+      this->_position++;
+      return true;
+    }
+
+    while (true) {
+      this->_skip_whitespace();
+      if (this->_position >= this->_text.size() ||
+          this->_text[this->_position] != '"') {
+        error = this->_error_at("Expected member name");
+        return false;
+      }
+      std::string key;
+      if (!this->_parse_string(key, error)) {
+        return false;
+      }
+
+      this->_skip_whitespace();
+      if (this->_position >= this->_text.size() ||
+          this->_text[this->_position] != ':') {
+        error = this->_error_at("Expected ':'");
+        return false;
+      }
+      this->_position++;
+
+      Value member;
+      if (!this->_parse_value(member, error)) {
+        return false;
+      }
+      value.set(key, member);
+
+      this->_skip_whitespace();
+      if (this->_position >= this->_text.size()) {
+        error = this->_error_at("Unterminated object");
+        return false;
+      }
+      char c = this->_text[this->_position++];
+      if (c == '}') {
+        return true;
+      } else if (c != ',') {
+        error = this->_error_at("Expected ',' or '}'");
+        return false;
+      }
+    }
+  }
+
+  const std::string &_text;
+  std::size_t _position;
+};
+
+inline void write_string(std::string &output, const std::string &text) {
+  output += '"';
+  for (std::size_t i = 0; i < text.size(); i++) {
+    unsigned char c = static_cast<unsigned char>(text[i]);
+    switch (c) {
+    case '"':
+      output += "\\\"";
+      break;
+    case '\\':
+      output += "\\\\";
+      break;
+    case '\n':
+      output += "\\n";
+      break;
+    case '\r':
+      output += "\\r";
+      break;
+    case '\t':
+      output += "\\t";
+      break;
+    default:
+      if (c < 0x20) {
+        char buffer[8];
+        std::snprintf(buffer, sizeof(buffer), "\\u%04x", c);
+        output += buffer;
+      } else {
+        output += text[i];
+      }
+    }
+  }
+  output += '"';
+}
+
+inline void write_value(std::string &output, const Value &value, bool pretty,
+                        std::size_t depth) {
+  std::string indent = pretty ? std::string(2 * (depth + 1), ' ') : "";
+  std::string closing_indent = pretty ? std::string(2 * depth, ' ') : "";
+  const char *newline = pretty ? "\n" : "";
+
+  switch (value.type()) {
+  case Type::Null:
+    output += "null";
+    break;
+  case Type::Boolean:
+    output += (value.as_boolean() ? "true" : "false");
+    break;
+  case Type::Number:
+    if (std::isfinite(value.as_number())) {
+      char buffer[32];
+      std::snprintf(buffer, sizeof(buffer), "%.17g", value.as_number());
+      output += buffer;
+    } else {
+      output += "null";
+    }
+    break;
+  case Type::String:
+    write_string(output, value.as_string());
+    break;
+  case Type::Array:
+    if (value.size() == 0) {
+      output += "[]";
+      break;
+    }
+    output += "[";
+    output += newline;
+    for (std::size_t i = 0; i < value.size(); i++) {
+      output += indent;
+      write_value(output, value[i], pretty, depth + 1);
+      output += (i + 1 < value.size() ? "," : "");
+      output += newline;
+    }
+    output += closing_indent + "]";
+    break;
+  case Type::Object:
+    if (value.size() == 0) {
+      output += "{}";
+      break;
+    }
+    output += "{";
+    output += newline;
+    for (std::size_t i = 0; i < value.members().size(); i++) {
+      output += indent;
+      write_string(output, value.members()[i].first);
+      output += (pretty ? ": " : ":");
+      write_value(output, value.members()[i].second, pretty, depth + 1);
+      output += (i + 1 < value.members().size() ? "," : "");
+      output += newline;
+    }
+    output += closing_indent + "}";
+    break;
+  }
+}
+
+} // namespace Detail
+
+/**
+ * @brief Parses a JSON document.
+ *
+ * @param text The JSON text.
+ * @param value Receives the parsed document.
+ * @param error Receives a description of the problem if parsing fails.
+ * @return true if the text is a valid JSON document, false otherwise.
+ */
+inline bool parse(const std::string &text, Value &value, std::string &error) {
+  Detail::Parser parser(text);
+  return parser.parse_document(value, error);
+}
+
+#ifndef MCAP_TESTER_NO_STDIO
+/**
+ * @brief Reads and parses a JSON file.
+ *
+ * @param path The file to read.
+ * @param value Receives the parsed document.
+ * @param error Receives a description of the problem if loading fails.
+ * @return true if the file exists and is a valid JSON document.
+ */
+inline bool load_file(const std::string &path, Value &value,
+                      std::string &error) {
+  std::ifstream file(path.c_str());
+  if (!file) {
+    error = "Cannot open " + path + ".";
+    return false;
+  }
+  std::ostringstream contents;
+  contents << file.rdbuf();
+  return parse(contents.str(), value, error);
+}
+#endif
+
+/**
+ * @brief Serializes a value to JSON text.
+ *
+ * @param value The value to serialize.
+ * @param pretty If true, members and elements are indented on separate lines.
+ * @return The JSON text.
+ */
+inline std::string serialize(const Value &value, bool pretty = true) {
+  std::string output;
+  Detail::write_value(output, value, pretty, 0);
+  return output;
+}
+
+#ifndef MCAP_TESTER_NO_STDIO
+/**
+ * @brief Writes a value to a JSON file.
+ *
+ * @param path The file to write.
+ * @param value The value to serialize.
+ * @return true if the file was written successfully, false otherwise.
+ */
+inline bool save_file(const std::string &path, const Value &value) {
+  std::ofstream file(path.c_str());
+  if (!file) {
+    return false;
+  }
+  file << serialize(value, true) << "\n";
+  return static_cast<bool>(file);
+}
+#endif
+
+} // namespace JSON
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_JSON_HPP
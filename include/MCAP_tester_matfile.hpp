@@ -0,0 +1,723 @@
+/**
+ * @file MCAP_tester_matfile.hpp
+ * @brief Provides a reader for MATLAB Level-5 MAT files so that golden data
+ * handed over by control engineers can be compared directly.
+ *
+ * The Tester::MatFile namespace reads numeric variables (double, single and
+ * all integer classes, real or complex) from Level-5 MAT files, including the
+ * compressed variables written by default since MATLAB 7. Variables are
+ * returned as FlatArray objects converted from MATLAB's column-major order to
+ * row-major order. Struct, cell, sparse and character arrays are skipped, and
+ * v7.3 (HDF5 based) files are rejected.
+ *
+ * This header is optional. Define MCAP_TESTER_ENABLE_MATFILE before including
+ * MCAP_tester.hpp to include it automatically and to enable
+ * MCAPTester::expect_near_matfile.
+ */
+
+#ifndef MCAP_TESTER_MATFILE_HPP
+#define MCAP_TESTER_MATFILE_HPP
+
+#include <cstdint>
+#include <cstring>
+#include <fstream>
+#include <iterator>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester_flat_array.hpp"
+
+namespace Tester {
+
+namespace MatFile {
+
+/**
+ * @brief A numeric variable read from a MAT file.
+ *
+ * @tparam T Type the stored elements are converted to.
+ */
+template <typename T> struct Variable {
+  Variable() : name(), real(), imaginary(), is_complex(false) {}
+
+  std::string name;
+  FlatArray<T> real;
+  FlatArray<T> imaginary;
+  bool is_complex;
+};
+
+namespace Detail {
+
+/* MAT-file data types. */
+static const std::uint32_t MI_INT8 = 1;
+static const std::uint32_t MI_UINT8 = 2;
+static const std::uint32_t MI_INT16 = 3;
+static const std::uint32_t MI_UINT16 = 4;
+static const std::uint32_t MI_INT32 = 5;
+static const std::uint32_t MI_UINT32 = 6;
+static const std::uint32_t MI_SINGLE = 7;
+static const std::uint32_t MI_DOUBLE = 9;
+static const std::uint32_t MI_INT64 = 12;
+static const std::uint32_t MI_UINT64 = 13;
+static const std::uint32_t MI_MATRIX = 14;
+static const std::uint32_t MI_COMPRESSED = 15;
+
+/* MATLAB array classes. */
+static const std::uint32_t MX_DOUBLE_CLASS = 6;
+static const std::uint32_t MX_UINT64_CLASS = 15;
+
+static const std::uint32_t COMPLEX_FLAG = 0x0800;
+
+/**
+ * @brief Minimal DEFLATE (RFC 1951) decoder for miCOMPRESSED elements.
+ */
+class Inflater {
+public:
+  Inflater(const unsigned char *input, std::size_t size)
+      : _input(input), _size(size), _position(0), _bit_buffer(0),
+        _bit_count(0), _error(false) {}
+
+  bool inflate(std::vector<unsigned char> &output) {
+    int last = 0;
+    do {
+      last = this->_bits(1);
+      int type = this->_bits(2);
+
+      if (this->_error) {
+        return false;
+      } else if (type == 0) {
+        this->_stored(output);
+      } else if (type == 1) {
+        this->_fixed(output);
+      } else if (type == 2) {
+        this->_dynamic(output);
+      } else {
+        return false;
+      }
+    } while (!last && !this->_error);
+
+    return !this->_error;
+  }
+
+protected:
+  struct Huffman {
+    std::vector<short> count;
+    std::vector<short> symbol;
+  };
+
+  int _bits(int need) {
+    long value = this->_bit_buffer;
+    while (this->_bit_count < need) {
+      if (this->_position >= this->_size) {
+        this->_error = true;
+        return 0;
+      }
+      value |= static_cast<long>(this->_input[this->_position++])
+               << this->_bit_count;
+      this->_bit_count += 8;
+    }
+    this->_bit_buffer = static_cast<int>(value >> need);
+    this->_bit_count -= need;
+    return static_cast<int>(value & ((1L << need) - 1));
+  }
+
+  void _stored(std::vector<unsigned char> &output) {
+    this->_bit_buffer = 0;
+    this->_bit_count = 0;
+
+    if (this->_position + 4 > this->_size) {
+      this->_error = true;
+      return;
+    }
+    unsigned length = this->_input[this->_position] |
+                      (this->_input[this->_position + 1] << 8);
+    unsigned complement = this->_input[this->_position + 2] |
+                          (this->_input[this->_position + 3] << 8);
+    this->_position += 4;
+
+    if (length != (~complement & 0xFFFFu) ||
+        this->_position + length > this->_size) {
+      this->_error = true;
+      return;
+    }
+    output.insert(output.end(), this->_input + this->_position,
+                  this->_input + this->_position + length);
+    this->_position += length;
+  }
+
+  static bool _construct(Huffman &huffman, const short *lengths, int n) {
+    huffman.count.assign(16, 0);
+    huffman.symbol.assign(n, 0);
+
+    for (int symbol = 0; symbol < n; symbol++) {
+      huffman.count[lengths[symbol]]++;
+    }
+    if (huffman.count[0] == n) {
+      return true;
+    }
+
+    int left = 1;
+    for (int length = 1; length < 16; length++) {
+      left <<= 1;
+      left -= huffman.count[length];
+      if (left < 0) {
+        return false;
+      }
+    }
+
+    short offsets[16];
+    offsets[1] = 0;
+    for (int length = 1; length < 15; length++) {
+      offsets[length + 1] =
+          static_cast<short>(offsets[length] + huffman.count[length]);
+    }
+    for (int symbol = 0; symbol < n; symbol++) {
+      if (lengths[symbol] != 0) {
+        huffman.symbol[offsets[lengths[symbol]]++] =
+            static_cast<short>(symbol);
+      }
+    }
+    return true;
+  }
+
+  int _decode(const Huffman &huffman) {
+    int code = 0;
+    int first = 0;
+    int index = 0;
+    for (int length = 1; length < 16; length++) {
+      code |= this->_bits(1);
+      if (this->_error) {
+        return -1;
+      }
+      int count = huffman.count[length];
+      if (code - count < first) {
+        return huffman.symbol[index + (code - first)];
+      }
+      index += count;
+      first += count;
+      first <<= 1;
+      code <<= 1;
+    }
+    this->_error = true;
+    return -1;
+  }
+
+  void _codes(std::vector<unsigned char> &output, const Huffman &length_code,
+              const Huffman &distance_code) {
+    static const short length_base[29] = {
+        3,  4,  5,  6,  7,  8,  9,  10, 11,  13,  15,  17,  19,  23, 27,
+        31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258};
+    static const short length_extra[29] = {0, 0, 0, 0, 0, 0, 0, 0, 1, 1,
+                                           1, 1, 2, 2, 2, 2, 3, 3, 3, 3,
+                                           4, 4, 4, 4, 5, 5, 5, 5, 0};
+    static const short distance_base[30] = {
+        1,   2,   3,   4,   5,   7,    9,    13,   17,   25,
+        33,  49,  65,  97,  129, 193,  257,  385,  513,  769,
+        1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577};
+    static const short distance_extra[30] = {
+        0, 0, 0, 0, 1, 1, 2, 2,  3,  3,  4,  4,  5,  5,  6,
+        6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13};
+
+    while (true) {
+      int symbol = this->_decode(length_code);
+      if (this->_error) {
+        return;
+      } else if (symbol < 256) {
+        output.push_back(static_cast<unsigned char>(symbol));
+      } else if (symbol == 256) {
+        return;
+      } else {
+        symbol -= 257;
+        if (symbol >= 29) {
+          this->_error = true;
+          return;
+        }
+        int length = length_base[symbol] + this->_bits(length_extra[symbol]);
+
+        symbol = this->_decode(distance_code);
+        if (this->_error || symbol < 0 || symbol >= 30) {
+          this->_error = true;
+          return;
+        }
+        std::size_t distance = static_cast<std::size_t>(
+            distance_base[symbol] + this->_bits(distance_extra[symbol]));
+        if (this->_error || distance > output.size()) {
+          this->_error = true;
+          return;
+        }
+
+        for (int i = 0; i < length; i++) {
+          output.push_back(output[output.size() - distance]);
+        }
+      }
+    }
+  }
+
+  void _fixed(std::vector<unsigned char> &output) {
+    short lengths[288];
+    for (int symbol = 0; symbol < 144; symbol++) {
+      lengths[symbol] = 8;
+    }
+    for (int symbol = 144; symbol < 256; symbol++) {
+      lengths[symbol] = 9;
+    }
+    for (int symbol = 256; symbol < 280; symbol++) {
+      lengths[symbol] = 7;
+    }
+    for (int symbol = 280; symbol < 288; symbol++) {
+      lengths[symbol] = 8;
+    }
+    Huffman length_code;
+    _construct(length_code, lengths, 288);
+
+    for (int symbol = 0; symbol < 30; symbol++) {
+      lengths[symbol] = 5;
+    }
+    Huffman distance_code;
+    _construct(distance_code, lengths, 30);
+
+    this->_codes(output, length_code, distance_code);
+  }
+
+  void _dynamic(std::vector<unsigned char> &output) {
+    static const short order[19] = {16, 17, 18, 0, 8,  7, 9,  6, 10, 5,
+                                    11, 4,  12, 3, 13, 2, 14, 1, 15};
+
+    int length_count = this->_bits(5) + 257;
+    int distance_count = this->_bits(5) + 1;
+    int code_count = this->_bits(4) + 4;
+    if (this->_error || length_count > 286 || distance_count > 30) {
+      this->_error = true;
+      return;
+    }
+
+    short lengths[320];
+    int index = 0;
+    for (; index < code_count; index++) {
+      lengths[order[index]] = static_cast<short>(this->_bits(3));
+    }
+    for (; index < 19; index++) {
+      lengths[order[index]] = 0;
+    }
+
+    Huffman length_code;
+    if (!_construct(length_code, lengths, 19)) {
+      this->_error = true;
+      return;
+    }
+
+    index = 0;
+    while (index < length_count + distance_count) {
+      int symbol = this->_decode(length_code);
+      if (this->_error) {
+        return;
+      }
+
+      if (symbol < 16) {
+        lengths[index++] = static_cast<short>(symbol);
+        continue;
+      }
+
+      short repeated = 0;
+      int repeat = 0;
+      if (symbol == 16) {
+        if (index == 0) {
+          this->_error = true;
+          return;
+        }
+        repeated = lengths[index - 1];
+        repeat = 3 + this->_bits(2);
+      } else if (symbol == 17) {
+        repeat = 3 + this->_bits(3);
+      } else {
+        repeat = 11 + this->_bits(7);
+      }
+
+      if (index + repeat > length_count + distance_count) {
+        this->_error = true;
+        return;
+      }
+      while (repeat-- > 0) {
+        lengths[index++] = repeated;
+      }
+    }
+
+    Huffman literal_code;
+    Huffman distance_code;
+    if (!_construct(literal_code, lengths, length_count) ||
+        !_construct(distance_code, lengths + length_count, distance_count)) {
+      this->_error = true;
+      return;
+    }
+
+    this->_codes(output, literal_code, distance_code);
+  }
+
+  const unsigned char *_input;
+  std::size_t _size;
+  std::size_t _position;
+  int _bit_buffer;
+  int _bit_count;
+  bool _error;
+};
+
+/**
+ * @brief Decompresses a zlib stream (RFC 1950) holding a compressed element.
+ */
+inline bool zlib_decompress(const unsigned char *input, std::size_t size,
+                            std::vector<unsigned char> &output) {
+  if (size < 2 || (input[0] & 0x0F) != 8 ||
+      ((input[0] << 8) | input[1]) % 31 != 0 || (input[1] & 0x20) != 0) {
+    return false;
+  }
+  Inflater inflater(input + 2, size - 2);
+  return inflater.inflate(output);
+}
+
+inline std::uint32_t read_u32(const unsigned char *bytes, bool little_endian) {
+  if (little_endian) {
+    return static_cast<std::uint32_t>(bytes[0]) |
+           (static_cast<std::uint32_t>(bytes[1]) << 8) |
+           (static_cast<std::uint32_t>(bytes[2]) << 16) |
+           (static_cast<std::uint32_t>(bytes[3]) << 24);
+  } else {
+    return static_cast<std::uint32_t>(bytes[3]) |
+           (static_cast<std::uint32_t>(bytes[2]) << 8) |
+           (static_cast<std::uint32_t>(bytes[1]) << 16) |
+           (static_cast<std::uint32_t>(bytes[0]) << 24);
+  }
+}
+
+inline std::uint64_t read_unsigned(const unsigned char *bytes,
+                                   std::size_t width, bool little_endian) {
+  std::uint64_t value = 0;
+  for (std::size_t i = 0; i < width; i++) {
+    std::size_t index = little_endian ? (width - 1 - i) : i;
+    value = (value << 8) | bytes[index];
+  }
+  return value;
+}
+
+/**
+ * @brief Converts the payload of a numeric data element to T.
+ */
+template <typename T>
+inline bool convert_numeric(std::uint32_t type, const unsigned char *bytes,
+                            std::size_t size, bool little_endian,
+                            std::vector<T> &values) {
+  std::size_t width = 0;
+  switch (type) {
+  case MI_INT8:
+  case MI_UINT8:
+    width = 1;
+    break;
+  case MI_INT16:
+  case MI_UINT16:
+    width = 2;
+    break;
+  case MI_INT32:
+  case MI_UINT32:
+  case MI_SINGLE:
+    width = 4;
+    break;
+  case MI_DOUBLE:
+  case MI_INT64:
+  case MI_UINT64:
+    width = 8;
+    break;
+  default:
+    return false;
+  }
+
+  values.clear();
+  values.reserve(size / width);
+  for (std::size_t offset = 0; offset + width <= size; offset += width) {
+    std::uint64_t raw = read_unsigned(bytes + offset, width, little_endian);
+
+    double value = 0.0;
+    switch (type) {
+    case MI_INT8:
+      value = static_cast<std::int8_t>(raw);
+      break;
+    case MI_INT16:
+      value = static_cast<std::int16_t>(raw);
+      break;
+    case MI_INT32:
+      value = static_cast<std::int32_t>(raw);
+      break;
+    case MI_INT64:
+      value = static_cast<double>(static_cast<std::int64_t>(raw));
+      break;
+    case MI_SINGLE: {
+      std::uint32_t bits = static_cast<std::uint32_t>(raw);
+      float single = 0.0f;
+      std::memcpy(&single, &bits, sizeof(single));
+      value = single;
+      break;
+    }
+    case MI_DOUBLE:
+      std::memcpy(&value, &raw, sizeof(value));
+      break;
+    default:
+      value = static_cast<double>(raw);
+      break;
+    }
+    values.push_back(static_cast<T>(value));
+  }
+  return true;
+}
+
+/**
+ * @brief Reorders column-major data into row-major order for the given
+ * shape.
+ */
+template <typename T>
+inline std::vector<T> column_major_to_row_major(
+    const std::vector<T> &values, const std::vector<std::size_t> &shape) {
+  std::vector<T> reordered(values.size());
+  std::vector<std::size_t> index(shape.size(), 0);
+
+  for (std::size_t k = 0; k < values.size(); k++) {
+    std::size_t row_major = 0;
+    for (std::size_t d = 0; d < shape.size(); d++) {
+      row_major = row_major * shape[d] + index[d];
+    }
+    reordered[row_major] = values[k];
+
+    for (std::size_t d = 0; d < shape.size(); d++) {
+      if (++index[d] < shape[d]) {
+        break;
+      }
+      index[d] = 0;
+    }
+  }
+  return reordered;
+}
+
+/**
+ * @brief Reads one data element tag and returns the payload location.
+ *
+ * Handles both the regular 8-byte tag and the small data element format in
+ * which the payload is packed into the tag itself.
+ */
+inline bool read_element(const unsigned char *bytes, std::size_t size,
+                         std::size_t &offset, bool little_endian,
+                         std::uint32_t &type, const unsigned char *&payload,
+                         std::size_t &payload_size) {
+  if (offset + 8 > size) {
+    return false;
+  }
+
+  std::uint32_t first = read_u32(bytes + offset, little_endian);
+  if ((first >> 16) != 0) {
+    type = first & 0xFFFF;
+    payload_size = first >> 16;
+    payload = bytes + offset + 4;
+    offset += 8;
+    return payload_size <= 4;
+  }
+
+  type = first;
+  payload_size = read_u32(bytes + offset + 4, little_endian);
+  payload = bytes + offset + 8;
+  if (payload_size > size - offset - 8) {
+    return false;
+  }
+
+  std::size_t padded = (type == MI_COMPRESSED)
+                           ? payload_size
+                           : ((payload_size + 7) / 8) * 8;
+  offset += 8 + padded;
+  if (offset > size) {
+    offset = size;
+  }
+  return true;
+}
+
+/**
+ * @brief Parses the contents of an miMATRIX element.
+ *
+ * @return true if the matrix is a numeric array that was converted, false if
+ * it is of an unsupported class or malformed.
+ */
+template <typename T>
+inline bool parse_matrix(const unsigned char *bytes, std::size_t size,
+                         bool little_endian, Variable<T> &variable) {
+  std::size_t offset = 0;
+  std::uint32_t type = 0;
+  const unsigned char *payload = nullptr;
+  std::size_t payload_size = 0;
+
+  /* Array flags */
+  if (!read_element(bytes, size, offset, little_endian, type, payload,
+                    payload_size) ||
+      payload_size < 8) {
+    return false;
+  }
+  std::uint32_t flags = read_u32(payload, little_endian);
+  std::uint32_t array_class = flags & 0xFF;
+  if (array_class < MX_DOUBLE_CLASS || array_class > MX_UINT64_CLASS) {
+    return false;
+  }
+  variable.is_complex = (flags & COMPLEX_FLAG) != 0;
+
+  /* Dimensions */
+  if (!read_element(bytes, size, offset, little_endian, type, payload,
+                    payload_size)) {
+    return false;
+  }
+  std::vector<std::size_t> shape;
+  for (std::size_t i = 0; i + 4 <= payload_size; i += 4) {
+    shape.push_back(read_u32(payload + i, little_endian));
+  }
+
+  /* Array name */
+  if (!read_element(bytes, size, offset, little_endian, type, payload,
+                    payload_size)) {
+    return false;
+  }
+  variable.name.assign(reinterpret_cast<const char *>(payload), payload_size);
+
+  std::size_t element_count = 1;
+  for (std::size_t i = 0; i < shape.size(); i++) {
+    element_count *= shape[i];
+  }
+
+  /* Real part, then imaginary part if complex */
+  for (int part = 0; part < (variable.is_complex ? 2 : 1); part++) {
+    std::vector<T> column_major;
+    if (!read_element(bytes, size, offset, little_endian, type, payload,
+                      payload_size) ||
+        !convert_numeric(type, payload, payload_size, little_endian,
+                         column_major) ||
+        column_major.size() != element_count) {
+      return false;
+    }
+
+    FlatArray<T> &target = (part == 0) ? variable.real : variable.imaginary;
+    target.shape = shape;
+    target.data = column_major_to_row_major(column_major, shape);
+    target.layout = ArrayLayout::ColumnMajor;
+  }
+
+  return true;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Reads every numeric variable stored in a Level-5 MAT file.
+ *
+ * @tparam T Type the stored elements are converted to.
+ * @param path The MAT file to read.
+ * @param variables Receives the numeric variables in file order.
+ * @param error Receives a description of the problem if reading fails.
+ * @return true if the file is a readable Level-5 MAT file.
+ */
+template <typename T>
+inline bool read_variables(const std::string &path,
+                           std::vector<Variable<T>> &variables,
+                           std::string &error) {
+  std::ifstream file(path.c_str(), std::ios::binary);
+  if (!file) {
+    error = "Cannot open " + path + ".";
+    return false;
+  }
+  std::vector<unsigned char> bytes((std::istreambuf_iterator<char>(file)),
+                                   std::istreambuf_iterator<char>());
+
+  if (bytes.size() < 128) {
+    error = path + " is too short to be a MAT file.";
+    return false;
+  }
+
+  bool little_endian = false;
+  if (bytes[126] == 'I' && bytes[127] == 'M') {
+    little_endian = true;
+  } else if (bytes[126] == 'M' && bytes[127] == 'I') {
+    little_endian = false;
+  } else {
+    error = path + " has no MAT file endian indicator.";
+    return false;
+  }
+
+  std::uint32_t version =
+      static_cast<std::uint32_t>(Detail::read_unsigned(&bytes[124], 2,
+                                                       little_endian));
+  if (version != 0x0100) {
+    error = path + " is not a Level-5 MAT file (v7.3 files are HDF5 based).";
+    return false;
+  }
+
+  variables.clear();
+  std::size_t offset = 128;
+  while (offset < bytes.size()) {
+    std::uint32_t type = 0;
+    const unsigned char *payload = nullptr;
+    std::size_t payload_size = 0;
+    if (!Detail::read_element(&bytes[0], bytes.size(), offset, little_endian,
+                              type, payload, payload_size)) {
+      error = path + " contains a truncated data element.";
+      return false;
+    }
+
+    std::vector<unsigned char> inflated;
+    if (type == Detail::MI_COMPRESSED) {
+      if (!Detail::zlib_decompress(payload, payload_size, inflated)) {
+        error = path + " contains a corrupt compressed element.";
+        return false;
+      }
+      std::size_t inner_offset = 0;
+      if (!Detail::read_element(&inflated[0], inflated.size(), inner_offset,
+                                little_endian, type, payload,
+                                payload_size)) {
+        error = path + " contains a truncated compressed element.";
+        return false;
+      }
+    }
+
+    Variable<T> variable;
+    if (type == Detail::MI_MATRIX &&
+        Detail::parse_matrix(payload, payload_size, little_endian,
+                             variable)) {
+      variables.push_back(variable);
+    }
+  }
+
+  return true;
+}
+
+/**
+ * @brief Reads the real part of a single numeric variable from a Level-5 MAT
+ * file.
+ *
+ * @tparam T Type the stored elements are converted to.
+ * @param path The MAT file to read.
+ * @param name The name of the variable.
+ * @param values Receives the variable in row-major order with MATLAB's shape.
+ * @param error Receives a description of the problem if loading fails.
+ * @return true if the variable was found and converted.
+ */
+template <typename T>
+inline bool load_variable(const std::string &path, const std::string &name,
+                          FlatArray<T> &values, std::string &error) {
+  std::vector<Variable<T>> variables;
+  if (!read_variables(path, variables, error)) {
+    return false;
+  }
+
+  for (std::size_t i = 0; i < variables.size(); i++) {
+    if (variables[i].name == name) {
+      values = variables[i].real;
+      return true;
+    }
+  }
+
+  error = "Variable " + name + " not found in " + path + ".";
+  return false;
+}
+
+} // namespace MatFile
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_MATFILE_HPP
@@ -0,0 +1,157 @@
+/**
+ * @file MCAP_tester_metric_bundle.hpp
+ * @brief Provides the Tester::MetricBundle class template for grading a
+ * controller variant by a weighted combination of scenario metrics.
+ *
+ * Systems engineers rarely accept or reject a controller on one metric: a
+ * little more overshoot is fine if the effort drops. A MetricBundle collects
+ * the metrics of one scenario, e.g. overshoot, effort, settling time and RMS
+ * error, each with the limit that scores 1 and a weight. Every metric is
+ * normalized by its limit, so lower is better, and the combined score is the
+ * weighted mean of the normalized metrics:
+ *
+ *   score = sum(weight * value / limit) / sum(weight)
+ *
+ * A variant at every limit thus scores exactly 1.
+ *
+ * Example:
+ *   MetricBundle<double> bundle;
+ *   bundle.add("overshoot", overshoot, 0.1, 2.0);
+ *   bundle.add("effort", Comparison::compute_effort(u, dt,
+ *                                                   EffortMeasure::Squared),
+ *              50.0);
+ *   bundle.expect_score_below(tester, 1.0, "variant B grade");
+ */
+
+#ifndef MCAP_TESTER_METRIC_BUNDLE_HPP
+#define MCAP_TESTER_METRIC_BUNDLE_HPP
+
+#include <cstddef>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+namespace Tester {
+
+/**
+ * @brief A weighted set of metrics with a combined score.
+ *
+ * @tparam T Type of the metric values.
+ */
+template <typename T> class MetricBundle {
+public:
+  MetricBundle() : _metrics() {}
+
+  /**
+   * @brief Registers a metric, where lower values are better.
+   *
+   * @param name The name of the metric, listed in the verdict.
+   * @param value The measured value.
+   * @param limit The value that scores 1; must be positive.
+   * @param weight The weight of the metric in the combined score; must not
+   * be negative.
+   */
+  void add(const std::string &name, T value, T limit,
+           T weight = static_cast<T>(1)) {
+    Metric metric;
+    metric.name = name;
+    metric.value = value;
+    metric.limit = limit;
+    metric.weight = weight;
+    this->_metrics.push_back(metric);
+  }
+
+  /**
+   * @brief Returns the number of registered metrics.
+   */
+  std::size_t size() const { return this->_metrics.size(); }
+
+  /**
+   * @brief Returns the combined score, or 0 if the bundle has no metric or
+   * no positive weight.
+   */
+  T score() const {
+    T weighted = static_cast<T>(0);
+    T total_weight = static_cast<T>(0);
+    for (std::size_t i = 0; i < this->_metrics.size(); i++) {
+      const Metric &metric = this->_metrics[i];
+      weighted += metric.weight * metric.value / metric.limit;
+      total_weight += metric.weight;
+    }
+    return total_weight > static_cast<T>(0) ? weighted / total_weight
+                                            : static_cast<T>(0);
+  }
+
+  /**
+   * @brief Checks that the combined score is at most a threshold.
+   *
+   * The detail lists every metric with its value, limit, weight and
+   * normalized score. An empty bundle, a non-positive limit or a negative
+   * or all-zero weight is reported as a failure.
+   *
+   * @param tester The tester that receives the verdict.
+   * @param max_score The largest acceptable score, e.g. 1.
+   * @param message The message to display in case of failure.
+   */
+  void expect_score_below(MCAPTester<T> &tester, T max_score,
+                          const std::string &message) const {
+    std::string error = this->_validate();
+    if (!error.empty()) {
+      tester.expect_true(false, message, error, FailureCategory::Structural);
+      return;
+    }
+
+    T score = this->score();
+    std::string detail = "Score " + format_number(score) + " (allowed " +
+                         format_number(max_score) + "):";
+    for (std::size_t i = 0; i < this->_metrics.size(); i++) {
+      const Metric &metric = this->_metrics[i];
+      detail += std::string(i == 0 ? " " : ", ") + metric.name + " " +
+                format_number(metric.value) + " of " +
+                format_number(metric.limit) + " (weight " +
+                format_number(metric.weight) + ", " +
+                format_number(metric.value / metric.limit) + ")";
+    }
+    tester.expect_true(score <= max_score, message, detail + ".");
+  }
+
+protected:
+  struct Metric {
+    Metric()
+        : name(), value(static_cast<T>(0)), limit(static_cast<T>(1)),
+          weight(static_cast<T>(1)) {}
+
+    std::string name;
+    T value;
+    T limit;
+    T weight;
+  };
+
+  std::string _validate() const {
+    if (this->_metrics.empty()) {
+      return "The bundle has no metrics.";
+    }
+    T total_weight = static_cast<T>(0);
+    for (std::size_t i = 0; i < this->_metrics.size(); i++) {
+      const Metric &metric = this->_metrics[i];
+      if (!(metric.limit > static_cast<T>(0))) {
+        return "The limit of " + metric.name + " is not positive.";
+      }
+      if (!(metric.weight >= static_cast<T>(0))) {
+        return "The weight of " + metric.name + " is negative.";
+      }
+      total_weight += metric.weight;
+    }
+    if (!(total_weight > static_cast<T>(0))) {
+      return "All weights are zero.";
+    }
+    return std::string();
+  }
+
+  std::vector<Metric> _metrics;
+};
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_METRIC_BUNDLE_HPP
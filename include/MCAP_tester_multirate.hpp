@@ -0,0 +1,243 @@
+/**
+ * @file MCAP_tester_multirate.hpp
+ * @brief Provides the Tester::MultiRateOrchestrator class template for
+ * comparing multi-rate generated code against its reference streams.
+ *
+ * Models with several sample rates (for example a 10 ms control loop and a
+ * 1 ms current loop) produce outputs at different rates, while the reference
+ * streams exported from the model may be logged at yet another rate. The
+ * orchestrator keeps the rate bookkeeping in one place: every task is
+ * registered with its period, start time and tolerance, every reference
+ * stream with its own period and start time, and each task output is paired
+ * with the reference samples taken at exactly the task's sample instants.
+ * All comparisons are reported through one MCAPTester, so the results end up
+ * in a single report.
+ */
+
+#ifndef MCAP_TESTER_MULTIRATE_HPP
+#define MCAP_TESTER_MULTIRATE_HPP
+
+#include <cmath>
+#include <cstddef>
+#include <map>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+namespace Tester {
+
+/**
+ * @brief A uniformly sampled signal.
+ *
+ * Sample k was taken at `start_time + k * period`.
+ *
+ * @tparam T Type of the samples.
+ */
+template <typename T> struct SampledStream {
+  SampledStream()
+      : samples(), period(static_cast<T>(0)), start_time(static_cast<T>(0)) {}
+
+  SampledStream(const std::vector<T> &samples, T period, T start_time)
+      : samples(samples), period(period), start_time(start_time) {}
+
+  std::vector<T> samples;
+  T period;
+  T start_time;
+};
+
+/**
+ * @brief Pairs the outputs of the tasks of a multi-rate model with the
+ * reference stream and rate they belong to.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> class MultiRateOrchestrator {
+public:
+  /**
+   * @brief Creates an orchestrator that reports through a tester.
+   *
+   * @param tester The tester that receives every comparison; it must outlive
+   * the orchestrator.
+   */
+  explicit MultiRateOrchestrator(MCAPTester<T> &tester)
+      : _tester(tester), _tasks(), _references(), _outputs() {}
+
+  /**
+   * @brief Registers a task of the generated code.
+   *
+   * @param task_name The name of the task, e.g. "control_10ms".
+   * @param period The sample period of the task in seconds.
+   * @param tolerance The tolerance applied to every output of the task.
+   * @param start_time The time of the first sample of the task in seconds.
+   */
+  void add_task(const std::string &task_name, T period, T tolerance,
+                T start_time = static_cast<T>(0)) {
+    Task task;
+    task.period = period;
+    task.tolerance = tolerance;
+    task.start_time = start_time;
+    this->_tasks[task_name] = task;
+  }
+
+  /**
+   * @brief Registers the reference stream of a signal.
+   *
+   * The reference may be sampled faster than the task producing the signal,
+   * as long as the task's sample instants fall on reference samples.
+   *
+   * @param signal_name The name of the signal.
+   * @param samples The reference samples.
+   * @param period The sample period of the reference in seconds.
+   * @param start_time The time of the first reference sample in seconds.
+   */
+  void add_reference(const std::string &signal_name,
+                     const std::vector<T> &samples, T period,
+                     T start_time = static_cast<T>(0)) {
+    this->_references[signal_name] =
+        SampledStream<T>(samples, period, start_time);
+  }
+
+  /**
+   * @brief Registers an output produced by a task of the generated code.
+   *
+   * @param task_name The task that produced the output, registered with
+   * add_task.
+   * @param signal_name The name of the signal; it selects the reference
+   * stream.
+   * @param samples One sample per task step.
+   */
+  void add_output(const std::string &task_name, const std::string &signal_name,
+                  const std::vector<T> &samples) {
+    Output output;
+    output.task_name = task_name;
+    output.signal_name = signal_name;
+    output.samples = samples;
+    this->_outputs.push_back(output);
+  }
+
+  /**
+   * @brief Compares every registered output against its reference.
+   *
+   * Each output is checked with the tolerance of its task under the message
+   * "<task>/<signal>". The reference is sampled at the task's sample
+   * instants; outputs without a reference, tasks that are not registered and
+   * instants that do not fall on a reference sample are reported as
+   * failures. Only the time range covered by both streams is compared.
+   *
+   * @return true if every output matched its reference.
+   */
+  bool run() {
+    bool all_passed = true;
+    for (std::size_t i = 0; i < this->_outputs.size(); i++) {
+      const Output &output = this->_outputs[i];
+      std::string message = output.task_name + "/" + output.signal_name;
+      std::size_t failures = this->_failure_count();
+
+      typename std::map<std::string, Task>::const_iterator task =
+          this->_tasks.find(output.task_name);
+      typename std::map<std::string, SampledStream<T>>::const_iterator
+          reference = this->_references.find(output.signal_name);
+
+      std::vector<T> actual;
+      std::vector<T> expected;
+      std::string error;
+      if (task == this->_tasks.end()) {
+        error = "Task " + output.task_name + " is not registered.";
+      } else if (reference == this->_references.end()) {
+        error = "No reference stream for signal " + output.signal_name + ".";
+      } else {
+        this->_align(output, task->second, reference->second, actual,
+                     expected, error);
+      }
+
+      if (!error.empty()) {
+        this->_tester.expect_true(false, message, error,
+                                  FailureCategory::Structural);
+      } else {
+        this->_tester.expect_near(actual, expected, task->second.tolerance,
+                                  message);
+      }
+
+      if (this->_failure_count() != failures) {
+        all_passed = false;
+      }
+    }
+    return all_passed;
+  }
+
+protected:
+  struct Task {
+    Task()
+        : period(static_cast<T>(0)), tolerance(static_cast<T>(0)),
+          start_time(static_cast<T>(0)) {}
+
+    T period;
+    T tolerance;
+    T start_time;
+  };
+
+  struct Output {
+    std::string task_name;
+    std::string signal_name;
+    std::vector<T> samples;
+  };
+
+  std::size_t _failure_count() const {
+    std::size_t count = 0;
+    const std::vector<AssertionRecord> &records = this->_tester.get_records();
+    for (std::size_t i = 0; i < records.size(); i++) {
+      if (records[i].status == ComparisonStatus::Failure) {
+        count++;
+      }
+    }
+    return count;
+  }
+
+  void _align(const Output &output, const Task &task,
+              const SampledStream<T> &reference, std::vector<T> &actual,
+              std::vector<T> &expected, std::string &error) const {
+    if (!(task.period > static_cast<T>(0)) ||
+        !(reference.period > static_cast<T>(0))) {
+      error = "Sample periods must be positive.";
+      return;
+    }
+
+    /* Sample instants are matched with a relative slack of 1e-6 of the
+     * reference period, so periods such as 0.001 and 0.01 still line up. */
+    double slack = 1e-6;
+    for (std::size_t k = 0; k < output.samples.size(); k++) {
+      double time = static_cast<double>(task.start_time) +
+                    static_cast<double>(k) * static_cast<double>(task.period);
+      double position = (time - static_cast<double>(reference.start_time)) /
+                        static_cast<double>(reference.period);
+      double index = std::floor(position + 0.5);
+
+      if (index < 0.0) {
+        continue;
+      }
+      if (index >= static_cast<double>(reference.samples.size())) {
+        break;
+      }
+      if (std::abs(position - index) > slack) {
+        error = "Task sample " + std::to_string(k) + " at t = " +
+                format_number(static_cast<T>(time)) +
+                " s does not fall on a reference sample.";
+        return;
+      }
+
+      actual.push_back(output.samples[k]);
+      expected.push_back(
+          reference.samples[static_cast<std::size_t>(index)]);
+    }
+  }
+
+  MCAPTester<T> &_tester;
+  std::map<std::string, Task> _tasks;
+  std::map<std::string, SampledStream<T>> _references;
+  std::vector<Output> _outputs;
+};
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_MULTIRATE_HPP
@@ -0,0 +1,457 @@
+/**
+ * @file MCAP_tester_numerics.hpp
+ * @brief Provides the numerical building blocks used by the MCAP_tester
+ * comparison functions.
+ *
+ * The Tester::Numerics namespace holds small, dependency-free routines that
+ * several assertions need, such as the discrete Fourier transform. They are
+ * computed in double precision regardless of the element type under test, so
+ * that the reference side of a comparison does not add noticeable error of
+ * its own. The compensated summation routines instead work in the element
+ * type itself and reach nearly its full precision, for reference values of
+ * long accumulations in any type. They require IEEE 754 arithmetic without
+ * value-changing optimizations such as -ffast-math.
+ */
+
+#ifndef MCAP_TESTER_NUMERICS_HPP
+#define MCAP_TESTER_NUMERICS_HPP
+
+#include <algorithm>
+#include <cmath>
+#include <complex>
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <limits>
+#include <utility>
+#include <vector>
+
+namespace Tester {
+
+namespace Numerics {
+
+static const double PI = 3.14159265358979323846;
+
+/**
+ * @brief In-place iterative radix-2 FFT. The size must be a power of two.
+ *
+ * @param values The sequence to transform.
+ * @param inverse If true, computes the unscaled inverse transform.
+ */
+inline void fft_radix2(std::vector<std::complex<double>> &values,
+                       bool inverse) {
+  std::size_t n = values.size();
+
+  for (std::size_t i = 1, j = 0; i < n; i++) {
+    std::size_t bit = n >> 1;
+    for (; j & bit; bit >>= 1) {
+      j ^= bit;
+    }
+    j ^= bit;
+    if (i < j) {
+      std::swap(values[i], values[j]);
+    }
+  }
+
+  for (std::size_t length = 2; length <= n; length <<= 1) {
+    double angle = 2.0 * PI / static_cast<double>(length) * (inverse ? 1 : -1);
+    std::complex<double> step(std::cos(angle), std::sin(angle));
+    for (std::size_t start = 0; start < n; start += length) {
+      std::complex<double> twiddle(1.0, 0.0);
+      for (std::size_t k = 0; k < length / 2; k++) {
+        std::complex<double> even = values[start + k];
+        std::complex<double> odd = values[start + k + length / 2] * twiddle;
+        values[start + k] = even + odd;
+        values[start + k + length / 2] = even - odd;
+        twiddle *= step;
+      }
+    }
+  }
+}
+
+/**
+ * @brief Computes the discrete Fourier transform of a sequence of any
+ * length.
+ *
+ * Power-of-two lengths use the radix-2 FFT directly; other lengths use
+ * Bluestein's algorithm, so the cost stays O(N log N).
+ *
+ * @param values The sequence to transform.
+ * @return X[k] = sum_n x[n] exp(-2 pi i k n / N).
+ */
+inline std::vector<std::complex<double>>
+dft(const std::vector<std::complex<double>> &values) {
+  std::size_t n = values.size();
+  if (n <= 1 || (n & (n - 1)) == 0) {
+    std::vector<std::complex<double>> result = values;
+    fft_radix2(result, false);
+    return result;
+  }
+
+  std::size_t m = 1;
+  while (m < 2 * n - 1) {
+    m <<= 1;
+  }
+
+  std::vector<std::complex<double>> chirp(n);
+  for (std::size_t k = 0; k < n; k++) {
+    /* k^2 is reduced modulo 2n to keep the angle accurate for large k. */
+    std::size_t k_squared = (k * k) % (2 * n);
+    double angle =
+        -PI * static_cast<double>(k_squared) / static_cast<double>(n);
+    chirp[k] = std::complex<double>(std::cos(angle), std::sin(angle));
+  }
+
+  std::vector<std::complex<double>> a(m, std::complex<double>(0.0, 0.0));
+  std::vector<std::complex<double>> b(m, std::complex<double>(0.0, 0.0));
+  for (std::size_t k = 0; k < n; k++) {
+    a[k] = values[k] * chirp[k];
+  }
+  b[0] = std::conj(chirp[0]);
+  for (std::size_t k = 1; k < n; k++) {
+    b[k] = std::conj(chirp[k]);
+    b[m - k] = std::conj(chirp[k]);
+  }
+
+  fft_radix2(a, false);
+  fft_radix2(b, false);
+  for (std::size_t k = 0; k < m; k++) {
+    a[k] *= b[k];
+  }
+  fft_radix2(a, true);
+
+  std::vector<std::complex<double>> result(n);
+  for (std::size_t k = 0; k < n; k++) {
+    result[k] = chirp[k] * a[k] / static_cast<double>(m);
+  }
+  return result;
+}
+
+/**
+ * @brief Computes the discrete Fourier transform of a real sequence.
+ */
+template <typename T>
+inline std::vector<std::complex<double>>
+dft_real(const std::vector<T> &values) {
+  std::vector<std::complex<double>> complex_values(values.size());
+  for (std::size_t i = 0; i < values.size(); i++) {
+    complex_values[i] =
+        std::complex<double>(static_cast<double>(values[i]), 0.0);
+  }
+  return dft(complex_values);
+}
+
+/**
+ * @brief Wraps an angle in radians into the interval (-pi, pi].
+ */
+inline double wrap_angle(double angle) {
+  double wrapped = std::fmod(angle + PI, 2.0 * PI);
+  if (wrapped <= 0.0) {
+    wrapped += 2.0 * PI;
+  }
+  return wrapped - PI;
+}
+
+/**
+ * @brief Computes the eigenvalues of a real symmetric matrix with the cyclic
+ * Jacobi method.
+ *
+ * @param matrix The n x n matrix in row-major order; only symmetric input
+ * gives meaningful results.
+ * @param n The number of rows and columns.
+ * @return The eigenvalues in ascending order.
+ */
+inline std::vector<double> symmetric_eigenvalues(std::vector<double> matrix,
+                                                 std::size_t n) {
+  for (std::size_t sweep = 0; sweep < 100; sweep++) {
+    double off_diagonal = 0.0;
+    for (std::size_t p = 0; p < n; p++) {
+      for (std::size_t q = p + 1; q < n; q++) {
+        off_diagonal += matrix[p * n + q] * matrix[p * n + q];
+      }
+    }
+    if (off_diagonal < 1e-300) {
+      break;
+    }
+
+    for (std::size_t p = 0; p < n; p++) {
+      for (std::size_t q = p + 1; q < n; q++) {
+        double a_pq = matrix[p * n + q];
+        if (a_pq == 0.0) {
+          continue;
+        }
+
+        double theta = (matrix[q * n + q] - matrix[p * n + p]) / (2.0 * a_pq);
+        double t = (theta >= 0.0 ? 1.0 : -1.0) /
+                   (std::abs(theta) + std::sqrt(theta * theta + 1.0));
+        double c = 1.0 / std::sqrt(t * t + 1.0);
+        double s = t * c;
+
+        for (std::size_t k = 0; k < n; k++) {
+          double a_kp = matrix[k * n + p];
+          double a_kq = matrix[k * n + q];
+          matrix[k * n + p] = c * a_kp - s * a_kq;
+          matrix[k * n + q] = s * a_kp + c * a_kq;
+        }
+        for (std::size_t k = 0; k < n; k++) {
+          double a_pk = matrix[p * n + k];
+          double a_qk = matrix[q * n + k];
+          matrix[p * n + k] = c * a_pk - s * a_qk;
+          matrix[q * n + k] = s * a_pk + c * a_qk;
+        }
+      }
+    }
+  }
+
+  std::vector<double> eigenvalues(n);
+  for (std::size_t i = 0; i < n; i++) {
+    eigenvalues[i] = matrix[i * n + i];
+  }
+  std::sort(eigenvalues.begin(), eigenvalues.end());
+  return eigenvalues;
+}
+
+/**
+ * @brief Running sum with Neumaier's compensation of the rounding error.
+ *
+ * The error of the compensated sum of n terms is bounded by about
+ * epsilon * |sum| + n * epsilon^2 * sum |terms|, i.e. the sum is correct to
+ * nearly working precision as long as n * epsilon is small, unlike the
+ * n * epsilon * sum |terms| bound of recursive summation. Reference values
+ * of long accumulations computed this way make a tolerance failure
+ * attributable to the code under test rather than to the reference.
+ *
+ * @tparam T Floating-point type of the terms.
+ */
+template <typename T> class CompensatedSum {
+public:
+  CompensatedSum()
+      : _sum(static_cast<T>(0)), _compensation(static_cast<T>(0)) {}
+
+  /**
+   * @brief Adds a term to the sum.
+   */
+  void add(T term) {
+    T sum = this->_sum + term;
+    if (std::abs(this->_sum) >= std::abs(term)) {
+      this->_compensation += (this->_sum - sum) + term;
+    } else {
+      this->_compensation += (term - sum) + this->_sum;
+    }
+    this->_sum = sum;
+  }
+
+  /**
+   * @brief Returns the compensated sum of all terms added so far.
+   */
+  T value() const { return this->_sum + this->_compensation; }
+
+protected:
+  T _sum;
+  T _compensation;
+};
+
+/**
+ * @brief Sums values with compensation of the rounding error.
+ *
+ * @param values The terms to sum.
+ * @return The sum, accurate to nearly working precision.
+ */
+template <typename T> inline T compensated_sum(const std::vector<T> &values) {
+  CompensatedSum<T> sum;
+  for (std::size_t i = 0; i < values.size(); i++) {
+    sum.add(values[i]);
+  }
+  return sum.value();
+}
+
+/**
+ * @brief Computes a dot product with compensation of the rounding errors of
+ * both the products and the sum.
+ *
+ * The rounding error of every product is recovered exactly with a fused
+ * multiply-add, so the result is as accurate as if it were computed in twice
+ * the working precision and then rounded.
+ *
+ * @param x The first operand.
+ * @param y The second operand; only the common length of x and y is used.
+ * @return The dot product.
+ */
+template <typename T>
+inline T compensated_dot(const std::vector<T> &x, const std::vector<T> &y) {
+  CompensatedSum<T> sum;
+  for (std::size_t i = 0; i < x.size() && i < y.size(); i++) {
+    T product = x[i] * y[i];
+    sum.add(product);
+    sum.add(std::fma(x[i], y[i], -product));
+  }
+  return sum.value();
+}
+
+/**
+ * @brief Computes the Euclidean norm with compensated accumulation of the
+ * squares.
+ *
+ * The values are scaled by their largest magnitude first, so the norm does
+ * not overflow or underflow for values whose squares would.
+ *
+ * @param values The vector whose norm is computed.
+ * @return The Euclidean norm.
+ */
+template <typename T>
+inline T compensated_norm(const std::vector<T> &values) {
+  T scale = static_cast<T>(0);
+  for (std::size_t i = 0; i < values.size(); i++) {
+    scale = std::max(scale, std::abs(values[i]));
+  }
+  if (scale == static_cast<T>(0) || std::isinf(scale)) {
+    return scale;
+  }
+
+  std::vector<T> scaled(values.size());
+  for (std::size_t i = 0; i < values.size(); i++) {
+    scaled[i] = values[i] / scale;
+  }
+  return scale * std::sqrt(compensated_dot(scaled, scaled));
+}
+
+/* Maps the bit pattern of a float onto an unsigned integer that is
+ * monotonic in the value, so neighbouring floats differ by 1. */
+template <typename Bits, typename F> inline Bits ordered_bits(F value) {
+  Bits bits;
+  std::memcpy(&bits, &value, sizeof(bits));
+  const Bits sign = static_cast<Bits>(1) << (sizeof(Bits) * 8 - 1);
+  return (bits & sign) ? static_cast<Bits>(~bits) : (bits | sign);
+}
+
+template <typename Bits, typename F>
+inline std::uint64_t ordered_distance(F a, F b) {
+  if (std::isnan(a) || std::isnan(b)) {
+    return std::numeric_limits<std::uint64_t>::max();
+  }
+  if (a == b) {
+    return 0;
+  }
+  Bits x = ordered_bits<Bits>(a);
+  Bits y = ordered_bits<Bits>(b);
+  return static_cast<std::uint64_t>(x > y ? x - y : y - x);
+}
+
+/**
+ * @brief Returns the number of representable values between two floats,
+ * i.e. their distance in units in the last place (ULP).
+ *
+ * Equal values, including +0 and -0, are 0 apart, adjacent values 1 apart,
+ * and the distance remains meaningful across zero and up to infinity. A NaN
+ * is at the largest possible distance from everything.
+ */
+inline std::uint64_t ulp_distance(float a, float b) {
+  return ordered_distance<std::uint32_t>(a, b);
+}
+
+/**
+ * @brief Returns the distance of two doubles in units in the last place.
+ */
+inline std::uint64_t ulp_distance(double a, double b) {
+  return ordered_distance<std::uint64_t>(a, b);
+}
+
+/**
+ * @brief Returns the distance of two long doubles in units in the last place
+ * of double, since the layout of long double is platform-specific.
+ */
+inline std::uint64_t ulp_distance(long double a, long double b) {
+  return ulp_distance(static_cast<double>(a), static_cast<double>(b));
+}
+
+/**
+ * @brief Returns the cumulative distribution function of the standard normal
+ * distribution.
+ */
+inline double normal_cdf(double x) {
+  return 0.5 * std::erfc(-x / std::sqrt(2.0));
+}
+
+/* Evaluates the continued fraction of the incomplete beta function with the
+ * modified Lentz method. */
+inline double incomplete_beta_fraction(double a, double b, double x) {
+  const double tiny = 1e-300;
+  double c = 1.0;
+  double d = 1.0 - (a + b) * x / (a + 1.0);
+  d = 1.0 / (std::abs(d) < tiny ? tiny : d);
+  double h = d;
+  for (int m = 1; m <= 300; m++) {
+    for (int step = 0; step < 2; step++) {
+      double numerator =
+          (step == 0) ? m * (b - m) * x / ((a + 2 * m - 1) * (a + 2 * m))
+                      : -(a + m) * (a + b + m) * x /
+                            ((a + 2 * m) * (a + 2 * m + 1));
+      d = 1.0 + numerator * d;
+      d = 1.0 / (std::abs(d) < tiny ? tiny : d);
+      c = 1.0 + numerator / c;
+      c = std::abs(c) < tiny ? tiny : c;
+      h *= d * c;
+      if (step == 1 && std::abs(d * c - 1.0) < 1e-15) {
+        return h;
+      }
+    }
+  }
+  return h;
+}
+
+/**
+ * @brief Returns the regularized incomplete beta function I_x(a, b) for
+ * a, b > 0 and x in [0, 1].
+ */
+inline double regularized_incomplete_beta(double a, double b, double x) {
+  if (x <= 0.0) {
+    return 0.0;
+  } else if (x >= 1.0) {
+    return 1.0;
+  }
+  double front = std::exp(std::lgamma(a + b) - std::lgamma(a) -
+                          std::lgamma(b) + a * std::log(x) +
+                          b * std::log(1.0 - x));
+  if (x < (a + 1.0) / (a + b + 2.0)) {
+    return front * incomplete_beta_fraction(a, b, x) / a;
+  }
+  return 1.0 - front * incomplete_beta_fraction(b, a, 1.0 - x) / b;
+}
+
+/**
+ * @brief Returns the cumulative distribution function of Student's t
+ * distribution with `degrees` degrees of freedom.
+ */
+inline double student_t_cdf(double t, double degrees) {
+  double tail = 0.5 * regularized_incomplete_beta(
+                          0.5 * degrees, 0.5, degrees / (degrees + t * t));
+  return t > 0.0 ? 1.0 - tail : tail;
+}
+
+/**
+ * @brief Returns the survival function Q(lambda) = 2 sum (-1)^(k-1)
+ * exp(-2 k^2 lambda^2) of the Kolmogorov distribution.
+ */
+inline double kolmogorov_survival(double lambda) {
+  const double exponent = -2.0 * lambda * lambda;
+  double sign = 2.0;
+  double sum = 0.0;
+  double previous = 0.0;
+  for (int k = 1; k <= 100; k++) {
+    double term = sign * std::exp(exponent * k * k);
+    sum += term;
+    if (std::abs(term) <= 1e-3 * previous || std::abs(term) <= 1e-8 * sum) {
+      return sum < 0.0 ? 0.0 : (sum > 1.0 ? 1.0 : sum);
+    }
+    sign = -sign;
+    previous = std::abs(term);
+  }
+  /* The series only fails to converge for tiny lambda, where Q is 1. */
+  return 1.0;
+}
+
+} // namespace Numerics
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_NUMERICS_HPP
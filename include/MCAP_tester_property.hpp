@@ -0,0 +1,337 @@
+/**
+ * @file MCAP_tester_property.hpp
+ * @brief Provides property-based testing with shrinking on top of the
+ * tolerance checks of Tester::MCAPTester.
+ *
+ * A property is a function that makes assertions on a scratch tester for one
+ * randomly generated input, e.g. that a generated filter agrees with its
+ * reference on any bounded input vector. Property::check evaluates the
+ * property for many inputs drawn from a strategy. When an input falsifies
+ * it, the failing scratch tester is turned into a shrink step: the strategy
+ * proposes simpler inputs (shorter, smaller, closer to zero) and every one
+ * that still fails replaces the input, until none does. The minimal failing
+ * input and the failure it causes are reported as a single assertion.
+ *
+ * A strategy is any class with
+ *
+ *   typedef ... Value;
+ *   Value generate(std::mt19937_64 &random) const;
+ *   std::vector<Value> shrink(const Value &value) const;
+ *   std::string describe(const Value &value) const;
+ *
+ * ArrayStrategy and MatrixStrategy generate bounded vectors and matrices
+ * with shape and range control.
+ */
+
+#ifndef MCAP_TESTER_PROPERTY_HPP
+#define MCAP_TESTER_PROPERTY_HPP
+
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <exception>
+#include <random>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+namespace Tester {
+
+namespace Property {
+
+namespace Detail {
+
+/* The simplest value of a range: zero if the range contains it, otherwise
+ * the bound closest to zero. */
+template <typename T> inline T simplest(T low, T high) {
+  const T zero = static_cast<T>(0);
+  return low > zero ? low : (high < zero ? high : zero);
+}
+
+template <typename T>
+inline void shrink_elements(const std::vector<T> &values, T target,
+                            std::vector<std::vector<T>> &candidates) {
+  for (std::size_t i = 0; i < values.size(); i++) {
+    if (values[i] == target) {
+      continue;
+    }
+    std::vector<T> candidate = values;
+    candidate[i] = target;
+    candidates.push_back(candidate);
+
+    T halfway = target + (values[i] - target) / static_cast<T>(2);
+    if (halfway != values[i] && halfway != target) {
+      candidate[i] = halfway;
+      candidates.push_back(candidate);
+    }
+  }
+}
+
+template <typename T>
+inline std::string describe_values(const std::vector<T> &values) {
+  const std::size_t shown = 20;
+  std::string text = "[";
+  for (std::size_t i = 0; i < values.size() && i < shown; i++) {
+    text += (i == 0 ? "" : ", ") + format_number(values[i]);
+  }
+  if (values.size() > shown) {
+    text += ", ... (" + std::to_string(values.size()) + " elements)";
+  }
+  return text + "]";
+}
+
+} // namespace Detail
+
+/**
+ * @brief Generates vectors with a length in [min_size, max_size] and
+ * elements uniformly distributed in [low, high].
+ *
+ * @tparam T Type of the elements.
+ */
+template <typename T> class ArrayStrategy {
+public:
+  typedef std::vector<T> Value;
+
+  ArrayStrategy(std::size_t min_size, std::size_t max_size, T low, T high)
+      : _min_size(min_size), _max_size(max_size), _low(low), _high(high) {}
+
+  Value generate(std::mt19937_64 &random) const {
+    std::uniform_int_distribution<std::size_t> size(this->_min_size,
+                                                     this->_max_size);
+    std::uniform_real_distribution<double> element(
+        static_cast<double>(this->_low), static_cast<double>(this->_high));
+    Value value(size(random));
+    for (std::size_t i = 0; i < value.size(); i++) {
+      value[i] = static_cast<T>(element(random));
+    }
+    return value;
+  }
+
+  /**
+   * @brief Proposes simpler vectors: the shortest allowed prefix, the first
+   * half, every vector with one element removed, and every vector with one
+   * element moved to or halfway towards the simplest value of the range.
+   */
+  std::vector<Value> shrink(const Value &value) const {
+    std::vector<Value> candidates;
+    if (value.size() > this->_min_size) {
+      candidates.push_back(
+          Value(value.begin(), value.begin() + this->_min_size));
+      std::size_t half = value.size() / 2;
+      if (half > this->_min_size) {
+        candidates.push_back(Value(value.begin(), value.begin() + half));
+      }
+      for (std::size_t i = 0; i < value.size(); i++) {
+        Value candidate = value;
+        candidate.erase(candidate.begin() + i);
+        candidates.push_back(candidate);
+      }
+    }
+    Detail::shrink_elements(value, Detail::simplest(this->_low, this->_high),
+                            candidates);
+    return candidates;
+  }
+
+  std::string describe(const Value &value) const {
+    return Detail::describe_values(value);
+  }
+
+protected:
+  std::size_t _min_size;
+  std::size_t _max_size;
+  T _low;
+  T _high;
+};
+
+/**
+ * @brief Generates matrices with a number of rows in [min_rows, max_rows],
+ * of columns in [min_columns, max_columns] and elements uniformly
+ * distributed in [low, high].
+ *
+ * @tparam T Type of the elements.
+ */
+template <typename T> class MatrixStrategy {
+public:
+  typedef std::vector<std::vector<T>> Value;
+
+  MatrixStrategy(std::size_t min_rows, std::size_t max_rows,
+                 std::size_t min_columns, std::size_t max_columns, T low,
+                 T high)
+      : _min_rows(min_rows), _max_rows(max_rows), _min_columns(min_columns),
+        _max_columns(max_columns), _low(low), _high(high) {}
+
+  Value generate(std::mt19937_64 &random) const {
+    std::uniform_int_distribution<std::size_t> rows(this->_min_rows,
+                                                    this->_max_rows);
+    std::uniform_int_distribution<std::size_t> columns(this->_min_columns,
+                                                       this->_max_columns);
+    std::uniform_real_distribution<double> element(
+        static_cast<double>(this->_low), static_cast<double>(this->_high));
+    Value value(rows(random));
+    std::size_t column_count = columns(random);
+    for (std::size_t i = 0; i < value.size(); i++) {
+      value[i].resize(column_count);
+      for (std::size_t j = 0; j < column_count; j++) {
+        value[i][j] = static_cast<T>(element(random));
+      }
+    }
+    return value;
+  }
+
+  /**
+   * @brief Proposes simpler matrices: every matrix with one row or the last
+   * column removed, and every matrix with one element moved to or halfway
+   * towards the simplest value of the range.
+   */
+  std::vector<Value> shrink(const Value &value) const {
+    std::vector<Value> candidates;
+    if (value.size() > this->_min_rows) {
+      for (std::size_t i = 0; i < value.size(); i++) {
+        Value candidate = value;
+        candidate.erase(candidate.begin() + i);
+        candidates.push_back(candidate);
+      }
+    }
+    if (!value.empty() && value[0].size() > this->_min_columns) {
+      Value candidate = value;
+      for (std::size_t i = 0; i < candidate.size(); i++) {
+        candidate[i].pop_back();
+      }
+      candidates.push_back(candidate);
+    }
+
+    T target = Detail::simplest(this->_low, this->_high);
+    for (std::size_t i = 0; i < value.size(); i++) {
+      std::vector<std::vector<T>> rows;
+      Detail::shrink_elements(value[i], target, rows);
+      for (std::size_t k = 0; k < rows.size(); k++) {
+        Value candidate = value;
+        candidate[i] = rows[k];
+        candidates.push_back(candidate);
+      }
+    }
+    return candidates;
+  }
+
+  std::string describe(const Value &value) const {
+    std::string text = "[";
+    for (std::size_t i = 0; i < value.size(); i++) {
+      text += (i == 0 ? "" : ", ") + Detail::describe_values(value[i]);
+    }
+    return text + "]";
+  }
+
+protected:
+  std::size_t _min_rows;
+  std::size_t _max_rows;
+  std::size_t _min_columns;
+  std::size_t _max_columns;
+  T _low;
+  T _high;
+};
+
+/**
+ * @brief Evaluates a property for one input on a fresh quiet tester.
+ *
+ * @param failure Receives the message and detail of the first failing
+ * assertion, or of the exception that escaped the property.
+ * @return true if the property holds for the input.
+ */
+template <typename T, typename Value, typename PropertyFunction>
+inline bool holds(const PropertyFunction &property, const Value &value,
+                  std::string &failure) {
+  MCAPTester<T> scratch;
+  scratch.set_verbosity(Verbosity::Quiet);
+  try {
+    property(scratch, value);
+  } catch (const std::exception &error) {
+    failure = std::string("Uncaught exception: ") + error.what();
+    scratch.reset_test_failed_flag();
+    return false;
+  }
+
+  const std::vector<AssertionRecord> &records = scratch.get_records();
+  for (std::size_t i = 0; i < records.size(); i++) {
+    if (records[i].status == ComparisonStatus::Failure) {
+      failure = records[i].message;
+      if (!records[i].detail.empty()) {
+        failure += " " + records[i].detail;
+      }
+      scratch.reset_test_failed_flag();
+      return false;
+    }
+  }
+  return true;
+}
+
+/**
+ * @brief Checks a property for random inputs of a strategy and reports the
+ * minimal falsifying input, if any, as one assertion.
+ *
+ * Example:
+ *   Property::check(tester, "filter matches reference",
+ *                   Property::ArrayStrategy<double>(1, 64, -10.0, 10.0),
+ *                   [](MCAPTester<double> &t, const std::vector<double> &u) {
+ *                     t.expect_near(filter(u), reference(u), 1e-9, "output");
+ *                   });
+ *
+ * @param tester The tester that receives the verdict.
+ * @param message The message to display in case of failure.
+ * @param strategy Generates and shrinks the inputs.
+ * @param property Makes assertions on the given scratch tester for an input.
+ * @param cases The number of random inputs to try.
+ * @param seed The seed of the random generator; the failure report names it
+ * so a failure can be reproduced.
+ * @param max_shrink_steps The largest number of accepted shrink steps.
+ * @return true if the property held for all inputs.
+ */
+template <typename T, typename Strategy, typename PropertyFunction>
+inline bool check(MCAPTester<T> &tester, const std::string &message,
+                  const Strategy &strategy, const PropertyFunction &property,
+                  std::size_t cases = 100, std::uint64_t seed = 1,
+                  std::size_t max_shrink_steps = 1000) {
+  typedef typename Strategy::Value Value;
+  std::mt19937_64 random(seed);
+  for (std::size_t n = 0; n < cases; n++) {
+    Value value = strategy.generate(random);
+    std::string failure;
+    if (holds<T>(property, value, failure)) {
+      continue;
+    }
+
+    std::size_t steps = 0;
+    bool shrunk = true;
+    while (shrunk && steps < max_shrink_steps) {
+      shrunk = false;
+      std::vector<Value> candidates = strategy.shrink(value);
+      for (std::size_t i = 0; i < candidates.size(); i++) {
+        std::string candidate_failure;
+        if (!holds<T>(property, candidates[i], candidate_failure)) {
+          value = candidates[i];
+          failure = candidate_failure;
+          shrunk = true;
+          steps++;
+          break;
+        }
+      }
+    }
+
+    tester.expect_true(false, message,
+                       "Falsified by case " + std::to_string(n + 1) + " of " +
+                           std::to_string(cases) + " (seed " +
+                           std::to_string(seed) + "), minimal input after " +
+                           std::to_string(steps) + " shrink steps: " +
+                           strategy.describe(value) + ". " + failure);
+    return false;
+  }
+
+  tester.expect_true(true, message);
+  return true;
+}
+
+} // namespace Property
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_PROPERTY_HPP
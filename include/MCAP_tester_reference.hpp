@@ -0,0 +1,585 @@
+/**
+ * @file MCAP_tester_reference.hpp
+ * @brief Provides reference implementations of common generated blocks for
+ * differential tests.
+ *
+ * Differential tests compare generated code against a reference, and a
+ * reference of uncertain quality makes every failure ambiguous. The blocks in
+ * the Tester::Reference namespace follow the documented semantics of the
+ * corresponding Simulink blocks, including the discretization, the order of
+ * updating state and producing output, and the behaviour at limits, so
+ * generated code can be stepped side by side with them:
+ *
+ *   Reference::RateLimiter<double> reference(1.0, -1.0, 0.01, 0.0);
+ *   for (...) {
+ *     expected.push_back(reference.step(input[k]));
+ *   }
+ *   tester.expect_near(actual, expected, 1e-12, "rate limiter");
+ */
+
+#ifndef MCAP_TESTER_REFERENCE_HPP
+#define MCAP_TESTER_REFERENCE_HPP
+
+#include <cmath>
+#include <cstddef>
+#include <functional>
+#include <limits>
+#include <string>
+#include <vector>
+
+namespace Tester {
+
+namespace Reference {
+
+/**
+ * @brief Limits a value to the range [lower, upper], like the Saturation
+ * block.
+ */
+template <typename T> inline T saturate(T value, T lower, T upper) {
+  if (value > upper) {
+    return upper;
+  } else if (value < lower) {
+    return lower;
+  }
+  return value;
+}
+
+/**
+ * @brief Discrete PID controller in parallel form with output saturation.
+ *
+ * The output of step k is
+ *   u[k] = sat(kp * e[k] + I[k] + kd * (e[k] - e[k - 1]) / dt),
+ * where the integrator uses the forward Euler method,
+ *   I[k + 1] = I[k] + ki * dt * e[k],
+ * and the derivative uses the backward difference with e[-1] = e[0], so the
+ * first step has no derivative kick. Integration is suspended while the
+ * output is saturated and the error would drive it further into saturation
+ * (clamping anti-windup).
+ *
+ * @tparam T Type of the signals.
+ */
+template <typename T> class PID {
+public:
+  /**
+   * @param kp The proportional gain.
+   * @param ki The integral gain.
+   * @param kd The derivative gain.
+   * @param dt The sample time in seconds.
+   * @param lower The lower output limit.
+   * @param upper The upper output limit.
+   */
+  PID(T kp, T ki, T kd, T dt, T lower, T upper)
+      : _kp(kp), _ki(ki), _kd(kd), _dt(dt), _lower(lower), _upper(upper),
+        _integrator(static_cast<T>(0)), _previous_error(static_cast<T>(0)),
+        _first_step(true) {}
+
+  /**
+   * @brief Computes the output for the current error and advances the state.
+   *
+   * @param error The control error e[k].
+   * @return The saturated controller output u[k].
+   */
+  T step(T error) {
+    if (this->_first_step) {
+      this->_previous_error = error;
+      this->_first_step = false;
+    }
+
+    T derivative = this->_kd * (error - this->_previous_error) / this->_dt;
+    T unsaturated = this->_kp * error + this->_integrator + derivative;
+    T output = saturate(unsaturated, this->_lower, this->_upper);
+
+    bool winding_up =
+        (unsaturated > this->_upper && error > static_cast<T>(0)) ||
+        (unsaturated < this->_lower && error < static_cast<T>(0));
+    if (!winding_up) {
+      this->_integrator += this->_ki * this->_dt * error;
+    }
+    this->_previous_error = error;
+    return output;
+  }
+
+  /**
+   * @brief Returns the state of the integrator, I[k + 1] after a step.
+   */
+  T get_integrator() const { return this->_integrator; }
+
+  /**
+   * @brief Restores the initial state.
+   */
+  void reset() {
+    this->_integrator = static_cast<T>(0);
+    this->_previous_error = static_cast<T>(0);
+    this->_first_step = true;
+  }
+
+protected:
+  T _kp;
+  T _ki;
+  T _kd;
+  T _dt;
+  T _lower;
+  T _upper;
+  T _integrator;
+  T _previous_error;
+  bool _first_step;
+};
+
+/**
+ * @brief Discretization of a continuous-time block.
+ */
+enum class Discretization {
+  ForwardEuler,
+  BackwardEuler,
+  Tustin,
+  ZeroOrderHold
+};
+
+/**
+ * @brief Discrete first-order low-pass filter 1 / (time_constant * s + 1).
+ *
+ * @tparam T Type of the signals.
+ */
+template <typename T> class FirstOrderFilter {
+public:
+  /**
+   * @param time_constant The time constant in seconds; must be positive.
+   * @param dt The sample time in seconds.
+   * @param method The discretization of the continuous filter.
+   * @param initial_output The output before the first step.
+   */
+  FirstOrderFilter(T time_constant, T dt, Discretization method,
+                   T initial_output = static_cast<T>(0))
+      : _time_constant(time_constant), _dt(dt), _method(method),
+        _initial_output(initial_output), _output(initial_output),
+        _previous_input(initial_output) {}
+
+  /**
+   * @brief Filters the next input sample.
+   *
+   * With ForwardEuler and ZeroOrderHold the output of step k depends only on
+   * earlier inputs, e.g. y[k] = y[k - 1] + dt / tau * (u[k - 1] - y[k - 1])
+   * for ForwardEuler; BackwardEuler and Tustin also use the current input.
+   * ZeroOrderHold is exact for inputs that are held constant between
+   * samples.
+   *
+   * @param input The input sample u[k].
+   * @return The output sample y[k].
+   */
+  T step(T input) {
+    T tau = this->_time_constant;
+    T dt = this->_dt;
+    T y = this->_output;
+    if (this->_method == Discretization::ForwardEuler) {
+      y += dt / tau * (this->_previous_input - y);
+    } else if (this->_method == Discretization::BackwardEuler) {
+      y += dt / (tau + dt) * (input - y);
+    } else if (this->_method == Discretization::Tustin) {
+      T a = (static_cast<T>(2) * tau - dt) / (static_cast<T>(2) * tau + dt);
+      T b = dt / (static_cast<T>(2) * tau + dt);
+      y = a * y + b * (input + this->_previous_input);
+    } else {
+      T alpha = static_cast<T>(1) - std::exp(-dt / tau);
+      y += alpha * (this->_previous_input - y);
+    }
+
+    this->_output = y;
+    this->_previous_input = input;
+    return y;
+  }
+
+  /**
+   * @brief Restores the initial state.
+   */
+  void reset() {
+    this->_output = this->_initial_output;
+    this->_previous_input = this->_initial_output;
+  }
+
+protected:
+  T _time_constant;
+  T _dt;
+  Discretization _method;
+  T _initial_output;
+  T _output;
+  T _previous_input;
+};
+
+/**
+ * @brief Discrete rate limiter with the semantics of the Rate Limiter block.
+ *
+ * With the rate r = (u[k] - y[k - 1]) / dt, the output is
+ * y[k - 1] + rising_rate * dt if r > rising_rate, y[k - 1] + falling_rate *
+ * dt if r < falling_rate, and u[k] otherwise. As in Simulink, the falling
+ * slew rate is negative.
+ *
+ * @tparam T Type of the signals.
+ */
+template <typename T> class RateLimiter {
+public:
+  /**
+   * @param rising_rate The largest allowed rate of increase per second.
+   * @param falling_rate The most negative allowed rate per second.
+   * @param dt The sample time in seconds.
+   * @param initial_output The output before the first step.
+   */
+  RateLimiter(T rising_rate, T falling_rate, T dt,
+              T initial_output = static_cast<T>(0))
+      : _rising_rate(rising_rate), _falling_rate(falling_rate), _dt(dt),
+        _initial_output(initial_output), _output(initial_output) {}
+
+  /**
+   * @brief Limits the rate of the next input sample.
+   *
+   * @param input The input sample u[k].
+   * @return The output sample y[k].
+   */
+  T step(T input) {
+    T rate = (input - this->_output) / this->_dt;
+    if (rate > this->_rising_rate) {
+      this->_output += this->_rising_rate * this->_dt;
+    } else if (rate < this->_falling_rate) {
+      this->_output += this->_falling_rate * this->_dt;
+    } else {
+      this->_output = input;
+    }
+    return this->_output;
+  }
+
+  /**
+   * @brief Restores the initial state.
+   */
+  void reset() { this->_output = this->_initial_output; }
+
+protected:
+  T _rising_rate;
+  T _falling_rate;
+  T _dt;
+  T _initial_output;
+  T _output;
+};
+
+namespace Detail {
+
+template <typename T>
+inline bool has_columns(const std::vector<std::vector<T>> &matrix,
+                        std::size_t columns) {
+  for (std::size_t i = 0; i < matrix.size(); i++) {
+    if (matrix[i].size() != columns) {
+      return false;
+    }
+  }
+  return true;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Discrete state-space model with the semantics of the Discrete
+ * State-Space block.
+ *
+ * Every step produces y[k] = C x[k] + D u[k] and then advances the state to
+ * x[k + 1] = A x[k] + B u[k]. With n states, m inputs and p outputs, A is
+ * n x n, B is n x m, C is p x n and D is p x m; all matrices are given as
+ * rows.
+ *
+ * @tparam T Type of the signals.
+ */
+template <typename T> class StateSpace {
+public:
+  typedef std::vector<std::vector<T>> Matrix;
+
+  /**
+   * @param A The state matrix.
+   * @param B The input matrix.
+   * @param C The output matrix.
+   * @param D The feedthrough matrix.
+   * @param initial_state The state x[0]; zero if empty.
+   */
+  StateSpace(const Matrix &A, const Matrix &B, const Matrix &C,
+             const Matrix &D,
+             const std::vector<T> &initial_state = std::vector<T>())
+      : _A(A), _B(B), _C(C), _D(D),
+        _initial_state(initial_state.empty()
+                           ? std::vector<T>(A.size(), static_cast<T>(0))
+                           : initial_state),
+        _state(this->_initial_state) {}
+
+  std::size_t state_count() const { return this->_A.size(); }
+  std::size_t output_count() const { return this->_D.size(); }
+  std::size_t input_count() const {
+    return this->_D.empty() ? 0 : this->_D[0].size();
+  }
+
+  /**
+   * @brief Returns true if all matrix dimensions and the initial state are
+   * consistent.
+   */
+  bool valid() const {
+    std::size_t n = this->state_count();
+    std::size_t m = this->input_count();
+    std::size_t p = this->output_count();
+    return this->_B.size() == n && this->_C.size() == p &&
+           this->_initial_state.size() == n &&
+           Detail::has_columns(this->_A, n) &&
+           Detail::has_columns(this->_B, m) &&
+           Detail::has_columns(this->_C, n) &&
+           Detail::has_columns(this->_D, m);
+  }
+
+  /**
+   * @brief Computes the output for the current input and advances the state.
+   *
+   * @param input The input vector u[k] with input_count() elements.
+   * @return The output vector y[k].
+   */
+  std::vector<T> step(const std::vector<T> &input) {
+    std::size_t n = this->state_count();
+    std::vector<T> output(this->output_count(), static_cast<T>(0));
+    for (std::size_t i = 0; i < output.size(); i++) {
+      for (std::size_t j = 0; j < n; j++) {
+        output[i] += this->_C[i][j] * this->_state[j];
+      }
+      for (std::size_t j = 0; j < input.size(); j++) {
+        output[i] += this->_D[i][j] * input[j];
+      }
+    }
+
+    std::vector<T> next(n, static_cast<T>(0));
+    for (std::size_t i = 0; i < n; i++) {
+      for (std::size_t j = 0; j < n; j++) {
+        next[i] += this->_A[i][j] * this->_state[j];
+      }
+      for (std::size_t j = 0; j < input.size(); j++) {
+        next[i] += this->_B[i][j] * input[j];
+      }
+    }
+    this->_state = next;
+    return output;
+  }
+
+  /**
+   * @brief Restores the initial state and simulates a whole input sequence.
+   *
+   * @param inputs One input vector per sample.
+   * @return One output vector per sample.
+   */
+  Matrix simulate(const Matrix &inputs) {
+    this->reset();
+    Matrix outputs;
+    for (std::size_t k = 0; k < inputs.size(); k++) {
+      outputs.push_back(this->step(inputs[k]));
+    }
+    return outputs;
+  }
+
+  /**
+   * @brief Returns the state x[k + 1] after a step.
+   */
+  const std::vector<T> &state() const { return this->_state; }
+
+  /**
+   * @brief Restores the initial state.
+   */
+  void reset() { this->_state = this->_initial_state; }
+
+protected:
+  Matrix _A;
+  Matrix _B;
+  Matrix _C;
+  Matrix _D;
+  std::vector<T> _initial_state;
+  std::vector<T> _state;
+};
+
+/**
+ * @brief Numerical integration method of a discrete integrator or solver.
+ */
+enum class IntegrationMethod { ForwardEuler, BackwardEuler, Tustin, RK4 };
+
+/**
+ * @brief Returns the name of an integration method, e.g. "Tustin".
+ */
+inline std::string integration_method_name(IntegrationMethod method) {
+  if (method == IntegrationMethod::ForwardEuler) {
+    return "ForwardEuler";
+  } else if (method == IntegrationMethod::BackwardEuler) {
+    return "BackwardEuler";
+  } else if (method == IntegrationMethod::Tustin) {
+    return "Tustin";
+  }
+  return "RK4";
+}
+
+namespace Detail {
+
+/* Solves state = base + weight * derivative(state, input) with Newton
+ * iterations on a finite-difference slope. */
+template <typename T>
+inline T solve_implicit(const std::function<T(T, T)> &derivative, T base,
+                        T weight, T input, T guess) {
+  T state = guess;
+  for (int iteration = 0; iteration < 50; iteration++) {
+    T residual = state - base - weight * derivative(state, input);
+    T step = std::sqrt(std::numeric_limits<T>::epsilon()) *
+             (std::abs(state) + static_cast<T>(1));
+    T shifted =
+        (state + step) - base - weight * derivative(state + step, input);
+    T slope = (shifted - residual) / step;
+    if (slope == static_cast<T>(0)) {
+      break;
+    }
+
+    T next = state - residual / slope;
+    if (next == state) {
+      break;
+    }
+    state = next;
+  }
+  return state;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Integrates the state equation x' = derivative(x, u) over a sampled
+ * input.
+ *
+ * Output k is the state at sample k, so output 0 is `initial_state`. The
+ * input is taken as linear between samples, which gives RK4 its half-step
+ * inputs (u[k] + u[k + 1]) / 2. The implicit methods BackwardEuler and Tustin
+ * are solved with Newton iterations. For a pure integrator (x' = u), RK4 and
+ * Tustin give the same result.
+ *
+ * @param input The input samples u[k].
+ * @param dt The sample time in seconds.
+ * @param method The integration method.
+ * @param initial_state The state at sample 0.
+ * @param derivative The right-hand side of the state equation.
+ * @return The state at every sample.
+ */
+template <typename T>
+inline std::vector<T> integrate(const std::vector<T> &input, T dt,
+                                IntegrationMethod method, T initial_state,
+                                const std::function<T(T, T)> &derivative) {
+  std::vector<T> states;
+  T x = initial_state;
+  T half = static_cast<T>(0.5);
+  for (std::size_t k = 0; k < input.size(); k++) {
+    states.push_back(x);
+    if (k + 1 == input.size()) {
+      break;
+    }
+
+    T u = input[k];
+    T u_next = input[k + 1];
+    if (method == IntegrationMethod::ForwardEuler) {
+      x += dt * derivative(x, u);
+    } else if (method == IntegrationMethod::BackwardEuler) {
+      x = Detail::solve_implicit(derivative, x, dt, u_next, x);
+    } else if (method == IntegrationMethod::Tustin) {
+      T base = x + half * dt * derivative(x, u);
+      x = Detail::solve_implicit(derivative, base, half * dt, u_next, x);
+    } else {
+      T u_half = half * (u + u_next);
+      T k1 = derivative(x, u);
+      T k2 = derivative(x + half * dt * k1, u_half);
+      T k3 = derivative(x + half * dt * k2, u_half);
+      T k4 = derivative(x + dt * k3, u_next);
+      x += dt / static_cast<T>(6) *
+           (k1 + static_cast<T>(2) * k2 + static_cast<T>(2) * k3 + k4);
+    }
+  }
+  return states;
+}
+
+/**
+ * @brief Integrates a sampled input, like the Discrete-Time Integrator block
+ * with a gain of 1.
+ *
+ * @param input The input samples u[k].
+ * @param dt The sample time in seconds.
+ * @param method The integration method.
+ * @param initial_state The output at sample 0.
+ * @return The integrator output at every sample.
+ */
+template <typename T>
+inline std::vector<T> integrate(const std::vector<T> &input, T dt,
+                                IntegrationMethod method,
+                                T initial_state = static_cast<T>(0)) {
+  return integrate<T>(input, dt, method, initial_state,
+                      [](T, T u) { return u; });
+}
+
+/**
+ * @brief How a lookup table treats inputs outside its breakpoints.
+ */
+enum class Extrapolation { Clip, Linear };
+
+namespace Detail {
+
+template <typename T>
+inline std::size_t find_interval(const std::vector<T> &breakpoints, T value) {
+  std::size_t low = 0;
+  std::size_t high = breakpoints.size() - 1;
+  while (high - low > 1) {
+    std::size_t middle = low + (high - low) / 2;
+    if (value < breakpoints[middle]) {
+      high = middle;
+    } else {
+      low = middle;
+    }
+  }
+  return low;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Bilinear interpolation in a two-dimensional lookup table, like the
+ * 2-D Lookup Table block with the interpolation method Linear.
+ *
+ * Breakpoints must be strictly increasing, with at least two per dimension.
+ * With Extrapolation::Clip, inputs outside the breakpoints are clipped to
+ * the first or last breakpoint; with Extrapolation::Linear, the first or
+ * last interval is extended.
+ *
+ * @param row_breakpoints The breakpoints of the first input.
+ * @param column_breakpoints The breakpoints of the second input.
+ * @param table The table values; table[i][j] belongs to row_breakpoints[i]
+ * and column_breakpoints[j].
+ * @param row_input The first input.
+ * @param column_input The second input.
+ * @param extrapolation The treatment of inputs outside the breakpoints.
+ * @return The interpolated value.
+ */
+template <typename T>
+inline T interpolate_2d(const std::vector<T> &row_breakpoints,
+                        const std::vector<T> &column_breakpoints,
+                        const std::vector<std::vector<T>> &table, T row_input,
+                        T column_input,
+                        Extrapolation extrapolation = Extrapolation::Clip) {
+  if (extrapolation == Extrapolation::Clip) {
+    row_input = saturate(row_input, row_breakpoints.front(),
+                         row_breakpoints.back());
+    column_input = saturate(column_input, column_breakpoints.front(),
+                            column_breakpoints.back());
+  }
+
+  std::size_t i = Detail::find_interval(row_breakpoints, row_input);
+  std::size_t j = Detail::find_interval(column_breakpoints, column_input);
+  T u = (row_input - row_breakpoints[i]) /
+        (row_breakpoints[i + 1] - row_breakpoints[i]);
+  T v = (column_input - column_breakpoints[j]) /
+        (column_breakpoints[j + 1] - column_breakpoints[j]);
+
+  T one = static_cast<T>(1);
+  return (one - u) * (one - v) * table[i][j] + (one - u) * v * table[i][j + 1] +
+         u * (one - v) * table[i + 1][j] + u * v * table[i + 1][j + 1];
+}
+
+} // namespace Reference
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_REFERENCE_HPP
@@ -0,0 +1,298 @@
+/**
+ * @file MCAP_tester_reference_runner.hpp
+ * @brief Provides the Tester::ReferenceRunner class template for comparing
+ * generated code against a live external reference implementation.
+ *
+ * Dumped reference data goes stale as soon as the reference model changes.
+ * ReferenceRunner instead runs the reference executable (for example a
+ * Python script or a C++ program) for every comparison: the input samples
+ * are written to a temporary CSV file, which is either redirected to the
+ * standard input of the command or passed to it as a path, and the numbers
+ * the command prints to its standard output, as CSV or JSON, are the
+ * expected values. The command is run through the shell with popen, so it
+ * may contain arguments and pipes.
+ */
+
+#ifndef MCAP_TESTER_REFERENCE_RUNNER_HPP
+#define MCAP_TESTER_REFERENCE_RUNNER_HPP
+
+#include <cstddef>
+#include <cstdio>
+#include <cstdlib>
+#include <fstream>
+#include <iomanip>
+#include <limits>
+#include <sstream>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+#ifndef _WIN32
+#include <sys/wait.h>
+#include <unistd.h>
+#endif
+
+namespace Tester {
+
+/**
+ * @brief How the inputs reach the reference command: as its standard input,
+ * or as the path of a CSV file that replaces the placeholder "{input}" in the
+ * command, or is appended to it if there is no placeholder.
+ */
+enum class ReferenceInput { Stdin, CsvFile };
+
+/**
+ * @brief The format of the standard output of the reference command.
+ *
+ * Csv: one row of comma- or whitespace-separated numbers per line. Json: a
+ * number or a (nested) array of numbers, as accepted by json_to_flat_array.
+ */
+enum class ReferenceOutput { Csv, Json };
+
+namespace ReferenceDetail {
+
+inline bool create_temporary_file(std::string &path) {
+#ifdef _WIN32
+  char *name = _tempnam(nullptr, "mcap");
+  if (name == nullptr) {
+    return false;
+  }
+  path = name;
+  std::free(name);
+  return true;
+#else
+  const char *directory = std::getenv("TMPDIR");
+  std::string pattern = std::string(directory != nullptr ? directory : "/tmp") +
+                        "/mcap_reference_XXXXXX";
+  std::vector<char> name(pattern.begin(), pattern.end());
+  name.push_back('\0');
+  int descriptor = mkstemp(name.data());
+  if (descriptor < 0) {
+    return false;
+  }
+  close(descriptor);
+  path = name.data();
+  return true;
+#endif
+}
+
+template <typename T>
+inline bool parse_csv(const std::string &text, FlatArray<T> &values,
+                      std::string &error) {
+  FlatArray<T> parsed;
+  std::size_t rows = 0;
+  std::size_t columns = 0;
+  std::istringstream lines(text);
+  std::string line;
+  while (std::getline(lines, line)) {
+    for (std::size_t i = 0; i < line.size(); i++) {
+      if (line[i] == ',' || line[i] == ';' || line[i] == '\r') {
+        line[i] = ' ';
+      }
+    }
+
+    std::istringstream fields(line);
+    std::string field;
+    std::size_t count = 0;
+    while (fields >> field) {
+      char *end = nullptr;
+      double value = std::strtod(field.c_str(), &end);
+      if (*end != '\0') {
+        error = "Invalid number \"" + field + "\" in the reference output.";
+        return false;
+      }
+      parsed.data.push_back(static_cast<T>(value));
+      count++;
+    }
+    if (count == 0) {
+      continue;
+    }
+    if (rows > 0 && count != columns) {
+      parsed.ragged = true;
+    }
+    columns = count;
+    rows++;
+  }
+
+  parsed.shape.push_back(rows);
+  parsed.shape.push_back(columns);
+  values = parsed;
+  return true;
+}
+
+} // namespace ReferenceDetail
+
+/**
+ * @brief Runs an external reference command and compares its output with
+ * actual values.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> class ReferenceRunner {
+public:
+  /**
+   * @brief Creates a runner for a shell command; the inputs are passed on
+   * its standard input and its output is read as CSV.
+   *
+   * @param command The command line, e.g. "python3 reference/filter.py".
+   */
+  explicit ReferenceRunner(const std::string &command)
+      : _command(command), _input(ReferenceInput::Stdin),
+        _output(ReferenceOutput::Csv) {}
+
+  /**
+   * @brief Sets how the inputs reach the command.
+   */
+  void set_input_mode(ReferenceInput input) { this->_input = input; }
+
+  /**
+   * @brief Sets the format of the output of the command.
+   */
+  void set_output_format(ReferenceOutput output) { this->_output = output; }
+
+  /**
+   * @brief Runs the command on a set of inputs.
+   *
+   * Every element of `inputs` becomes one CSV line, e.g. the input channels
+   * of one sample.
+   *
+   * @param inputs The input rows.
+   * @param output Receives the parsed output of the command.
+   * @param error Receives a description of the problem if the command could
+   * not be run, exited with a nonzero status or printed invalid output.
+   * @return true on success.
+   */
+  bool run(const std::vector<std::vector<T>> &inputs, FlatArray<T> &output,
+           std::string &error) const {
+    std::string path;
+    if (!ReferenceDetail::create_temporary_file(path)) {
+      error = "Cannot create a temporary input file.";
+      return false;
+    }
+
+    bool success = this->_write_inputs(path, inputs, error) &&
+                   this->_execute(path, output, error);
+    std::remove(path.c_str());
+    return success;
+  }
+
+  /**
+   * @brief Runs the command on a set of inputs and checks actual values
+   * against its output within a tolerance.
+   *
+   * As in expect_near_matfile, singleton dimensions are removed from both
+   * sides before comparing. A command that cannot be run is reported as a
+   * failure.
+   *
+   * @tparam Container Any container accepted by make_flat_array.
+   * @param tester The tester that receives the verdict.
+   * @param actual The actual values of the generated code.
+   * @param inputs The input rows passed to the reference.
+   * @param tolerance The maximum allowed difference between corresponding
+   * elements.
+   * @param message The message to display in case of failure.
+   */
+  template <typename Container>
+  void expect_near(MCAPTester<T> &tester, const Container &actual,
+                   const std::vector<std::vector<T>> &inputs, T tolerance,
+                   const std::string &message) const {
+    FlatArray<T> expected;
+    std::string error;
+    if (!this->run(inputs, expected, error)) {
+      tester.expect_true(false, message, error,
+                         FailureCategory::Infrastructure);
+      return;
+    }
+
+    tester.expect_near(squeeze_flat_array(make_flat_array(actual)),
+                       squeeze_flat_array(expected), tolerance, message);
+  }
+
+protected:
+  bool _write_inputs(const std::string &path,
+                     const std::vector<std::vector<T>> &inputs,
+                     std::string &error) const {
+    std::ofstream file(path.c_str());
+    file << std::setprecision(std::numeric_limits<T>::max_digits10);
+    for (std::size_t i = 0; i < inputs.size(); i++) {
+      for (std::size_t j = 0; j < inputs[i].size(); j++) {
+        file << (j == 0 ? "" : ",") << inputs[i][j];
+      }
+      file << "\n";
+    }
+
+    if (!file) {
+      error = "Cannot write the input file " + path + ".";
+      return false;
+    }
+    return true;
+  }
+
+  bool _execute(const std::string &path, FlatArray<T> &output,
+                std::string &error) const {
+    std::string quoted = "\"" + path + "\"";
+    std::string command = this->_command;
+    if (this->_input == ReferenceInput::Stdin) {
+      command += " < " + quoted;
+    } else if (command.find("{input}") != std::string::npos) {
+      command.replace(command.find("{input}"), 7, quoted);
+    } else {
+      command += " " + quoted;
+    }
+
+#ifdef _WIN32
+    FILE *pipe = _popen(command.c_str(), "r");
+#else
+    FILE *pipe = popen(command.c_str(), "r");
+#endif
+    if (pipe == nullptr) {
+      error = "Cannot run the reference command: " + this->_command;
+      return false;
+    }
+
+    std::string text;
+    char buffer[4096];
+    std::size_t count = 0;
+    while ((count = std::fread(buffer, 1, sizeof(buffer), pipe)) > 0) {
+      text.append(buffer, count);
+    }
+
+#ifdef _WIN32
+    int status = _pclose(pipe);
+#else
+    int status = pclose(pipe);
+    if (status != -1 && WIFEXITED(status)) {
+      status = WEXITSTATUS(status);
+    }
+#endif
+    if (status != 0) {
+      error = "The reference command exited with status " +
+              std::to_string(status) + ": " + this->_command;
+      return false;
+    }
+
+    if (this->_output == ReferenceOutput::Csv) {
+      return ReferenceDetail::parse_csv(text, output, error);
+    }
+
+    JSON::Value value;
+    if (!JSON::parse(text, value, error)) {
+      error = "Invalid JSON in the reference output: " + error;
+      return false;
+    }
+    if (!json_to_flat_array(value, output)) {
+      error = "The reference output is not a numeric array.";
+      return false;
+    }
+    return true;
+  }
+
+  std::string _command;
+  ReferenceInput _input;
+  ReferenceOutput _output;
+};
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_REFERENCE_RUNNER_HPP
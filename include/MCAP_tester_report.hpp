@@ -0,0 +1,1351 @@
+/**
+ * @file MCAP_tester_report.hpp
+ * @brief Provides the assertion records and result reports of MCAP_tester.
+ *
+ * Every assertion made through Tester::MCAPTester is recorded as an
+ * AssertionRecord. The records of one test executable can be written to a
+ * JSON report, and the reports of a suite split across many executables (for
+ * example one per generated model) can be merged into a single
+ * CombinedReport that gives one consolidated verdict for a release gate.
+ * Report::diff_reports lists what changed between the reports of two runs.
+ *
+ * A report file has the following layout:
+ *
+ *   {
+ *     "schema_version": 8,
+ *     "name": "model_a",
+ *     "metadata": {"target": "cortex-m7"},
+ *     "summary": {"assertions": 3, "failures": 1, "warnings": 0,
+ *                 "passed": false,
+ *                 "categories": {"tolerance": 1, "structural": 0,
+ *                                "timing": 0, "resource": 0,
+ *                                "infrastructure": 0, "assertion": 0}},
+ *     "assertions": [
+ *       {"message": "...", "status": "failure", "detail": "...",
+ *        "rationale": "...", "location": "test_model_a.cpp:42",
+ *        "error": 0.02, "tolerance": 0.01, "relative_error": 0.01,
+ *        "category": "tolerance"}
+ *     ]
+ *   }
+ *
+ * Schema history:
+ *   1: Initial layout without "summary.passed" and "rationale".
+ *   2: Adds "summary.passed" and the tolerance rationale of every assertion.
+ *   3: Adds the source location of every assertion made through the
+ *      MCAP_CHECK macros; it is empty for other assertions.
+ *   4: Adds the measured error and the tolerance of every tolerance
+ *      comparison; both are null for other assertions.
+ *   5: Adds the failure category of every failure, null for other
+ *      assertions and for failures of older reports, and the number of
+ *      failures per category to the summary.
+ *   6: Adds the measured error relative to the expected magnitude of every
+ *      element-wise tolerance comparison; it is null for other assertions
+ *      and for errors that are not finite.
+ *   7: Adds the metadata of the run, an object of strings that is empty
+ *      unless set with MCAPTester::set_metadata.
+ *   8: Adds the category "assertion" for failed conditions that name no
+ *      other category, and its count to the summary.
+ *
+ * Compatibility guarantee: a new schema version only adds fields; existing
+ * fields are never removed, renamed or given a different type. Readers of
+ * this header accept every older version by upgrading it with
+ * Report::upgrade, and reject reports written by a newer version.
+ */
+
+#ifndef MCAP_TESTER_REPORT_HPP
+#define MCAP_TESTER_REPORT_HPP
+
+#include <cstddef>
+#include <limits>
+#include <map>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "MCAP_tester_comparison.hpp"
+#include "MCAP_tester_json.hpp"
+
+namespace Tester {
+
+/**
+ * @brief What kind of problem a failure indicates, so CI can route it.
+ *
+ * Tolerance failures are numeric deviations, the default for comparisons.
+ * Structural failures are mismatched shapes, sizes or layouts and invalid
+ * test configurations. Timing failures are exceeded run-time budgets.
+ * Resource failures are exhausted memory, stack or other budgets of the
+ * target. Infrastructure failures are problems of the test environment,
+ * e.g. unreadable fixtures, unwritable files or aborted tests. Assertion
+ * failures are failed conditions checked with expect_true that name no
+ * other category. Assertions that did not fail have the category None.
+ */
+enum class FailureCategory {
+  None,
+  Tolerance,
+  Structural,
+  Timing,
+  Resource,
+  Infrastructure,
+  Assertion
+};
+
+/**
+ * @brief The outcome of a single assertion.
+ *
+ * If `measured`, the assertion was a tolerance comparison: `error` is the
+ * largest absolute difference it found and `tolerance` the failure tolerance
+ * it applied. `relative_error` is the largest difference relative to the
+ * magnitude of the expected value, i.e. the smallest relative tolerance the
+ * comparison passes with, or NaN if it is not known.
+ */
+struct AssertionRecord {
+  AssertionRecord()
+      : message(), status(ComparisonStatus::Pass), detail(), rationale(),
+        location(), measured(false), error(0.0), tolerance(0.0),
+        category(FailureCategory::None),
+        relative_error(std::numeric_limits<double>::quiet_NaN()) {}
+
+  AssertionRecord(const std::string &message, ComparisonStatus status,
+                  const std::string &detail,
+                  const std::string &rationale = std::string(),
+                  const std::string &location = std::string())
+      : message(message), status(status), detail(detail), rationale(rationale),
+        location(location), measured(false), error(0.0), tolerance(0.0),
+        category(FailureCategory::None),
+        relative_error(std::numeric_limits<double>::quiet_NaN()) {}
+
+  std::string message;
+  ComparisonStatus status;
+  std::string detail;
+  std::string rationale;
+  std::string location;
+  bool measured;
+  double error;
+  double tolerance;
+  FailureCategory category;
+  double relative_error;
+};
+
+/**
+ * @brief A tolerance comparison whose error grew against a previous run.
+ */
+struct ErrorDrift {
+  ErrorDrift() : message(), previous_error(0.0), current_error(0.0) {}
+
+  std::string message;
+  double previous_error;
+  double current_error;
+};
+
+/**
+ * @brief The allowed growth of the error of one tolerance comparison, as a
+ * fraction per release and cumulative since the baseline release, e.g. 0.05
+ * and 0.15.
+ */
+struct RegressionBudget {
+  RegressionBudget()
+      : message(), max_release_growth(0.0), max_cumulative_growth(0.0) {}
+
+  RegressionBudget(const std::string &message, double max_release_growth,
+                   double max_cumulative_growth)
+      : message(message), max_release_growth(max_release_growth),
+        max_cumulative_growth(max_cumulative_growth) {}
+
+  std::string message;
+  double max_release_growth;
+  double max_cumulative_growth;
+};
+
+/**
+ * @brief One line of a regression budget ledger.
+ *
+ * The baseline is the oldest and the previous release the latest release in
+ * the history that measured the comparison. A growth is the relative change
+ * of the current error, infinite for an error growing from zero. `history`
+ * is false for a comparison the history does not contain yet and `current`
+ * false for one the current run did not measure; neither exhausts a budget.
+ */
+struct LedgerEntry {
+  LedgerEntry()
+      : budget(), history(false), current(false), baseline_error(0.0),
+        previous_error(0.0), current_error(0.0), release_growth(0.0),
+        cumulative_growth(0.0), exhausted(false) {}
+
+  RegressionBudget budget;
+  bool history;
+  bool current;
+  double baseline_error;
+  double previous_error;
+  double current_error;
+  double release_growth;
+  double cumulative_growth;
+  bool exhausted;
+};
+
+/**
+ * @brief One assertion in the side-by-side comparison of two
+ * implementations.
+ *
+ * `in_a` and `in_b` tell which implementation made the assertion. The
+ * errors are only meaningful if `measured`, i.e. if both made it as a
+ * tolerance comparison. `better` is 'a', 'b' or '=' for a tie, decided by
+ * the lower error, or by the status (pass before warning before failure)
+ * for assertions without a measured error; it is ' ' unless both made the
+ * assertion.
+ */
+struct ImplementationRow {
+  ImplementationRow()
+      : message(), in_a(false), in_b(false), status_a(ComparisonStatus::Pass),
+        status_b(ComparisonStatus::Pass), measured(false), error_a(0.0),
+        error_b(0.0), better(' ') {}
+
+  std::string message;
+  bool in_a;
+  bool in_b;
+  ComparisonStatus status_a;
+  ComparisonStatus status_b;
+  bool measured;
+  double error_a;
+  double error_b;
+  char better;
+};
+
+/**
+ * @brief Side-by-side comparison of the results of two implementations,
+ * e.g. the code of an old and a new code-generator version.
+ */
+struct ImplementationComparison {
+  ImplementationComparison() : name_a(), name_b(), rows() {}
+
+  /**
+   * @brief Returns the number of assertions in which one implementation is
+   * better: 'a' or 'b'.
+   */
+  std::size_t count_better(char implementation) const {
+    std::size_t count = 0;
+    for (std::size_t i = 0; i < this->rows.size(); i++) {
+      count += this->rows[i].better == implementation ? 1 : 0;
+    }
+    return count;
+  }
+
+  std::string name_a;
+  std::string name_b;
+  std::vector<ImplementationRow> rows;
+};
+
+/**
+ * @brief The changes between the results of two runs, e.g. of two nightly
+ * builds.
+ *
+ * A newly failing assertion fails in the current run but did not fail in
+ * the previous one, or is new; a newly passing assertion failed in the
+ * previous run and no longer fails. `changed_errors` lists the tolerance
+ * comparisons of both runs whose error changed by more than the threshold
+ * of the diff, in either direction. `added` and `removed` list the
+ * assertions made by only one of the runs.
+ */
+struct ReportDiff {
+  ReportDiff()
+      : newly_failing(), newly_passing(), changed_errors(), added(),
+        removed() {}
+
+  /**
+   * @brief Returns true if the runs did not differ.
+   */
+  bool empty() const {
+    return this->newly_failing.empty() && this->newly_passing.empty() &&
+           this->changed_errors.empty() && this->added.empty() &&
+           this->removed.empty();
+  }
+
+  std::vector<std::string> newly_failing;
+  std::vector<std::string> newly_passing;
+  std::vector<ErrorDrift> changed_errors;
+  std::vector<std::string> added;
+  std::vector<std::string> removed;
+};
+
+/**
+ * @brief Consolidated verdict over several report files.
+ *
+ * `failed_reports` lists the names of the reports that contain at least one
+ * failure, and `errors` describes report files that could not be read. A
+ * report that cannot be read counts as failed.
+ */
+struct CombinedReport {
+  CombinedReport()
+      : report_count(0), assertion_count(0), failure_count(0),
+        warning_count(0), failed_reports(), errors(), assertions() {}
+
+  bool passed() const {
+    return this->failure_count == 0 && this->errors.empty();
+  }
+
+  std::size_t report_count;
+  std::size_t assertion_count;
+  std::size_t failure_count;
+  std::size_t warning_count;
+  std::vector<std::string> failed_reports;
+  std::vector<std::string> errors;
+  std::vector<AssertionRecord> assertions;
+};
+
+namespace Report {
+
+static const int SCHEMA_VERSION = 8;
+
+/**
+ * @brief Returns "pass", "warning" or "failure".
+ */
+inline std::string status_to_string(ComparisonStatus status) {
+  if (status == ComparisonStatus::Failure) {
+    return "failure";
+  } else if (status == ComparisonStatus::Warning) {
+    return "warning";
+  }
+  return "pass";
+}
+
+/**
+ * @brief Parses a status written by status_to_string.
+ *
+ * @return true on success, false if the text is not a known status.
+ */
+inline bool status_from_string(const std::string &text,
+                               ComparisonStatus &status) {
+  if (text == "pass") {
+    status = ComparisonStatus::Pass;
+  } else if (text == "warning") {
+    status = ComparisonStatus::Warning;
+  } else if (text == "failure") {
+    status = ComparisonStatus::Failure;
+  } else {
+    return false;
+  }
+  return true;
+}
+
+/**
+ * @brief Returns "tolerance", "structural", "timing", "resource",
+ * "infrastructure", "assertion", or an empty string for
+ * FailureCategory::None.
+ */
+inline std::string category_to_string(FailureCategory category) {
+  switch (category) {
+  case FailureCategory::Tolerance:
+    return "tolerance";
+  case FailureCategory::Structural:
+    return "structural";
+  case FailureCategory::Timing:
+    return "timing";
+  case FailureCategory::Resource:
+    return "resource";
+  case FailureCategory::Infrastructure:
+    return "infrastructure";
+  case FailureCategory::Assertion:
+    return "assertion";
+  default:
+    return "";
+  }
+}
+
+/**
+ * @brief Parses a category written by category_to_string.
+ *
+ * @return true on success, false if the text is not a known category.
+ */
+inline bool category_from_string(const std::string &text,
+                                 FailureCategory &category) {
+  static const FailureCategory categories[] = {
+      FailureCategory::Tolerance,      FailureCategory::Structural,
+      FailureCategory::Timing,         FailureCategory::Resource,
+      FailureCategory::Infrastructure, FailureCategory::Assertion};
+  for (std::size_t i = 0; i < 6; i++) {
+    if (text == category_to_string(categories[i])) {
+      category = categories[i];
+      return true;
+    }
+  }
+  return false;
+}
+
+/**
+ * @brief Returns the number of failures of a category.
+ */
+inline std::size_t count_failures(const std::vector<AssertionRecord> &records,
+                                  FailureCategory category) {
+  std::size_t count = 0;
+  for (std::size_t i = 0; i < records.size(); i++) {
+    count += (records[i].status == ComparisonStatus::Failure &&
+              records[i].category == category)
+                 ? 1
+                 : 0;
+  }
+  return count;
+}
+
+/**
+ * @brief Summarizes the failures by category, e.g. "2 tolerance, 1
+ * infrastructure", or returns an empty string if nothing failed.
+ *
+ * Failures without a category, e.g. from reports older than schema version
+ * 5, are counted as "uncategorized".
+ */
+inline std::string
+format_category_summary(const std::vector<AssertionRecord> &records) {
+  static const FailureCategory categories[] = {
+      FailureCategory::Tolerance,      FailureCategory::Structural,
+      FailureCategory::Timing,         FailureCategory::Resource,
+      FailureCategory::Infrastructure, FailureCategory::Assertion,
+      FailureCategory::None};
+  std::string text;
+  for (std::size_t i = 0; i < 7; i++) {
+    std::size_t count = count_failures(records, categories[i]);
+    if (count == 0) {
+      continue;
+    }
+    std::string name = categories[i] == FailureCategory::None
+                           ? "uncategorized"
+                           : category_to_string(categories[i]);
+    text += (text.empty() ? "" : ", ") + std::to_string(count) + " " + name;
+  }
+  return text;
+}
+
+/**
+ * @brief Builds the JSON report of a list of assertion records.
+ *
+ * @param name The name of the report, e.g. the test executable or model.
+ * @param records The assertion records to include.
+ * @param metadata Information about the run, e.g. the target.
+ * @return The report as a JSON object.
+ */
+inline JSON::Value
+to_json(const std::string &name, const std::vector<AssertionRecord> &records,
+        const std::map<std::string, std::string> &metadata =
+            std::map<std::string, std::string>()) {
+  std::size_t failures = 0;
+  std::size_t warnings = 0;
+  JSON::Value assertions = JSON::Value::array();
+  for (std::size_t i = 0; i < records.size(); i++) {
+    if (records[i].status == ComparisonStatus::Failure) {
+      failures++;
+    } else if (records[i].status == ComparisonStatus::Warning) {
+      warnings++;
+    }
+
+    JSON::Value entry = JSON::Value::object();
+    entry.set("message", JSON::Value(records[i].message));
+    entry.set("status", JSON::Value(status_to_string(records[i].status)));
+    entry.set("detail", JSON::Value(records[i].detail));
+    entry.set("rationale", JSON::Value(records[i].rationale));
+    entry.set("location", JSON::Value(records[i].location));
+    entry.set("error", records[i].measured ? JSON::Value(records[i].error)
+                                           : JSON::Value());
+    entry.set("tolerance", records[i].measured
+                               ? JSON::Value(records[i].tolerance)
+                               : JSON::Value());
+    entry.set("category",
+              records[i].category == FailureCategory::None
+                  ? JSON::Value()
+                  : JSON::Value(category_to_string(records[i].category)));
+    entry.set("relative_error", records[i].measured
+                                    ? JSON::Value(records[i].relative_error)
+                                    : JSON::Value());
+    assertions.push_back(entry);
+  }
+
+  static const FailureCategory categorized[] = {
+      FailureCategory::Tolerance,      FailureCategory::Structural,
+      FailureCategory::Timing,         FailureCategory::Resource,
+      FailureCategory::Infrastructure, FailureCategory::Assertion};
+  JSON::Value categories = JSON::Value::object();
+  for (std::size_t c = 0; c < 6; c++) {
+    categories.set(category_to_string(categorized[c]),
+                   JSON::Value(count_failures(records, categorized[c])));
+  }
+
+  JSON::Value summary = JSON::Value::object();
+  summary.set("assertions", JSON::Value(records.size()));
+  summary.set("failures", JSON::Value(failures));
+  summary.set("warnings", JSON::Value(warnings));
+  summary.set("passed", JSON::Value(failures == 0));
+  summary.set("categories", categories);
+
+  JSON::Value entries = JSON::Value::object();
+  for (std::map<std::string, std::string>::const_iterator it =
+           metadata.begin();
+       it != metadata.end(); ++it) {
+    entries.set(it->first, JSON::Value(it->second));
+  }
+
+  JSON::Value report = JSON::Value::object();
+  report.set("schema_version", JSON::Value(SCHEMA_VERSION));
+  report.set("name", JSON::Value(name));
+  report.set("metadata", entries);
+  report.set("summary", summary);
+  report.set("assertions", assertions);
+  return report;
+}
+
+/**
+ * @brief Upgrades a report of any supported schema version to the current
+ * version.
+ *
+ * Reports without a schema_version field are treated as version 1.
+ *
+ * @param report The report to upgrade.
+ * @param upgraded Receives the report in the current schema version.
+ * @param error Receives a description of the problem on failure.
+ * @return true on success, false if the report is not an object or was
+ * written by a newer, unsupported version.
+ */
+inline bool upgrade(const JSON::Value &report, JSON::Value &upgraded,
+                    std::string &error) {
+  if (!report.is_object()) {
+    error = "Not a report.";
+    return false;
+  }
+
+  int version = 1;
+  if (report.has("schema_version")) {
+    const JSON::Value &field = report.get("schema_version");
+    if (!field.is_number() || field.as_number() < 1 ||
+        field.as_number() != static_cast<int>(field.as_number())) {
+      error = "Invalid schema version.";
+      return false;
+    }
+    version = static_cast<int>(field.as_number());
+  }
+  if (version > SCHEMA_VERSION) {
+    error = "Unsupported schema version " + std::to_string(version) + ".";
+    return false;
+  }
+
+  JSON::Value result = report;
+  if (version < 2) {
+    JSON::Value summary = result.get("summary");
+    if (summary.is_object()) {
+      const JSON::Value &failures = summary.get("failures");
+      bool passed = !failures.is_number() || failures.as_number() == 0.0;
+      summary.set("passed", JSON::Value(passed));
+      result.set("summary", summary);
+    }
+
+    const JSON::Value &assertions = report.get("assertions");
+    if (assertions.is_array()) {
+      JSON::Value upgraded_assertions = JSON::Value::array();
+      for (std::size_t i = 0; i < assertions.size(); i++) {
+        JSON::Value entry = assertions[i];
+        if (entry.is_object()) {
+          entry.set("rationale", JSON::Value(""));
+        }
+        upgraded_assertions.push_back(entry);
+      }
+      result.set("assertions", upgraded_assertions);
+    }
+  }
+  if (version < 3) {
+    const JSON::Value &assertions = result.get("assertions");
+    if (assertions.is_array()) {
+      JSON::Value upgraded_assertions = JSON::Value::array();
+      for (std::size_t i = 0; i < assertions.size(); i++) {
+        JSON::Value entry = assertions[i];
+        if (entry.is_object()) {
+          entry.set("location", JSON::Value(""));
+        }
+        upgraded_assertions.push_back(entry);
+      }
+      result.set("assertions", upgraded_assertions);
+    }
+  }
+  if (version < 4) {
+    const JSON::Value &assertions = result.get("assertions");
+    if (assertions.is_array()) {
+      JSON::Value upgraded_assertions = JSON::Value::array();
+      for (std::size_t i = 0; i < assertions.size(); i++) {
+        JSON::Value entry = assertions[i];
+        if (entry.is_object()) {
+          entry.set("error", JSON::Value());
+          entry.set("tolerance", JSON::Value());
+        }
+        upgraded_assertions.push_back(entry);
+      }
+      result.set("assertions", upgraded_assertions);
+    }
+  }
+  if (version < 5) {
+    const JSON::Value &assertions = result.get("assertions");
+    if (assertions.is_array()) {
+      JSON::Value upgraded_assertions = JSON::Value::array();
+      for (std::size_t i = 0; i < assertions.size(); i++) {
+        JSON::Value entry = assertions[i];
+        if (entry.is_object()) {
+          entry.set("category", JSON::Value());
+        }
+        upgraded_assertions.push_back(entry);
+      }
+      result.set("assertions", upgraded_assertions);
+    }
+  }
+  if (version < 6) {
+    const JSON::Value &assertions = result.get("assertions");
+    if (assertions.is_array()) {
+      JSON::Value upgraded_assertions = JSON::Value::array();
+      for (std::size_t i = 0; i < assertions.size(); i++) {
+        JSON::Value entry = assertions[i];
+        if (entry.is_object()) {
+          entry.set("relative_error", JSON::Value());
+        }
+        upgraded_assertions.push_back(entry);
+      }
+      result.set("assertions", upgraded_assertions);
+    }
+  }
+  if (version < 7) {
+    result.set("metadata", JSON::Value::object());
+  }
+  if (version < 8) {
+    JSON::Value summary = result.get("summary");
+    if (summary.get("categories").is_object()) {
+      JSON::Value categories = summary.get("categories");
+      categories.set("assertion", JSON::Value(0));
+      summary.set("categories", categories);
+      result.set("summary", summary);
+    }
+  }
+
+  result.set("schema_version", JSON::Value(SCHEMA_VERSION));
+  upgraded = result;
+  return true;
+}
+
+/**
+ * @brief Reads the name and assertion records back from a JSON report.
+ *
+ * Reports of older schema versions are upgraded first.
+ *
+ * @param report The report as produced by to_json.
+ * @param name Receives the name of the report.
+ * @param records Receives the assertion records.
+ * @param error Receives a description of the problem on failure.
+ * @return true on success, false if the report is malformed.
+ */
+inline bool from_json(const JSON::Value &report, std::string &name,
+                      std::vector<AssertionRecord> &records,
+                      std::string &error) {
+  JSON::Value current;
+  if (!upgrade(report, current, error)) {
+    return false;
+  }
+  if (!current.get("name").is_string() ||
+      !current.get("assertions").is_array()) {
+    error = "Not a report.";
+    return false;
+  }
+
+  const JSON::Value &assertions = current.get("assertions");
+  std::vector<AssertionRecord> result;
+  for (std::size_t i = 0; i < assertions.size(); i++) {
+    const JSON::Value &entry = assertions[i];
+    AssertionRecord record;
+    if (!entry.get("message").is_string() ||
+        !entry.get("status").is_string() ||
+        !status_from_string(entry.get("status").as_string(), record.status)) {
+      error = "Malformed assertion " + std::to_string(i) + ".";
+      return false;
+    }
+    record.message = entry.get("message").as_string();
+    if (entry.get("detail").is_string()) {
+      record.detail = entry.get("detail").as_string();
+    }
+    if (entry.get("rationale").is_string()) {
+      record.rationale = entry.get("rationale").as_string();
+    }
+    if (entry.get("location").is_string()) {
+      record.location = entry.get("location").as_string();
+    }
+    if (entry.get("error").is_number() && entry.get("tolerance").is_number()) {
+      record.measured = true;
+      record.error = entry.get("error").as_number();
+      record.tolerance = entry.get("tolerance").as_number();
+      if (entry.get("relative_error").is_number()) {
+        record.relative_error = entry.get("relative_error").as_number();
+      }
+    }
+    if (entry.get("category").is_string() &&
+        !category_from_string(entry.get("category").as_string(),
+                              record.category)) {
+      error = "Malformed assertion " + std::to_string(i) + ".";
+      return false;
+    }
+    result.push_back(record);
+  }
+
+  name = current.get("name").as_string();
+  records = result;
+  return true;
+}
+
+/**
+ * @brief Finds the tolerance comparisons whose error grew against the
+ * results of a previous run, e.g. the archived results of the last commit.
+ *
+ * Comparisons are matched by message; those missing from either side are
+ * ignored. A comparison drifts if its error exceeds the previous error by
+ * more than the fraction `max_growth` of it, so an error growing from zero
+ * always drifts.
+ *
+ * @param current The records of the current run.
+ * @param previous The records of the previous run.
+ * @param max_growth The allowed relative growth of an error, e.g. 0.1.
+ * @return The drifted comparisons, in the order of `current`.
+ */
+inline std::vector<ErrorDrift>
+find_error_drift(const std::vector<AssertionRecord> &current,
+                 const std::vector<AssertionRecord> &previous,
+                 double max_growth) {
+  std::vector<ErrorDrift> drifts;
+  for (std::size_t i = 0; i < current.size(); i++) {
+    if (!current[i].measured) {
+      continue;
+    }
+    for (std::size_t j = 0; j < previous.size(); j++) {
+      if (!previous[j].measured || previous[j].message != current[i].message) {
+        continue;
+      }
+      if (current[i].error > previous[j].error * (1.0 + max_growth)) {
+        ErrorDrift drift;
+        drift.message = current[i].message;
+        drift.previous_error = previous[j].error;
+        drift.current_error = current[i].error;
+        drifts.push_back(drift);
+      }
+      break;
+    }
+  }
+  return drifts;
+}
+
+namespace Detail {
+
+inline const AssertionRecord *
+find_measured(const std::vector<AssertionRecord> &records,
+              const std::string &message) {
+  for (std::size_t i = 0; i < records.size(); i++) {
+    if (records[i].measured && records[i].message == message) {
+      return &records[i];
+    }
+  }
+  return nullptr;
+}
+
+inline double growth(double from, double to) {
+  if (from > 0.0) {
+    return (to - from) / from;
+  }
+  return to > 0.0 ? std::numeric_limits<double>::infinity() : 0.0;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Checks the current results against regression budgets over the
+ * stored results of earlier releases.
+ *
+ * A budget is exhausted if the error grew against the previous release by
+ * more than `max_release_growth` or against the baseline release by more
+ * than `max_cumulative_growth`.
+ *
+ * @param history The records of earlier releases, oldest first, e.g. read
+ * with load_history.
+ * @param current The records of the current run.
+ * @param budgets The budgets, one per comparison message.
+ * @return One ledger entry per budget, in the order of `budgets`.
+ */
+inline std::vector<LedgerEntry>
+check_regression_budgets(const std::vector<std::vector<AssertionRecord>>
+                             &history,
+                         const std::vector<AssertionRecord> &current,
+                         const std::vector<RegressionBudget> &budgets) {
+  std::vector<LedgerEntry> ledger;
+  for (std::size_t b = 0; b < budgets.size(); b++) {
+    LedgerEntry entry;
+    entry.budget = budgets[b];
+    for (std::size_t r = 0; r < history.size(); r++) {
+      const AssertionRecord *record =
+          Detail::find_measured(history[r], budgets[b].message);
+      if (record == nullptr) {
+        continue;
+      }
+      if (!entry.history) {
+        entry.baseline_error = record->error;
+      }
+      entry.previous_error = record->error;
+      entry.history = true;
+    }
+
+    const AssertionRecord *record =
+        Detail::find_measured(current, budgets[b].message);
+    if (record != nullptr) {
+      entry.current = true;
+      entry.current_error = record->error;
+    }
+    if (entry.history && entry.current) {
+      entry.release_growth =
+          Detail::growth(entry.previous_error, entry.current_error);
+      entry.cumulative_growth =
+          Detail::growth(entry.baseline_error, entry.current_error);
+      entry.exhausted =
+          entry.release_growth > budgets[b].max_release_growth ||
+          entry.cumulative_growth > budgets[b].max_cumulative_growth;
+    }
+    ledger.push_back(entry);
+  }
+  return ledger;
+}
+
+namespace Detail {
+
+inline std::string pad(const std::string &text, std::size_t width) {
+  return text.size() < width ? text + std::string(width - text.size(), ' ')
+                             : text;
+}
+
+inline int status_rank(ComparisonStatus status) {
+  return status == ComparisonStatus::Pass
+             ? 0
+             : (status == ComparisonStatus::Warning ? 1 : 2);
+}
+
+inline std::string implementation_cell(const ImplementationRow &row,
+                                       bool b) {
+  if (!(b ? row.in_b : row.in_a)) {
+    return "-";
+  }
+  if (row.measured) {
+    return format_number(b ? row.error_b : row.error_a);
+  }
+  return status_to_string(b ? row.status_b : row.status_a);
+}
+
+inline std::string growth_cell(double growth, double budget) {
+  return (growth >= 0.0 ? "+" : "") + format_number(growth * 100.0) +
+         "% of " + format_number(budget * 100.0) + "%";
+}
+
+} // namespace Detail
+
+/**
+ * @brief Compares the results of two implementations assertion by
+ * assertion, e.g. the records of the same tests run against the code of an
+ * old and a new code-generator version.
+ *
+ * Assertions are matched by message; every assertion of either side gets a
+ * row, in the order of `results_a` followed by those only in `results_b`.
+ *
+ * @param name_a The name of the first implementation.
+ * @param results_a The records of the first implementation.
+ * @param name_b The name of the second implementation.
+ * @param results_b The records of the second implementation.
+ * @return The side-by-side comparison.
+ */
+inline ImplementationComparison
+compare_implementations(const std::string &name_a,
+                        const std::vector<AssertionRecord> &results_a,
+                        const std::string &name_b,
+                        const std::vector<AssertionRecord> &results_b) {
+  ImplementationComparison comparison;
+  comparison.name_a = name_a;
+  comparison.name_b = name_b;
+  std::vector<bool> matched(results_b.size(), false);
+  for (std::size_t i = 0; i < results_a.size(); i++) {
+    ImplementationRow row;
+    row.message = results_a[i].message;
+    row.in_a = true;
+    row.status_a = results_a[i].status;
+    row.error_a = results_a[i].error;
+    for (std::size_t j = 0; j < results_b.size(); j++) {
+      if (!matched[j] && results_b[j].message == row.message) {
+        matched[j] = true;
+        row.in_b = true;
+        row.status_b = results_b[j].status;
+        row.error_b = results_b[j].error;
+        row.measured = results_a[i].measured && results_b[j].measured;
+        break;
+      }
+    }
+
+    if (row.in_b) {
+      double a = row.measured ? row.error_a
+                              : Detail::status_rank(row.status_a);
+      double b = row.measured ? row.error_b
+                              : Detail::status_rank(row.status_b);
+      row.better = a < b ? 'a' : (b < a ? 'b' : '=');
+    }
+    comparison.rows.push_back(row);
+  }
+
+  for (std::size_t j = 0; j < results_b.size(); j++) {
+    if (!matched[j]) {
+      ImplementationRow row;
+      row.message = results_b[j].message;
+      row.in_b = true;
+      row.status_b = results_b[j].status;
+      row.error_b = results_b[j].error;
+      comparison.rows.push_back(row);
+    }
+  }
+  return comparison;
+}
+
+/**
+ * @brief Formats an implementation comparison as a text table.
+ *
+ * Every row shows the error of both implementations, or their status for
+ * assertions without a measured error ("-" if an implementation did not
+ * make the assertion), the better one and the relative change of the error
+ * from a to b.
+ */
+inline std::string
+format_comparison(const ImplementationComparison &comparison) {
+  std::size_t width = 9;
+  for (std::size_t i = 0; i < comparison.rows.size(); i++) {
+    if (comparison.rows[i].message.size() > width) {
+      width = comparison.rows[i].message.size();
+    }
+  }
+
+  std::string text = "A/B comparison: " + comparison.name_a + " (a) vs " +
+                     comparison.name_b + " (b)\n";
+  text += Detail::pad("assertion", width) + "  " + Detail::pad("a", 12) +
+          "  " + Detail::pad("b", 12) + "  better  change\n";
+  for (std::size_t i = 0; i < comparison.rows.size(); i++) {
+    const ImplementationRow &row = comparison.rows[i];
+    std::string change;
+    if (row.measured && row.error_a > 0.0) {
+      change = format_number((row.error_b - row.error_a) / row.error_a *
+                             100.0) +
+               "%";
+    } else if (row.measured) {
+      change = row.error_b > 0.0 ? "from 0" : "0%";
+    }
+    text += Detail::pad(row.message, width) + "  " +
+            Detail::pad(Detail::implementation_cell(row, false), 12) + "  " +
+            Detail::pad(Detail::implementation_cell(row, true), 12) + "  " +
+            Detail::pad(std::string(1, row.better), 6) + "  " + change;
+    text.erase(text.find_last_not_of(' ') + 1);
+    text += "\n";
+  }
+  text += std::to_string(comparison.count_better('a')) + " better in " +
+          comparison.name_a + ", " +
+          std::to_string(comparison.count_better('b')) + " better in " +
+          comparison.name_b + ".\n";
+  return text;
+}
+
+/**
+ * @brief Finds the changes between the results of two runs.
+ *
+ * Assertions are matched by message, in order if a message occurs more than
+ * once. An error changes if it differs from the previous error by more than
+ * the fraction `min_change` of it, so an error growing from zero always
+ * changes.
+ *
+ * @param previous The records of the previous run.
+ * @param current The records of the current run.
+ * @param min_change The relative change of an error to report, e.g. 0.1.
+ * @return The changes, every list in the order of `current`, except
+ * `removed` in the order of `previous`.
+ */
+inline ReportDiff diff_results(const std::vector<AssertionRecord> &previous,
+                               const std::vector<AssertionRecord> &current,
+                               double min_change) {
+  ReportDiff diff;
+  std::vector<bool> matched(previous.size(), false);
+  for (std::size_t i = 0; i < current.size(); i++) {
+    const AssertionRecord &record = current[i];
+    const AssertionRecord *before = nullptr;
+    for (std::size_t j = 0; j < previous.size(); j++) {
+      if (!matched[j] && previous[j].message == record.message) {
+        matched[j] = true;
+        before = &previous[j];
+        break;
+      }
+    }
+
+    bool failing = record.status == ComparisonStatus::Failure;
+    if (before == nullptr) {
+      diff.added.push_back(record.message);
+      if (failing) {
+        diff.newly_failing.push_back(record.message);
+      }
+      continue;
+    }
+    bool failed = before->status == ComparisonStatus::Failure;
+    if (failing && !failed) {
+      diff.newly_failing.push_back(record.message);
+    } else if (failed && !failing) {
+      diff.newly_passing.push_back(record.message);
+    }
+    if (record.measured && before->measured) {
+      double change = Detail::growth(before->error, record.error);
+      if (change > min_change || change < -min_change) {
+        ErrorDrift drift;
+        drift.message = record.message;
+        drift.previous_error = before->error;
+        drift.current_error = record.error;
+        diff.changed_errors.push_back(drift);
+      }
+    }
+  }
+
+  for (std::size_t j = 0; j < previous.size(); j++) {
+    if (!matched[j]) {
+      diff.removed.push_back(previous[j].message);
+    }
+  }
+  return diff;
+}
+
+/**
+ * @brief Formats the changes between two runs as text, one section per
+ * kind of change, e.g. for the triage of a nightly run.
+ */
+inline std::string format_report_diff(const ReportDiff &diff) {
+  std::string text =
+      "Report diff: " + std::to_string(diff.newly_failing.size()) +
+      " newly failing, " + std::to_string(diff.newly_passing.size()) +
+      " newly passing, " + std::to_string(diff.changed_errors.size()) +
+      " changed error(s), " + std::to_string(diff.added.size()) +
+      " added, " + std::to_string(diff.removed.size()) + " removed.\n";
+  const std::vector<std::string> *lists[] = {&diff.newly_failing,
+                                             &diff.newly_passing, &diff.added,
+                                             &diff.removed};
+  const char *titles[] = {"Newly failing", "Newly passing", "Added",
+                          "Removed"};
+  for (std::size_t l = 0; l < 4; l++) {
+    if (l == 2 && !diff.changed_errors.empty()) {
+      text += "Changed errors:\n";
+      for (std::size_t i = 0; i < diff.changed_errors.size(); i++) {
+        const ErrorDrift &drift = diff.changed_errors[i];
+        double change =
+            Detail::growth(drift.previous_error, drift.current_error);
+        text += "  " + drift.message + ": " +
+                format_number(drift.previous_error) + " -> " +
+                format_number(drift.current_error) + " (" +
+                (change >= 0.0 ? "+" : "") + format_number(change * 100.0) +
+                "%)\n";
+      }
+    }
+    if (!lists[l]->empty()) {
+      text += std::string(titles[l]) + ":\n";
+      for (std::size_t i = 0; i < lists[l]->size(); i++) {
+        text += "  " + (*lists[l])[i] + "\n";
+      }
+    }
+  }
+  return text;
+}
+
+/**
+ * @brief Formats a regression budget ledger as a text table.
+ *
+ * Every line shows the baseline, previous and current error and the growth
+ * used against each budget, e.g. "+3% of 5%".
+ */
+inline std::string format_ledger(const std::vector<LedgerEntry> &ledger) {
+  std::size_t width = 10;
+  for (std::size_t i = 0; i < ledger.size(); i++) {
+    if (ledger[i].budget.message.size() > width) {
+      width = ledger[i].budget.message.size();
+    }
+  }
+
+  std::string text = Detail::pad("comparison", width) + "  " +
+                     Detail::pad("baseline", 12) + "  " +
+                     Detail::pad("previous", 12) + "  " +
+                     Detail::pad("current", 12) + "  " +
+                     Detail::pad("release", 16) + "  " +
+                     Detail::pad("cumulative", 16) + "  status\n";
+  for (std::size_t i = 0; i < ledger.size(); i++) {
+    const LedgerEntry &entry = ledger[i];
+    std::string status = entry.exhausted ? "EXHAUSTED" : "ok";
+    std::string release;
+    std::string cumulative;
+    if (!entry.current) {
+      status = "not measured";
+    } else if (!entry.history) {
+      status = "new";
+    } else {
+      release = Detail::growth_cell(entry.release_growth,
+                                    entry.budget.max_release_growth);
+      cumulative = Detail::growth_cell(entry.cumulative_growth,
+                                       entry.budget.max_cumulative_growth);
+    }
+    text +=
+        Detail::pad(entry.budget.message, width) + "  " +
+        Detail::pad(entry.history ? format_number(entry.baseline_error) : "-",
+                    12) +
+        "  " +
+        Detail::pad(entry.history ? format_number(entry.previous_error) : "-",
+                    12) +
+        "  " +
+        Detail::pad(entry.current ? format_number(entry.current_error) : "-",
+                    12) +
+        "  " + Detail::pad(release, 16) + "  " + Detail::pad(cumulative, 16) +
+        "  " + status + "\n";
+  }
+  return text;
+}
+
+#ifndef MCAP_TESTER_NO_STDIO
+/**
+ * @brief Reads the assertion records back from a report file, e.g. the
+ * results archived by a previous run.
+ *
+ * @param path The report file to read.
+ * @param records Receives the assertion records.
+ * @param error Receives a description of the problem on failure.
+ * @return true on success.
+ */
+inline bool load_results(const std::string &path,
+                         std::vector<AssertionRecord> &records,
+                         std::string &error) {
+  JSON::Value report;
+  std::string name;
+  return JSON::load_file(path, report, error) &&
+         from_json(report, name, records, error);
+}
+
+/**
+ * @brief Reads the results archived by several releases, e.g. to check
+ * regression budgets.
+ *
+ * @param paths The report files, oldest release first.
+ * @param history Receives the assertion records of every file.
+ * @param error Receives a description of the problem on failure.
+ * @return true if every file was read.
+ */
+inline bool load_history(const std::vector<std::string> &paths,
+                         std::vector<std::vector<AssertionRecord>> &history,
+                         std::string &error) {
+  std::vector<std::vector<AssertionRecord>> result(paths.size());
+  for (std::size_t i = 0; i < paths.size(); i++) {
+    if (!load_results(paths[i], result[i], error)) {
+      error = paths[i] + ": " + error;
+      return false;
+    }
+  }
+  history = result;
+  return true;
+}
+
+/**
+ * @brief Finds the changes between the report files of two runs, so the
+ * triage of a run can focus on what changed since the previous one.
+ *
+ * @param previous_path The report file of the previous run.
+ * @param current_path The report file of the current run.
+ * @param diff Receives the changes, see diff_results.
+ * @param error Receives a description of the problem on failure.
+ * @param min_change The relative change of an error to report.
+ * @return true if both files were read.
+ */
+inline bool diff_reports(const std::string &previous_path,
+                         const std::string &current_path, ReportDiff &diff,
+                         std::string &error, double min_change = 0.1) {
+  std::vector<AssertionRecord> previous;
+  std::vector<AssertionRecord> current;
+  if (!load_results(previous_path, previous, error)) {
+    error = previous_path + ": " + error;
+    return false;
+  }
+  if (!load_results(current_path, current, error)) {
+    error = current_path + ": " + error;
+    return false;
+  }
+  diff = diff_results(previous, current, min_change);
+  return true;
+}
+
+/**
+ * @brief Checks that a report file conforms to its schema version.
+ *
+ * Besides the required fields and their types, the summary counts must agree
+ * with the listed assertions.
+ *
+ * @param path The report file to validate.
+ * @param error Receives a description of the first problem found.
+ * @return true if the report is valid, false otherwise.
+ */
+inline bool validate_report(const std::string &path, std::string &error) {
+  JSON::Value report;
+  if (!JSON::load_file(path, report, error)) {
+    return false;
+  }
+  if (!report.get("schema_version").is_number()) {
+    error = "Missing schema version.";
+    return false;
+  }
+
+  std::string name;
+  std::vector<AssertionRecord> records;
+  if (!from_json(report, name, records, error)) {
+    return false;
+  }
+
+  JSON::Value current;
+  if (!upgrade(report, current, error)) {
+    return false;
+  }
+  const JSON::Value &summary = current.get("summary");
+  if (!summary.get("assertions").is_number() ||
+      !summary.get("failures").is_number() ||
+      !summary.get("warnings").is_number()) {
+    error = "Malformed summary.";
+    return false;
+  }
+
+  JSON::Value expected_summary = to_json(name, records).get("summary");
+  const char *counts[] = {"assertions", "failures", "warnings"};
+  for (std::size_t i = 0; i < 3; i++) {
+    if (summary.get(counts[i]).as_number() !=
+        expected_summary.get(counts[i]).as_number()) {
+      error = std::string("Summary count \"") + counts[i] +
+              "\" does not match the assertions.";
+      return false;
+    }
+  }
+  if (summary.has("passed") &&
+      (!summary.get("passed").is_boolean() ||
+       summary.get("passed").as_boolean() !=
+           expected_summary.get("passed").as_boolean())) {
+    error = "Summary verdict does not match the assertions.";
+    return false;
+  }
+  if (summary.has("categories")) {
+    const JSON::Value &categories = summary.get("categories");
+    const std::vector<std::pair<std::string, JSON::Value>> &expected =
+        expected_summary.get("categories").members();
+    for (std::size_t i = 0; i < expected.size(); i++) {
+      const std::string &key = expected[i].first;
+      if (!categories.get(key).is_number() ||
+          categories.get(key).as_number() != expected[i].second.as_number()) {
+        error = "Summary category \"" + key +
+                "\" does not match the assertions.";
+        return false;
+      }
+    }
+  }
+  if (report.has("metadata")) {
+    const JSON::Value &metadata = report.get("metadata");
+    bool valid = metadata.is_object();
+    for (std::size_t i = 0; valid && i < metadata.members().size(); i++) {
+      valid = metadata.members()[i].second.is_string();
+    }
+    if (!valid) {
+      error = "Malformed metadata.";
+      return false;
+    }
+  }
+  return true;
+}
+
+/**
+ * @brief Writes the JSON report of a list of assertion records to a file.
+ *
+ * @return true on success, false if the file cannot be written.
+ */
+inline bool save_report(const std::string &path, const std::string &name,
+                        const std::vector<AssertionRecord> &records,
+                        const std::map<std::string, std::string> &metadata =
+                            std::map<std::string, std::string>()) {
+  return JSON::save_file(path, to_json(name, records, metadata));
+}
+
+/**
+ * @brief Merges the report files of several test executables.
+ *
+ * Assertion messages in the combined report are prefixed with the name of
+ * their report, as in "model_a: step response".
+ *
+ * @param paths The report files to merge.
+ * @return The consolidated verdict and the assertions of all reports.
+ */
+inline CombinedReport merge_reports(const std::vector<std::string> &paths) {
+  CombinedReport combined;
+  for (std::size_t i = 0; i < paths.size(); i++) {
+    JSON::Value report;
+    std::string name;
+    std::vector<AssertionRecord> records;
+    std::string error;
+    if (!JSON::load_file(paths[i], report, error) ||
+        !from_json(report, name, records, error)) {
+      combined.errors.push_back(paths[i] + ": " + error);
+      continue;
+    }
+
+    combined.report_count++;
+    bool failed = false;
+    for (std::size_t j = 0; j < records.size(); j++) {
+      if (records[j].status == ComparisonStatus::Failure) {
+        combined.failure_count++;
+        failed = true;
+      } else if (records[j].status == ComparisonStatus::Warning) {
+        combined.warning_count++;
+      }
+      AssertionRecord record(name + ": " + records[j].message,
+                             records[j].status, records[j].detail,
+                             records[j].rationale);
+      record.measured = records[j].measured;
+      record.error = records[j].error;
+      record.tolerance = records[j].tolerance;
+      record.category = records[j].category;
+      record.relative_error = records[j].relative_error;
+      combined.assertions.push_back(record);
+    }
+    combined.assertion_count += records.size();
+    if (failed) {
+      combined.failed_reports.push_back(name);
+    }
+  }
+  return combined;
+}
+
+/**
+ * @brief Writes a combined report to a file.
+ *
+ * The file has the layout of a single report named "combined", extended by
+ * the number of merged reports, the names of the failed reports and the
+ * errors encountered while merging.
+ *
+ * @return true on success, false if the file cannot be written.
+ */
+inline bool save_combined_report(const std::string &path,
+                                 const CombinedReport &combined) {
+  JSON::Value report = to_json("combined", combined.assertions);
+
+  JSON::Value failed_reports = JSON::Value::array();
+  for (std::size_t i = 0; i < combined.failed_reports.size(); i++) {
+    failed_reports.push_back(JSON::Value(combined.failed_reports[i]));
+  }
+  JSON::Value errors = JSON::Value::array();
+  for (std::size_t i = 0; i < combined.errors.size(); i++) {
+    errors.push_back(JSON::Value(combined.errors[i]));
+  }
+
+  report.set("passed", JSON::Value(combined.passed()));
+  report.set("report_count", JSON::Value(combined.report_count));
+  report.set("failed_reports", failed_reports);
+  report.set("errors", errors);
+  return JSON::save_file(path, report);
+}
+#endif
+
+} // namespace Report
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_REPORT_HPP
@@ -0,0 +1,554 @@
+/**
+ * @file MCAP_tester_runner.hpp
+ * @brief Provides test registration and a command line test runner for
+ * MCAP_tester.
+ *
+ * Instead of writing a main() harness for every generated project, test
+ * functions are registered with MCAP_REGISTER_TEST and executed by
+ * Runner::main, which a test executable provides by defining
+ * MCAP_TESTER_RUNNER_MAIN before including this header in exactly one
+ * translation unit:
+ *
+ *   #define MCAP_TESTER_RUNNER_MAIN
+ *   #include "MCAP_tester_runner.hpp"
+ *
+ *   MCAP_REGISTER_TEST(step_response) {
+ *     tester.expect_near(simulate_step(), 1.0, 1e-6, "final value");
+ *   }
+ *
+ * Tests of asynchronous code are registered with MCAP_REGISTER_ASYNC_TEST
+ * and signal their completion instead of returning it, so their waits never
+ * block a thread; see AsyncRegistrar.
+ *
+ * The resulting mcap-test executable accepts:
+ *
+ *   mcap-test [--list] [--jobs N] [--report PATH] [--html PATH] [FILTER...]
+ *
+ * Only the tests whose name contains one of the filters are run. With
+ * --jobs, the tests run on N worker threads; the output of every test is
+ * buffered and printed as a whole, so it is never interleaved. Programs using
+ * more than one job must be built with thread support, e.g. -pthread.
+ * --report writes the JSON report of all tests and --html a self-contained
+ * HTML page with every test, its run time and its assertions. The
+ * closing summary counts the failures by category, e.g. "Failures by
+ * category: 2 tolerance, 1 infrastructure.", so CI can route them. The
+ * exit code is 0 if every selected test passed, 1 if one failed and 2 for
+ * invalid arguments or an unwritable report or page.
+ *
+ * The runner needs iostreams and file I/O, so it is unavailable with
+ * MCAP_TESTER_NO_STDIO. With MCAP_TESTER_NO_EXCEPTIONS, it runs the tests
+ * without catching what escapes them.
+ */
+
+#ifndef MCAP_TESTER_RUNNER_HPP
+#define MCAP_TESTER_RUNNER_HPP
+
+#include <atomic>
+#include <chrono>
+#include <cstddef>
+#include <cstdlib>
+#include <exception>
+#include <functional>
+#include <future>
+#include <iostream>
+#include <memory>
+#include <mutex>
+#include <new>
+#include <sstream>
+#include <string>
+#include <thread>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+#include "MCAP_tester_async.hpp"
+
+#ifdef MCAP_TESTER_NO_STDIO
+#error "MCAP_tester_runner.hpp requires iostreams and file I/O."
+#endif
+
+namespace Tester {
+
+namespace Runner {
+
+/**
+ * @brief A registered test: its name and a function that runs it, printing
+ * to the given stream and returning the records of its assertions.
+ */
+struct TestCase {
+  TestCase() : name(), run() {}
+
+  TestCase(
+      const std::string &name,
+      const std::function<std::vector<AssertionRecord>(std::ostream &)> &run)
+      : name(name), run(run) {}
+
+  std::string name;
+  std::function<std::vector<AssertionRecord>(std::ostream &)> run;
+};
+
+/**
+ * @brief The outcome of one executed test.
+ */
+struct TestOutcome {
+  TestOutcome() : name(), records(), output(), seconds(0.0) {}
+
+  bool passed() const {
+    for (std::size_t i = 0; i < this->records.size(); i++) {
+      if (this->records[i].status == ComparisonStatus::Failure) {
+        return false;
+      }
+    }
+    return true;
+  }
+
+  std::string name;
+  std::vector<AssertionRecord> records;
+  std::string output;
+  double seconds;
+};
+
+/**
+ * @brief The outcomes of all executed tests, in registration order.
+ */
+struct RunSummary {
+  RunSummary() : outcomes() {}
+
+  bool passed() const { return this->failure_count() == 0; }
+
+  std::size_t failure_count() const {
+    std::size_t count = 0;
+    for (std::size_t i = 0; i < this->outcomes.size(); i++) {
+      count += this->outcomes[i].passed() ? 0 : 1;
+    }
+    return count;
+  }
+
+  /**
+   * @brief Returns the records of all tests, with every message prefixed by
+   * the name of its test, as in "step_response: final value".
+   */
+  std::vector<AssertionRecord> records() const {
+    std::vector<AssertionRecord> records;
+    for (std::size_t i = 0; i < this->outcomes.size(); i++) {
+      const TestOutcome &outcome = this->outcomes[i];
+      for (std::size_t j = 0; j < outcome.records.size(); j++) {
+        AssertionRecord record = outcome.records[j];
+        record.message = outcome.name + ": " + record.message;
+        records.push_back(record);
+      }
+    }
+    return records;
+  }
+
+  std::vector<TestOutcome> outcomes;
+};
+
+/**
+ * @brief The command line options of the runner.
+ */
+struct Options {
+  Options() : filters(), jobs(1), report_path(), list(false), html_path() {}
+
+  std::vector<std::string> filters;
+  std::size_t jobs;
+  std::string report_path;
+  bool list;
+  std::string html_path;
+};
+
+/**
+ * @brief Returns the registry of all tests, in registration order.
+ */
+inline std::vector<TestCase> &registry() {
+  static std::vector<TestCase> tests;
+  return tests;
+}
+
+/**
+ * @brief Returns the runtime that async tests run on, or nullptr if every
+ * async test runs on a private Async::EventLoop.
+ */
+inline Async::Scheduler *&provided_scheduler() {
+  static Async::Scheduler *scheduler = nullptr;
+  return scheduler;
+}
+
+/**
+ * @brief Runs every async test on the given runtime instead of a private
+ * event loop; call before Runner::main, e.g. with the scheduler of the
+ * application's I/O runtime.
+ *
+ * @param scheduler The runtime; it must outlive the run, and nullptr
+ * restores the private event loops.
+ */
+inline void set_scheduler(Async::Scheduler *scheduler) {
+  provided_scheduler() = scheduler;
+}
+
+namespace Detail {
+
+/* Runs a test body and records an escaping exception as an additional
+ * failure, categorized as a resource failure if it is std::bad_alloc and as
+ * an infrastructure failure otherwise. Without exceptions, the body is just
+ * run. */
+inline void run_guarded(const std::function<void()> &body,
+                        std::vector<AssertionRecord> &aborted) {
+#ifdef MCAP_TESTER_NO_EXCEPTIONS
+  (void)aborted;
+  body();
+#else
+  try {
+    body();
+  } catch (const std::bad_alloc &error) {
+    aborted.push_back(AssertionRecord(
+        "Test aborted", ComparisonStatus::Failure,
+        std::string("Out of memory: ") + error.what()));
+    aborted.back().category = FailureCategory::Resource;
+  } catch (const std::exception &error) {
+    aborted.push_back(AssertionRecord(
+        "Test aborted", ComparisonStatus::Failure,
+        std::string("Uncaught exception: ") + error.what()));
+    aborted.back().category = FailureCategory::Infrastructure;
+  } catch (...) {
+    aborted.push_back(AssertionRecord(
+        "Test aborted", ComparisonStatus::Failure, "Uncaught exception."));
+    aborted.back().category = FailureCategory::Infrastructure;
+  }
+#endif
+}
+
+/* Returns the records of a finished test followed by the aborted ones, which
+ * are also printed. */
+template <typename T>
+inline std::vector<AssertionRecord>
+finish(MCAPTester<T> &tester, const std::vector<AssertionRecord> &aborted,
+       std::ostream &output) {
+  std::vector<AssertionRecord> records = tester.get_records();
+  records.insert(records.end(), aborted.begin(), aborted.end());
+  for (std::size_t i = 0; i < aborted.size(); i++) {
+    output << "FAILURE: " << aborted[i].message << " " << aborted[i].detail
+           << std::endl;
+  }
+  return records;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Registers a test function at static initialization; used by
+ * MCAP_REGISTER_TEST.
+ *
+ * The function receives a fresh tester for every run. An exception escaping
+ * the function, including the one of a fail-fast tester, is recorded as an
+ * additional failure of the test, categorized as a resource failure if it
+ * is std::bad_alloc and as an infrastructure failure otherwise.
+ *
+ * @tparam T Type of the compared values of the test.
+ */
+template <typename T> struct Registrar {
+  Registrar(const std::string &name, void (*body)(MCAPTester<T> &)) {
+    registry().push_back(
+        TestCase(name, [name, body](std::ostream &output) {
+          MCAPTester<T> tester;
+          tester.set_output(output);
+          std::vector<AssertionRecord> aborted;
+          Detail::run_guarded([&]() { body(tester); }, aborted);
+          return Detail::finish(tester, aborted, output);
+        }));
+  }
+};
+
+/**
+ * @brief Registers an async test function at static initialization; used by
+ * MCAP_REGISTER_ASYNC_TEST.
+ *
+ * The function receives a fresh tester, the scheduler of the runtime and a
+ * `done` function, starts its work as tasks of the runtime and returns; the
+ * test finishes when it calls `done`. Without a runtime set by
+ * set_scheduler, the test runs on a private Async::EventLoop driven by the
+ * runner thread, and a test whose loop runs out of tasks before `done` is
+ * called is aborted. On a provided runtime, the runner thread waits for
+ * `done` without running tasks. Exceptions are recorded as for Registrar,
+ * including those of the tasks of a private loop.
+ *
+ * @tparam T Type of the compared values of the test.
+ */
+template <typename T> struct AsyncRegistrar {
+  typedef void (*Body)(MCAPTester<T> &, const Async::Scheduler &,
+                       const std::function<void()> &);
+
+  AsyncRegistrar(const std::string &name, Body body) {
+    registry().push_back(
+        TestCase(name, [name, body](std::ostream &output) {
+          MCAPTester<T> tester;
+          tester.set_output(output);
+          std::shared_ptr<std::promise<void>> finished =
+              std::make_shared<std::promise<void>>();
+          std::shared_ptr<std::once_flag> once =
+              std::make_shared<std::once_flag>();
+          std::function<void()> done = [finished, once]() {
+            std::call_once(*once, [&finished]() { finished->set_value(); });
+          };
+          std::future<void> completion = finished->get_future();
+
+          Async::EventLoop loop;
+          Async::Scheduler *provided = provided_scheduler();
+          Async::Scheduler scheduler =
+              provided != nullptr ? *provided : loop.scheduler();
+          std::vector<AssertionRecord> aborted;
+          bool completed = false;
+          Detail::run_guarded(
+              [&]() {
+                body(tester, scheduler, done);
+                if (provided != nullptr) {
+                  completion.wait();
+                  completed = true;
+                  return;
+                }
+                while (completion.wait_for(std::chrono::seconds(0)) !=
+                           std::future_status::ready &&
+                       loop.run_one()) {
+                }
+                completed = completion.wait_for(std::chrono::seconds(0)) ==
+                            std::future_status::ready;
+              },
+              aborted);
+          if (!completed && aborted.empty()) {
+            aborted.push_back(AssertionRecord(
+                "Test aborted", ComparisonStatus::Failure,
+                "The event loop ran out of tasks before done was called."));
+            aborted.back().category = FailureCategory::Infrastructure;
+          }
+          return Detail::finish(tester, aborted, output);
+        }));
+  }
+};
+
+/**
+ * @brief Parses the command line of the runner.
+ *
+ * @param error Receives a description of the first invalid argument.
+ * @return true if the arguments are valid.
+ */
+inline bool parse_arguments(int argc, const char *const *argv,
+                            Options &options, std::string &error) {
+  for (int i = 1; i < argc; i++) {
+    std::string argument = argv[i];
+    if (argument == "--list") {
+      options.list = true;
+    } else if (argument == "--jobs" || argument == "-j") {
+      char *end = nullptr;
+      long jobs = i + 1 < argc ? std::strtol(argv[i + 1], &end, 10) : 0;
+      if (i + 1 >= argc || *end != '\0' || jobs < 1) {
+        error = argument + " requires a positive number.";
+        return false;
+      }
+      options.jobs = static_cast<std::size_t>(jobs);
+      i++;
+    } else if (argument == "--report") {
+      if (i + 1 >= argc) {
+        error = "--report requires a path.";
+        return false;
+      }
+      options.report_path = argv[++i];
+    } else if (argument == "--html") {
+      if (i + 1 >= argc) {
+        error = "--html requires a path.";
+        return false;
+      }
+      options.html_path = argv[++i];
+    } else if (argument.compare(0, 2, "--") == 0) {
+      error = "Unknown option " + argument + ".";
+      return false;
+    } else {
+      options.filters.push_back(argument);
+    }
+  }
+  return true;
+}
+
+/**
+ * @brief Returns the registered tests selected by the filters; all tests if
+ * there are no filters.
+ */
+inline std::vector<TestCase> select_tests(const Options &options) {
+  std::vector<TestCase> selected;
+  const std::vector<TestCase> &tests = registry();
+  for (std::size_t i = 0; i < tests.size(); i++) {
+    bool matches = options.filters.empty();
+    for (std::size_t j = 0; j < options.filters.size() && !matches; j++) {
+      matches = tests[i].name.find(options.filters[j]) != std::string::npos;
+    }
+    if (matches) {
+      selected.push_back(tests[i]);
+    }
+  }
+  return selected;
+}
+
+/**
+ * @brief Runs the selected tests and prints the outcome of every test as it
+ * finishes.
+ *
+ * @param options The filters and the number of jobs.
+ * @param output The stream that receives the output of the tests.
+ * @return The outcomes of the selected tests, in registration order.
+ */
+inline RunSummary run(const Options &options, std::ostream &output) {
+  std::vector<TestCase> tests = select_tests(options);
+  RunSummary summary;
+  summary.outcomes.resize(tests.size());
+
+  std::atomic<std::size_t> next(0);
+  std::mutex output_mutex;
+  std::function<void()> worker = [&]() {
+    for (std::size_t i = next++; i < tests.size(); i = next++) {
+      std::ostringstream buffer;
+      TestOutcome &outcome = summary.outcomes[i];
+      outcome.name = tests[i].name;
+      std::chrono::steady_clock::time_point start =
+          std::chrono::steady_clock::now();
+      outcome.records = tests[i].run(buffer);
+      outcome.seconds = std::chrono::duration<double>(
+                            std::chrono::steady_clock::now() - start)
+                            .count();
+      outcome.output = buffer.str();
+
+      std::lock_guard<std::mutex> lock(output_mutex);
+      output << (outcome.passed() ? "PASS " : "FAIL ") << outcome.name
+             << std::endl
+             << outcome.output;
+    }
+  };
+
+  std::size_t jobs = options.jobs < tests.size() ? options.jobs : tests.size();
+  if (jobs <= 1) {
+    worker();
+  } else {
+    std::vector<std::thread> threads;
+    for (std::size_t j = 0; j < jobs; j++) {
+      threads.push_back(std::thread(worker));
+    }
+    for (std::size_t j = 0; j < threads.size(); j++) {
+      threads[j].join();
+    }
+  }
+
+  return summary;
+}
+
+/**
+ * @brief The entry point of a runner executable.
+ *
+ * @return The exit code described in the file documentation.
+ */
+inline int main(int argc, const char *const *argv) {
+  Options options;
+  std::string error;
+  if (!parse_arguments(argc, argv, options, error)) {
+    std::cerr << error << std::endl
+              << "Usage: " << (argc > 0 ? argv[0] : "mcap-test")
+              << " [--list] [--jobs N] [--report PATH] [--html PATH]"
+              << " [FILTER...]"
+              << std::endl;
+    return 2;
+  }
+
+  if (options.list) {
+    std::vector<TestCase> tests = select_tests(options);
+    for (std::size_t i = 0; i < tests.size(); i++) {
+      std::cout << tests[i].name << std::endl;
+    }
+    return 0;
+  }
+
+  RunSummary summary = run(options, std::cout);
+  std::cout << summary.outcomes.size() << " test(s), "
+            << summary.failure_count() << " failed." << std::endl;
+  std::string categories =
+      Report::format_category_summary(summary.records());
+  if (!categories.empty()) {
+    std::cout << "Failures by category: " << categories << "." << std::endl;
+  }
+  for (std::size_t i = 0; i < summary.outcomes.size(); i++) {
+    if (!summary.outcomes[i].passed()) {
+      std::cout << "  " << summary.outcomes[i].name << std::endl;
+    }
+  }
+
+  if (!options.report_path.empty() &&
+      !Report::save_report(options.report_path, "mcap-test",
+                           summary.records())) {
+    std::cerr << "Cannot write " << options.report_path << "." << std::endl;
+    return 2;
+  }
+  if (!options.html_path.empty()) {
+    std::vector<Report::HtmlCase> cases;
+    for (std::size_t i = 0; i < summary.outcomes.size(); i++) {
+      const TestOutcome &outcome = summary.outcomes[i];
+      cases.push_back(
+          Report::HtmlCase(outcome.name, outcome.records, outcome.seconds));
+    }
+    if (!Report::save_html_report(options.html_path, "mcap-test", cases)) {
+      std::cerr << "Cannot write " << options.html_path << "." << std::endl;
+      return 2;
+    }
+  }
+  return summary.passed() ? 0 : 1;
+}
+
+} // namespace Runner
+
+} // namespace Tester
+
+/**
+ * @brief Defines and registers a test function of an MCAPTester<type>, named
+ * `tester` inside the body.
+ *
+ * Example: MCAP_REGISTER_TYPED_TEST(float, filter_step) { ... }
+ */
+#define MCAP_REGISTER_TYPED_TEST(type, name)                                   \
+  static void name(::Tester::MCAPTester<type> &tester);                        \
+  static const ::Tester::Runner::Registrar<type> name##_registrar(#name,       \
+                                                                  &name);      \
+  static void name(::Tester::MCAPTester<type> &tester)
+
+/**
+ * @brief Defines and registers a test function of an MCAPTester<double>,
+ * named `tester` inside the body.
+ */
+#define MCAP_REGISTER_TEST(name) MCAP_REGISTER_TYPED_TEST(double, name)
+
+/**
+ * @brief Defines and registers an async test function of an
+ * MCAPTester<type>, named `tester`, `scheduler` and `done` inside the body.
+ *
+ * Example:
+ *   MCAP_REGISTER_ASYNC_TYPED_TEST(double, acquisition) {
+ *     start_acquisition(scheduler);
+ *     Async::expect_eventually(scheduler, tester, ..., "acquisition",
+ *                              [done](bool) { done(); });
+ *   }
+ */
+#define MCAP_REGISTER_ASYNC_TYPED_TEST(type, name)                             \
+  static void name(::Tester::MCAPTester<type> &tester,                         \
+                   const ::Tester::Async::Scheduler &scheduler,                \
+                   const std::function<void()> &done);                         \
+  static const ::Tester::Runner::AsyncRegistrar<type> name##_registrar(        \
+      #name, &name);                                                           \
+  static void name(::Tester::MCAPTester<type> &tester,                         \
+                   const ::Tester::Async::Scheduler &scheduler,                \
+                   const std::function<void()> &done)
+
+/**
+ * @brief Defines and registers an async test function of an
+ * MCAPTester<double>, named `tester`, `scheduler` and `done` inside the body.
+ */
+#define MCAP_REGISTER_ASYNC_TEST(name)                                         \
+  MCAP_REGISTER_ASYNC_TYPED_TEST(double, name)
+
+#ifdef MCAP_TESTER_RUNNER_MAIN
+int main(int argc, char **argv) { return ::Tester::Runner::main(argc, argv); }
+#endif
+
+#endif // MCAP_TESTER_RUNNER_HPP
@@ -0,0 +1,415 @@
+/**
+ * @file MCAP_tester_scenario.hpp
+ * @brief Provides scenario description files for runs of the
+ * Tester::ClosedLoopHarness.
+ *
+ * Test engineers add closed-loop scenarios as files instead of code, so no
+ * recompilation is needed. Scenarios use the JSON format that the rest of
+ * MCAP_tester reads with MCAP_tester_json.hpp. A scenario file looks like:
+ *
+ *   {
+ *     "name": "step with gust",
+ *     "steps": 500,
+ *     "initial_measurement": [0.0],
+ *     "reference": {"type": "step", "time": 0.1, "initial": [0.0],
+ *                   "final": [1.0]},
+ *     "disturbance": [0.0],
+ *     "injections": [
+ *       {"type": "disturbance", "name": "gust", "start": 2.0, "end": 2.5,
+ *        "offset": [0.3]},
+ *       {"type": "sensor_dropout", "name": "sensor", "start": 3.0,
+ *        "end": 3.2, "channel": 0},
+ *       {"type": "parameter_change", "name": "heavier load", "time": 4.0,
+ *        "parameter": "load", "value": 1.5}
+ *     ],
+ *     "expected": {"final_measurement": [1.0], "final_tolerance": 0.01,
+ *                  "max_measurement": [1.2], "max_abs_command": [10.0]}
+ *   }
+ *
+ * A profile ("reference", "disturbance") is either an array of channel
+ * values, which is constant, or an object of one of the types:
+ *
+ *   {"type": "constant", "value": [...]}
+ *   {"type": "step", "time": t, "initial": [...], "final": [...]}
+ *   {"type": "ramp", "start": t, "initial": [...], "slope": [...]}
+ *   {"type": "table", "time": [t0, t1, ...], "values": [[...], [...], ...]}
+ *
+ * A table holds every value until the next time. All fields except "steps"
+ * are optional. The expected metrics are checked per channel: the final
+ * measurement within "final_tolerance" (default 0), the largest and smallest
+ * measurement against "max_measurement" and "min_measurement", and the
+ * largest command magnitude against "max_abs_command".
+ */
+
+#ifndef MCAP_TESTER_SCENARIO_HPP
+#define MCAP_TESTER_SCENARIO_HPP
+
+#include <algorithm>
+#include <cmath>
+#include <cstddef>
+#include <functional>
+#include <map>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+#include "MCAP_tester_harness.hpp"
+#include "MCAP_tester_json.hpp"
+
+#ifdef MCAP_TESTER_NO_STDIO
+#error "MCAP_tester_scenario.hpp reads scenario files and requires file I/O."
+#endif
+
+#ifdef _WIN32
+#include <windows.h>
+#else
+#include <dirent.h>
+#endif
+
+namespace Tester {
+
+namespace Scenario {
+
+/**
+ * @brief Creates a fresh harness, with fresh controller and plant state, for
+ * every scenario.
+ */
+template <typename T>
+using HarnessFactory = std::function<ClosedLoopHarness<T>()>;
+
+/**
+ * @brief The parameters that parameter_change injections may set, by name.
+ *
+ * A setter usually changes state shared with the closures of the harness
+ * that the HarnessFactory created for the current scenario.
+ */
+template <typename T>
+using Parameters = std::map<std::string, std::function<void(T)>>;
+
+namespace Detail {
+
+/* Returns the names of the files in a directory with a given extension,
+ * sorted. */
+inline std::vector<std::string> list_files(const std::string &directory,
+                                           const std::string &extension) {
+  std::vector<std::string> files;
+#ifdef _WIN32
+  WIN32_FIND_DATAA data;
+  std::string pattern = directory + "\\*" + extension;
+  HANDLE handle = FindFirstFileA(pattern.c_str(), &data);
+  if (handle != INVALID_HANDLE_VALUE) {
+    do {
+      files.push_back(data.cFileName);
+    } while (FindNextFileA(handle, &data));
+    FindClose(handle);
+  }
+#else
+  DIR *handle = opendir(directory.c_str());
+  if (handle != nullptr) {
+    for (dirent *entry = readdir(handle); entry != nullptr;
+         entry = readdir(handle)) {
+      std::string name = entry->d_name;
+      if (name.size() > extension.size() &&
+          name.compare(name.size() - extension.size(), extension.size(),
+                       extension) == 0) {
+        files.push_back(name);
+      }
+    }
+    closedir(handle);
+  }
+#endif
+  std::sort(files.begin(), files.end());
+  return files;
+}
+
+template <typename T>
+inline bool read_values(const JSON::Value &value, std::vector<T> &values) {
+  if (!value.is_array()) {
+    return false;
+  }
+  values.clear();
+  for (std::size_t i = 0; i < value.size(); i++) {
+    if (!value[i].is_number()) {
+      return false;
+    }
+    values.push_back(static_cast<T>(value[i].as_number()));
+  }
+  return true;
+}
+
+template <typename T>
+inline bool read_profile(const JSON::Value &value,
+                         typename ClosedLoopHarness<T>::Profile &profile,
+                         std::string &error) {
+  typedef std::vector<T> Signal;
+  Signal first;
+  Signal second;
+  const std::string type = value.get("type").as_string();
+
+  if (read_values(value, first) ||
+      (type == "constant" && read_values(value.get("value"), first))) {
+    profile = [first](T) { return first; };
+    return true;
+  } else if (type == "step" && value.get("time").is_number() &&
+             read_values(value.get("initial"), first) &&
+             read_values(value.get("final"), second)) {
+    T time = static_cast<T>(value.get("time").as_number());
+    profile = [time, first, second](T t) { return t < time ? first : second; };
+    return true;
+  } else if (type == "ramp" && value.get("start").is_number() &&
+             read_values(value.get("initial"), first) &&
+             read_values(value.get("slope"), second) &&
+             first.size() == second.size()) {
+    T start = static_cast<T>(value.get("start").as_number());
+    profile = [start, first, second](T t) {
+      Signal ramp = first;
+      for (std::size_t i = 0; i < ramp.size() && t > start; i++) {
+        ramp[i] += second[i] * (t - start);
+      }
+      return ramp;
+    };
+    return true;
+  } else if (type == "table" && read_values(value.get("time"), first) &&
+             value.get("values").is_array() &&
+             value.get("values").size() == first.size() && !first.empty()) {
+    std::vector<Signal> rows(first.size());
+    for (std::size_t i = 0; i < rows.size(); i++) {
+      if (!read_values(value.get("values")[i], rows[i])) {
+        error = "Invalid table values.";
+        return false;
+      }
+    }
+    profile = [first, rows](T t) {
+      std::size_t i = 0;
+      while (i + 1 < first.size() && first[i + 1] <= t) {
+        i++;
+      }
+      return rows[i];
+    };
+    return true;
+  }
+
+  error = "Invalid profile.";
+  return false;
+}
+
+template <typename T>
+inline bool add_injections(ClosedLoopHarness<T> &harness,
+                           const JSON::Value &injections,
+                           const Parameters<T> &parameters,
+                           std::string &error) {
+  if (!injections.is_null() && !injections.is_array()) {
+    error = "The injections must be an array.";
+    return false;
+  }
+  for (std::size_t i = 0; i < injections.size(); i++) {
+    const JSON::Value &injection = injections[i];
+    const std::string type = injection.get("type").as_string();
+    const std::string name = injection.get("name").is_string()
+                                 ? injection.get("name").as_string()
+                                 : type + " " + std::to_string(i);
+    T start = static_cast<T>(injection.get("start").as_number());
+    T end = static_cast<T>(injection.get("end").as_number());
+    std::vector<T> offset;
+
+    if (type == "disturbance" &&
+        read_values(injection.get("offset"), offset)) {
+      harness.add_disturbance(name, start, end, offset);
+    } else if (type == "sensor_dropout" &&
+               injection.get("channel").is_number()) {
+      harness.add_sensor_dropout(
+          name, start, end,
+          static_cast<std::size_t>(injection.get("channel").as_number()));
+    } else if (type == "parameter_change") {
+      typename Parameters<T>::const_iterator parameter =
+          parameters.find(injection.get("parameter").as_string());
+      if (parameter == parameters.end()) {
+        error = "Unknown parameter \"" +
+                injection.get("parameter").as_string() + "\" in injection " +
+                name + ".";
+        return false;
+      }
+      std::function<void(T)> setter = parameter->second;
+      T value = static_cast<T>(injection.get("value").as_number());
+      harness.add_parameter_change(
+          name, static_cast<T>(injection.get("time").as_number()),
+          [setter, value]() { setter(value); });
+    } else {
+      error = "Invalid injection " + name + ".";
+      return false;
+    }
+  }
+  return true;
+}
+
+/* Appends a description of every channel of `signal` whose extreme over the
+ * run violates a bound to `failures`. */
+template <typename T>
+inline void check_bound(const std::vector<std::vector<T>> &signal,
+                        const JSON::Value &bound, const std::string &label,
+                        int direction, std::vector<std::string> &failures) {
+  std::vector<T> limits;
+  if (!read_values(bound, limits)) {
+    return;
+  }
+  for (std::size_t j = 0; j < limits.size(); j++) {
+    std::vector<T> values = channel(signal, j);
+    for (std::size_t k = 0; k < values.size(); k++) {
+      T value = direction == 0 ? std::abs(values[k]) : values[k];
+      bool violated = direction < 0 ? !(value >= limits[j])
+                                    : !(value <= limits[j]);
+      if (violated) {
+        failures.push_back(label + " of channel " + std::to_string(j) +
+                           " is " + format_number(values[k]) + " at step " +
+                           std::to_string(k) + ", limit " +
+                           format_number(limits[j]) + ".");
+        break;
+      }
+    }
+  }
+}
+
+} // namespace Detail
+
+/**
+ * @brief Runs one scenario file and reports its outcome as one assertion,
+ * named "Scenario: <name>".
+ *
+ * An unreadable or invalid file is reported as a failure. The injections
+ * applied during the run are recorded with record_injections.
+ *
+ * @param tester The tester that receives the verdict.
+ * @param path The scenario file.
+ * @param make_harness Creates the harness the scenario runs on.
+ * @param parameters The parameters that injections may change.
+ * @return true if the scenario passed.
+ */
+template <typename T>
+inline bool run_scenario_file(MCAPTester<T> &tester, const std::string &path,
+                              const HarnessFactory<T> &make_harness,
+                              const Parameters<T> &parameters = {}) {
+  JSON::Value scenario;
+  std::string error;
+  if (!JSON::load_file(path, scenario, error)) {
+    tester.expect_true(false, "Scenario: " + path, error,
+                       FailureCategory::Infrastructure);
+    return false;
+  }
+
+  std::string name = scenario.get("name").is_string()
+                         ? scenario.get("name").as_string()
+                         : path;
+  std::string message = "Scenario: " + name;
+  if (!scenario.get("steps").is_number()) {
+    tester.expect_true(false, message, "Missing number of steps.",
+                       FailureCategory::Infrastructure);
+    return false;
+  }
+
+  ClosedLoopHarness<T> harness = make_harness();
+  typename ClosedLoopHarness<T>::Profile profile;
+  std::vector<T> initial_measurement;
+  if (Detail::read_values(scenario.get("initial_measurement"),
+                          initial_measurement)) {
+    harness.set_initial_measurement(initial_measurement);
+  }
+  if (scenario.has("reference")) {
+    if (!Detail::read_profile<T>(scenario.get("reference"), profile, error)) {
+      tester.expect_true(false, message, "Reference: " + error,
+                         FailureCategory::Infrastructure);
+      return false;
+    }
+    harness.set_reference(profile);
+  }
+  if (scenario.has("disturbance")) {
+    if (!Detail::read_profile<T>(scenario.get("disturbance"), profile,
+                                 error)) {
+      tester.expect_true(false, message, "Disturbance: " + error,
+                         FailureCategory::Infrastructure);
+      return false;
+    }
+    harness.set_disturbance(profile);
+  }
+  if (!Detail::add_injections(harness, scenario.get("injections"), parameters,
+                              error)) {
+    tester.expect_true(false, message, error, FailureCategory::Infrastructure);
+    return false;
+  }
+
+  Trajectories<T> trajectories = harness.run_closed_loop(
+      static_cast<std::size_t>(scenario.get("steps").as_number()));
+
+  const JSON::Value &expected = scenario.get("expected");
+  std::vector<std::string> failures;
+  std::vector<T> final_measurement;
+  if (Detail::read_values(expected.get("final_measurement"),
+                          final_measurement)) {
+    T tolerance = static_cast<T>(expected.get("final_tolerance").as_number());
+    std::vector<T> actual = trajectories.measurement.empty()
+                                ? std::vector<T>()
+                                : trajectories.measurement.back();
+    for (std::size_t j = 0; j < final_measurement.size(); j++) {
+      if (j >= actual.size() ||
+          !(std::abs(actual[j] - final_measurement[j]) <= tolerance)) {
+        failures.push_back(
+            "Final measurement of channel " + std::to_string(j) + " is " +
+            (j < actual.size() ? format_number(actual[j]) : "missing") +
+            ", expected " + format_number(final_measurement[j]) + ".");
+      }
+    }
+  }
+  Detail::check_bound(trajectories.measurement, expected.get("max_measurement"),
+                      "Largest measurement", 1, failures);
+  Detail::check_bound(trajectories.measurement, expected.get("min_measurement"),
+                      "Smallest measurement", -1, failures);
+  Detail::check_bound(trajectories.command, expected.get("max_abs_command"),
+                      "Command", 0, failures);
+
+  record_injections(tester, trajectories);
+  std::string detail;
+  for (std::size_t i = 0; i < failures.size(); i++) {
+    detail += (i == 0 ? "" : " ") + failures[i];
+  }
+  tester.expect_true(failures.empty(), message, detail);
+  return failures.empty();
+}
+
+/**
+ * @brief Runs every scenario file (*.json) of a directory in name order,
+ * reporting one assertion per scenario.
+ *
+ * @param tester The tester that receives the verdicts.
+ * @param directory The directory holding the scenario files.
+ * @param make_harness Creates the harness every scenario runs on.
+ * @param parameters The parameters that injections may change.
+ * @return The number of scenarios that failed; an empty or missing directory
+ * is reported as a failure and counts as one.
+ */
+template <typename T>
+inline std::size_t run_scenarios(MCAPTester<T> &tester,
+                                 const std::string &directory,
+                                 const HarnessFactory<T> &make_harness,
+                                 const Parameters<T> &parameters = {}) {
+  std::vector<std::string> files = Detail::list_files(directory, ".json");
+  if (files.empty()) {
+    tester.expect_true(false, "Scenarios: " + directory,
+                       "No scenario files found.",
+                       FailureCategory::Infrastructure);
+    return 1;
+  }
+
+  std::size_t failures = 0;
+  for (std::size_t i = 0; i < files.size(); i++) {
+    if (!run_scenario_file(tester, directory + "/" + files[i], make_harness,
+                           parameters)) {
+      failures++;
+    }
+  }
+  return failures;
+}
+
+} // namespace Scenario
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_SCENARIO_HPP
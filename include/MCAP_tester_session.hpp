@@ -0,0 +1,483 @@
+/**
+ * @file MCAP_tester_session.hpp
+ * @brief Provides session archives that record the inputs of assertions so
+ * that their verdicts can be reproduced later for audits.
+ *
+ * When session recording is enabled on a Tester::MCAPTester, every
+ * element-wise comparison (expect_near in all its forms, the default
+ * tolerance, angle, signal, trajectory, resampled, pass-rate, reduction,
+ * integration and boundary checks) is stored as a SessionEntry with its
+ * kind, message, parameters, both input arrays and the verdict of the
+ * comparison. Checks of aggregates or geometric quantities (the error
+ * metrics, norms, spectra, unordered values, quaternions, rotations,
+ * phasors, sparse and integer arrays) cannot be replayed from two arrays;
+ * while recording, each of them adds a warning that it was not recorded.
+ * The archive is a JSON file:
+ *
+ *   {
+ *     "session_version": 1,
+ *     "entries": [
+ *       {"kind": "near", "message": "...", "parameters": [0.1, 0.1, 0],
+ *        "status": "pass", "actual": {...}, "expected": {...}}
+ *     ]
+ *   }
+ *
+ * With SessionStorage::Inline the arrays are embedded as
+ * {"shape": [...], "data": [...]}, where non-finite values are written as the
+ * strings "nan", "inf" and "-inf". With SessionStorage::Referenced they are
+ * written with save_flat_array into the directory `<archive>.data` and
+ * referenced as {"file": "...", "fingerprint": "0x..."}; the exact
+ * fingerprint guards against the files being modified afterwards.
+ *
+ * Session::replay_session re-evaluates every entry with the side-effect-free
+ * comparison functions and reports any verdict that differs from the
+ * recorded one. Verdicts decided by the degenerate array policies are not
+ * recorded, since they do not depend on the values.
+ */
+
+#ifndef MCAP_TESTER_SESSION_HPP
+#define MCAP_TESTER_SESSION_HPP
+
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <limits>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester_comparison.hpp"
+#include "MCAP_tester_flat_array.hpp"
+#include "MCAP_tester_json.hpp"
+#include "MCAP_tester_report.hpp"
+
+namespace Tester {
+
+/**
+ * @brief How a session archive stores the recorded arrays.
+ */
+enum class SessionStorage { Inline, Referenced };
+
+/**
+ * @brief The recorded inputs and verdict of one comparison.
+ *
+ * For the kind "near", `parameters` holds the warning tolerance, the failure
+ * tolerance and the ShapePolicy as a number. For the kind "angle", it holds
+ * the tolerance and the AngleUnit as a number. For the kind "combined", it
+ * holds the absolute and relative tolerance and the NanPolicy as a number.
+ * The kinds "tolerance", "trajectory", "adaptive_trajectory", "resampled"
+ * and "pass_rate" start with the absolute, relative and ULP bound of a
+ * Tolerance; "trajectory" adds the settling samples, "adaptive_trajectory"
+ * the settling samples and the stride, and "pass_rate" the minimum rate.
+ * For "resampled", `actual` and `expected` have two columns, the time stamp
+ * and the value of every sample.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> struct SessionEntry {
+  SessionEntry()
+      : kind(), message(), parameters(), actual(), expected(),
+        status(ComparisonStatus::Pass) {}
+
+  std::string kind;
+  std::string message;
+  std::vector<double> parameters;
+  FlatArray<T> actual;
+  FlatArray<T> expected;
+  ComparisonStatus status;
+};
+
+/**
+ * @brief Outcome of replaying a session archive.
+ *
+ * `mismatches` describes every entry whose replayed verdict differs from the
+ * recorded one, and `errors` every problem with the archive itself.
+ */
+struct SessionReplay {
+  SessionReplay()
+      : entry_count(0), reproduced_count(0), mismatches(), errors() {}
+
+  bool reproduced() const {
+    return this->mismatches.empty() && this->errors.empty();
+  }
+
+  std::size_t entry_count;
+  std::size_t reproduced_count;
+  std::vector<std::string> mismatches;
+  std::vector<std::string> errors;
+};
+
+namespace Session {
+
+static const int SESSION_VERSION = 1;
+
+namespace Detail {
+
+inline JSON::Value encode_number(double value) {
+  if (std::isnan(value)) {
+    return JSON::Value("nan");
+  } else if (std::isinf(value)) {
+    return JSON::Value(value > 0.0 ? "inf" : "-inf");
+  }
+  return JSON::Value(value);
+}
+
+inline bool decode_number(const JSON::Value &value, double &number) {
+  if (value.is_number()) {
+    number = value.as_number();
+  } else if (value.is_string() && value.as_string() == "nan") {
+    number = std::numeric_limits<double>::quiet_NaN();
+  } else if (value.is_string() && value.as_string() == "inf") {
+    number = std::numeric_limits<double>::infinity();
+  } else if (value.is_string() && value.as_string() == "-inf") {
+    number = -std::numeric_limits<double>::infinity();
+  } else {
+    return false;
+  }
+  return true;
+}
+
+template <typename T>
+inline JSON::Value encode_inline(const FlatArray<T> &values) {
+  JSON::Value shape = JSON::Value::array();
+  for (std::size_t i = 0; i < values.shape.size(); i++) {
+    shape.push_back(JSON::Value(values.shape[i]));
+  }
+  JSON::Value data = JSON::Value::array();
+  for (std::size_t i = 0; i < values.data.size(); i++) {
+    data.push_back(encode_number(static_cast<double>(values.data[i])));
+  }
+
+  JSON::Value encoded = JSON::Value::object();
+  encoded.set("shape", shape);
+  encoded.set("data", data);
+  return encoded;
+}
+
+#ifndef MCAP_TESTER_NO_STDIO
+template <typename T>
+inline bool decode_array(const JSON::Value &encoded, FlatArray<T> &values,
+                         std::string &error) {
+  if (encoded.get("file").is_string()) {
+    const std::string &path = encoded.get("file").as_string();
+    FlatArray<T> loaded;
+    if (!load_flat_array(path, loaded)) {
+      error = "Cannot read " + path + ".";
+      return false;
+    }
+    if (!encoded.get("fingerprint").is_string() ||
+        fingerprint_to_string(compute_fingerprint(
+            loaded, static_cast<T>(0))) !=
+            encoded.get("fingerprint").as_string()) {
+      error = "Fingerprint of " + path + " does not match.";
+      return false;
+    }
+    values = loaded;
+    return true;
+  }
+
+  const JSON::Value &shape = encoded.get("shape");
+  const JSON::Value &data = encoded.get("data");
+  if (!shape.is_array() || !data.is_array()) {
+    error = "Malformed array.";
+    return false;
+  }
+
+  FlatArray<T> decoded;
+  std::size_t count = 1;
+  for (std::size_t i = 0; i < shape.size(); i++) {
+    if (!shape[i].is_number() || shape[i].as_number() < 0.0) {
+      error = "Malformed array shape.";
+      return false;
+    }
+    decoded.shape.push_back(static_cast<std::size_t>(shape[i].as_number()));
+    count *= decoded.shape.back();
+  }
+  if (count != data.size()) {
+    error = "Array data does not match its shape.";
+    return false;
+  }
+  for (std::size_t i = 0; i < data.size(); i++) {
+    double number = 0.0;
+    if (!decode_number(data[i], number)) {
+      error = "Malformed array data.";
+      return false;
+    }
+    decoded.data.push_back(static_cast<T>(number));
+  }
+
+  values = decoded;
+  return true;
+}
+#endif
+
+/* Splits an array of {time, value} rows into its two columns. */
+template <typename T>
+inline bool split_columns(const FlatArray<T> &samples, std::vector<T> &times,
+                          std::vector<T> &values) {
+  if (samples.shape.size() != 2 || samples.shape[1] != 2) {
+    return false;
+  }
+  for (std::size_t k = 0; k < samples.shape[0]; k++) {
+    times.push_back(samples.data[2 * k]);
+    values.push_back(samples.data[2 * k + 1]);
+  }
+  return true;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Re-evaluates a recorded comparison with the comparison functions.
+ *
+ * @param entry The recorded comparison.
+ * @param result Receives the comparison result.
+ * @return true on success, false if the kind or its parameters are unknown.
+ */
+template <typename T>
+inline bool evaluate(const SessionEntry<T> &entry,
+                     ComparisonResult<T> &result) {
+  const std::vector<double> &parameters = entry.parameters;
+  Tolerance<T> tolerance;
+  if (parameters.size() >= 3) {
+    tolerance = Tolerance<T>::absolute_relative(static_cast<T>(parameters[0]),
+                                                static_cast<T>(parameters[1]))
+                    .or_ulps(static_cast<std::uint64_t>(parameters[2]));
+  }
+
+  if (entry.kind == "tolerance" && parameters.size() == 3) {
+    result = Comparison::compare_arrays(entry.actual, entry.expected,
+                                        tolerance);
+    return true;
+  } else if (entry.kind == "combined" && parameters.size() == 3) {
+    result = Comparison::compare_arrays_combined(
+        entry.actual, entry.expected, static_cast<T>(parameters[0]),
+        static_cast<T>(parameters[1]),
+        static_cast<NanPolicy>(static_cast<int>(parameters[2])));
+    return true;
+  } else if (entry.kind == "trajectory" && parameters.size() == 4) {
+    result = Comparison::compare_trajectory(
+        entry.actual, entry.expected, tolerance,
+        static_cast<std::size_t>(parameters[3]));
+    return true;
+  } else if (entry.kind == "adaptive_trajectory" && parameters.size() == 5) {
+    result = Comparison::compare_trajectory_adaptive(
+        entry.actual, entry.expected, tolerance,
+        static_cast<std::size_t>(parameters[3]),
+        static_cast<std::size_t>(parameters[4]), tolerance.scaled(0.5));
+    return true;
+  } else if (entry.kind == "resampled" && parameters.size() == 3) {
+    std::vector<T> actual_times, actual, expected_times, expected;
+    if (!Detail::split_columns(entry.actual, actual_times, actual) ||
+        !Detail::split_columns(entry.expected, expected_times, expected)) {
+      return false;
+    }
+    result = Comparison::compare_resampled(actual, actual_times, expected,
+                                           expected_times, tolerance);
+    return true;
+  } else if (entry.kind == "pass_rate" && parameters.size() == 4) {
+    PassRates rates = Comparison::compute_pass_rates(
+        entry.actual, entry.expected, tolerance);
+    result = ComparisonResult<T>();
+    for (std::size_t j = 0; j < rates.pass_rates.size(); j++) {
+      if (rates.pass_rates[j] < parameters[3]) {
+        result.status = ComparisonStatus::Failure;
+      }
+    }
+    if (!rates.shape_matches) {
+      result.status = ComparisonStatus::Failure;
+      result.kind = MismatchKind::SizeMismatch;
+    }
+    return true;
+  } else if (entry.kind == "near" && parameters.size() == 3) {
+    T warning_tolerance = static_cast<T>(parameters[0]);
+    T failure_tolerance = static_cast<T>(parameters[1]);
+    ShapePolicy policy =
+        static_cast<ShapePolicy>(static_cast<int>(parameters[2]));
+    if (policy == ShapePolicy::Exact) {
+      result = Comparison::compare_arrays(entry.actual, entry.expected,
+                                          warning_tolerance,
+                                          failure_tolerance);
+    } else {
+      result = Comparison::compare_arrays(entry.actual, entry.expected,
+                                          failure_tolerance, policy);
+    }
+    return true;
+  } else if (entry.kind == "angle" && parameters.size() == 2) {
+    AngleUnit unit = static_cast<AngleUnit>(static_cast<int>(parameters[1]));
+    result = Comparison::compare_angles(entry.actual, entry.expected,
+                                        static_cast<T>(parameters[0]), unit);
+    return true;
+  }
+  return false;
+}
+
+#ifndef MCAP_TESTER_NO_STDIO
+/**
+ * @brief Writes a session archive.
+ *
+ * @param path The archive file to write.
+ * @param entries The recorded comparisons.
+ * @param storage Whether the arrays are embedded or written to separate
+ * files in the directory `<path>.data`.
+ * @return true on success, false if a file cannot be written.
+ */
+template <typename T>
+inline bool save_session(const std::string &path,
+                         const std::vector<SessionEntry<T>> &entries,
+                         SessionStorage storage) {
+  std::string data_directory = path + ".data";
+  if (storage == SessionStorage::Referenced &&
+      !create_directories(data_directory)) {
+    return false;
+  }
+
+  JSON::Value encoded_entries = JSON::Value::array();
+  for (std::size_t i = 0; i < entries.size(); i++) {
+    const SessionEntry<T> &entry = entries[i];
+    JSON::Value parameters = JSON::Value::array();
+    for (std::size_t k = 0; k < entry.parameters.size(); k++) {
+      parameters.push_back(Detail::encode_number(entry.parameters[k]));
+    }
+
+    JSON::Value encoded = JSON::Value::object();
+    encoded.set("kind", JSON::Value(entry.kind));
+    encoded.set("message", JSON::Value(entry.message));
+    encoded.set("parameters", parameters);
+    encoded.set("status", JSON::Value(Report::status_to_string(entry.status)));
+
+    const FlatArray<T> *arrays[] = {&entry.actual, &entry.expected};
+    const char *names[] = {"actual", "expected"};
+    for (std::size_t k = 0; k < 2; k++) {
+      if (storage == SessionStorage::Inline) {
+        encoded.set(names[k], Detail::encode_inline(*arrays[k]));
+        continue;
+      }
+
+      std::string file = data_directory + "/" + std::to_string(i) + "_" +
+                         names[k] + ".txt";
+      if (!save_flat_array(file, *arrays[k])) {
+        return false;
+      }
+      JSON::Value reference = JSON::Value::object();
+      reference.set("file", JSON::Value(file));
+      reference.set("fingerprint",
+                    JSON::Value(fingerprint_to_string(compute_fingerprint(
+                        *arrays[k], static_cast<T>(0)))));
+      encoded.set(names[k], reference);
+    }
+    encoded_entries.push_back(encoded);
+  }
+
+  JSON::Value archive = JSON::Value::object();
+  archive.set("session_version", JSON::Value(SESSION_VERSION));
+  archive.set("entries", encoded_entries);
+  return JSON::save_file(path, archive);
+}
+
+/**
+ * @brief Reads the entries of a session archive.
+ *
+ * @param path The archive file to read.
+ * @param entries Receives the recorded comparisons.
+ * @param error Receives a description of the problem on failure.
+ * @return true on success, false if the archive is missing or malformed.
+ */
+template <typename T>
+inline bool load_session(const std::string &path,
+                         std::vector<SessionEntry<T>> &entries,
+                         std::string &error) {
+  JSON::Value archive;
+  if (!JSON::load_file(path, archive, error)) {
+    return false;
+  }
+  if (!archive.get("session_version").is_number() ||
+      archive.get("session_version").as_number() != SESSION_VERSION ||
+      !archive.get("entries").is_array()) {
+    error = "Not a supported session archive.";
+    return false;
+  }
+
+  const JSON::Value &encoded_entries = archive.get("entries");
+  std::vector<SessionEntry<T>> result;
+  for (std::size_t i = 0; i < encoded_entries.size(); i++) {
+    const JSON::Value &encoded = encoded_entries[i];
+    std::string prefix = "Entry " + std::to_string(i) + ": ";
+    SessionEntry<T> entry;
+    if (!encoded.get("kind").is_string() ||
+        !encoded.get("message").is_string() ||
+        !encoded.get("parameters").is_array() ||
+        !encoded.get("status").is_string() ||
+        !Report::status_from_string(encoded.get("status").as_string(),
+                                    entry.status)) {
+      error = prefix + "Malformed entry.";
+      return false;
+    }
+    entry.kind = encoded.get("kind").as_string();
+    entry.message = encoded.get("message").as_string();
+
+    const JSON::Value &parameters = encoded.get("parameters");
+    for (std::size_t k = 0; k < parameters.size(); k++) {
+      double number = 0.0;
+      if (!Detail::decode_number(parameters[k], number)) {
+        error = prefix + "Malformed parameters.";
+        return false;
+      }
+      entry.parameters.push_back(number);
+    }
+
+    if (!Detail::decode_array(encoded.get("actual"), entry.actual, error) ||
+        !Detail::decode_array(encoded.get("expected"), entry.expected,
+                              error)) {
+      error = prefix + error;
+      return false;
+    }
+    result.push_back(entry);
+  }
+
+  entries = result;
+  return true;
+}
+
+/**
+ * @brief Replays a session archive and checks that every recorded verdict is
+ * reproduced.
+ *
+ * @tparam T Type of the compared values; must match the recording tester.
+ * @param path The archive file written by MCAPTester::save_session.
+ * @return The number of entries, the number of reproduced verdicts and a
+ * description of every verdict that was not reproduced.
+ */
+template <typename T>
+inline SessionReplay replay_session(const std::string &path) {
+  SessionReplay replay;
+  std::vector<SessionEntry<T>> entries;
+  std::string error;
+  if (!load_session(path, entries, error)) {
+    replay.errors.push_back(error);
+    return replay;
+  }
+
+  replay.entry_count = entries.size();
+  for (std::size_t i = 0; i < entries.size(); i++) {
+    ComparisonResult<T> result;
+    if (!evaluate(entries[i], result)) {
+      replay.errors.push_back("Entry " + std::to_string(i) +
+                              ": Unknown kind \"" + entries[i].kind + "\".");
+    } else if (result.status == entries[i].status) {
+      replay.reproduced_count++;
+    } else {
+      replay.mismatches.push_back(
+          "Entry " + std::to_string(i) + " (" + entries[i].message +
+          "): recorded " + Report::status_to_string(entries[i].status) +
+          ", replayed " + Report::status_to_string(result.status) + ".");
+    }
+  }
+  return replay;
+}
+#endif
+
+} // namespace Session
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_SESSION_HPP
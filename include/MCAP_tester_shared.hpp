@@ -0,0 +1,693 @@
+/**
+ * @file MCAP_tester_shared.hpp
+ * @brief Provides the Tester::SharedMCAPTester class template for making
+ * assertions from several threads.
+ *
+ * A test harness that runs many scenarios on worker threads cannot share a
+ * plain MCAPTester, since its flag, counters and records are not
+ * synchronized. SharedMCAPTester owns one MCAPTester and serializes every
+ * call to it with a mutex, so all workers report into one aggregated set of
+ * records and one report. The output of concurrent failures is never
+ * interleaved, but the order of the records follows the order in which the
+ * workers acquire the lock. Programs using this header must be built with
+ * thread support, e.g. -pthread.
+ */
+
+#ifndef MCAP_TESTER_SHARED_HPP
+#define MCAP_TESTER_SHARED_HPP
+
+#include <cstddef>
+#include <functional>
+#include <mutex>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+namespace Tester {
+
+/**
+ * @brief A thread-safe wrapper around MCAPTester.
+ *
+ * Every assertion method of MCAPTester has a counterpart that forwards its
+ * arguments unchanged to the overloads of the same name. Functions passed to
+ * an assertion, e.g. a scenario or a reference model, run under the lock
+ * and must not call the shared tester. Any other method, including
+ * configuration, can be called under the lock through apply. The call-site
+ * macros are not supported, since the call site is tester state shared
+ * between threads; set the call site inside apply instead.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> class SharedMCAPTester {
+public:
+  SharedMCAPTester() : _mutex(), _tester() {}
+
+  /**
+   * @brief Creates a shared tester from a configured tester, e.g. the result
+   * of MCAPTester<T>::builder().
+   */
+  explicit SharedMCAPTester(const MCAPTester<T> &tester)
+      : _mutex(), _tester(tester) {}
+
+  /**
+   * @brief Calls a function with exclusive access to the underlying tester.
+   *
+   * Example: shared.apply([&](MCAPTester<double> &tester) {
+   *   tester.set_call_site(__FILE__, __LINE__);
+   *   tester.expect_near_trajectory(actual, expected, 1e-6, "trajectory");
+   * });
+   *
+   * @param function The function to call; it must not call the shared tester
+   * itself.
+   */
+  void apply(const std::function<void(MCAPTester<T> &)> &function) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    function(this->_tester);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near.
+   */
+  template <typename... Arguments> void expect_near(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_default.
+   */
+  template <typename... Arguments>
+  void expect_near_default(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_default(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_true.
+   */
+  void expect_true(bool condition, const std::string &message,
+                   const std::string &detail = std::string(),
+                   FailureCategory category = FailureCategory::Assertion) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_true(condition, message, detail, category);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_eq_array_int.
+   */
+  template <typename... Arguments>
+  void expect_eq_array_int(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_eq_array_int(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_signals.
+   */
+  template <typename... Arguments>
+  void expect_near_signals(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_signals(std::forward<Arguments>(arguments)...);
+  }
+
+#ifdef MCAP_TESTER_ENABLE_MATFILE
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_matfile.
+   */
+  template <typename... Arguments>
+  void expect_near_matfile(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_matfile(std::forward<Arguments>(arguments)...);
+  }
+#endif
+
+#ifdef MCAP_TESTER_ENABLE_SPARSE
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_sparse.
+   */
+  template <typename... Arguments>
+  void expect_near_sparse(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_sparse(std::forward<Arguments>(arguments)...);
+  }
+#endif
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Thread-safe MCAPTester::run_fixture.
+   */
+  template <typename... Arguments> void run_fixture(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.run_fixture(std::forward<Arguments>(arguments)...);
+  }
+#endif
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_trajectory.
+   */
+  template <typename... Arguments>
+  void expect_near_trajectory(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_trajectory(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_resampled.
+   */
+  template <typename... Arguments>
+  void expect_near_resampled(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_resampled(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_pass_rate_above.
+   */
+  template <typename... Arguments>
+  void expect_pass_rate_above(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_pass_rate_above(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_run_to_run_variance_below.
+   */
+  template <typename... Arguments>
+  void expect_run_to_run_variance_below(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_run_to_run_variance_below(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_reset_restores_state.
+   */
+  template <typename... Arguments>
+  void expect_reset_restores_state(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_reset_restores_state(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_behavior_at_bounds.
+   */
+  template <typename... Arguments>
+  void expect_behavior_at_bounds(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_behavior_at_bounds(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_trajectory_adaptive.
+   */
+  template <typename... Arguments>
+  void expect_near_trajectory_adaptive(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_trajectory_adaptive(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_spectrum.
+   */
+  template <typename... Arguments>
+  void expect_near_spectrum(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_spectrum(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_rmse_below.
+   */
+  template <typename... Arguments>
+  void expect_rmse_below(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_rmse_below(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_mae_below.
+   */
+  template <typename... Arguments>
+  void expect_mae_below(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_mae_below(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_max_error_below.
+   */
+  template <typename... Arguments>
+  void expect_max_error_below(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_max_error_below(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_rms_below.
+   */
+  template <typename... Arguments>
+  void expect_rms_below(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_rms_below(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_peak_to_peak_below.
+   */
+  template <typename... Arguments>
+  void expect_peak_to_peak_below(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_peak_to_peak_below(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_total_effort_below.
+   */
+  template <typename... Arguments>
+  void expect_total_effort_below(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_total_effort_below(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_not_significantly_worse.
+   */
+  template <typename... Arguments>
+  void expect_not_significantly_worse(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_not_significantly_worse(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_mean_var.
+   */
+  template <typename... Arguments>
+  void expect_near_mean_var(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_mean_var(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_same_distribution.
+   */
+  template <typename... Arguments>
+  void expect_same_distribution(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_same_distribution(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_norm.
+   */
+  template <typename... Arguments>
+  void expect_near_norm(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_norm(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_accumulation.
+   */
+  template <typename... Arguments>
+  void expect_near_accumulation(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_accumulation(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_matches_integration.
+   */
+  template <typename... Arguments>
+  void expect_matches_integration(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_matches_integration(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_steady_state_near.
+   */
+  template <typename... Arguments>
+  void expect_steady_state_near(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_steady_state_near(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_no_limit_cycle.
+   */
+  template <typename... Arguments>
+  void expect_no_limit_cycle(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_no_limit_cycle(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_saturation_consistent.
+   */
+  template <typename... Arguments>
+  void expect_saturation_consistent(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_saturation_consistent(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_rate_limiter_consistent.
+   */
+  template <typename... Arguments>
+  void expect_rate_limiter_consistent(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_rate_limiter_consistent(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_no_integrator_windup.
+   */
+  template <typename... Arguments>
+  void expect_no_integrator_windup(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_no_integrator_windup(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_monotonic_increasing.
+   */
+  template <typename... Arguments>
+  void expect_monotonic_increasing(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_monotonic_increasing(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_monotonic_decreasing.
+   */
+  template <typename... Arguments>
+  void expect_monotonic_decreasing(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_monotonic_decreasing(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_all_in_range.
+   */
+  template <typename... Arguments>
+  void expect_all_in_range(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_all_in_range(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_reduced_near.
+   */
+  template <typename... Arguments>
+  void expect_reduced_near(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_reduced_near(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_row_sums_near.
+   */
+  template <typename... Arguments>
+  void expect_row_sums_near(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_row_sums_near(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_col_sums_near.
+   */
+  template <typename... Arguments>
+  void expect_col_sums_near(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_col_sums_near(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_row_means_near.
+   */
+  template <typename... Arguments>
+  void expect_row_means_near(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_row_means_near(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_col_means_near.
+   */
+  template <typename... Arguments>
+  void expect_col_means_near(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_col_means_near(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_symmetric.
+   */
+  template <typename... Arguments>
+  void expect_symmetric(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_symmetric(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_positive_definite.
+   */
+  template <typename... Arguments>
+  void expect_positive_definite(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_positive_definite(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_valid_covariance.
+   */
+  template <typename... Arguments>
+  void expect_valid_covariance(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_valid_covariance(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_orthogonal.
+   */
+  template <typename... Arguments>
+  void expect_orthogonal(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_orthogonal(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_diagonally_dominant.
+   */
+  template <typename... Arguments>
+  void expect_diagonally_dominant(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_diagonally_dominant(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_unordered.
+   */
+  template <typename... Arguments>
+  void expect_near_unordered(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_unordered(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_angle_scalar.
+   */
+  template <typename... Arguments>
+  void expect_near_angle_scalar(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_angle_scalar(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_angle.
+   */
+  template <typename... Arguments>
+  void expect_near_angle(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_angle(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_quaternion.
+   */
+  template <typename... Arguments>
+  void expect_near_quaternion(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_quaternion(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_rotation.
+   */
+  template <typename... Arguments>
+  void expect_near_rotation(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_rotation(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_near_phasor.
+   */
+  template <typename... Arguments>
+  void expect_near_phasor(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_near_phasor(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_stable_continuous.
+   */
+  template <typename... Arguments>
+  void expect_stable_continuous(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_stable_continuous(
+        std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_stable_discrete.
+   */
+  template <typename... Arguments>
+  void expect_stable_discrete(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_stable_discrete(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_fingerprint_matches.
+   */
+  template <typename... Arguments>
+  void expect_fingerprint_matches(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_fingerprint_matches(
+        std::forward<Arguments>(arguments)...);
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Thread-safe MCAPTester::expect_matches_snapshot.
+   */
+  template <typename... Arguments>
+  void expect_matches_snapshot(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_matches_snapshot(
+        std::forward<Arguments>(arguments)...);
+  }
+#endif
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_runtime_below.
+   */
+  template <typename... Arguments>
+  void expect_runtime_below(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_runtime_below(std::forward<Arguments>(arguments)...);
+  }
+
+  /**
+   * @brief Thread-safe MCAPTester::expect_all_modes_exercised.
+   */
+  template <typename... Arguments>
+  void expect_all_modes_exercised(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.expect_all_modes_exercised(
+        std::forward<Arguments>(arguments)...);
+  }
+
+#ifndef MCAP_TESTER_NO_STDIO
+  /**
+   * @brief Thread-safe MCAPTester::replay_session.
+   */
+  template <typename... Arguments>
+  void replay_session(Arguments &&...arguments) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.replay_session(std::forward<Arguments>(arguments)...);
+  }
+#endif
+
+  /**
+   * @brief Thread-safe MCAPTester::check_regression_budgets.
+   */
+  std::vector<LedgerEntry> check_regression_budgets(
+      const std::vector<std::vector<AssertionRecord>> &history) {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    return this->_tester.check_regression_budgets(history);
+  }
+
+  /**
+   * @brief Returns a copy of the records of all assertions made so far.
+   */
+  std::vector<AssertionRecord> get_records() const {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    return this->_tester.get_records();
+  }
+
+  /**
+   * @brief Returns the number of warnings reported so far.
+   */
+  std::size_t get_warning_count() const {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    return this->_tester.get_warning_count();
+  }
+
+  /**
+   * @brief Writes the aggregated records to a JSON report file.
+   *
+   * @return true on success, false if the file cannot be written.
+   */
+  bool write_report(const std::string &path, const std::string &name) const {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    return this->_tester.write_report(path, name);
+  }
+
+  /**
+   * @brief Throws std::runtime_error if any assertion from any thread
+   * failed.
+   */
+  void throw_error_if_test_failed() {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.throw_error_if_test_failed();
+  }
+
+  /**
+   * @brief Resets the test failed flag.
+   */
+  void reset_test_failed_flag() {
+    std::lock_guard<std::mutex> lock(this->_mutex);
+    this->_tester.reset_test_failed_flag();
+  }
+
+protected:
+  mutable std::mutex _mutex;
+  MCAPTester<T> _tester;
+};
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_SHARED_HPP
@@ -0,0 +1,212 @@
+/**
+ * @file MCAP_tester_sparse.hpp
+ * @brief Provides compressed sparse row matrices and their comparison for
+ * MCAP_tester.
+ *
+ * Generated solver code such as MPC usually stores Hessians and constraint
+ * matrices in compressed sparse row (CSR) form. A sparse comparison checks
+ * the sparsity patterns and the stored values separately, so a missing or
+ * extra nonzero is reported by its position instead of as an element
+ * mismatch against an implicit zero. This header is used by
+ * Tester::MCAPTester when MCAP_TESTER_ENABLE_SPARSE is defined.
+ */
+
+#ifndef MCAP_TESTER_SPARSE_HPP
+#define MCAP_TESTER_SPARSE_HPP
+
+#include <cmath>
+#include <cstddef>
+#include <utility>
+#include <vector>
+
+#include "MCAP_tester_comparison.hpp"
+
+namespace Tester {
+
+/**
+ * @brief A matrix in compressed sparse row form.
+ *
+ * The stored entries of row i are at positions row_pointers[i] to
+ * row_pointers[i + 1] - 1 of `column_indices` and `values`, so
+ * `row_pointers` has rows + 1 elements. Column indices within a row need not
+ * be sorted.
+ *
+ * @tparam T Type of the stored values.
+ */
+template <typename T> struct SparseMatrix {
+  SparseMatrix()
+      : rows(0), columns(0), row_pointers(1, 0), column_indices(), values() {}
+
+  std::size_t rows;
+  std::size_t columns;
+  std::vector<std::size_t> row_pointers;
+  std::vector<std::size_t> column_indices;
+  std::vector<T> values;
+};
+
+/**
+ * @brief Outcome of a sparse matrix comparison.
+ *
+ * `missing` lists the (row, column) positions stored in the expected matrix
+ * but not in the actual one, `extra` the positions stored only in the actual
+ * matrix. Values are compared at the positions stored in both; `index`,
+ * `actual_value` and `expected_value` of the inherited fields refer to the
+ * first mismatching value, whose position is `mismatch_position`.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T>
+struct SparseComparisonResult : public ComparisonResult<T> {
+  SparseComparisonResult()
+      : ComparisonResult<T>(), missing(), extra(), mismatch_position(0, 0) {}
+
+  std::vector<std::pair<std::size_t, std::size_t>> missing;
+  std::vector<std::pair<std::size_t, std::size_t>> extra;
+  std::pair<std::size_t, std::size_t> mismatch_position;
+};
+
+/**
+ * @brief Builds a sparse matrix from a dense one, storing every nonzero
+ * element.
+ *
+ * @param dense The dense matrix with shape {rows, columns}.
+ * @return The sparse matrix, or an empty 0 x 0 matrix if `dense` is not a
+ * regular two-dimensional array.
+ */
+template <typename T>
+inline SparseMatrix<T> make_sparse_matrix(const FlatArray<T> &dense) {
+  SparseMatrix<T> sparse;
+  if (dense.ragged || dense.shape.size() != 2) {
+    return sparse;
+  }
+
+  sparse.rows = dense.shape[0];
+  sparse.columns = dense.shape[1];
+  for (std::size_t i = 0; i < sparse.rows; i++) {
+    for (std::size_t j = 0; j < sparse.columns; j++) {
+      T value = dense.data[i * sparse.columns + j];
+      if (value != static_cast<T>(0)) {
+        sparse.column_indices.push_back(j);
+        sparse.values.push_back(value);
+      }
+    }
+    sparse.row_pointers.push_back(sparse.values.size());
+  }
+  return sparse;
+}
+
+namespace Comparison {
+
+namespace Detail {
+
+template <typename T>
+inline bool valid_sparse_matrix(const SparseMatrix<T> &matrix) {
+  if (matrix.row_pointers.size() != matrix.rows + 1 ||
+      matrix.row_pointers[0] != 0 ||
+      matrix.row_pointers.back() != matrix.values.size() ||
+      matrix.column_indices.size() != matrix.values.size()) {
+    return false;
+  }
+  for (std::size_t i = 0; i < matrix.rows; i++) {
+    if (matrix.row_pointers[i] > matrix.row_pointers[i + 1]) {
+      return false;
+    }
+  }
+  for (std::size_t k = 0; k < matrix.column_indices.size(); k++) {
+    if (matrix.column_indices[k] >= matrix.columns) {
+      return false;
+    }
+  }
+  return true;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Compares the sparsity patterns and the stored values of two sparse
+ * matrices.
+ *
+ * The matrices must have the same dimensions and be well formed; otherwise
+ * the result is a size mismatch. A pattern difference is an element
+ * mismatch even if all common values agree.
+ *
+ * @tparam T Type of the stored values.
+ * @param actual The actual sparse matrix.
+ * @param expected The expected sparse matrix.
+ * @param tolerance The maximum allowed difference between values stored at
+ * the same position.
+ * @return The structured comparison result.
+ */
+template <typename T>
+inline SparseComparisonResult<T>
+compare_sparse(const SparseMatrix<T> &actual, const SparseMatrix<T> &expected,
+               T tolerance) {
+  SparseComparisonResult<T> result;
+  if (actual.rows != expected.rows || actual.columns != expected.columns ||
+      !Detail::valid_sparse_matrix(actual) ||
+      !Detail::valid_sparse_matrix(expected)) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::SizeMismatch;
+    return result;
+  }
+
+  const std::size_t none = static_cast<std::size_t>(-1);
+  std::vector<std::size_t> actual_position(actual.columns, none);
+  for (std::size_t i = 0; i < actual.rows; i++) {
+    for (std::size_t k = actual.row_pointers[i];
+         k < actual.row_pointers[i + 1]; k++) {
+      actual_position[actual.column_indices[k]] = k;
+    }
+
+    for (std::size_t k = expected.row_pointers[i];
+         k < expected.row_pointers[i + 1]; k++) {
+      std::size_t j = expected.column_indices[k];
+      std::size_t a = actual_position[j];
+      if (a == none) {
+        result.missing.push_back(std::make_pair(i, j));
+        continue;
+      }
+      actual_position[j] = none;
+
+      T error = std::abs(actual.values[a] - expected.values[k]);
+      if (std::isnan(error) || error > result.max_error) {
+        result.max_error = error;
+      }
+      if (error <= tolerance) {
+        continue;
+      }
+
+      result.mismatch_count++;
+      if (result.status != ComparisonStatus::Failure) {
+        result.status = ComparisonStatus::Failure;
+        result.kind = MismatchKind::ElementMismatch;
+        result.index = a;
+        result.actual_value = actual.values[a];
+        result.expected_value = expected.values[k];
+        result.error = error;
+        result.mismatch_position = std::make_pair(i, j);
+      }
+    }
+
+    for (std::size_t k = actual.row_pointers[i];
+         k < actual.row_pointers[i + 1]; k++) {
+      std::size_t j = actual.column_indices[k];
+      if (actual_position[j] != none) {
+        result.extra.push_back(std::make_pair(i, j));
+        actual_position[j] = none;
+      }
+    }
+  }
+
+  if (!result.missing.empty() || !result.extra.empty()) {
+    result.status = ComparisonStatus::Failure;
+    result.kind = MismatchKind::ElementMismatch;
+  }
+  return result;
+}
+
+} // namespace Comparison
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_SPARSE_HPP
@@ -0,0 +1,238 @@
+/**
+ * @file MCAP_tester_state_space.hpp
+ * @brief Provides the Tester::StateSpaceTest class template for checking
+ * generated discrete state-space code against a reference simulation.
+ *
+ * Generated state-space code is usually validated by simulating a step or an
+ * impulse and comparing the trajectories. A StateSpaceTest holds the (A, B,
+ * C, D) model as a Reference::StateSpace, simulates the reference response
+ * for any input sequence and compares it with the output of the generated
+ * implementation, given either as a logged output sequence or as a function
+ * stepping the implementation once per sample:
+ *
+ *   Reference::StateSpace<double> model(A, B, C, D);
+ *   StateSpaceTest<double> test(tester, model);
+ *   test.expect_step_response(100, [&](const std::vector<double> &u) {
+ *     return controller.step(u);
+ *   }, 1e-12, "controller step response");
+ *
+ * Every comparison is reported like expect_near_trajectory, with one row per
+ * sample and one column per output.
+ */
+
+#ifndef MCAP_TESTER_STATE_SPACE_HPP
+#define MCAP_TESTER_STATE_SPACE_HPP
+
+#include <cstddef>
+#include <functional>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+#include "MCAP_tester_reference.hpp"
+
+namespace Tester {
+
+/**
+ * @brief Compares generated state-space code with the simulated response of
+ * its model.
+ *
+ * @tparam T Type of the signals.
+ */
+template <typename T> class StateSpaceTest {
+public:
+  typedef std::vector<std::vector<T>> Matrix;
+  typedef std::function<std::vector<T>(const std::vector<T> &)>
+      Implementation;
+
+  /**
+   * @brief Creates a test that reports through a tester.
+   *
+   * @param tester The tester that receives every comparison; it must outlive
+   * the test.
+   * @param model The reference model; its initial state is the initial
+   * state of every simulation.
+   */
+  StateSpaceTest(MCAPTester<T> &tester, const Reference::StateSpace<T> &model)
+      : _tester(tester), _model(model) {}
+
+  /**
+   * @brief Returns an input sequence whose channel `channel` is `amplitude`
+   * at every sample and whose other channels are 0.
+   */
+  static Matrix step_input(std::size_t horizon, std::size_t input_count,
+                           std::size_t channel,
+                           T amplitude = static_cast<T>(1)) {
+    Matrix inputs(horizon, std::vector<T>(input_count, static_cast<T>(0)));
+    for (std::size_t k = 0; k < horizon && channel < input_count; k++) {
+      inputs[k][channel] = amplitude;
+    }
+    return inputs;
+  }
+
+  /**
+   * @brief Returns an input sequence whose channel `channel` is `amplitude`
+   * at the first sample and 0 otherwise, i.e. a discrete unit impulse.
+   */
+  static Matrix impulse_input(std::size_t horizon, std::size_t input_count,
+                              std::size_t channel,
+                              T amplitude = static_cast<T>(1)) {
+    Matrix inputs(horizon, std::vector<T>(input_count, static_cast<T>(0)));
+    if (horizon > 0 && channel < input_count) {
+      inputs[0][channel] = amplitude;
+    }
+    return inputs;
+  }
+
+  /**
+   * @brief Returns the simulated reference response to an input sequence.
+   */
+  Matrix reference_response(const Matrix &inputs) const {
+    Reference::StateSpace<T> model = this->_model;
+    return model.simulate(inputs);
+  }
+
+  /**
+   * @brief Checks a logged output sequence against the reference response to
+   * the input sequence that produced it.
+   *
+   * @param inputs One input vector per sample.
+   * @param actual_outputs One output vector per sample of the implementation.
+   * @param tolerance The tolerance per output sample.
+   * @param message The message to display in case of failure.
+   * @param settling_samples The number of leading samples to exclude.
+   */
+  void expect_output(const Matrix &inputs, const Matrix &actual_outputs,
+                     const Tolerance<T> &tolerance,
+                     const std::string &message,
+                     std::size_t settling_samples = 0) {
+    std::string error = this->_validate(inputs);
+    if (!error.empty()) {
+      this->_tester.expect_true(false, message, error,
+                                FailureCategory::Structural);
+      return;
+    }
+    this->_tester.expect_near_trajectory(actual_outputs,
+                                         this->reference_response(inputs),
+                                         tolerance, message, settling_samples);
+  }
+
+  /**
+   * @brief Steps an implementation through an input sequence and checks its
+   * outputs against the reference response.
+   *
+   * The implementation is called once per sample, in order, and must be in
+   * its initial state before the call.
+   *
+   * @param inputs One input vector per sample.
+   * @param implementation Steps the generated code with u[k] and returns
+   * y[k].
+   * @param tolerance The tolerance per output sample.
+   * @param message The message to display in case of failure.
+   * @param settling_samples The number of leading samples to exclude.
+   */
+  void expect_response(const Matrix &inputs,
+                       const Implementation &implementation,
+                       const Tolerance<T> &tolerance,
+                       const std::string &message,
+                       std::size_t settling_samples = 0) {
+    std::string error = this->_validate(inputs);
+    if (!error.empty()) {
+      this->_tester.expect_true(false, message, error,
+                                FailureCategory::Structural);
+      return;
+    }
+    Matrix actual_outputs;
+    for (std::size_t k = 0; k < inputs.size(); k++) {
+      actual_outputs.push_back(implementation(inputs[k]));
+    }
+    this->expect_output(inputs, actual_outputs, tolerance, message,
+                        settling_samples);
+  }
+
+  /**
+   * @brief Checks the response of an implementation to a step on one input
+   * channel.
+   *
+   * @param horizon The number of samples to simulate.
+   * @param implementation Steps the generated code with u[k] and returns
+   * y[k].
+   * @param tolerance The tolerance per output sample.
+   * @param message The message to display in case of failure.
+   * @param channel The input channel that receives the step.
+   * @param amplitude The height of the step.
+   */
+  void expect_step_response(std::size_t horizon,
+                            const Implementation &implementation,
+                            const Tolerance<T> &tolerance,
+                            const std::string &message,
+                            std::size_t channel = 0,
+                            T amplitude = static_cast<T>(1)) {
+    if (!this->_accept_channel(channel, message)) {
+      return;
+    }
+    this->expect_response(step_input(horizon, this->_model.input_count(),
+                                     channel, amplitude),
+                          implementation, tolerance, message);
+  }
+
+  /**
+   * @brief Checks the response of an implementation to a discrete impulse
+   * on one input channel.
+   *
+   * @param horizon The number of samples to simulate.
+   * @param implementation Steps the generated code with u[k] and returns
+   * y[k].
+   * @param tolerance The tolerance per output sample.
+   * @param message The message to display in case of failure.
+   * @param channel The input channel that receives the impulse.
+   * @param amplitude The height of the impulse.
+   */
+  void expect_impulse_response(std::size_t horizon,
+                               const Implementation &implementation,
+                               const Tolerance<T> &tolerance,
+                               const std::string &message,
+                               std::size_t channel = 0,
+                               T amplitude = static_cast<T>(1)) {
+    if (!this->_accept_channel(channel, message)) {
+      return;
+    }
+    this->expect_response(impulse_input(horizon, this->_model.input_count(),
+                                        channel, amplitude),
+                          implementation, tolerance, message);
+  }
+
+protected:
+  std::string _validate(const Matrix &inputs) const {
+    if (!this->_model.valid()) {
+      return "The state-space matrices have inconsistent dimensions.";
+    }
+    for (std::size_t k = 0; k < inputs.size(); k++) {
+      if (inputs[k].size() != this->_model.input_count()) {
+        return "Input sample " + std::to_string(k) + " has " +
+               std::to_string(inputs[k].size()) + " elements, expected " +
+               std::to_string(this->_model.input_count()) + ".";
+      }
+    }
+    return std::string();
+  }
+
+  bool _accept_channel(std::size_t channel, const std::string &message) {
+    if (channel < this->_model.input_count()) {
+      return true;
+    }
+    this->_tester.expect_true(
+        false, message,
+        "Input channel " + std::to_string(channel) + " out of range (" +
+            std::to_string(this->_model.input_count()) + " inputs).",
+        FailureCategory::Structural);
+    return false;
+  }
+
+  MCAPTester<T> &_tester;
+  Reference::StateSpace<T> _model;
+};
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_STATE_SPACE_HPP
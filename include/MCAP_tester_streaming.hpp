@@ -0,0 +1,113 @@
+/**
+ * @file MCAP_tester_streaming.hpp
+ * @brief Provides the Tester::StreamingComparator class template for
+ * comparing logs too large to hold in memory.
+ *
+ * HIL logs can be gigabytes long, so loading both the actual and the expected
+ * log before comparing them is not an option. The streaming comparator
+ * receives both logs in chunks of equal length, compares each chunk as it
+ * arrives and keeps only the accumulated verdict: the first mismatch, the
+ * number of mismatches and the maximum error. Memory use is bounded by the
+ * chunk size, and the final verdict is identical to the one of a single
+ * comparison over the whole logs.
+ */
+
+#ifndef MCAP_TESTER_STREAMING_HPP
+#define MCAP_TESTER_STREAMING_HPP
+
+#include <cstddef>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+namespace Tester {
+
+/**
+ * @brief Compares two streams chunk by chunk within a tolerance.
+ *
+ * @tparam T Type of the compared values.
+ */
+template <typename T> class StreamingComparator {
+public:
+  /**
+   * @brief Creates a comparator for streams that must agree within a
+   * tolerance.
+   *
+   * @param tolerance The maximum allowed difference between corresponding
+   * elements.
+   */
+  explicit StreamingComparator(T tolerance)
+      : _tolerance(tolerance), _element_count(0), _result() {}
+
+  /**
+   * @brief Compares the next chunk of both streams.
+   *
+   * Both chunks must have the same length. A chunk pair of different lengths
+   * turns the result into a size mismatch, and later chunks are ignored.
+   *
+   * @param actual_chunk The next chunk of actual values.
+   * @param expected_chunk The next chunk of expected values.
+   */
+  void push(const std::vector<T> &actual_chunk,
+            const std::vector<T> &expected_chunk) {
+    if (this->_result.kind == MismatchKind::SizeMismatch) {
+      return;
+    }
+    if (actual_chunk.size() != expected_chunk.size()) {
+      this->_result.status = ComparisonStatus::Failure;
+      this->_result.kind = MismatchKind::SizeMismatch;
+      return;
+    }
+
+    ComparisonResult<T> chunk = Comparison::compare_arrays(
+        make_flat_array(actual_chunk), make_flat_array(expected_chunk),
+        this->_tolerance);
+    chunk.index += this->_element_count;
+    Comparison::Detail::merge_range(this->_result, chunk);
+    this->_element_count += actual_chunk.size();
+  }
+
+  /**
+   * @brief Returns the number of element pairs compared so far.
+   */
+  std::size_t get_element_count() const { return this->_element_count; }
+
+  /**
+   * @brief Returns the accumulated result; `index` is the position of the
+   * first mismatch in the whole stream.
+   */
+  const ComparisonResult<T> &get_result() const { return this->_result; }
+
+  /**
+   * @brief Reports the accumulated result through a tester.
+   *
+   * @param tester The tester that receives the verdict.
+   * @param message The message to display in case of failure.
+   * @return true if the streams matched.
+   */
+  bool finish(MCAPTester<T> &tester, const std::string &message) const {
+    std::string detail = this->_result.describe();
+    if (this->_result.kind == MismatchKind::ElementMismatch) {
+      detail += " " + std::to_string(this->_result.mismatch_count) + " of " +
+                std::to_string(this->_element_count) +
+                " elements outside tolerance, first at element " +
+                std::to_string(this->_result.index) + ": " +
+                format_number(this->_result.actual_value) + " vs " +
+                format_number(this->_result.expected_value) +
+                ". Max error " + format_number(this->_result.max_error) + ".";
+    }
+
+    tester.expect_true(this->_result.passed(), message, detail);
+    return this->_result.passed();
+  }
+
+protected:
+  T _tolerance;
+  std::size_t _element_count;
+  ComparisonResult<T> _result;
+};
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_STREAMING_HPP
@@ -0,0 +1,280 @@
+/**
+ * @file MCAP_tester_tuning.hpp
+ * @brief Provides an interactive prompt for trying alternative tolerances on
+ * a failed comparison during target bring-up.
+ *
+ * Negotiating tolerances for a new target usually means rerunning a long
+ * scenario for every candidate value. Tuning::attach instead opens a prompt
+ * whenever a recorded comparison fails: the recorded arrays are compared
+ * again with every tolerance and shape policy the user enters, and the
+ * prompt prints whether the comparison would pass, without touching the
+ * verdict of the test. An input line is either a command or a candidate:
+ *
+ *   1e-6                          absolute tolerance 1e-6
+ *   abs=1e-9 rel=1e-6 ulps=4      combined tolerance, see Tolerance
+ *   rel=1e-6 policy=broadcast     with the shape policy exact, broadcast or
+ *                                 strict instead of the recorded one
+ *   show                          the recorded comparison and its verdict
+ *   help                          the list of inputs
+ *   continue                      resumes the test, as do an empty line
+ *                                 and the end of the input
+ *
+ * Angle comparisons only take an absolute tolerance. Since the prompt reads
+ * until the end of its input, a test with an attached prompt still finishes
+ * in CI when the standard input is closed or /dev/null.
+ */
+
+#ifndef MCAP_TESTER_TUNING_HPP
+#define MCAP_TESTER_TUNING_HPP
+
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <iostream>
+#include <sstream>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester.hpp"
+
+namespace Tester {
+
+namespace Tuning {
+
+namespace Detail {
+
+inline std::string policy_name(ShapePolicy policy) {
+  switch (policy) {
+  case ShapePolicy::Broadcast:
+    return "broadcast";
+  case ShapePolicy::Strict:
+    return "strict";
+  default:
+    return "exact";
+  }
+}
+
+inline bool parse_policy(const std::string &text, ShapePolicy &policy) {
+  if (text == "exact") {
+    policy = ShapePolicy::Exact;
+  } else if (text == "broadcast") {
+    policy = ShapePolicy::Broadcast;
+  } else if (text == "strict") {
+    policy = ShapePolicy::Strict;
+  } else {
+    return false;
+  }
+  return true;
+}
+
+inline bool parse_number(const std::string &text, double &number) {
+  char *end = nullptr;
+  number = std::strtod(text.c_str(), &end);
+  return !text.empty() && *end == '\0' && number >= 0.0;
+}
+
+/* Parses a candidate line into a tolerance and a shape policy. */
+template <typename T>
+inline bool parse_candidate(const std::string &line, Tolerance<T> &tolerance,
+                            ShapePolicy &policy, std::string &error) {
+  std::istringstream tokens(line);
+  std::string token;
+  double absolute = 0.0;
+  double relative = 0.0;
+  double ulps = 0.0;
+  while (tokens >> token) {
+    std::size_t equals = token.find('=');
+    std::string key = equals == std::string::npos ? "abs"
+                                                  : token.substr(0, equals);
+    std::string value = equals == std::string::npos
+                            ? token
+                            : token.substr(equals + 1);
+    bool valid = false;
+    if (key == "abs") {
+      valid = parse_number(value, absolute);
+    } else if (key == "rel") {
+      valid = parse_number(value, relative);
+    } else if (key == "ulps") {
+      valid = parse_number(value, ulps);
+    } else if (key == "policy") {
+      valid = parse_policy(value, policy);
+    }
+    if (!valid) {
+      error = "Invalid input " + token + "; type help for the inputs.";
+      return false;
+    }
+  }
+  tolerance =
+      Tolerance<T>::absolute_relative(static_cast<T>(absolute),
+                                      static_cast<T>(relative))
+          .or_ulps(static_cast<std::uint64_t>(ulps));
+  return true;
+}
+
+template <typename T>
+inline std::string describe_outcome(const ComparisonResult<T> &result) {
+  if (result.kind == MismatchKind::SizeMismatch ||
+      result.kind == MismatchKind::LayoutMismatch) {
+    return "would fail: " + result.describe();
+  } else if (!result.passed()) {
+    return "would fail: " + std::to_string(result.mismatch_count) +
+           " element(s) outside, largest error " +
+           format_number(result.max_error) + ".";
+  }
+  return "would pass, largest error " + format_number(result.max_error) +
+         ".";
+}
+
+template <typename T>
+inline std::string describe_entry(const SessionEntry<T> &entry) {
+  std::string text = "Tuning \"" + entry.message + "\"";
+  const std::vector<double> &parameters = entry.parameters;
+  if (entry.kind == "near" && parameters.size() == 3) {
+    text += ": recorded tolerance " + format_number(parameters[1]);
+    if (parameters[0] != parameters[1]) {
+      text += " (warning " + format_number(parameters[0]) + ")";
+    }
+    text += ", policy " +
+            policy_name(static_cast<ShapePolicy>(
+                static_cast<int>(parameters[2])));
+  } else if (entry.kind == "angle" && parameters.size() == 2) {
+    text += ": recorded angle tolerance " + format_number(parameters[0]);
+  }
+
+  ComparisonResult<T> result;
+  if (Session::evaluate(entry, result)) {
+    text += ". " + result.describe();
+    if (result.kind != MismatchKind::SizeMismatch &&
+        result.kind != MismatchKind::LayoutMismatch &&
+        !std::isnan(result.max_error)) {
+      text += " The smallest passing absolute tolerance is " +
+              format_number(result.max_error) + ".";
+    }
+  }
+  return text;
+}
+
+} // namespace Detail
+
+/**
+ * @brief Compares a recorded comparison again with another tolerance and
+ * shape policy.
+ *
+ * @param entry The recorded comparison.
+ * @param tolerance The candidate tolerance; angle comparisons use only its
+ * absolute part.
+ * @param policy The candidate shape policy; ignored for angle comparisons.
+ * @param result Receives the comparison result.
+ * @return true on success, false if the kind or its parameters are unknown.
+ */
+template <typename T>
+inline bool reevaluate(const SessionEntry<T> &entry,
+                       const Tolerance<T> &tolerance, ShapePolicy policy,
+                       ComparisonResult<T> &result) {
+  if (entry.kind == "near" && entry.parameters.size() == 3) {
+    result = Comparison::compare_arrays(entry.actual, entry.expected,
+                                        tolerance, policy);
+    return true;
+  } else if (entry.kind == "angle" && entry.parameters.size() == 2) {
+    AngleUnit unit =
+        static_cast<AngleUnit>(static_cast<int>(entry.parameters[1]));
+    result = Comparison::compare_angles(entry.actual, entry.expected,
+                                        tolerance.absolute_part(), unit);
+    return true;
+  }
+  return false;
+}
+
+/**
+ * @brief Runs the tuning prompt for one recorded comparison until the user
+ * continues or the input ends.
+ *
+ * @param entry The recorded comparison, typically a failed one.
+ * @param input The stream the candidates are read from.
+ * @param output The stream the prompt and the outcomes are written to.
+ */
+template <typename T>
+inline void prompt(const SessionEntry<T> &entry, std::istream &input,
+                   std::ostream &output) {
+  const char *help =
+      "Enter a tolerance as 1e-6 or abs=A rel=R ulps=N, optionally with "
+      "policy=exact|broadcast|strict; show, help, or continue (or an empty "
+      "line).";
+  ShapePolicy recorded = ShapePolicy::Exact;
+  if (entry.kind == "near" && entry.parameters.size() == 3) {
+    recorded = static_cast<ShapePolicy>(static_cast<int>(entry.parameters[2]));
+  }
+
+  output << Detail::describe_entry(entry) << std::endl << help << std::endl;
+  std::string line;
+  while (output << "tune> " << std::flush && std::getline(input, line)) {
+    std::string command = line;
+    command.erase(0, command.find_first_not_of(" \t"));
+    command.erase(command.find_last_not_of(" \t\r") + 1);
+    if (command.empty() || command == "continue" || command == "c") {
+      break;
+    } else if (command == "help") {
+      output << help << std::endl;
+      continue;
+    } else if (command == "show") {
+      output << Detail::describe_entry(entry) << std::endl;
+      continue;
+    }
+
+    Tolerance<T> tolerance;
+    ShapePolicy policy = recorded;
+    std::string error;
+    ComparisonResult<T> result;
+    if (!Detail::parse_candidate(command, tolerance, policy, error)) {
+      output << error << std::endl;
+    } else if (!reevaluate(entry, tolerance, policy, result)) {
+      output << "The comparison kind " << entry.kind
+             << " cannot be re-evaluated." << std::endl;
+    } else if (entry.kind == "angle" && !tolerance.is_absolute()) {
+      output << "Angle comparisons only take an absolute tolerance."
+             << std::endl;
+    } else {
+      output << tolerance.describe() << ", policy "
+             << Detail::policy_name(policy) << ": "
+             << Detail::describe_outcome(result) << std::endl;
+    }
+  }
+  output << std::endl;
+}
+
+/**
+ * @brief Opens the tuning prompt for every recorded comparison that fails on
+ * a tester from now on.
+ *
+ * Session recording is enabled on the tester, so the element-wise
+ * comparisons (expect_near with scalar tolerances and the angle
+ * comparisons) keep their inputs. The tester must stay at its address while
+ * attached.
+ *
+ * @param tester The tester to watch.
+ * @param input The stream the candidates are read from.
+ * @param output The stream the prompt and the outcomes are written to.
+ */
+template <typename T>
+inline void attach(MCAPTester<T> &tester, std::istream &input = std::cin,
+                   std::ostream &output = std::cout) {
+  tester.set_session_recording(true);
+  MCAPTester<T> *watched = &tester;
+  std::istream *in = &input;
+  std::ostream *out = &output;
+  tester.on_failure([watched, in, out](const AssertionRecord &record) {
+    const std::vector<SessionEntry<T>> &entries =
+        watched->get_session_entries();
+    if (!entries.empty() && entries.back().message == record.message &&
+        entries.back().status == ComparisonStatus::Failure) {
+      prompt(entries.back(), *in, *out);
+    }
+  });
+}
+
+} // namespace Tuning
+
+} // namespace Tester
+
+#endif // MCAP_TESTER_TUNING_HPP
@@ -0,0 +1,8 @@
+/**
+ * @file main.cpp
+ * @brief Provides the main function of the mcap-test executable, which runs
+ * every test registered by the test_*.cpp files.
+ */
+
+#define MCAP_TESTER_RUNNER_MAIN
+#include "MCAP_tester_runner.hpp"
@@ -0,0 +1,853 @@
+/**
+ * @file test_assertions.cpp
+ * @brief Tests the element-wise assertions of Tester::MCAPTester: a passing
+ * and a failing case for every kind of check.
+ */
+
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <cstring>
+#include <functional>
+#include <limits>
+#include <map>
+#include <stdexcept>
+#include <string>
+#include <thread>
+#include <vector>
+
+#include "MCAP_tester_reference_runner.hpp"
+#include "MCAP_tester_runner.hpp"
+#include "MCAP_tester_shared.hpp"
+#include "test_support.hpp"
+
+using namespace Tester;
+using TestSupport::expect_failed;
+using TestSupport::expect_passed;
+using TestSupport::quiet_tester;
+
+namespace {
+
+void append_u32(std::string &bytes, std::uint32_t value) {
+  for (int shift = 0; shift < 32; shift += 8) {
+    bytes += static_cast<char>((value >> shift) & 0xFF);
+  }
+}
+
+void append_element(std::string &bytes, std::uint32_t type,
+                    const std::string &payload) {
+  append_u32(bytes, type);
+  append_u32(bytes, static_cast<std::uint32_t>(payload.size()));
+  bytes += payload;
+  bytes.append((8 - payload.size() % 8) % 8, '\0');
+}
+
+/* Builds a little-endian Level-5 MAT file holding one double matrix. */
+std::string matfile_bytes(const std::string &name, std::uint32_t rows,
+                          std::uint32_t columns,
+                          const std::vector<double> &column_major) {
+  std::string header(116, ' ');
+  header.append(8, '\0');
+  header += std::string("\x00\x01IM", 4);
+
+  std::string flags;
+  append_u32(flags, 6);
+  append_u32(flags, 0);
+  std::string dimensions;
+  append_u32(dimensions, rows);
+  append_u32(dimensions, columns);
+  std::string real;
+  for (std::size_t i = 0; i < column_major.size(); i++) {
+    std::uint64_t bits = 0;
+    std::memcpy(&bits, &column_major[i], sizeof(bits));
+    append_u32(real, static_cast<std::uint32_t>(bits));
+    append_u32(real, static_cast<std::uint32_t>(bits >> 32));
+  }
+
+  std::string matrix;
+  append_element(matrix, 6, flags);
+  append_element(matrix, 5, dimensions);
+  append_element(matrix, 1, name);
+  append_element(matrix, 9, real);
+  append_element(header, 14, matrix);
+  return header;
+}
+
+} // namespace
+
+MCAP_REGISTER_TEST(fingerprint_matches) {
+  std::vector<double> values = {0.1, 0.2, 0.3, 0.4};
+  std::uint64_t fingerprint =
+      compute_fingerprint(make_flat_array(values), 1e-6);
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_fingerprint_matches(
+      std::vector<double>{0.1 + 1e-9, 0.2, 0.3, 0.4}, fingerprint, 1e-6,
+      "fingerprint");
+  expect_passed(tester, tested, "values in the same bins pass");
+
+  tested.expect_fingerprint_matches(std::vector<double>{0.1, 0.2, 0.3, 0.5},
+                                    fingerprint, 1e-6, "fingerprint");
+  expect_failed(tester, tested, "a changed value fails");
+  TestSupport::expect_detail_contains(tester, tested, "Fingerprint mismatch",
+                                      "the failure names the mismatch");
+}
+
+MCAP_REGISTER_TEST(fingerprint_falls_back_to_the_golden_file) {
+  std::vector<double> golden = {0.1, 0.2, 0.3, 0.4};
+  TestSupport::TemporaryFile file("fingerprint_golden.txt");
+  tester.expect_true(save_flat_array(file.path(), make_flat_array(golden)),
+                     "the golden file is written");
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_fingerprint_matches(std::vector<double>{0.1, 0.2, 0.3, 0.4},
+                                    0, 1e-6, file.path(), "fingerprint");
+  expect_passed(tester, tested,
+                "a fingerprint mismatch within the quantum passes");
+
+  tested.expect_fingerprint_matches(std::vector<double>{0.1, 0.2, 0.3, 0.5},
+                                    0, 1e-6, file.path(), "fingerprint");
+  expect_failed(tester, tested, "a mismatch against the golden file fails");
+
+  tested.expect_fingerprint_matches(golden, 0, 1e-6, file.path() + ".missing",
+                                    "fingerprint");
+  expect_failed(tester, tested, "a missing golden file fails");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Infrastructure,
+                     "a missing golden file is an infrastructure failure");
+}
+
+MCAP_REGISTER_TEST(fixture_entries_use_their_tolerances) {
+  TestSupport::TemporaryFile fixture("fixture.json");
+  fixture.write("{\"tolerance\": 1e-9, \"entries\": ["
+                "{\"name\": \"gain\", \"expected\": 0.5},"
+                "{\"name\": \"K\", \"expected\": [[1.0, 2.0], [3.0, 4.0]],"
+                " \"tolerance\": 0.1}]}");
+  std::vector<std::vector<double>> K = {{1.0, 2.0}, {3.0, 4.05}};
+  std::function<FlatArray<double>(const std::string &)> matching =
+      [&K](const std::string &name) {
+        return name == "gain" ? make_flat_scalar(0.5) : make_flat_array(K);
+      };
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.run_fixture(fixture.path(), matching);
+  tester.expect_true(!tested.get_test_failed_flag(),
+                     "values within their entry tolerances pass");
+  TestSupport::expect_equal(tester, tested.get_records().size(), 2,
+                            "one assertion per entry");
+
+  K[1][1] = 4.2;
+  tested.run_fixture(fixture.path(), matching);
+  expect_failed(tester, tested, "a value outside its entry tolerance fails");
+  tester.expect_true(TestSupport::last_record(tested).message == "K",
+                     "the failure is named after the entry");
+}
+
+MCAP_REGISTER_TEST(fixture_malformed_or_missing_fails) {
+  std::function<FlatArray<double>(const std::string &)> zero =
+      [](const std::string &) { return make_flat_scalar(0.0); };
+  TestSupport::TemporaryFile fixture("fixture_malformed.json");
+  fixture.write("{\"entries\": [{\"expected\": 0.5, \"tolerance\": 1}]}");
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.run_fixture(fixture.path(), zero);
+  expect_failed(tester, tested, "an entry without a name fails");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Infrastructure,
+                     "a malformed entry is an infrastructure failure");
+
+  tested.run_fixture(fixture.path() + ".missing", zero);
+  expect_failed(tester, tested, "a missing fixture fails");
+}
+
+MCAP_REGISTER_TEST(dual_threshold_scalar) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near(1.0, 1.05, 0.1, 0.5, "scalar");
+  expect_passed(tester, tested, "within the warning tolerance passes");
+
+  tested.expect_near(1.0, 1.25, 0.1, 0.5, "scalar");
+  TestSupport::expect_warned(tester, tested,
+                             "between both tolerances warns");
+  TestSupport::expect_equal(tester, tested.get_warning_count(), 1,
+                            "the warning is counted");
+  tester.expect_true(!tested.get_test_failed_flag(),
+                     "a warning does not fail the test");
+
+  tested.expect_near(1.0, 2.0, 0.1, 0.5, "scalar");
+  expect_failed(tester, tested, "beyond the failure tolerance fails");
+}
+
+MCAP_REGISTER_TEST(dual_threshold_arrays) {
+  std::vector<double> expected = {1.0, 2.0, 3.0};
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near(std::vector<double>{1.0, 2.2, 3.0}, expected, 0.1, 0.5,
+                     "array");
+  TestSupport::expect_warned(tester, tested,
+                             "an element between both tolerances warns");
+
+  tested.expect_near(std::vector<double>{1.0, 2.2, 3.9}, expected, 0.1, 0.5,
+                     "array");
+  expect_failed(tester, tested, "an element beyond the failure tolerance "
+                                "fails");
+
+  tested.expect_near(std::vector<double>{1.0, 2.0, 3.0}, expected, -0.1, 0.5,
+                     "array");
+  expect_failed(tester, tested, "a negative warning tolerance is rejected");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "as a configuration error");
+}
+
+MCAP_REGISTER_TEST(matfile_matches_and_mismatches) {
+  TestSupport::TemporaryFile matfile("golden.mat");
+  matfile.write(matfile_bytes("gain", 2, 2, {1.0, 3.0, 2.0, 4.0}));
+  std::vector<std::vector<double>> actual = {{1.0, 2.0}, {3.0, 4.0}};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_matfile(actual, matfile.path(), "gain", 1e-12,
+                             "matrix");
+  expect_passed(tester, tested, "the column-major matrix matches");
+
+  actual[1][0] = 3.5;
+  tested.expect_near_matfile(actual, matfile.path(), "gain", 0.25, "matrix");
+  expect_failed(tester, tested, "an element off by more than the tolerance "
+                                "fails");
+
+  matfile.write(matfile_bytes("row", 1, 3, {1.0, 2.0, 3.0}));
+  tested.expect_near_matfile(std::vector<double>{1.0, 2.0, 3.0},
+                             matfile.path(), "row", 0.0, "row vector");
+  expect_passed(tester, tested, "a row vector matches a std::vector");
+}
+
+MCAP_REGISTER_TEST(matfile_missing_variable_or_file_fails) {
+  TestSupport::TemporaryFile matfile("golden_missing.mat");
+  matfile.write(matfile_bytes("gain", 1, 1, {1.0}));
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_matfile(std::vector<double>{1.0}, matfile.path(),
+                             "offset", 1.0, "variable");
+  expect_failed(tester, tested, "a missing variable fails");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Infrastructure,
+                     "a missing variable is an infrastructure failure");
+
+  tested.expect_near_matfile(std::vector<double>{1.0},
+                             matfile.path() + ".missing", "gain", 1.0,
+                             "file");
+  expect_failed(tester, tested, "a missing file fails");
+
+  matfile.write("not a MAT file");
+  tested.expect_near_matfile(std::vector<double>{1.0}, matfile.path(),
+                             "gain", 1.0, "short");
+  expect_failed(tester, tested, "a truncated file fails");
+}
+
+MCAP_REGISTER_TEST(snapshot_is_recorded_then_compared) {
+  TestSupport::TemporaryFile snapshot("snapshot_speed.txt");
+  MCAPTester<double> tested = quiet_tester();
+  tested.set_snapshot_directory(".");
+
+  std::vector<double> speed = {1.0, 2.0, 3.0};
+  tested.expect_matches_snapshot("mcap_test_snapshot_speed", speed, 0.0);
+  expect_passed(tester, tested, "a missing snapshot is recorded");
+  FlatArray<double> recorded;
+  tester.expect_true(load_flat_array(snapshot.path(), recorded) &&
+                         recorded.data == speed,
+                     "the recorded snapshot holds the actual values");
+
+  tested.expect_matches_snapshot("mcap_test_snapshot_speed",
+                                 std::vector<double>{1.0, 2.0, 3.05}, 0.1);
+  expect_passed(tester, tested, "values within the tolerance match");
+
+  tested.expect_matches_snapshot("mcap_test_snapshot_speed",
+                                 std::vector<double>{1.0, 2.0, 3.5}, 0.1);
+  expect_failed(tester, tested, "values beyond the tolerance fail");
+
+  tested.expect_matches_snapshot("mcap_test_snapshot_speed",
+                                 std::vector<double>{1.0, 2.0}, 0.1);
+  expect_failed(tester, tested, "a different size fails");
+}
+
+MCAP_REGISTER_TEST(snapshot_unwritable_directory_fails) {
+  TestSupport::TemporaryFile blocker("snapshot_blocker");
+  blocker.write("a file, not a directory");
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.set_snapshot_directory(blocker.path() + "/snapshots");
+  tested.expect_matches_snapshot("speed", std::vector<double>{1.0}, 0.0);
+  expect_failed(tester, tested, "a snapshot that cannot be written fails");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Infrastructure,
+                     "as an infrastructure failure");
+}
+
+MCAP_REGISTER_TEST(shape_policy_broadcast_assertions) {
+  std::vector<std::vector<double>> matrix = {{1.0, 2.0}, {1.0, 2.5}};
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near(matrix, std::vector<double>{1.0, 2.0}, 0.5, "row",
+                     ShapePolicy::Broadcast);
+  expect_passed(tester, tested, "a row broadcast onto every row passes");
+  tested.expect_near(matrix, std::vector<double>{1.0, 2.0}, 0.1, "row",
+                     ShapePolicy::Broadcast);
+  expect_failed(tester, tested, "a broadcast row outside the tolerance "
+                                "fails");
+  tested.expect_near(matrix, make_flat_scalar(1.5), 1.0, "scalar",
+                     ShapePolicy::Broadcast);
+  expect_passed(tester, tested, "a scalar broadcast onto every element "
+                                "passes");
+  tested.expect_near(matrix, std::vector<double>{1.0, 2.0, 3.0}, 1e9,
+                     "incompatible", ShapePolicy::Broadcast);
+  expect_failed(tester, tested, "shapes that do not broadcast fail");
+  tested.expect_near(matrix, std::vector<double>{1.0, 2.0}, 1e9, "exact",
+                     ShapePolicy::Exact);
+  expect_failed(tester, tested, "the exact policy does not broadcast");
+}
+
+MCAP_REGISTER_TEST(shape_policy_strict_assertions) {
+  const double row_major[] = {1.0, 2.0, 3.0, 4.0};
+  const double column_major[] = {1.0, 3.0, 2.0, 4.0};
+  FlatArray<double> rows =
+      make_flat_array_from_buffer(row_major, 2, 2, ArrayLayout::RowMajor);
+  FlatArray<double> columns = make_flat_array_from_buffer(
+      column_major, 2, 2, ArrayLayout::ColumnMajor);
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near(rows, rows, 0.0, "same layout", ShapePolicy::Strict);
+  expect_passed(tester, tested, "equal layouts pass the strict policy");
+  tested.expect_near(rows, columns, 0.0, "layouts", ShapePolicy::Strict);
+  expect_failed(tester, tested, "different layouts fail the strict policy");
+  tested.expect_near(rows, columns, 0.0, "layouts", ShapePolicy::Exact);
+  expect_passed(tester, tested, "the exact policy ignores the layout");
+}
+
+MCAP_REGISTER_TEST(try_variants_report_nothing) {
+  MCAPTester<double> tested = quiet_tester();
+  std::vector<double> expected = {1.0, 2.0};
+
+  tester.expect_true(tested.try_expect_near_scalar(1.0, 1.05, 0.1).passed(),
+                     "a scalar within the tolerance");
+  tester.expect_true(!tested.try_expect_near_scalar(1.0, 2.0, 0.1).passed(),
+                     "a scalar beyond the tolerance");
+  tester.expect_true(tested.try_expect_near_scalar(1.0, 1.25, 0.1, 0.5)
+                             .status == ComparisonStatus::Warning,
+                     "a scalar between both tolerances");
+
+  ComparisonResult<double> failing =
+      tested.try_expect_near(std::vector<double>{1.0, 2.5}, expected, 0.1);
+  tester.expect_true(!failing.passed(), "an array beyond the tolerance");
+  TestSupport::expect_equal(tester, failing.index, 1,
+                            "the result names the first mismatch");
+  tester.expect_true(
+      tested.try_expect_near(std::vector<double>{1.0, 2.05}, expected, 0.1)
+          .passed(),
+      "an array within the tolerance");
+  tester.expect_true(tested.try_expect_near(std::vector<double>{1.0, 2.2},
+                                            expected, 0.1, 0.5)
+                             .status == ComparisonStatus::Warning,
+                     "an array between both tolerances");
+  tester.expect_true(!tested.try_expect_near(make_flat_array(expected),
+                                             make_flat_scalar(1.0), 0.1,
+                                             ShapePolicy::Broadcast)
+                          .passed(),
+                     "an array under a shape policy");
+
+  tester.expect_true(tested.get_records().empty() &&
+                         !tested.get_test_failed_flag() &&
+                         tested.get_warning_count() == 0,
+                     "no outcome is recorded, flagged or counted");
+}
+
+MCAP_REGISTER_TEST(accumulation_bound_of_sums_and_dot_products) {
+  std::vector<double> terms(1000, 0.1);
+  double naive = 0.0;
+  for (std::size_t i = 0; i < terms.size(); i++) {
+    naive += terms[i];
+  }
+  const double exact = 100.0;
+  TestSupport::expect_same(
+      tester, Comparison::accumulation_tolerance(1000, 100.0),
+      1000 * std::numeric_limits<double>::epsilon() * 100.0,
+      "the bound is n * epsilon * sum |terms|");
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_accumulation(naive, exact, terms.size(), 100.0, "sum");
+  expect_passed(tester, tested, "a naive sum is within the bound");
+  tested.expect_near_accumulation(exact + 1e-9, exact, terms.size(), 100.0,
+                                  "sum");
+  expect_failed(tester, tested, "an error beyond the bound fails");
+  TestSupport::expect_detail_contains(tester, tested, "for 1000 terms",
+                                      "the derived bound is reported");
+
+  std::vector<double> ones(1000, 1.0);
+  tested.expect_near_accumulation(naive, exact, terms, ones, "dot");
+  expect_passed(tester, tested, "a naive dot product is within the bound");
+  tested.expect_near_accumulation(exact + 1e-9, exact, terms, ones, "dot");
+  expect_failed(tester, tested, "a dot product beyond the bound fails");
+  tested.expect_near_accumulation(exact, exact, terms, {1.0}, "dot");
+  expect_failed(tester, tested, "operands of different length fail");
+}
+
+MCAP_REGISTER_TEST(builder_configured_default_tolerance) {
+  const double not_a_number = std::numeric_limits<double>::quiet_NaN();
+  MCAPTester<double> tested = MCAPTesterBuilder<double>()
+                                  .verbosity(Verbosity::Quiet)
+                                  .ignore_environment()
+                                  .default_tolerance(0.1, 0.01)
+                                  .nan_policy(NanPolicy::MatchNan)
+                                  .build();
+
+  tested.expect_near_default(101.0, 100.0, "scalar");
+  expect_passed(tester, tested, "an error within absolute + relative * "
+                                "|expected| passes");
+  tested.expect_near_default(101.5, 100.0, "scalar");
+  expect_failed(tester, tested, "an error beyond the combined tolerance "
+                                "fails");
+  tested.expect_near_default(std::vector<double>{1.0, not_a_number},
+                             std::vector<double>{1.05, not_a_number},
+                             "arrays");
+  expect_passed(tester, tested, "NaN matches NaN under the configured "
+                                "policy");
+  tested.expect_near_default(std::vector<double>{1.0, 2.0},
+                             std::vector<double>{1.0, 2.5}, "arrays");
+  expect_failed(tester, tested, "an array element beyond the tolerance "
+                                "fails");
+  TestSupport::expect_detail_contains(tester, tested,
+                                      "outside 0.1 + 0.01 * |expected|",
+                                      "the default tolerances are reported");
+
+  MCAPTester<double> defaults = quiet_tester();
+  defaults.expect_near_default(not_a_number, not_a_number, "nan");
+  expect_failed(tester, defaults, "NaN fails under the default policy");
+  defaults.set_default_tolerance(-1.0);
+  defaults.expect_near_default(1.0, 1.0, "negative");
+  expect_failed(tester, defaults, "a negative default tolerance is "
+                                  "rejected");
+}
+
+MCAP_REGISTER_TEST(conditions_fail_as_assertions) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_true(true, "condition");
+  expect_passed(tester, tested, "a true condition passes");
+
+  tested.expect_true(false, "condition");
+  expect_failed(tester, tested, "a false condition fails");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Assertion,
+                     "a false condition is an assertion failure");
+  tester.expect_true(Report::format_category_summary(tested.get_records()) ==
+                         "1 assertion",
+                     "assertion failures are counted by category");
+
+  tested.expect_true(false, "budget", "", FailureCategory::Resource);
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Resource,
+                     "a condition can name its category");
+}
+
+MCAP_REGISTER_TEST(shared_tester_aggregates_every_thread) {
+  SharedMCAPTester<double> shared(quiet_tester());
+  std::vector<std::thread> workers;
+  for (int w = 0; w < 4; w++) {
+    workers.push_back(std::thread([&shared, w] {
+      for (int i = 0; i < 100; i++) {
+        shared.expect_near(1.0, 1.0, 0.0, "worker");
+      }
+      shared.expect_true(w != 2, "worker result");
+      shared.expect_near(1.0, 1.25, 0.1, 0.5, "worker warning");
+
+      std::vector<std::vector<double>> trajectory = {{0.0}, {0.5}, {1.0}};
+      shared.expect_near_trajectory(trajectory, trajectory, 1e-9,
+                                    "worker trajectory");
+      shared.expect_rmse_below(trajectory, trajectory, 1e-9, "worker rmse");
+      shared.expect_all_in_range(trajectory, 0.0, 1.0, "worker range");
+      shared.expect_symmetric(
+          std::vector<std::vector<double>>{{1.0, 2.0}, {2.0, 1.0}}, 1e-9,
+          "worker symmetric");
+    }));
+  }
+  for (std::size_t w = 0; w < workers.size(); w++) {
+    workers[w].join();
+  }
+
+  std::vector<AssertionRecord> records = shared.get_records();
+  TestSupport::expect_equal(tester, records.size(), 424,
+                            "every assertion of every thread is recorded");
+  std::size_t failures = 0;
+  for (std::size_t i = 0; i < records.size(); i++) {
+    failures += records[i].status == ComparisonStatus::Failure;
+  }
+  TestSupport::expect_equal(tester, failures, 1,
+                            "the failure of one thread is recorded once");
+  TestSupport::expect_equal(tester, shared.get_warning_count(), 4,
+                            "the warnings of every thread are counted");
+
+  bool thrown = false;
+  try {
+    shared.throw_error_if_test_failed();
+  } catch (const std::runtime_error &) {
+    thrown = true;
+  }
+  tester.expect_true(thrown, "a failure from any thread fails the test");
+
+  shared.reset_test_failed_flag();
+  shared.apply([](MCAPTester<double> &inner) {
+    inner.expect_near(1.0, 1.0, 0.0, "apply");
+  });
+  thrown = false;
+  try {
+    shared.throw_error_if_test_failed();
+  } catch (const std::runtime_error &) {
+    thrown = true;
+  }
+  tester.expect_true(!thrown, "passing assertions after a reset pass");
+}
+
+MCAP_REGISTER_TEST(reference_runner_compares_the_command_output) {
+  /* cat echoes the input CSV, so the reference returns its inputs. */
+  std::vector<std::vector<double>> inputs = {{1.0, 2.0}, {3.0, 4.5}};
+  std::vector<std::vector<double>> actual = inputs;
+
+  MCAPTester<double> tested = quiet_tester();
+  ReferenceRunner<double> echo("cat");
+  echo.expect_near(tested, actual, inputs, 0.0, "stdin");
+  expect_passed(tester, tested, "the output read from stdin matches");
+  actual[1][1] = 5.0;
+  echo.expect_near(tested, actual, inputs, 0.1, "stdin");
+  expect_failed(tester, tested, "a deviation from the reference fails");
+
+  ReferenceRunner<double> file("cat {input}");
+  file.set_input_mode(ReferenceInput::CsvFile);
+  file.expect_near(tested, inputs, inputs, 0.0, "file");
+  expect_passed(tester, tested, "the inputs are passed as a file path");
+
+  /* The braces redirect the input file to the whole command. */
+  ReferenceRunner<double> json("{ cat >/dev/null; echo '[0.5, 1.5]'; }");
+  json.set_output_format(ReferenceOutput::Json);
+  json.expect_near(tested, std::vector<double>{0.5, 1.5}, inputs, 0.0,
+                   "json");
+  expect_passed(tester, tested, "a JSON output is parsed");
+}
+
+MCAP_REGISTER_TEST(reference_runner_failures_are_infrastructure) {
+  std::vector<std::vector<double>> inputs = {{1.0}};
+  MCAPTester<double> tested = quiet_tester();
+
+  ReferenceRunner<double> failing("{ cat >/dev/null; exit 3; }");
+  failing.expect_near(tested, std::vector<double>{1.0}, inputs, 1e9,
+                      "status");
+  expect_failed(tester, tested, "a failing command fails");
+  TestSupport::expect_detail_contains(tester, tested, "exited with status 3",
+                                      "the exit status is reported");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Infrastructure,
+                     "as an infrastructure failure");
+
+  ReferenceRunner<double> garbage("{ cat >/dev/null; echo '[1,'; }");
+  garbage.set_output_format(ReferenceOutput::Json);
+  garbage.expect_near(tested, std::vector<double>{1.0}, inputs, 1e9,
+                      "json");
+  expect_failed(tester, tested, "an unparsable output fails");
+}
+
+MCAP_REGISTER_TEST(invalid_tolerances_are_configuration_errors) {
+  std::vector<double> values = {1.0, 2.0};
+  MCAPTester<double> tested = quiet_tester();
+
+  tested.expect_near(values, values, 0.0, "zero");
+  expect_passed(tester, tested, "a zero tolerance is valid");
+  tested.expect_near(values, values, -1.0, "negative");
+  expect_failed(tester, tested, "a negative tolerance fails");
+  TestSupport::expect_detail_contains(tester, tested,
+                                      "Configuration error: invalid tolerance",
+                                      "the detail names the configuration "
+                                      "error");
+
+  tested.expect_near(values, values,
+                     std::numeric_limits<double>::quiet_NaN(), "NaN");
+  expect_failed(tester, tested, "a NaN tolerance fails even on equal arrays");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "a NaN tolerance is a structural failure");
+
+  tested.expect_near(values, values,
+                     Tolerance<double>::absolute_relative(0.0, -1e-6),
+                     "relative");
+  expect_failed(tester, tested, "a negative relative tolerance fails");
+
+  std::vector<std::vector<double>> trajectory = {{0.0, 1.0}, {1.0, 2.0}};
+  tested.expect_near_trajectory(trajectory, trajectory, -1.0, "trajectory");
+  expect_failed(tester, tested, "a negative trajectory tolerance fails");
+  TestSupport::expect_detail_contains(tester, tested,
+                                      "Configuration error: invalid tolerance",
+                                      "the trajectory check reports a "
+                                      "configuration error");
+  tested.expect_near_trajectory_adaptive(
+      trajectory, trajectory, std::numeric_limits<double>::quiet_NaN(),
+      "adaptive", 2);
+  expect_failed(tester, tested, "a NaN adaptive trajectory tolerance fails");
+
+  tested.expect_pass_rate_above(values, values, -1.0, 0.0, "pass rate");
+  expect_failed(tester, tested, "a negative pass-rate tolerance fails even "
+                                "with a zero minimum rate");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "an invalid pass-rate tolerance is a structural "
+                     "failure");
+
+  tested.expect_rmse_below(values, values, -1.0, "rmse");
+  expect_failed(tester, tested, "a negative metric threshold fails");
+  tested.expect_symmetric(
+      std::vector<std::vector<double>>{{1.0, 0.0}, {0.0, 1.0}}, -1.0,
+      "symmetric");
+  expect_failed(tester, tested, "a negative property tolerance fails");
+}
+
+MCAP_REGISTER_TEST(tolerance_scale_reaches_every_comparison) {
+  setenv("MCAP_TOL_SCALE", "2", 1);
+  MCAPTester<double> tested =
+      MCAPTesterBuilder<double>().verbosity(Verbosity::Quiet).build();
+  unsetenv("MCAP_TOL_SCALE");
+
+  /* Every comparison below differs by 0.15 with a tolerance of 0.1. */
+  std::vector<std::vector<double>> expected = {{0.0}, {1.0}};
+  std::vector<std::vector<double>> actual = {{0.15}, {1.0}};
+  tested.expect_near_trajectory(actual, expected, 0.1, "trajectory");
+  expect_passed(tester, tested, "the trajectory tolerance is scaled");
+  tested.expect_pass_rate_above(actual, expected, 0.1, 1.0, "pass rate");
+  expect_passed(tester, tested, "the pass-rate tolerance is scaled");
+  tested.expect_max_error_below(actual, expected, 0.1, "max error");
+  expect_passed(tester, tested, "the metric threshold is scaled");
+  tested.expect_symmetric(
+      std::vector<std::vector<double>>{{1.0, 0.15}, {0.0, 1.0}}, 0.1,
+      "symmetric");
+  expect_passed(tester, tested, "the property tolerance is scaled");
+  tested.expect_near_unordered(std::vector<double>{1.15, 2.0},
+                               std::vector<double>{2.0, 1.0}, 0.1,
+                               "unordered");
+  expect_passed(tester, tested, "the matching tolerance is scaled");
+
+  tested.expect_near_trajectory(actual, expected, 0.07, "trajectory");
+  expect_failed(tester, tested, "an error beyond the scaled tolerance "
+                                "fails");
+}
+
+MCAP_REGISTER_TEST(combined_tolerance_assertions) {
+  MCAPTester<double> tested = quiet_tester();
+  std::vector<double> expected = {101.0, 1.0};
+  std::vector<double> actual = {100.0, 1.0};
+
+  tested.expect_near(actual, expected, Tolerance<double>::relative(0.01),
+                     "relative");
+  expect_passed(tester, tested, "an error within the relative bound passes");
+  tested.expect_near(actual, expected, Tolerance<double>::relative(0.001),
+                     "relative");
+  expect_failed(tester, tested, "an error beyond the relative bound fails");
+  TestSupport::expect_detail_contains(
+      tester, tested, "1 element(s) outside 0.001 * |expected|, first",
+      "the detail states the tolerance");
+
+  double next = std::nextafter(1.0, 2.0);
+  tested.expect_near(next, 1.0, Tolerance<double>::ulps(1), "ulps");
+  expect_passed(tester, tested, "adjacent scalars pass within one ULP");
+  tested.expect_near(std::nextafter(next, 2.0), 1.0,
+                     Tolerance<double>::ulps(1), "ulps");
+  expect_failed(tester, tested, "scalars two ULP apart fail");
+
+  std::vector<std::vector<double>> trajectory = {{100.0}, {1.0}};
+  tested.expect_near_trajectory(
+      trajectory, std::vector<std::vector<double>>{{101.0}, {1.0}},
+      Tolerance<double>::relative(0.01), "trajectory");
+  expect_passed(tester, tested, "trajectories take the same tolerance");
+}
+
+MCAP_REGISTER_TEST(failures_name_the_smallest_passing_tolerance) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near(std::vector<double>{1.0, 2.5},
+                     std::vector<double>{1.0, 2.0}, 1.0, "within");
+  expect_passed(tester, tested, "a passing check passes");
+  tester.expect_true(TestSupport::last_record(tested).detail.find(
+                         "Smallest passing") == std::string::npos,
+                     "a pass names no passing tolerance");
+
+  tested.expect_near(std::vector<double>{1.0, 2.5},
+                     std::vector<double>{1.0, 2.0}, 0.1, "beyond");
+  expect_failed(tester, tested, "a failing check fails");
+  TestSupport::expect_detail_contains(
+      tester, tested,
+      "Smallest passing tolerance: 0.5 absolute or 0.25 relative.",
+      "the detail names the absolute and relative tolerance");
+  TestSupport::expect_same(tester, TestSupport::last_record(tested).error,
+                           0.5, "the record keeps the largest error");
+  TestSupport::expect_same(tester,
+                           TestSupport::last_record(tested).relative_error,
+                           0.25, "the record keeps the relative error");
+
+  tested.expect_near(0.5, 0.0, 0.1, "zero");
+  TestSupport::expect_detail_contains(tester, tested,
+                                      "no relative tolerance passes.",
+                                      "no relative bound passes for zero");
+
+  std::vector<std::vector<double>> trajectory = {{1.0, 2.0}, {1.0, 2.0}};
+  std::vector<std::vector<double>> diverging = {{1.0, 2.0}, {1.0, 2.5}};
+  tested.expect_near_trajectory(diverging, trajectory, 0.1, "trajectory");
+  expect_failed(tester, tested, "a failing trajectory fails");
+  TestSupport::expect_detail_contains(
+      tester, tested,
+      "Smallest passing tolerance: 0.5 absolute or 0.25 relative.",
+      "a trajectory failure names the passing tolerances");
+  TestSupport::expect_same(tester,
+                           TestSupport::last_record(tested).relative_error,
+                           0.25, "a trajectory records its relative error");
+
+  tested.expect_pass_rate_above(diverging, trajectory, 0.1, 1.0, "rate");
+  expect_failed(tester, tested, "a failing pass rate fails");
+  TestSupport::expect_detail_contains(
+      tester, tested, "Smallest passing tolerance: 0.5 absolute",
+      "a pass-rate failure names the passing tolerance");
+  TestSupport::expect_same(tester, TestSupport::last_record(tested).error,
+                           0.5, "a pass rate records the largest error");
+  TestSupport::expect_same(tester,
+                           TestSupport::last_record(tested).tolerance, 0.1,
+                           "a pass rate records its tolerance");
+}
+
+MCAP_REGISTER_TEST(integer_arrays_compare_exactly) {
+  std::vector<std::vector<std::int64_t>> expected = {{1, 2}, {3, 4}};
+  std::vector<std::vector<std::int64_t>> swapped = {{1, 2}, {4, 3}};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_eq_array_int(expected, expected, "equal");
+  expect_passed(tester, tested, "equal integer arrays pass");
+  tested.expect_eq_array_int(swapped, expected, "swapped");
+  expect_failed(tester, tested, "differing integer arrays fail");
+  TestSupport::expect_detail_contains(tester, tested,
+                                      "2 of 4 elements differ:",
+                                      "the detail counts the differences");
+  TestSupport::expect_detail_contains(tester, tested, " 3 vs 4.",
+                                      "the detail lists every difference");
+
+  /* Adjacent values beyond 2^53 are equal once converted to double. */
+  std::vector<std::int64_t> large = {(std::int64_t(1) << 53) + 1};
+  tested.expect_eq_array_int(
+      large, std::vector<std::int64_t>{std::int64_t(1) << 53}, "large");
+  expect_failed(tester, tested, "large integers are not rounded");
+  tested.expect_eq_array_int(std::vector<int>{1, 2}, std::vector<int>{1},
+                             "lengths");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "a size mismatch is a structural failure");
+}
+
+MCAP_REGISTER_TEST(failure_guard_throws_unhandled_failures) {
+  bool thrown = false;
+  try {
+    MCAPTester<double> tested = quiet_tester();
+    FailureGuard<double> guard(tested);
+    tested.expect_near(1.0, 1.0, 0.0, "equal");
+  } catch (const std::runtime_error &) {
+    thrown = true;
+  }
+  tester.expect_true(!thrown, "a guard of a passing tester does not throw");
+
+  std::string summary;
+  try {
+    MCAPTester<double> tested = quiet_tester();
+    FailureGuard<double> guard(tested);
+    tested.expect_near(1.0, 2.0, 0.0, "unhandled");
+  } catch (const std::runtime_error &error) {
+    summary = error.what();
+  }
+  tester.expect_true(summary.find("unhandled") != std::string::npos,
+                     "a guard throws the unhandled failures");
+
+  std::size_t throws = 0;
+  try {
+    MCAPTester<double> tested = quiet_tester();
+    FailureGuard<double> guard(tested);
+    tested.expect_near(1.0, 2.0, 0.0, "handled");
+    try {
+      tested.throw_error_if_test_failed();
+    } catch (const std::runtime_error &) {
+      throws++;
+    }
+  } catch (const std::runtime_error &) {
+    throws++;
+  }
+  TestSupport::expect_equal(tester, throws, 1,
+                            "a thrown failure is not thrown again");
+
+  thrown = false;
+  try {
+    MCAPTester<double> tested = quiet_tester();
+    FailureGuard<double> guard(tested);
+    tested.expect_near(1.0, 2.0, 0.0, "reset");
+    tested.reset_test_failed_flag();
+  } catch (const std::runtime_error &) {
+    thrown = true;
+  }
+  tester.expect_true(!thrown, "a cleared failure is not thrown");
+
+  std::string propagated;
+  try {
+    MCAPTester<double> tested = quiet_tester();
+    FailureGuard<double> guard(tested);
+    tested.expect_near(1.0, 2.0, 0.0, "propagating");
+    throw std::logic_error("fixture");
+  } catch (const std::exception &error) {
+    propagated = error.what();
+  }
+  tester.expect_true(propagated == "fixture",
+                     "a guard does not throw while an exception propagates");
+}
+
+MCAP_REGISTER_TEST(recorded_comparisons_replay_their_verdicts) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.set_session_recording(true);
+  std::vector<double> values = {1.0, 2.0, 3.0, 4.0};
+  std::vector<double> drifted = {1.0, 2.0, 3.0, 4.5};
+  std::vector<double> times = {0.0, 1.0, 2.0, 3.0};
+  Tolerance<double> relative = Tolerance<double>::relative(0.01);
+  typedef std::map<std::string, std::vector<double>> Signals;
+
+  tested.expect_near(make_flat_array(values), make_flat_array(drifted),
+                     relative, "tolerance");
+  tested.expect_near_default(values, values, "combined");
+  tested.expect_near_signals(Signals{{"speed", values}},
+                             Signals{{"speed", drifted}}, relative,
+                             "signals");
+  tested.expect_near_trajectory(values, drifted, relative, "trajectory", 1);
+  tested.expect_near_trajectory_adaptive(values, drifted, relative,
+                                         "adaptive", 2);
+  tested.expect_near_resampled(values, times, drifted, times, relative,
+                               "resampled");
+  tested.expect_pass_rate_above(values, drifted, relative, 0.7, "pass rate");
+  tested.expect_near_accumulation(10.0, 10.0, 4, 10.0, "accumulation");
+
+  const std::vector<SessionEntry<double>> &entries =
+      tested.get_session_entries();
+  const char *kinds[] = {"tolerance",  "combined",  "tolerance",
+                         "trajectory", "adaptive_trajectory",
+                         "resampled",  "pass_rate", "near"};
+  TestSupport::expect_equal(tester, entries.size(), 8,
+                            "every element-wise comparison is recorded");
+  for (std::size_t k = 0; k < entries.size() && k < 8; k++) {
+    ComparisonResult<double> result;
+    bool evaluated = Session::evaluate(entries[k], result);
+    tester.expect_true(entries[k].kind == kinds[k] && evaluated &&
+                           result.status == entries[k].status,
+                       "a recorded comparison replays its verdict",
+                       entries[k].kind + ": " + entries[k].message);
+  }
+  tester.expect_true(entries[2].message == "signals: speed",
+                     "a recorded signal names its key");
+
+  tested.expect_near_norm(values, drifted, 0.5, "norm");
+  const std::vector<AssertionRecord> &records = tested.get_records();
+  tester.expect_true(records.size() >= 2 &&
+                         records[records.size() - 2].status ==
+                             ComparisonStatus::Warning,
+                     "a non-replayable check warns while recording");
+  TestSupport::expect_equal(tester, entries.size(), 8,
+                            "a non-replayable check is not recorded");
+}
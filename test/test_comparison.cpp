@@ -0,0 +1,360 @@
+/**
+ * @file test_comparison.cpp
+ * @brief Tests the side-effect-free comparison functions of
+ * MCAP_tester_comparison.hpp.
+ */
+
+#include <algorithm>
+#include <cmath>
+#include <cstddef>
+#include <limits>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester_runner.hpp"
+#include "test_support.hpp"
+
+using namespace Tester;
+using TestSupport::expect_equal;
+using TestSupport::expect_same;
+
+namespace {
+
+const double not_a_number = std::numeric_limits<double>::quiet_NaN();
+const double infinity = std::numeric_limits<double>::infinity();
+
+FlatArray<double> flat(const std::vector<double> &values) {
+  return make_flat_array(values);
+}
+
+FlatArray<double> flat_matrix(const std::vector<std::vector<double>> &values) {
+  return make_flat_array(values);
+}
+
+/* Checks that two results agree in every field. */
+void expect_same_result(MCAPTester<double> &tester,
+                        const ComparisonResult<double> &actual,
+                        const ComparisonResult<double> &expected,
+                        const std::string &message) {
+  tester.expect_true(actual.status == expected.status, message + ": status");
+  tester.expect_true(actual.kind == expected.kind, message + ": kind");
+  expect_equal(tester, actual.index, expected.index, message + ": index");
+  expect_same(tester, actual.actual_value, expected.actual_value,
+              message + ": actual value");
+  expect_same(tester, actual.expected_value, expected.expected_value,
+              message + ": expected value");
+  expect_same(tester, actual.error, expected.error, message + ": error");
+  expect_same(tester, actual.max_error, expected.max_error,
+              message + ": max error");
+  expect_equal(tester, actual.mismatch_count, expected.mismatch_count,
+               message + ": mismatch count");
+  expect_same(tester, actual.max_relative_error, expected.max_relative_error,
+              message + ": max relative error");
+}
+
+} // namespace
+
+MCAP_REGISTER_TEST(comparison_scalar_at_the_tolerance_passes) {
+  ComparisonResult<double> result = Comparison::compare_scalar(1.0, 1.5, 0.5);
+  tester.expect_true(result.status == ComparisonStatus::Pass, "status");
+  tester.expect_true(result.kind == MismatchKind::None, "kind");
+  expect_same(tester, result.max_error, 0.5, "max error");
+  expect_equal(tester, result.mismatch_count, 0, "mismatch count");
+}
+
+MCAP_REGISTER_TEST(comparison_scalar_above_the_tolerance_fails) {
+  ComparisonResult<double> result = Comparison::compare_scalar(1.0, 1.5, 0.25);
+  tester.expect_true(result.status == ComparisonStatus::Failure, "status");
+  tester.expect_true(result.kind == MismatchKind::ElementMismatch, "kind");
+  expect_same(tester, result.actual_value, 1.0, "actual value");
+  expect_same(tester, result.expected_value, 1.5, "expected value");
+  expect_same(tester, result.error, 0.5, "error");
+  expect_equal(tester, result.mismatch_count, 1, "mismatch count");
+  expect_same(tester, result.max_relative_error, 0.5 / 1.5,
+              "max relative error");
+}
+
+MCAP_REGISTER_TEST(comparison_scalar_warning_boundaries) {
+  ComparisonResult<double> at_warning =
+      Comparison::compare_scalar(1.0, 1.5, 0.5, 1.0);
+  tester.expect_true(at_warning.status == ComparisonStatus::Pass,
+                     "an error equal to the warning tolerance passes");
+
+  ComparisonResult<double> between =
+      Comparison::compare_scalar(1.0, 1.5, 0.25, 1.0);
+  tester.expect_true(between.status == ComparisonStatus::Warning,
+                     "an error between both tolerances warns");
+  tester.expect_true(between.passed(), "a warning passes");
+  tester.expect_true(between.kind == MismatchKind::ElementMismatch,
+                     "a warning reports its element");
+  expect_equal(tester, between.mismatch_count, 0,
+               "a warning is no mismatch");
+
+  ComparisonResult<double> at_failure =
+      Comparison::compare_scalar(1.0, 2.0, 0.25, 1.0);
+  tester.expect_true(at_failure.status == ComparisonStatus::Warning,
+                     "an error equal to the failure tolerance warns");
+
+  ComparisonResult<double> above =
+      Comparison::compare_scalar(1.0, 2.5, 0.25, 1.0);
+  tester.expect_true(above.status == ComparisonStatus::Failure,
+                     "an error above the failure tolerance fails");
+}
+
+MCAP_REGISTER_TEST(comparison_arrays_report_the_first_failure) {
+  ComparisonResult<double> result = Comparison::compare_arrays(
+      flat({1.0, 1.2, 3.0, 4.0, 9.0}), flat({1.0, 1.0, 1.0, 4.0, 4.0}), 0.1,
+      1.0);
+  tester.expect_true(result.status == ComparisonStatus::Failure, "status");
+  expect_equal(tester, result.index, 2,
+               "the first failure wins over an earlier warning");
+  expect_same(tester, result.error, 2.0, "error of the first failure");
+  expect_same(tester, result.max_error, 5.0, "max error over all elements");
+  expect_equal(tester, result.mismatch_count, 2, "mismatch count");
+  expect_same(tester, result.max_relative_error, 2.0,
+              "max relative error over all elements");
+}
+
+MCAP_REGISTER_TEST(comparison_arrays_warning_reports_the_first_warning) {
+  ComparisonResult<double> result = Comparison::compare_arrays(
+      flat({1.0, 1.5, 1.8}), flat({1.0, 1.0, 1.0}), 0.1, 1.0);
+  tester.expect_true(result.status == ComparisonStatus::Warning, "status");
+  expect_equal(tester, result.index, 1, "index");
+  expect_same(tester, result.max_error, 0.8, "max error");
+}
+
+MCAP_REGISTER_TEST(comparison_nan_error_sticks_in_max_error) {
+  ComparisonResult<double> result = Comparison::compare_arrays(
+      flat({1.0, not_a_number, 100.0}), flat({1.0, 1.0, 1.0}), 1.0);
+  tester.expect_true(result.status == ComparisonStatus::Failure, "status");
+  expect_equal(tester, result.index, 1, "index of the NaN");
+  expect_same(tester, result.max_error, not_a_number,
+              "a larger later error does not replace NaN");
+  expect_same(tester, result.max_relative_error, not_a_number,
+              "NaN sticks in the max relative error");
+  expect_equal(tester, result.mismatch_count, 2, "mismatch count");
+
+  ComparisonResult<double> combined =
+      Comparison::compare_arrays(flat({not_a_number, 100.0}), flat({1.0, 1.0}),
+                                 Tolerance<double>::relative(0.1));
+  expect_same(tester, combined.max_error, not_a_number,
+              "NaN sticks under a combined tolerance");
+  expect_same(tester, combined.max_relative_error, not_a_number,
+              "NaN sticks in the relative error under a combined tolerance");
+}
+
+MCAP_REGISTER_TEST(comparison_nan_expected_value_fails) {
+  ComparisonResult<double> result = Comparison::compare_scalar(
+      1.0, not_a_number, std::numeric_limits<double>::max());
+  tester.expect_true(result.status == ComparisonStatus::Failure, "status");
+  expect_same(tester, result.max_error, not_a_number, "max error");
+}
+
+MCAP_REGISTER_TEST(comparison_relative_error) {
+  ComparisonResult<double> result = Comparison::compare_arrays(
+      flat({1.1, 5.0}), flat({1.0, 4.0}), 10.0);
+  expect_same(tester, result.max_relative_error, 0.25,
+              "largest error relative to the expected magnitude");
+
+  ComparisonResult<double> missed_zero =
+      Comparison::compare_arrays(flat({1.0, 0.5}), flat({1.0, 0.0}), 10.0);
+  expect_same(tester, missed_zero.max_relative_error, infinity,
+              "a missed expected zero is infinitely far off");
+
+  ComparisonResult<double> hit_zero =
+      Comparison::compare_arrays(flat({0.0}), flat({0.0}), 0.0);
+  expect_same(tester, hit_zero.max_relative_error, 0.0,
+              "a matched expected zero has no relative error");
+}
+
+MCAP_REGISTER_TEST(comparison_size_mismatch) {
+  ComparisonResult<double> result =
+      Comparison::compare_arrays(flat({1.0, 2.0}), flat({1.0}), 10.0);
+  tester.expect_true(result.status == ComparisonStatus::Failure, "status");
+  tester.expect_true(result.kind == MismatchKind::SizeMismatch, "kind");
+
+  ComparisonResult<double> transposed = Comparison::compare_arrays(
+      flat_matrix({{1.0, 1.0, 1.0}, {1.0, 1.0, 1.0}}),
+      flat_matrix({{1.0, 1.0}, {1.0, 1.0}, {1.0, 1.0}}), 10.0);
+  tester.expect_true(transposed.kind == MismatchKind::SizeMismatch,
+                     "the same element count in another shape");
+
+  std::vector<std::vector<double>> ragged = {{1.0, 1.0}, {1.0}};
+  ComparisonResult<double> ragged_result = Comparison::compare_arrays(
+      flat_matrix(ragged), flat_matrix(ragged), 10.0);
+  tester.expect_true(ragged_result.kind == MismatchKind::SizeMismatch,
+                     "ragged arrays");
+}
+
+MCAP_REGISTER_TEST(comparison_layout_mismatch_under_strict_policy) {
+  const double row_major[] = {1.0, 2.0, 3.0, 4.0};
+  const double column_major[] = {1.0, 3.0, 2.0, 4.0};
+  FlatArray<double> rows =
+      make_flat_array_from_buffer(row_major, 2, 2, ArrayLayout::RowMajor);
+  FlatArray<double> columns = make_flat_array_from_buffer(
+      column_major, 2, 2, ArrayLayout::ColumnMajor);
+
+  ComparisonResult<double> exact =
+      Comparison::compare_arrays(rows, columns, 0.0, ShapePolicy::Exact);
+  tester.expect_true(exact.status == ComparisonStatus::Pass,
+                     "the exact policy compares logical elements");
+
+  ComparisonResult<double> strict =
+      Comparison::compare_arrays(rows, columns, 0.0, ShapePolicy::Strict);
+  tester.expect_true(strict.status == ComparisonStatus::Failure,
+                     "strict status");
+  tester.expect_true(strict.kind == MismatchKind::LayoutMismatch,
+                     "strict kind");
+
+  ComparisonResult<double> combined = Comparison::compare_arrays(
+      rows, columns, Tolerance<double>::relative(1.0), ShapePolicy::Strict);
+  tester.expect_true(combined.kind == MismatchKind::LayoutMismatch,
+                     "strict kind under a combined tolerance");
+
+  ComparisonResult<double> same_layout =
+      Comparison::compare_arrays(rows, rows, 0.0, ShapePolicy::Strict);
+  tester.expect_true(same_layout.status == ComparisonStatus::Pass,
+                     "strict passes for equal layouts");
+}
+
+MCAP_REGISTER_TEST(comparison_broadcasting) {
+  FlatArray<double> matrix = flat_matrix({{1.0, 2.0, 3.0}, {1.0, 2.0, 3.5}});
+  FlatArray<double> row = flat_matrix({{1.0, 2.0, 3.0}});
+
+  ComparisonResult<double> exact =
+      Comparison::compare_arrays(matrix, row, 1.0, ShapePolicy::Exact);
+  tester.expect_true(exact.kind == MismatchKind::SizeMismatch,
+                     "the exact policy does not broadcast");
+
+  ComparisonResult<double> passing =
+      Comparison::compare_arrays(matrix, row, 1.0, ShapePolicy::Broadcast);
+  tester.expect_true(passing.status == ComparisonStatus::Pass,
+                     "a row broadcast against every row");
+
+  ComparisonResult<double> failing =
+      Comparison::compare_arrays(matrix, row, 0.1, ShapePolicy::Broadcast);
+  tester.expect_true(failing.status == ComparisonStatus::Failure,
+                     "broadcast failure status");
+  expect_equal(tester, failing.index, 5, "broadcast failure index");
+  expect_same(tester, failing.expected_value, 3.0,
+              "broadcast expected value");
+
+  ComparisonResult<double> column = Comparison::compare_arrays(
+      matrix, flat_matrix({{2.0}, {2.0}}), 1.5, ShapePolicy::Broadcast);
+  tester.expect_true(column.status == ComparisonStatus::Pass,
+                     "a column broadcast against every column");
+
+  ComparisonResult<double> incompatible = Comparison::compare_arrays(
+      matrix, flat_matrix({{1.0, 2.0}}), 10.0, ShapePolicy::Broadcast);
+  tester.expect_true(incompatible.kind == MismatchKind::SizeMismatch,
+                     "incompatible shapes do not broadcast");
+
+  FlatArray<double> broadcast;
+  tester.expect_true(!Comparison::broadcast_to(matrix, row.shape, broadcast),
+                     "an array does not broadcast onto a smaller shape");
+}
+
+MCAP_REGISTER_TEST(comparison_merged_ranges_equal_one_pass) {
+  /* Warnings, failures and a NaN spread over the array, so every split
+   * point separates them differently. */
+  FlatArray<double> actual =
+      flat({1.0, 1.5, 1.0, 3.0, 1.0, 1.6, not_a_number, 1.0, 9.0, 1.0});
+  FlatArray<double> expected =
+      flat({1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0});
+  std::size_t size = actual.data.size();
+  ComparisonResult<double> whole =
+      Comparison::Detail::compare_range(actual, expected, 0.1, 1.0, 0, size);
+  expect_equal(tester, whole.index, 3, "the lowest-index failure");
+  expect_equal(tester, whole.mismatch_count, 3, "mismatches of one pass");
+
+  for (std::size_t split = 0; split <= size; split++) {
+    ComparisonResult<double> merged = Comparison::Detail::compare_range(
+        actual, expected, 0.1, 1.0, 0, split);
+    Comparison::Detail::merge_range(
+        merged, Comparison::Detail::compare_range(actual, expected, 0.1, 1.0,
+                                                  split, size));
+    expect_same_result(tester, merged, whole,
+                       "split at " + std::to_string(split));
+  }
+
+  for (std::size_t chunk = 1; chunk <= size; chunk++) {
+    ComparisonResult<double> merged = Comparison::Detail::compare_range(
+        actual, expected, 0.1, 1.0, 0, std::min(size, chunk));
+    for (std::size_t begin = chunk; begin < size; begin += chunk) {
+      Comparison::Detail::merge_range(
+          merged, Comparison::Detail::compare_range(
+                      actual, expected, 0.1, 1.0, begin,
+                      std::min(size, begin + chunk)));
+    }
+    expect_same_result(tester, merged, whole,
+                       "chunks of " + std::to_string(chunk));
+  }
+}
+
+MCAP_REGISTER_TEST(comparison_merged_warnings_keep_the_first_warning) {
+  FlatArray<double> actual = flat({1.0, 1.5, 1.0, 1.75});
+  FlatArray<double> expected = flat({1.0, 1.0, 1.0, 1.0});
+  ComparisonResult<double> merged =
+      Comparison::Detail::compare_range(actual, expected, 0.1, 1.0, 0, 2);
+  Comparison::Detail::merge_range(
+      merged,
+      Comparison::Detail::compare_range(actual, expected, 0.1, 1.0, 2, 4));
+  tester.expect_true(merged.status == ComparisonStatus::Warning, "status");
+  expect_equal(tester, merged.index, 1, "the first warning");
+  expect_same(tester, merged.max_error, 0.75, "max error");
+}
+
+MCAP_REGISTER_TEST(comparison_combined_tolerance_bounds) {
+  Tolerance<double> tolerance =
+      Tolerance<double>::absolute_relative(0.5, 0.1);
+  tester.expect_true(tolerance.accepts(11.5, 10.0),
+                     "an error equal to absolute + relative * |expected|");
+  tester.expect_true(!tolerance.accepts(11.6, 10.0),
+                     "an error above absolute + relative * |expected|");
+  tester.expect_true(!tolerance.accepts(not_a_number, 10.0),
+                     "NaN is never accepted");
+
+  double next = std::nextafter(1.0, 2.0);
+  tester.expect_true(Tolerance<double>::ulps(1).accepts(next, 1.0),
+                     "one ULP apart");
+  tester.expect_true(!Tolerance<double>::ulps(1).accepts(
+                         std::nextafter(next, 2.0), 1.0),
+                     "two ULP apart");
+  tester.expect_true(!Tolerance<double>::absolute(-1.0).valid(),
+                     "a negative bound is invalid");
+}
+
+MCAP_REGISTER_TEST(comparison_json_tolerance_ulps) {
+  const char *texts[] = {"{\"ulps\": 4}", "{\"ulps\": -1}",
+                         "{\"ulps\": 1.5}", "{\"ulps\": 1e30}",
+                         "{\"ulps\": 1e999}"};
+  bool accepted[5];
+  for (std::size_t k = 0; k < 5; k++) {
+    JSON::Value value;
+    std::string error;
+    Tolerance<double> tolerance;
+    accepted[k] = JSON::parse(texts[k], value, error) &&
+                  json_to_tolerance(value, tolerance);
+    if (k == 0) {
+      expect_equal(tester, accepted[k] ? tolerance.ulp_count() : 0, 4,
+                   "the ULP count is kept");
+    }
+  }
+  tester.expect_true(accepted[0], "an integer ULP count is accepted");
+  tester.expect_true(!accepted[1], "a negative ULP count is rejected");
+  tester.expect_true(!accepted[2], "a fractional ULP count is rejected");
+  tester.expect_true(!accepted[3], "a ULP count of 2^64 or more is rejected");
+  tester.expect_true(!accepted[4], "an infinite ULP count is rejected");
+}
+
+MCAP_REGISTER_TEST(comparison_integers) {
+  ComparisonResult<int> result = Comparison::compare_scalar(3, 5, 1);
+  tester.expect_true(result.status == ComparisonStatus::Failure, "status");
+  expect_same(tester, result.max_relative_error, 0.4,
+              "the relative error of integers keeps its fraction");
+
+  tester.expect_true(!Tolerance<int>(1).or_ulps(4).accepts(3, 5),
+                     "integers have no ULP bound");
+  tester.expect_true(Tolerance<int>(2).or_ulps(4).accepts(3, 5),
+                     "the absolute bound still applies");
+}
@@ -0,0 +1,174 @@
+/**
+ * @file test_conformance.cpp
+ * @brief Tests the verdicts that MCAP_tester_conformance.hpp documents, and
+ * the conformance suite itself.
+ */
+
+#include <cstddef>
+#include <functional>
+#include <limits>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester_conformance.hpp"
+#include "MCAP_tester_runner.hpp"
+#include "test_support.hpp"
+
+using namespace Tester;
+using TestSupport::expect_equal;
+using TestSupport::expect_failed;
+using TestSupport::expect_passed;
+using TestSupport::expect_warned;
+using TestSupport::quiet_tester;
+
+MCAP_REGISTER_TEST(conformance_floating_point_edge_values) {
+  const double largest = std::numeric_limits<double>::max();
+  const double infinity = std::numeric_limits<double>::infinity();
+  const double not_a_number = std::numeric_limits<double>::quiet_NaN();
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near(1.0, 1.0, 0.0, "identical");
+  expect_passed(tester, tested, "identical scalars pass");
+  tested.expect_near(1.0, 1.5, 0.5, "at the tolerance");
+  expect_passed(tester, tested, "a difference equal to the tolerance passes");
+  tested.expect_near(1.0, 1.5, 0.25, "above the tolerance");
+  expect_failed(tester, tested, "a difference above the tolerance fails");
+  tested.expect_near(-0.0, 0.0, 0.0, "negative zero");
+  expect_passed(tester, tested, "negative zero equals zero");
+  tested.expect_near(std::numeric_limits<double>::denorm_min(), 0.0,
+                     std::numeric_limits<double>::epsilon(), "denormal");
+  expect_passed(tester, tested, "a denormal within epsilon of zero passes");
+  tested.expect_near(largest, largest, 0.0, "largest");
+  expect_passed(tester, tested, "the largest finite values compare equal");
+  tested.expect_near(infinity, -infinity, largest, "infinities");
+  expect_failed(tester, tested, "opposite infinities fail");
+  tested.expect_near(not_a_number, 1.0, largest, "NaN actual");
+  expect_failed(tester, tested, "a NaN actual value fails");
+  tested.expect_near(not_a_number, not_a_number, largest, "NaN pair");
+  expect_failed(tester, tested, "NaN never matches NaN");
+}
+
+MCAP_REGISTER_TEST(conformance_array_shapes) {
+  const double largest = std::numeric_limits<double>::max();
+  MCAPTester<double> tested = quiet_tester();
+
+  const double not_a_number = std::numeric_limits<double>::quiet_NaN();
+  tested.expect_near(std::vector<double>{1.0, 1.0, not_a_number},
+                     std::vector<double>{1.0, 1.0, 1.0}, largest, "NaN last");
+  expect_failed(tester, tested, "a NaN in the last element fails");
+  tested.expect_near(std::vector<double>{1.0, 1.0, 1.0},
+                     std::vector<double>{1.0, 1.0, 0.0}, 0.5, "last element");
+  expect_failed(tester, tested, "a mismatch in the last element fails");
+  tested.expect_near(std::vector<double>{1.0, 1.0}, std::vector<double>{1.0},
+                     largest, "lengths");
+  expect_failed(tester, tested, "vectors of different length fail");
+  tested.expect_near(
+      std::vector<std::vector<double>>{{1.0, 1.0, 1.0}, {1.0, 1.0, 1.0}},
+      std::vector<std::vector<double>>{{1.0, 1.0}, {1.0, 1.0}, {1.0, 1.0}},
+      largest, "transposed");
+  expect_failed(tester, tested, "transposed shapes with equal elements fail");
+  std::vector<std::vector<double>> ragged = {{1.0, 1.0}, {1.0}};
+  tested.expect_near(ragged, ragged, largest, "ragged");
+  expect_failed(tester, tested, "ragged nested vectors fail");
+}
+
+MCAP_REGISTER_TEST(conformance_warning_and_degenerate_policies) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near(1.0, 1.5, 0.25, 1.0, "between");
+  expect_warned(tester, tested,
+                "a difference between the warning and failure tolerance "
+                "warns");
+
+  tested.expect_near(std::vector<double>(), std::vector<double>(), 0.0,
+                     "empty");
+  expect_passed(tester, tested, "empty arrays pass by default");
+  tested.set_empty_array_policy(DegenerateArrayPolicy::Fail);
+  tested.expect_near(std::vector<double>(), std::vector<double>(), 0.0,
+                     "empty");
+  expect_failed(tester, tested, "empty arrays fail under the fail policy");
+
+  tested.expect_near(std::vector<double>{1.0}, std::vector<double>{1.0}, 0.0,
+                     "single");
+  expect_passed(tester, tested, "single elements pass by default");
+
+  /* The warning of the policy precedes the record of the comparison. */
+  MCAPTester<double> warned = quiet_tester();
+  warned.set_single_element_policy(DegenerateArrayPolicy::Warn);
+  warned.expect_near(std::vector<double>{1.0}, std::vector<double>{1.0}, 0.0,
+                     "single");
+  tester.expect_true(!warned.get_records().empty() &&
+                         warned.get_records().front().status ==
+                             ComparisonStatus::Warning,
+                     "single elements warn under the warn policy");
+}
+
+MCAP_REGISTER_TEST(conformance_suite_passes_the_default_configuration) {
+  ConformanceResult result = Conformance::run<double>();
+  tester.expect_true(result.passed(), "double");
+  expect_equal(tester, result.failure_count(), 0, "double failures");
+  tester.expect_true(!result.cases.empty(), "the suite has cases");
+  tester.expect_true(Conformance::run<float>().passed(), "float");
+
+  MCAPTester<double> tested = quiet_tester();
+  Conformance::check<double>(tested, [](MCAPTester<double> &) {});
+  tester.expect_true(!tested.get_test_failed_flag(),
+                     "check reports every case as passed");
+  expect_equal(tester, tested.get_records().size(), result.cases.size(),
+               "check reports every case");
+}
+
+MCAP_REGISTER_TEST(conformance_suite_follows_the_configured_policies) {
+  ConformanceResult result =
+      Conformance::run<double>([](MCAPTester<double> &configured) {
+        configured.set_empty_array_policy(DegenerateArrayPolicy::Fail);
+        configured.set_single_element_policy(DegenerateArrayPolicy::Warn);
+      });
+  tester.expect_true(result.passed(), "passes");
+
+  std::size_t failures = 0;
+  std::size_t warnings = 0;
+  for (std::size_t i = 0; i < result.cases.size(); i++) {
+    if (result.cases[i].name.find("empty array policy") != std::string::npos) {
+      failures += result.cases[i].expected == ComparisonStatus::Failure;
+    } else if (result.cases[i].name.find("single element policy") !=
+               std::string::npos) {
+      warnings += result.cases[i].expected == ComparisonStatus::Warning;
+    }
+  }
+  expect_equal(tester, failures, 1, "the empty case expects a failure");
+  expect_equal(tester, warnings, 1, "the single-element case expects a "
+                                    "warning");
+}
+
+MCAP_REGISTER_TEST(conformance_suite_supports_fail_fast) {
+  ConformanceResult result = Conformance::run<double>(
+      [](MCAPTester<double> &configured) { configured.set_fail_fast(true); });
+  tester.expect_true(result.passed(), "passes");
+}
+
+MCAP_REGISTER_TEST(conformance_suite_detects_a_deviating_configuration) {
+  /* A configuration that fails an assertion of its own makes every case
+   * observe a failure, so the passing and warning cases deviate. */
+  std::function<void(MCAPTester<double> &)> deviating =
+      [](MCAPTester<double> &configured) {
+        configured.set_verbosity(Verbosity::Quiet);
+        configured.expect_true(false, "configuration");
+      };
+  ConformanceResult result = Conformance::run<double>(deviating);
+  tester.expect_true(!result.passed(), "the suite fails");
+
+  std::size_t expected_deviations = 0;
+  for (std::size_t i = 0; i < result.cases.size(); i++) {
+    expected_deviations +=
+        result.cases[i].expected != ComparisonStatus::Failure;
+  }
+  expect_equal(tester, result.failure_count(), expected_deviations,
+               "every case not expecting a failure deviates");
+
+  MCAPTester<double> tested = quiet_tester();
+  Conformance::check<double>(tested, deviating);
+  tester.expect_true(tested.get_test_failed_flag(),
+                     "check reports the deviations");
+  TestSupport::expect_detail_contains(tester, tested, "observed failure",
+                                      "check names the observed verdict");
+}
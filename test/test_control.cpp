@@ -0,0 +1,484 @@
+/**
+ * @file test_control.cpp
+ * @brief Tests the assertions on controllers and plants: multi-rate
+ * orchestration, windup, limiters, resets, bounds, modes and simulations.
+ */
+
+#include <cstddef>
+#include <functional>
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester_multirate.hpp"
+#include "MCAP_tester_runner.hpp"
+#include "MCAP_tester_scenario.hpp"
+#include "MCAP_tester_state_space.hpp"
+#include "test_support.hpp"
+
+using namespace Tester;
+using TestSupport::expect_detail_contains;
+using TestSupport::expect_equal;
+using TestSupport::expect_failed;
+using TestSupport::expect_passed;
+using TestSupport::quiet_tester;
+
+namespace {
+
+/* A reference sampled every millisecond whose sample k has the value k. */
+std::vector<double> millisecond_ramp(std::size_t samples) {
+  std::vector<double> ramp;
+  for (std::size_t k = 0; k < samples; k++) {
+    ramp.push_back(static_cast<double>(k));
+  }
+  return ramp;
+}
+
+/* A proportional controller on a plant that integrates `gain` times the
+ * command. Every loop starts from a zero state, as run_scenario_file expects
+ * of a harness factory. */
+ClosedLoopHarness<double> proportional_loop(const double &gain) {
+  std::shared_ptr<double> state = std::make_shared<double>(0.0);
+  return ClosedLoopHarness<double>(
+      0.01,
+      [](const std::vector<double> &reference,
+         const std::vector<double> &measurement) {
+        return std::vector<double>{reference[0] - measurement[0]};
+      },
+      [state, &gain](const std::vector<double> &command,
+                     const std::vector<double> &) {
+        *state += gain * command[0];
+        return std::vector<double>{*state};
+      });
+}
+
+} // namespace
+
+MCAP_REGISTER_TEST(multirate_outputs_match_their_references) {
+  MCAPTester<double> tested = quiet_tester();
+  MultiRateOrchestrator<double> orchestrator(tested);
+  orchestrator.add_task("fast", 0.001, 0.0);
+  orchestrator.add_task("slow", 0.01, 0.5, 0.002);
+  orchestrator.add_reference("ramp", millisecond_ramp(50), 0.001);
+  orchestrator.add_output("fast", "ramp", millisecond_ramp(50));
+  orchestrator.add_output("slow", "ramp", {2.0, 12.0, 22.4, 32.0, 42.0});
+
+  tester.expect_true(orchestrator.run(), "every output matches");
+  TestSupport::expect_equal(tester, tested.get_records().size(), 2,
+                            "one record per output");
+  tester.expect_true(tested.get_records()[1].message == "slow/ramp",
+                     "records are named after the task and signal");
+
+  orchestrator.add_output("slow", "ramp", {2.0, 12.0, 23.0});
+  tester.expect_true(!orchestrator.run(), "a deviating output fails");
+  expect_failed(tester, tested, "the deviating output is recorded");
+}
+
+MCAP_REGISTER_TEST(multirate_misconfigurations_fail) {
+  MCAPTester<double> tested = quiet_tester();
+  MultiRateOrchestrator<double> misaligned(tested);
+  misaligned.add_task("odd", 0.0015, 1e9);
+  misaligned.add_reference("ramp", millisecond_ramp(10), 0.001);
+  misaligned.add_output("odd", "ramp", {0.0, 1.5});
+  tester.expect_true(!misaligned.run(), "misaligned run");
+  expect_failed(tester, tested, "instants between reference samples fail");
+  expect_detail_contains(tester, tested, "does not fall on a reference "
+                                         "sample",
+                         "the misaligned instant is reported");
+
+  MultiRateOrchestrator<double> unregistered(tested);
+  unregistered.add_reference("ramp", millisecond_ramp(10), 0.001);
+  unregistered.add_output("missing", "ramp", {0.0});
+  tester.expect_true(!unregistered.run(), "unregistered run");
+  expect_detail_contains(tester, tested, "Task missing is not registered",
+                         "an unregistered task fails");
+
+  MultiRateOrchestrator<double> unreferenced(tested);
+  unreferenced.add_task("fast", 0.001, 1e9);
+  unreferenced.add_output("fast", "speed", {0.0});
+  tester.expect_true(!unreferenced.run(), "unreferenced run");
+  expect_detail_contains(tester, tested, "No reference stream for signal "
+                                         "speed",
+                         "a signal without reference fails");
+}
+
+MCAP_REGISTER_TEST(reset_restores_the_initial_state) {
+  double state = 0.0;
+  std::function<std::vector<double>()> run = [&state] {
+    std::vector<double> outputs;
+    for (int k = 0; k < 3; k++) {
+      state += 1.0;
+      outputs.push_back(state);
+    }
+    return outputs;
+  };
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_reset_restores_state(run, [&state] { state = 0.0; },
+                                     "reset");
+  expect_passed(tester, tested, "a complete reset passes");
+
+  tested.expect_reset_restores_state(run, [] {}, "leak");
+  expect_failed(tester, tested, "state leaking across the reset fails");
+  expect_detail_contains(tester, tested,
+                         "3 output(s) differ after reset: [0] 4 before, 7 "
+                         "after",
+                         "the leaked outputs are listed with both values");
+
+  int calls = 0;
+  tested.expect_reset_restores_state(
+      [&calls] { return std::vector<double>(++calls, 0.0); }, [] {},
+      "length");
+  expect_failed(tester, tested, "a changed output length fails");
+  expect_detail_contains(tester, tested, "from 1 to 2",
+                         "both lengths are reported");
+}
+
+MCAP_REGISTER_TEST(behavior_at_bounds_probes_every_bound) {
+  typedef std::function<std::vector<double>(const std::vector<double> &)>
+      Function;
+  Function saturate = [](const std::vector<double> &x) {
+    return std::vector<double>{x[0] < 0.0 ? 0.0 : (x[0] > 1.0 ? 1.0 : x[0]),
+                               x[1]};
+  };
+  /* Wraps around instead of saturating above the upper bound. */
+  Function wrapping = [](const std::vector<double> &x) {
+    return std::vector<double>{x[0] < 0.0 ? 0.0 : (x[0] > 1.0 ? 0.0 : x[0]),
+                               x[1]};
+  };
+  std::vector<InputRange<double>> ranges = {InputRange<double>(0.0, 1.0),
+                                            InputRange<double>(-5.0, 5.0)};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_behavior_at_bounds(saturate, ranges, saturate, 0.0, "same");
+  expect_passed(tester, tested, "an identical implementation passes");
+
+  tested.expect_behavior_at_bounds(wrapping, ranges, saturate, 0.1,
+                                   "wrapping");
+  expect_failed(tester, tested, "a defect just outside a bound fails");
+  expect_detail_contains(tester, tested, "Input 0 above the upper bound",
+                         "the failing probe is named");
+  expect_detail_contains(tester, tested, "output 0 is 0, expected 1.",
+                         "the failing output is reported");
+  tester.expect_true(TestSupport::last_record(tested).detail.find(
+                         "Input 1") == std::string::npos,
+                     "passing probes are not listed");
+  expect_detail_contains(tester, tested, "Smallest passing tolerance: 1",
+                         "the largest error over all probes is named");
+
+  tested.expect_behavior_at_bounds(
+      [](const std::vector<double> &x) { return x; }, ranges, saturate, 1e9,
+      "size");
+  expect_passed(tester, tested, "outputs of equal length within the "
+                                "tolerance pass");
+  tested.expect_behavior_at_bounds(
+      [](const std::vector<double> &) { return std::vector<double>(1); },
+      ranges, saturate, 1e9, "size");
+  expect_failed(tester, tested, "outputs of different length fail");
+  expect_detail_contains(tester, tested, "size mismatch",
+                         "the size mismatch is reported");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "a size mismatch is a structural failure");
+}
+
+MCAP_REGISTER_TEST(declared_modes_must_be_exercised) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.declare_modes({"idle", "run", "fault"});
+  tested.record_mode("idle");
+  tested.record_mode("run");
+  tested.record_mode("run");
+  tested.record_mode("startup");
+  tested.expect_all_modes_exercised("modes");
+  expect_failed(tester, tested, "a declared mode never recorded fails");
+  expect_detail_contains(tester, tested, "Modes never exercised: fault.",
+                         "only the missing declared mode is listed");
+  TestSupport::expect_equal(tester, tested.get_mode_counts().at("run"), 2,
+                            "recorded modes are counted");
+
+  tested.record_mode("fault");
+  tested.declare_mode("fault");
+  tested.expect_all_modes_exercised("modes");
+  expect_passed(tester, tested, "every declared mode recorded passes");
+  TestSupport::expect_equal(tester, tested.get_mode_counts().at("fault"), 1,
+                            "declaring a recorded mode keeps its count");
+}
+
+MCAP_REGISTER_TEST(integration_method_is_identified) {
+  typedef Reference::IntegrationMethod Method;
+  std::vector<double> input = {1.0, 2.0, 3.0, 4.0};
+  std::vector<double> forward_euler = {0.0, 1.0, 3.0, 6.0};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_matches_integration(forward_euler, input, 1.0,
+                                    Method::ForwardEuler, 1e-12, "euler");
+  expect_passed(tester, tested, "the declared method passes");
+  tested.expect_matches_integration(std::vector<double>{0.0, 1.5, 4.0, 7.5},
+                                    input, 1.0, Method::Tustin, 1e-12,
+                                    "tustin");
+  expect_passed(tester, tested, "Tustin averages consecutive inputs");
+
+  tested.expect_matches_integration(forward_euler, input, 1.0, Method::Tustin,
+                                    1e-12, "declared");
+  expect_failed(tester, tested, "another method fails");
+  expect_detail_contains(tester, tested, "Sample 1 is 1, Tustin gives 1.5.",
+                         "the first differing sample is reported");
+  expect_detail_contains(tester, tested,
+                         "The output matches ForwardEuler instead.",
+                         "the method actually used is named");
+
+  tested.expect_matches_integration(std::vector<double>{0.0, 9.0, 9.0, 9.0},
+                                    input, 1.0, Method::Tustin, 1e-12,
+                                    "none");
+  expect_failed(tester, tested, "an output matching no method fails");
+  tester.expect_true(TestSupport::last_record(tested).detail.find(
+                         "instead") == std::string::npos,
+                     "no other method is named");
+}
+
+MCAP_REGISTER_TEST(integration_of_a_state_equation) {
+  /* Backward Euler on x' = -x with dt = 0.5 divides the state by 1.5. */
+  std::function<double(double, double)> decay = [](double x, double) {
+    return -x;
+  };
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_matches_integration(
+      std::vector<double>{1.0, 2.0 / 3.0, 4.0 / 9.0}, std::vector<double>(3),
+      0.5, Reference::IntegrationMethod::BackwardEuler, 1e-9, "decay", 1.0,
+      decay);
+  expect_passed(tester, tested, "the implicit step is solved");
+  tested.expect_matches_integration(
+      std::vector<double>{1.0, 0.5, 0.25}, std::vector<double>(3), 0.5,
+      Reference::IntegrationMethod::BackwardEuler, 1e-9, "decay", 1.0,
+      decay);
+  expect_failed(tester, tested, "the explicit result fails the implicit "
+                                "method");
+  expect_detail_contains(tester, tested, "matches ForwardEuler instead",
+                         "the explicit method is named");
+  tester.expect_true(TestSupport::last_record(tested).measured,
+                     "the largest error is recorded");
+}
+
+MCAP_REGISTER_TEST(saturation_block_semantics) {
+  std::vector<double> input = {-2.0, 0.5, 2.0};
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_saturation_consistent(input, {-1.0, 0.5, 1.0}, -1.0, 1.0,
+                                      1e-12, "saturation");
+  expect_passed(tester, tested, "a clamped output passes");
+  tested.expect_saturation_consistent(input, {-1.0, 0.5, 2.0}, -1.0, 1.0,
+                                      1e-12, "saturation");
+  expect_failed(tester, tested, "an unclamped output fails");
+  expect_detail_contains(tester, tested,
+                         "Sample 2 violates saturation to [-1, 1]: input 2, "
+                         "output 2, expected 1.",
+                         "the violating sample is reported");
+  tested.expect_saturation_consistent(input, {-1.0}, -1.0, 1.0, 1e9,
+                                      "saturation");
+  expect_failed(tester, tested, "signals of different length fail");
+}
+
+MCAP_REGISTER_TEST(rate_limiter_block_semantics) {
+  /* Rising at 1 per s and falling at 2 per s with dt = 0.5. */
+  std::vector<double> input = {5.0, 5.0, 5.0, -5.0, -5.0};
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_rate_limiter_consistent(input, {0.5, 1.0, 1.5, 0.5, -0.5},
+                                        1.0, -2.0, 0.5, 0.0, 1e-12,
+                                        "limiter");
+  expect_passed(tester, tested, "slew-limited steps pass");
+  tested.expect_rate_limiter_consistent(input, {0.5, 1.0, 1.5, 0.0, -1.0},
+                                        1.0, -2.0, 0.5, 0.0, 1e-12,
+                                        "limiter");
+  expect_failed(tester, tested, "a fall faster than the limit fails");
+  expect_detail_contains(tester, tested,
+                         "Sample 3 violates rate limits [-2, 1] per s: input "
+                         "-5, output 0, expected 0.5.",
+                         "the first violating sample is reported");
+  tested.expect_rate_limiter_consistent({1.0, 1.0}, {0.5, 1.0}, 1.0, -2.0,
+                                        0.5, 0.0, 1e-12, "limiter");
+  expect_passed(tester, tested, "the output reaches a slow input");
+  tested.expect_rate_limiter_consistent(input, {0.5}, 1.0, -2.0, 0.5, 0.0,
+                                        1e9, "limiter");
+  expect_failed(tester, tested, "signals of different length fail");
+}
+
+MCAP_REGISTER_TEST(integrator_windup_is_measured) {
+  /* Saturated from sample 1 to 3 while the error reverses by 0.5 twice:
+   * windup 2 * 0.5 * (0.5 + 0.5) = 1. */
+  std::vector<double> error = {1.0, 1.0, -0.5, -0.5, 0.0};
+  std::vector<double> command = {0.5, 1.0, 1.0, 1.0, 0.5};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_no_integrator_windup(error, command, -1.0, 1.0, 2.0, 0.5,
+                                     1.0, "windup");
+  expect_passed(tester, tested, "a windup at the bound passes");
+  tested.expect_no_integrator_windup(error, command, -1.0, 1.0, 2.0, 0.5,
+                                     0.5, "windup");
+  expect_failed(tester, tested, "a windup beyond the bound fails");
+  expect_detail_contains(tester, tested,
+                         "Integrator windup of 1 during the saturation from "
+                         "sample 1 to 3 (command 1). Allowed: 0.5.",
+                         "the worst episode is reported");
+
+  /* With anti-windup the command leaves the limit when the error reverses. */
+  tested.expect_no_integrator_windup({1.0, 1.0, -0.5}, {0.5, 1.0, 0.8}, -1.0,
+                                     1.0, 2.0, 0.5, 0.0, "anti-windup");
+  expect_passed(tester, tested, "leaving the limit at once passes");
+  tested.expect_no_integrator_windup({-1.0, 0.5}, {-1.0, -1.0}, -1.0, 1.0,
+                                     2.0, 0.5, 0.1, "lower");
+  expect_failed(tester, tested, "a windup at the lower limit fails");
+}
+
+MCAP_REGISTER_TEST(integrator_windup_degenerate_logs) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_no_integrator_windup({}, {}, -1.0, 1.0, 2.0, 0.5, 0.0,
+                                     "empty");
+  expect_passed(tester, tested, "an empty log has no windup");
+  tested.expect_no_integrator_windup({0.0, 0.0}, {0.0, 0.0}, -1.0, 1.0, 2.0,
+                                     0.5, 0.0, "unsaturated");
+  expect_passed(tester, tested, "a log without saturation passes");
+
+  tested.expect_no_integrator_windup({}, {}, -1.0, 1.0, 2.0, 0.5, -0.1,
+                                     "negative");
+  expect_failed(tester, tested, "a negative windup bound is rejected");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "as a configuration error");
+  tested.expect_no_integrator_windup({0.0}, {0.0, 0.0}, -1.0, 1.0, 2.0, 0.5,
+                                     1e9, "size");
+  expect_failed(tester, tested, "logs of different length fail");
+}
+
+MCAP_REGISTER_TEST(scenario_files_report_their_expectations) {
+  const char *step = "{\"name\": \"step\", \"steps\": 50, "
+                     "\"initial_measurement\": [0], "
+                     "\"reference\": {\"type\": \"step\", \"time\": 0, "
+                     "\"initial\": [0], \"final\": [1]}, ";
+  double gain = 0.5;
+  Scenario::HarnessFactory<double> factory = [&gain]() {
+    return proportional_loop(gain);
+  };
+
+  MCAPTester<double> tested = quiet_tester();
+  TestSupport::TemporaryFile settled("scenario_settled.json");
+  settled.write(std::string(step) +
+                "\"expected\": {\"final_measurement\": [1], "
+                "\"final_tolerance\": 0.01, \"max_measurement\": [1.01]}}");
+  tester.expect_true(Scenario::run_scenario_file(tested, settled.path(),
+                                                 factory),
+                     "the settled scenario returns true");
+  expect_passed(tester, tested, "a loop that settles passes");
+  tester.expect_true(TestSupport::last_record(tested).message ==
+                         "Scenario: step",
+                     "the record is named after the scenario");
+
+  TestSupport::TemporaryFile limited("scenario_limited.json");
+  limited.write(std::string(step) +
+                "\"expected\": {\"max_abs_command\": [0.5]}}");
+  tester.expect_true(!Scenario::run_scenario_file(tested, limited.path(),
+                                                  factory),
+                     "the limited scenario returns false");
+  expect_failed(tester, tested, "a command above its limit fails");
+  expect_detail_contains(tester, tested, "Command of channel 0 is 1 at step 0",
+                         "the detail names the first violation");
+
+  /* An injected parameter change opens the loop from the first step. */
+  TestSupport::TemporaryFile frozen("scenario_frozen.json");
+  frozen.write(std::string(step) +
+               "\"injections\": [{\"type\": \"parameter_change\", "
+               "\"parameter\": \"gain\", \"time\": 0, \"value\": 0}], "
+               "\"expected\": {\"final_measurement\": [1], "
+               "\"final_tolerance\": 0.01}}");
+  Scenario::Parameters<double> parameters = {
+      {"gain", [&gain](double value) { gain = value; }}};
+  Scenario::run_scenario_file(tested, frozen.path(), factory, parameters);
+  expect_failed(tester, tested, "a loop without gain does not settle");
+  expect_detail_contains(tester, tested,
+                         "Final measurement of channel 0 is 0, expected 1.",
+                         "the detail names the final measurement");
+}
+
+MCAP_REGISTER_TEST(scenario_files_report_invalid_input_as_infrastructure) {
+  double gain = 0.5;
+  Scenario::HarnessFactory<double> factory = [&gain]() {
+    return proportional_loop(gain);
+  };
+  MCAPTester<double> tested = quiet_tester();
+
+  TestSupport::TemporaryFile stepless("scenario_stepless.json");
+  stepless.write("{\"name\": \"stepless\"}");
+  Scenario::run_scenario_file(tested, stepless.path(), factory);
+  expect_failed(tester, tested, "a scenario without steps fails");
+  expect_detail_contains(tester, tested, "Missing number of steps.",
+                         "the detail names the missing field");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Infrastructure,
+                     "a scenario without steps is an infrastructure failure");
+
+  TestSupport::TemporaryFile unknown("scenario_unknown.json");
+  unknown.write("{\"steps\": 1, \"injections\": [{\"type\": "
+                "\"parameter_change\", \"parameter\": \"gain\"}]}");
+  Scenario::run_scenario_file(tested, unknown.path(), factory);
+  expect_detail_contains(tester, tested, "Unknown parameter \"gain\"",
+                         "an unknown parameter fails");
+
+  Scenario::run_scenario_file(tested, "mcap_test_missing_scenario.json",
+                              factory);
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Infrastructure,
+                     "a missing file is an infrastructure failure");
+
+  expect_equal(tester,
+               Scenario::run_scenarios(tested, "mcap_test_no_scenarios",
+                                       factory),
+               1, "a missing directory counts as one failure");
+  expect_detail_contains(tester, tested, "No scenario files found.",
+                         "the detail names the missing scenarios");
+}
+
+MCAP_REGISTER_TEST(total_effort_within_its_budget) {
+  std::vector<double> command = {1.0, -1.0, 2.0};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_total_effort_below(command, 0.5, 2.0, "absolute");
+  expect_passed(tester, tested, "an effort equal to the budget passes");
+  tested.expect_total_effort_below(command, 0.5, 1.5, "absolute");
+  expect_failed(tester, tested, "an effort above the budget fails");
+  expect_detail_contains(tester, tested, "Integral of |u| 2, budget 1.5.",
+                         "the detail names the absolute effort");
+
+  tested.expect_total_effort_below(command, 0.5, 2.5, "squared",
+                                   EffortMeasure::Squared);
+  expect_failed(tester, tested, "the squared effort counts large commands "
+                                "more");
+  expect_detail_contains(tester, tested, "Integral of u^2 3, budget 2.5.",
+                         "the detail names the squared effort");
+}
+
+MCAP_REGISTER_TEST(state_space_step_and_impulse_responses) {
+  /* x[k + 1] = 0.5 x[k] + u[k], y[k] = x[k]. */
+  Reference::StateSpace<double> model({{0.5}}, {{1.0}}, {{1.0}}, {{0.0}});
+  double pole = 0.5;
+  double state = 0.0;
+  StateSpaceTest<double>::Implementation implementation =
+      [&pole, &state](const std::vector<double> &u) {
+        std::vector<double> y = {state};
+        state = pole * state + u[0];
+        return y;
+      };
+
+  MCAPTester<double> tested = quiet_tester();
+  StateSpaceTest<double> test(tested, model);
+  test.expect_step_response(20, implementation, 1e-12, "step");
+  expect_passed(tester, tested, "a matching step response passes");
+
+  pole = 0.6;
+  state = 0.0;
+  test.expect_impulse_response(20, implementation, 1e-12, "impulse");
+  expect_failed(tester, tested, "a misplaced pole fails the impulse response");
+
+  state = 0.0;
+  test.expect_step_response(20, implementation, 1e-12, "channel", 1);
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "a missing input channel is a structural failure");
+}
@@ -0,0 +1,404 @@
+/**
+ * @file test_matrices.cpp
+ * @brief Tests the assertions on matrices, angles, rotations and complex
+ * values: reductions, structural properties, stability and norms.
+ */
+
+#include <cmath>
+#include <complex>
+#include <cstddef>
+#include <functional>
+#include <string>
+#include <vector>
+
+#include "MCAP_tester_runner.hpp"
+#include "test_support.hpp"
+
+using namespace Tester;
+using TestSupport::expect_detail_contains;
+using TestSupport::expect_failed;
+using TestSupport::expect_passed;
+using TestSupport::quiet_tester;
+
+namespace {
+
+typedef std::vector<std::vector<double>> Matrix;
+
+} // namespace
+
+MCAP_REGISTER_TEST(reductions_of_rows_and_columns) {
+  Matrix matrix = {{1.0, 2.0, 3.0}, {4.0, 5.0, 6.0}};
+  MCAPTester<double> tested = quiet_tester();
+
+  tested.expect_row_sums_near(matrix, {6.0, 15.0}, 0.0, "row sums");
+  expect_passed(tester, tested, "matching row sums pass");
+  tested.expect_row_sums_near(matrix, {6.0, 14.0}, 0.5, "row sums");
+  expect_failed(tester, tested, "a row sum beyond the tolerance fails");
+
+  tested.expect_col_sums_near(matrix, {5.0, 7.0, 9.0}, 0.0, "column sums");
+  expect_passed(tester, tested, "matching column sums pass");
+  tested.expect_col_sums_near(matrix, {5.0, 7.0, 8.0}, 0.5, "column sums");
+  expect_failed(tester, tested, "a column sum beyond the tolerance fails");
+
+  tested.expect_row_means_near(matrix, {2.0, 5.0}, 1e-12, "row means");
+  expect_passed(tester, tested, "matching row means pass");
+  tested.expect_col_means_near(matrix, {2.5, 3.5, 4.5}, 1e-12,
+                               "column means");
+  expect_passed(tester, tested, "matching column means pass");
+  tested.expect_col_means_near(matrix, {2.5, 3.5, 5.0}, 0.1, "column means");
+  expect_failed(tester, tested, "a column mean beyond the tolerance fails");
+
+  tested.expect_row_sums_near(matrix, {6.0, 15.0, 0.0}, 1e9, "count");
+  expect_failed(tester, tested, "a wrong number of expected values fails");
+}
+
+MCAP_REGISTER_TEST(reductions_with_a_custom_reducer) {
+  Matrix matrix = {{1.0, -7.0}, {4.0, 2.0}};
+  std::function<double(const std::vector<double> &)> largest =
+      [](const std::vector<double> &values) {
+        double result = values[0];
+        for (std::size_t i = 1; i < values.size(); i++) {
+          result = values[i] > result ? values[i] : result;
+        }
+        return result;
+      };
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_reduced_near(matrix, ReductionAxis::Rows, largest,
+                             {1.0, 4.0}, 0.0, "largest");
+  expect_passed(tester, tested, "the custom reducer is applied per row");
+  tested.expect_reduced_near(matrix, ReductionAxis::Columns, largest,
+                             {1.0, 4.0}, 0.0, "largest");
+  expect_failed(tester, tested, "the custom reducer is applied per column");
+
+  tested.expect_reduced_near(std::vector<double>{1.0, 2.0},
+                             ReductionAxis::Rows, largest, {2.0}, 0.0,
+                             "vector");
+  expect_failed(tester, tested, "a one-dimensional array fails");
+  expect_detail_contains(tester, tested, "Not a two-dimensional array",
+                         "the structural problem is reported");
+}
+
+MCAP_REGISTER_TEST(symmetric_and_positive_definite) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_symmetric(Matrix{{2.0, 1.0}, {1.05, 3.0}}, 0.1, "symmetric");
+  expect_passed(tester, tested, "an asymmetry within the tolerance passes");
+  tested.expect_symmetric(Matrix{{2.0, 1.0}, {1.5, 3.0}}, 0.1, "symmetric");
+  expect_failed(tester, tested, "an asymmetry beyond the tolerance fails");
+  expect_detail_contains(tester, tested, "differs from its transpose by 0.5",
+                         "the asymmetric entry is reported");
+  tested.expect_symmetric(Matrix{{1.0, 2.0}}, 0.1, "square");
+  expect_failed(tester, tested, "a non-square matrix fails");
+
+  tested.expect_positive_definite(Matrix{{2.0, 1.0}, {1.0, 2.0}}, 1e-9,
+                                  "definite");
+  expect_passed(tester, tested, "eigenvalues 1 and 3 pass");
+  tested.expect_positive_definite(Matrix{{1.0, 2.0}, {2.0, 1.0}}, 1e-9,
+                                  "definite");
+  expect_failed(tester, tested, "a negative eigenvalue fails");
+  expect_detail_contains(tester, tested, "Not positive definite. Eigenvalue "
+                                         "0 is -1",
+                         "the violating eigenvalue is reported");
+  tested.expect_positive_definite(Matrix{{2.0, 0.0}, {1.0, 2.0}}, 1e-9,
+                                  "definite");
+  expect_failed(tester, tested, "an asymmetric matrix fails");
+}
+
+MCAP_REGISTER_TEST(orthogonal_and_diagonally_dominant) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_orthogonal(Matrix{{0.0, -1.0}, {1.0, 0.0}}, 1e-12,
+                           "orthogonal");
+  expect_passed(tester, tested, "a rotation by 90 degrees is orthogonal");
+  tested.expect_orthogonal(Matrix{{1.0, 0.5}, {0.0, 1.0}}, 1e-6,
+                           "orthogonal");
+  expect_failed(tester, tested, "a shear is not orthogonal");
+  expect_detail_contains(tester, tested, "of Q^T Q deviates",
+                         "the deviating entry of Q^T Q is reported");
+  tested.expect_orthogonal(Matrix{{1.0, 0.0}}, 1e-6, "square");
+  expect_failed(tester, tested, "a non-square matrix fails");
+
+  tested.expect_diagonally_dominant(Matrix{{3.0, -1.0}, {1.0, -2.0}}, 0.0,
+                                    "dominant");
+  expect_passed(tester, tested, "dominant rows pass");
+  tested.expect_diagonally_dominant(Matrix{{3.0, -1.0}, {2.0, 1.0}}, 0.5,
+                                    "dominant");
+  expect_failed(tester, tested, "a row dominated by its off-diagonal sum "
+                                "fails");
+  expect_detail_contains(tester, tested, "Row 1 has |diagonal| 1",
+                         "the violating row is reported");
+  tested.expect_diagonally_dominant(Matrix{{3.0, -1.0}, {2.0, 1.5}}, 0.5,
+                                    "dominant");
+  expect_passed(tester, tested, "an excess within the tolerance passes");
+}
+
+MCAP_REGISTER_TEST(unordered_values_are_paired) {
+  MCAPTester<double> tested = quiet_tester();
+  std::vector<double> values = {3.0, 1.0, 2.0};
+  tested.expect_near_unordered(values, std::vector<double>{1.0, 2.0, 3.05},
+                               0.1, "eigenvalues");
+  expect_passed(tester, tested, "the same values in another order pass");
+  tested.expect_near_unordered(values, std::vector<double>{1.0, 2.0, 4.0},
+                               0.1, "eigenvalues");
+  expect_failed(tester, tested, "a value without a partner fails");
+  expect_detail_contains(tester, tested, "Actual: 3 [0]; expected: 4 [2]",
+                         "the unmatched values are reported");
+  tested.expect_near_unordered(std::vector<double>{1.0, 1.0},
+                               std::vector<double>{1.0}, 0.1, "eigenvalues");
+  expect_failed(tester, tested, "sets of different size fail");
+  expect_detail_contains(tester, tested, "Size mismatch",
+                         "the size mismatch is reported");
+
+  typedef std::complex<double> Complex;
+  tested.expect_near_unordered(
+      std::vector<Complex>{Complex(-1.0, 2.0), Complex(-1.0, -2.0)},
+      std::vector<Complex>{Complex(-1.0, -2.0), Complex(-1.0, 2.0)}, 1e-12,
+      "poles");
+  expect_passed(tester, tested, "conjugate pairs in another order pass");
+  tested.expect_near_unordered(
+      std::vector<Complex>{Complex(-1.0, 2.0), Complex(-1.0, -2.0)},
+      std::vector<Complex>{Complex(-1.0, 2.0), Complex(-1.0, 2.0)}, 1e-12,
+      "poles");
+  expect_failed(tester, tested, "a value cannot be paired twice");
+}
+
+MCAP_REGISTER_TEST(stability_of_continuous_and_discrete_poles) {
+  typedef std::complex<double> Complex;
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_stable_continuous(std::vector<double>{-1.0, -0.2}, 0.1,
+                                  "continuous");
+  expect_passed(tester, tested, "poles left of the margin pass");
+  tested.expect_stable_continuous(
+      std::vector<Complex>{Complex(-1.0, 0.0), Complex(-0.05, 3.0)}, 0.1,
+      "continuous");
+  expect_failed(tester, tested, "a pole inside the margin fails");
+  expect_detail_contains(tester, tested,
+                         "Unstable poles: -0.05+3i [1] (required real part "
+                         "below -0.1)",
+                         "the violating pole is reported");
+
+  tested.expect_stable_discrete(
+      std::vector<Complex>{Complex(0.5, 0.5), Complex(0.5, -0.5)}, 0.1,
+      "discrete");
+  expect_passed(tester, tested, "poles inside the shrunk unit circle pass");
+  tested.expect_stable_discrete(std::vector<double>{0.5, -0.95}, 0.1,
+                                "discrete");
+  expect_failed(tester, tested, "a pole beyond 1 - margin fails");
+  expect_detail_contains(tester, tested, "-0.95 [1]",
+                         "the violating pole is reported");
+}
+
+MCAP_REGISTER_TEST(angles_wrap_around_the_circle) {
+  const double pi = 3.14159265358979323846;
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_angle_scalar(pi, -pi, 1e-12, "half turn");
+  expect_passed(tester, tested, "pi and -pi are the same angle");
+  tested.expect_near_angle_scalar(0.05, 2.0 * pi - 0.05, 0.2, "zero");
+  expect_passed(tester, tested, "angles on both sides of zero are close");
+  tested.expect_near_angle_scalar(0.0, pi / 2.0, 0.1, "quarter");
+  expect_failed(tester, tested, "a quarter turn apart fails");
+
+  tested.expect_near_angle_scalar(359.5, 0.5, 1.0, "degrees",
+                                  AngleUnit::Degrees);
+  expect_passed(tester, tested, "degrees wrap at 360");
+  tested.expect_near_angle_scalar(350.0, 10.0, 1.0, "degrees",
+                                  AngleUnit::Degrees);
+  expect_failed(tester, tested, "20 degrees apart fails a 1 degree "
+                                "tolerance");
+  tested.expect_near_angle_scalar(0.0, 0.0, -1.0, "negative");
+  expect_failed(tester, tested, "a negative tolerance is rejected");
+}
+
+MCAP_REGISTER_TEST(angle_arrays_compare_element_wise) {
+  const double pi = 3.14159265358979323846;
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_angle(std::vector<double>{pi, 0.0, 3.0 * pi},
+                           std::vector<double>{-pi, 2.0 * pi, pi}, 1e-9,
+                           "headings");
+  expect_passed(tester, tested, "whole turns apart pass");
+  tested.expect_near_angle(std::vector<double>{pi, 0.5},
+                           std::vector<double>{-pi, 0.0}, 0.1, "headings");
+  expect_failed(tester, tested, "an element beyond the tolerance fails");
+  tested.expect_near_angle(std::vector<double>{0.0, 0.0},
+                           std::vector<double>{0.0}, 0.1, "headings");
+  expect_failed(tester, tested, "a different size fails");
+}
+
+MCAP_REGISTER_TEST(quaternions_compare_by_rotation_angle) {
+  typedef std::vector<double> Quaternion;
+  Quaternion identity = {1.0, 0.0, 0.0, 0.0};
+  Quaternion about_z = {std::cos(0.1), 0.0, 0.0, std::sin(0.1)};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_quaternion(Quaternion{-1.0, 0.0, 0.0, 0.0}, identity,
+                                1e-12, "sign");
+  expect_passed(tester, tested, "q and -q are the same rotation");
+  tested.expect_near_quaternion(Quaternion{2.0, 0.0, 0.0, 0.0}, identity,
+                                1e-12, "norm");
+  expect_passed(tester, tested, "quaternions are normalized first");
+  tested.expect_near_quaternion(about_z, identity, 0.25, "rotation");
+  expect_passed(tester, tested, "a rotation within the tolerance passes");
+  tested.expect_near_quaternion(about_z, identity, 0.1, "rotation");
+  expect_failed(tester, tested, "a rotation beyond the tolerance fails");
+  expect_detail_contains(tester, tested, "Rotation differs by 0.2 rad",
+                         "the rotation angle is reported");
+
+  tested.expect_near_quaternion(Quaternion(4, 0.0), identity, 1e9, "zero");
+  expect_failed(tester, tested, "a zero quaternion fails");
+  tested.expect_near_quaternion(Quaternion{1.0, 0.0, 0.0}, identity, 1e9,
+                                "size");
+  expect_failed(tester, tested, "three components fail");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "as a structural failure");
+}
+
+MCAP_REGISTER_TEST(rotation_matrices_compare_by_geodesic_angle) {
+  Matrix identity = {{1.0, 0.0, 0.0}, {0.0, 1.0, 0.0}, {0.0, 0.0, 1.0}};
+  Matrix about_z = {{std::cos(0.2), -std::sin(0.2), 0.0},
+                    {std::sin(0.2), std::cos(0.2), 0.0},
+                    {0.0, 0.0, 1.0}};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_rotation(identity, identity, 1e-9, "same");
+  expect_passed(tester, tested, "identical rotations pass");
+  tested.expect_near_rotation(about_z, identity, 0.25, "rotation");
+  expect_passed(tester, tested, "a rotation within the tolerance passes");
+  tested.expect_near_rotation(about_z, identity, 0.1, "rotation");
+  expect_failed(tester, tested, "a rotation beyond the tolerance fails");
+  expect_detail_contains(tester, tested, "Rotation differs by 0.2 rad",
+                         "the relative rotation angle is reported");
+  tested.expect_near_rotation(Matrix{{1.0, 0.0}, {0.0, 1.0}},
+                              Matrix{{1.0, 0.0}, {0.0, 1.0}}, 1e9, "size");
+  expect_failed(tester, tested, "a 2 x 2 matrix fails");
+}
+
+MCAP_REGISTER_TEST(covariance_is_symmetric_semidefinite_and_bounded) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_valid_covariance(Matrix{{2.0, 1.0}, {1.0, 2.0}}, 1e-12, 0.0,
+                                 10.0, "covariance");
+  expect_passed(tester, tested, "a valid covariance passes");
+  tested.expect_valid_covariance(Matrix{{1.0, 1.0}, {1.0, 1.0}}, 1e-12,
+                                 -1e-12, 10.0, "singular");
+  expect_passed(tester, tested, "a semi-definite covariance passes");
+
+  tested.expect_valid_covariance(Matrix{{1.0, 2.0}, {2.0, 1.0}}, 1e-12, 0.0,
+                                 10.0, "indefinite");
+  expect_failed(tester, tested, "a negative eigenvalue fails");
+  expect_detail_contains(tester, tested,
+                         "Smallest eigenvalue -1 is below 0 by 1",
+                         "the eigenvalue violation is quantified");
+
+  tested.expect_valid_covariance(Matrix{{2.0, 1.0}, {1.2, 2.0}}, 0.1, 0.0,
+                                 3.0, "all");
+  expect_failed(tester, tested, "an asymmetric covariance with a large "
+                                "trace fails");
+  expect_detail_contains(tester, tested, "Not symmetric",
+                         "the asymmetry is reported");
+  expect_detail_contains(tester, tested, "Trace 4 exceeds 3 by 1",
+                         "the trace violation is reported in the same "
+                         "failure");
+
+  tested.expect_valid_covariance(Matrix{{1.0, 0.0}}, 1e9, -1e9, 1e9,
+                                 "square");
+  expect_failed(tester, tested, "a non-square matrix fails");
+}
+
+MCAP_REGISTER_TEST(phasors_check_magnitude_and_phase_separately) {
+  typedef std::complex<double> Complex;
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_phasor(std::polar(1.05, 0.01), Complex(1.0, 0.0), 0.1,
+                            1.0, "phasor");
+  expect_passed(tester, tested, "a phasor within both tolerances passes");
+  tested.expect_near_phasor(Complex(1.5, 0.0), Complex(1.0, 0.0), 0.1, 1.0,
+                            "phasor");
+  expect_failed(tester, tested, "a magnitude beyond the tolerance fails");
+  expect_detail_contains(tester, tested, "Magnitude mismatch at phasor 0",
+                         "the magnitude failure is named");
+  tested.expect_near_phasor(Complex(0.0, 1.0), Complex(1.0, 0.0), 0.1, 1.0,
+                            "phasor");
+  expect_failed(tester, tested, "a phase beyond the tolerance fails");
+  expect_detail_contains(tester, tested, "phase error 90 deg",
+                         "the phase error is reported in degrees");
+
+  tested.expect_near_phasor(
+      std::vector<Complex>{Complex(1.0, 0.0), Complex(0.0, -0.01)},
+      std::vector<Complex>{Complex(1.0, 0.0), Complex(0.01, 0.0)}, 0.1, 1.0,
+      "small");
+  expect_passed(tester, tested, "the phase of a near-zero phasor is not "
+                                "checked");
+  tested.expect_near_phasor(std::vector<Complex>(2),
+                            std::vector<Complex>(3), 1e9, 1e9, "size");
+  expect_failed(tester, tested, "sequences of different length fail");
+}
+
+MCAP_REGISTER_TEST(norm_based_comparison) {
+  /* The difference has Frobenius and spectral norm 0.5, while the expected
+   * matrix has Frobenius norm 5 and spectral norm 4. */
+  Matrix expected = {{3.0, 0.0}, {0.0, 4.0}};
+  Matrix actual = {{3.5, 0.0}, {0.0, 4.0}};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_norm(actual, expected, 0.1, "frobenius");
+  expect_passed(tester, tested, "a relative Frobenius error at the "
+                                "tolerance passes");
+  tested.expect_near_norm(actual, expected, 0.05, "frobenius");
+  expect_failed(tester, tested, "a relative Frobenius error beyond the "
+                                "tolerance fails");
+  expect_detail_contains(tester, tested,
+                         "Frobenius relative error 0.1 exceeds 0.05",
+                         "the relative error is reported");
+
+  tested.expect_near_norm(actual, expected, 0.13, "spectral",
+                          MatrixNorm::Spectral);
+  expect_passed(tester, tested, "a relative spectral error within the "
+                                "tolerance passes");
+  tested.expect_near_norm(actual, expected, 0.1, "spectral",
+                          MatrixNorm::Spectral);
+  expect_failed(tester, tested, "the spectral norm is stricter here");
+  expect_detail_contains(tester, tested, "Spectral relative error 0.125",
+                         "the spectral error is reported");
+
+  tested.expect_near_norm(Matrix{{1.0, 2.0}}, expected, 1e9, "size");
+  expect_failed(tester, tested, "matrices of different shape fail");
+}
+
+MCAP_REGISTER_TEST(sparse_patterns_and_values) {
+  SparseMatrix<double> expected =
+      make_sparse_matrix(make_flat_array(Matrix{{4.0, 0.0}, {1.0, 3.0}}));
+
+  MCAPTester<double> tested = quiet_tester();
+  SparseMatrix<double> unsorted;
+  unsorted.rows = 2;
+  unsorted.columns = 2;
+  unsorted.row_pointers = {0, 1, 3};
+  unsorted.column_indices = {0, 1, 0};
+  unsorted.values = {4.0, 3.05, 1.0};
+  tested.expect_near_sparse(unsorted, expected, 0.1, "unsorted");
+  expect_passed(tester, tested, "the same pattern in another column order "
+                                "passes");
+
+  tested.expect_near_sparse(
+      make_sparse_matrix(make_flat_array(Matrix{{4.0, 2.0}, {0.0, 3.5}})),
+      expected, 0.1, "pattern");
+  expect_failed(tester, tested, "a different pattern fails");
+  expect_detail_contains(tester, tested, "Missing nonzeros (1, 0).",
+                         "the missing nonzero is reported");
+  expect_detail_contains(tester, tested, "Extra nonzeros (0, 1).",
+                         "the extra nonzero is reported");
+  expect_detail_contains(tester, tested,
+                         "1 value mismatch(es), first at (1, 1): actual 3.5, "
+                         "expected 3",
+                         "the value mismatch is reported");
+
+  tested.expect_near_sparse(
+      make_sparse_matrix(make_flat_array(Matrix{{4.0, 0.0, 0.0}})), expected,
+      1e9, "size");
+  expect_failed(tester, tested, "different dimensions fail");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "as a structural failure");
+
+  unsorted.row_pointers = {0, 1};
+  tested.expect_near_sparse(unsorted, expected, 1e9, "malformed");
+  expect_failed(tester, tested, "a malformed matrix fails");
+}
@@ -0,0 +1,510 @@
+/**
+ * @file test_signals.cpp
+ * @brief Tests the assertions on time series and signals: trajectories,
+ * error metrics, spectra and the steady-state, limit-cycle and quietness
+ * checks.
+ */
+
+#include <cmath>
+#include <cstddef>
+#include <limits>
+#include <map>
+#include <string>
+#include <unordered_map>
+#include <vector>
+
+#include "MCAP_tester_runner.hpp"
+#include "MCAP_tester_streaming.hpp"
+#include "test_support.hpp"
+
+using namespace Tester;
+using TestSupport::expect_detail_contains;
+using TestSupport::expect_failed;
+using TestSupport::expect_passed;
+using TestSupport::quiet_tester;
+
+namespace {
+
+typedef std::vector<std::vector<double>> Trajectory;
+
+/* Samples 16 values of a sinusoid at 16 Hz, delayed by `delay` samples. */
+std::vector<double> sinusoid(double amplitude, double frequency,
+                             int delay = 0) {
+  std::vector<double> samples;
+  for (int n = 0; n < 16; n++) {
+    samples.push_back(amplitude * std::sin(2.0 * 3.14159265358979323846 *
+                                           frequency * (n - delay) / 16.0));
+  }
+  return samples;
+}
+
+} // namespace
+
+MCAP_REGISTER_TEST(trajectory_within_tolerance_passes) {
+  Trajectory expected = {{0.0, 1.0}, {0.5, 1.0}, {1.0, 1.0}};
+  Trajectory actual = {{0.0, 1.05}, {0.45, 1.0}, {1.0, 0.95}};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_trajectory(actual, expected, 0.1, "trajectory");
+  expect_passed(tester, tested, "every sample within the tolerance passes");
+}
+
+MCAP_REGISTER_TEST(trajectory_failure_reports_the_worst_sample) {
+  Trajectory expected = {{0.0, 1.0}, {0.0, 1.0}, {0.0, 1.0}, {0.0, 1.0}};
+  Trajectory actual = {{0.0, 1.0}, {0.5, 1.0}, {0.0, 0.0}, {0.0, 1.0}};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_trajectory(actual, expected, 0.1, "trajectory");
+  expect_failed(tester, tested, "a sample beyond the tolerance fails");
+  expect_detail_contains(tester, tested, "Worst error 1 at sample 2, signal 1",
+                         "the worst sample and signal are named");
+  expect_detail_contains(tester, tested, "samples 1 to 2",
+                         "the range of violating samples is named");
+  expect_detail_contains(tester, tested, "(2 elements)",
+                         "the violating elements are counted");
+
+  TrajectoryResult<double> result = Comparison::compare_trajectory(
+      make_flat_array(actual), make_flat_array(expected), 0.1, 0);
+  TestSupport::expect_equal(tester, result.envelope.size(), 4,
+                            "the envelope has one entry per sample");
+  TestSupport::expect_same(tester, result.envelope[1], 0.5,
+                           "the envelope holds the worst error per sample");
+
+  tested.expect_near_trajectory(Trajectory{{0.0, 1.0}}, expected, 0.1,
+                                "trajectory");
+  expect_failed(tester, tested, "a different number of samples fails");
+}
+
+MCAP_REGISTER_TEST(trajectory_settling_samples_are_excluded) {
+  Trajectory expected = {{1.0}, {1.0}, {1.0}, {1.0}};
+  Trajectory actual = {{0.0}, {0.5}, {1.0}, {1.0}};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_trajectory(actual, expected, 0.1, "settled", 2);
+  expect_passed(tester, tested, "the excluded transient does not count");
+  tested.expect_near_trajectory(actual, expected, 0.1, "settled", 1);
+  expect_failed(tester, tested, "a violation after the settling samples "
+                                "fails");
+}
+
+MCAP_REGISTER_TEST(error_metrics_at_and_beyond_their_thresholds) {
+  /* The errors are {1, 0, 0, 0}: RMSE 0.5, MAE 0.25 and max error 1. */
+  std::vector<double> actual = {1.0, 2.0, 3.0, 4.0};
+  std::vector<double> expected = {0.0, 2.0, 3.0, 4.0};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_rmse_below(actual, expected, 0.5, "rmse");
+  expect_passed(tester, tested, "an RMSE equal to the threshold passes");
+  tested.expect_rmse_below(actual, expected, 0.49, "rmse");
+  expect_failed(tester, tested, "an RMSE above the threshold fails");
+  expect_detail_contains(tester, tested, "RMSE 0.5 exceeds",
+                         "the computed RMSE is reported");
+
+  tested.expect_mae_below(actual, expected, 0.25, "mae");
+  expect_passed(tester, tested, "an MAE equal to the threshold passes");
+  tested.expect_mae_below(actual, expected, 0.2, "mae");
+  expect_failed(tester, tested, "an MAE above the threshold fails");
+  expect_detail_contains(tester, tested, "MAE 0.25 exceeds",
+                         "the computed MAE is reported");
+
+  tested.expect_max_error_below(actual, expected, 1.0, "max");
+  expect_passed(tester, tested, "a max error equal to the threshold passes");
+  tested.expect_max_error_below(actual, expected, 0.5, "max");
+  expect_failed(tester, tested, "a max error above the threshold fails");
+}
+
+MCAP_REGISTER_TEST(error_metrics_size_mismatch_and_nan_fail) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_rmse_below(std::vector<double>{1.0, 2.0},
+                           std::vector<double>{1.0}, 1e9, "size");
+  expect_failed(tester, tested, "arrays of different size fail");
+  expect_detail_contains(tester, tested, "Size mismatch",
+                         "the size mismatch is reported");
+
+  std::vector<double> with_nan = {1.0,
+                                  std::numeric_limits<double>::quiet_NaN()};
+  tested.expect_mae_below(with_nan, std::vector<double>{1.0, 1.0}, 1e9,
+                          "nan");
+  expect_failed(tester, tested, "a NaN error fails any threshold");
+}
+
+MCAP_REGISTER_TEST(spectrum_magnitude_within_the_band) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_spectrum(sinusoid(1.0, 2.0, 1), sinusoid(1.0, 2.0),
+                              16.0, 0.0, 8.0, 1e-9, "shifted");
+  expect_passed(tester, tested, "a time shift keeps the magnitudes");
+
+  tested.expect_near_spectrum(sinusoid(1.5, 2.0), sinusoid(1.0, 2.0), 16.0,
+                              0.0, 8.0, 0.1, "amplitude");
+  expect_failed(tester, tested, "a different amplitude fails");
+  expect_detail_contains(tester, tested, "Magnitude mismatch at 2 Hz",
+                         "the failing frequency is named");
+
+  tested.expect_near_spectrum(sinusoid(1.5, 2.0), sinusoid(1.0, 2.0), 16.0,
+                              3.0, 8.0, 0.1, "band");
+  expect_passed(tester, tested, "bins outside the band are not compared");
+
+  tested.expect_near_spectrum(sinusoid(1.0, 2.0), std::vector<double>(8),
+                              16.0, 0.0, 8.0, 1e9, "size");
+  expect_failed(tester, tested, "signals of different length fail");
+}
+
+MCAP_REGISTER_TEST(spectrum_phase_is_compared_when_requested) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_spectrum(sinusoid(1.0, 2.0), sinusoid(1.0, 2.0), 16.0,
+                              0.0, 8.0, 1e-9, 1e-9, "same");
+  expect_passed(tester, tested, "identical signals pass the phase check");
+
+  /* A delay of one sample shifts the phase at 2 Hz by pi / 4. */
+  tested.expect_near_spectrum(sinusoid(1.0, 2.0, 1), sinusoid(1.0, 2.0),
+                              16.0, 0.0, 8.0, 1e-9, 0.1, "shifted");
+  expect_failed(tester, tested, "a phase difference above the tolerance "
+                                "fails");
+  expect_detail_contains(tester, tested, "Phase mismatch at 2 Hz",
+                         "the phase failure is named");
+  tested.expect_near_spectrum(sinusoid(1.0, 2.0, 1), sinusoid(1.0, 2.0),
+                              16.0, 0.0, 8.0, 1e-9, 0.8, "shifted");
+  expect_passed(tester, tested, "a phase difference within the tolerance "
+                                "passes");
+}
+
+MCAP_REGISTER_TEST(adaptive_trajectory_locates_a_divergence) {
+  Trajectory expected(100, std::vector<double>(1, 0.0));
+  Trajectory actual = expected;
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_trajectory_adaptive(actual, expected, 0.1, "adaptive",
+                                         10);
+  expect_passed(tester, tested, "identical trajectories pass");
+
+  for (std::size_t i = 40; i <= 45; i++) {
+    actual[i][0] = 1.0;
+  }
+  actual[43][0] = 2.0;
+  tested.expect_near_trajectory_adaptive(actual, expected, 0.1, "adaptive",
+                                         10);
+  expect_failed(tester, tested, "a divergence at a coarse sample fails");
+  expect_detail_contains(tester, tested, "Worst error 2 at sample 43",
+                         "the refinement finds the worst sample");
+  expect_detail_contains(tester, tested, "samples 40 to 45",
+                         "the refinement finds the violating range");
+
+  AdaptiveTrajectoryResult<double> adaptive =
+      Comparison::compare_trajectory_adaptive(make_flat_array(actual),
+                                              make_flat_array(expected), 0.1,
+                                              0, 10, 0.05);
+  tester.expect_true(adaptive.samples_compared < 100,
+                     "not every sample is compared");
+  TrajectoryResult<double> full = Comparison::compare_trajectory(
+      make_flat_array(actual), make_flat_array(expected), 0.1, 0);
+  AdaptiveTrajectoryResult<double> stride_one =
+      Comparison::compare_trajectory_adaptive(make_flat_array(actual),
+                                              make_flat_array(expected), 0.1,
+                                              0, 1, 0.05);
+  TestSupport::expect_equal(tester, stride_one.mismatch_count,
+                            full.mismatch_count,
+                            "a stride of 1 compares like the full pass");
+  TestSupport::expect_equal(tester, stride_one.samples_compared, 100,
+                            "a stride of 1 compares every sample");
+}
+
+MCAP_REGISTER_TEST(monotonic_sequences_within_a_tolerance) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_monotonic_increasing({1.0, 2.0, 1.95, 3.0}, 0.1,
+                                     "increasing");
+  expect_passed(tester, tested, "a dip within the tolerance passes");
+  tested.expect_monotonic_increasing({1.0, 2.0, 1.5, 3.0}, 0.1,
+                                     "increasing");
+  expect_failed(tester, tested, "a dip beyond the tolerance fails");
+  expect_detail_contains(tester, tested,
+                         "Not increasing at element 2: 1.5 after 2",
+                         "the first violation is reported");
+
+  tested.expect_monotonic_decreasing({3.0, 3.0, 2.0, 1.0}, 0.0,
+                                     "decreasing");
+  expect_passed(tester, tested, "a non-increasing sequence passes");
+  tested.expect_monotonic_decreasing({3.0, 2.0, 2.5}, 0.1, "decreasing");
+  expect_failed(tester, tested, "a rise beyond the tolerance fails");
+  tested.expect_monotonic_decreasing(
+      {3.0, std::numeric_limits<double>::quiet_NaN()}, 1e9, "nan");
+  expect_failed(tester, tested, "a NaN breaks monotonicity");
+}
+
+MCAP_REGISTER_TEST(all_in_range_is_inclusive) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_all_in_range(std::vector<double>{-1.0, 0.0, 1.0}, -1.0, 1.0,
+                             "range");
+  expect_passed(tester, tested, "values on the bounds pass");
+  tested.expect_all_in_range(Trajectory{{0.0, 0.5}, {1.5, 0.0}}, -1.0, 1.0,
+                             "range");
+  expect_failed(tester, tested, "a value above the range fails");
+  expect_detail_contains(tester, tested, "Element 2 is 1.5, outside [-1, 1]",
+                         "the first violating element is reported");
+  tested.expect_all_in_range(
+      std::vector<double>{0.0, std::numeric_limits<double>::quiet_NaN()},
+      -1.0, 1.0, "nan");
+  expect_failed(tester, tested, "a NaN is outside every range");
+}
+
+MCAP_REGISTER_TEST(pass_rate_per_channel) {
+  /* Channel 0 has 9 of 10 samples within the tolerance, channel 1 all. */
+  Trajectory expected(10, std::vector<double>(2, 0.0));
+  Trajectory actual = expected;
+  actual[4][0] = 1.0;
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_pass_rate_above(actual, expected, 0.1, 0.9, "rate");
+  expect_passed(tester, tested, "a pass rate at the minimum passes");
+  tested.expect_pass_rate_above(actual, expected, 0.1, 0.95, "rate");
+  expect_failed(tester, tested, "a pass rate below the minimum fails");
+  expect_detail_contains(tester, tested,
+                         "Channel 0 pass rate 0.9 below 0.95 (1 of 10 "
+                         "samples outside tolerance).",
+                         "the failing channel is reported with its rate");
+  tester.expect_true(TestSupport::last_record(tested).detail.find(
+                         "Channel 1") == std::string::npos,
+                     "passing channels are not reported");
+
+  tested.expect_pass_rate_above(std::vector<double>{0.0, 5.0},
+                                std::vector<double>{0.0, 0.0}, 0.1, 0.5,
+                                "vector");
+  expect_passed(tester, tested, "a sequence is a single channel");
+  tested.expect_pass_rate_above(actual, Trajectory(9, {0.0, 0.0}), 0.1, 0.0,
+                                "size");
+  expect_failed(tester, tested, "a size mismatch fails");
+}
+
+MCAP_REGISTER_TEST(streaming_matches_a_single_comparison) {
+  std::vector<double> expected(10, 0.0);
+  std::vector<double> actual = expected;
+  actual[3] = 0.5;
+  actual[7] = 2.0;
+  ComparisonResult<double> whole = Comparison::compare_arrays(
+      make_flat_array(actual), make_flat_array(expected), 0.1);
+
+  StreamingComparator<double> streaming(0.1);
+  for (std::size_t start = 0; start < actual.size(); start += 4) {
+    std::size_t end = start + 4 < actual.size() ? start + 4 : actual.size();
+    streaming.push(std::vector<double>(actual.begin() + start,
+                                       actual.begin() + end),
+                   std::vector<double>(expected.begin() + start,
+                                       expected.begin() + end));
+  }
+  const ComparisonResult<double> &result = streaming.get_result();
+  TestSupport::expect_equal(tester, streaming.get_element_count(), 10,
+                            "every element is counted");
+  TestSupport::expect_equal(tester, result.index, whole.index,
+                            "the first mismatch is found in the stream");
+  TestSupport::expect_equal(tester, result.mismatch_count,
+                            whole.mismatch_count,
+                            "mismatches are counted over every chunk");
+  TestSupport::expect_same(tester, result.max_error, whole.max_error,
+                           "the max error spans every chunk");
+
+  MCAPTester<double> tested = quiet_tester();
+  tester.expect_true(!streaming.finish(tested, "stream"),
+                     "finish returns the verdict");
+  expect_failed(tester, tested, "mismatching streams fail");
+  expect_detail_contains(tester, tested,
+                         "2 of 10 elements outside tolerance, first at "
+                         "element 3: 0.5 vs 0. Max error 2.",
+                         "the position in the whole stream is reported");
+
+  StreamingComparator<double> matching(0.1);
+  matching.push(expected, expected);
+  matching.push(expected, expected);
+  tester.expect_true(matching.finish(tested, "stream"), "matching streams");
+  expect_passed(tester, tested, "matching streams pass");
+}
+
+MCAP_REGISTER_TEST(streaming_chunks_of_different_length_fail) {
+  StreamingComparator<double> streaming(1e9);
+  streaming.push(std::vector<double>(2), std::vector<double>(3));
+  streaming.push(std::vector<double>(2), std::vector<double>(2));
+  tester.expect_true(streaming.get_result().kind ==
+                         MismatchKind::SizeMismatch,
+                     "the size mismatch sticks");
+
+  MCAPTester<double> tested = quiet_tester();
+  streaming.finish(tested, "stream");
+  expect_failed(tester, tested, "a size mismatch fails the stream");
+}
+
+MCAP_REGISTER_TEST(steady_state_of_a_settling_signal) {
+  std::vector<double> response;
+  std::vector<double> oscillation;
+  for (int k = 0; k < 200; k++) {
+    response.push_back(1.0 - std::exp(-k / 10.0));
+    oscillation.push_back(std::sin(k * 0.5));
+  }
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_steady_state_near(response, 1.0, 0.01, 20, "settled");
+  expect_passed(tester, tested, "a settled value within tolerance passes");
+  tested.expect_steady_state_near(response, 0.9, 0.01, 20, "offset");
+  expect_failed(tester, tested, "a settled value outside tolerance fails");
+  expect_detail_contains(tester, tested, "Settled from sample",
+                         "the detail names the settling point");
+
+  tested.expect_steady_state_near(oscillation, 0.0, 0.01, 20, "oscillating");
+  expect_failed(tester, tested, "a signal that never settles fails");
+  expect_detail_contains(tester, tested, "The signal never settles",
+                         "the detail tells an unsettled signal apart");
+
+  tested.expect_steady_state_near(response, 1.0, 0.01, 1, "window");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "a window of one sample is a structural failure");
+}
+
+MCAP_REGISTER_TEST(limit_cycle_in_the_tail_of_a_signal) {
+  std::vector<double> settling;
+  std::vector<double> cycling;
+  std::vector<double> dithering;
+  for (int k = 0; k < 128; k++) {
+    double phase = std::sin(2.0 * 3.14159265358979323846 * k / 8.0);
+    settling.push_back(std::exp(-k / 10.0) * phase);
+    cycling.push_back(0.2 * phase);
+    dithering.push_back(0.01 * phase);
+  }
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_no_limit_cycle(settling, 0.05, "settling");
+  expect_passed(tester, tested, "a decaying oscillation passes");
+  tested.expect_no_limit_cycle(dithering, 0.05, "dithering");
+  expect_passed(tester, tested,
+                "an oscillation below the smallest amplitude passes");
+  tested.expect_no_limit_cycle(cycling, 0.05, "cycling");
+  expect_failed(tester, tested, "a sustained oscillation fails");
+  expect_detail_contains(tester, tested, "period of 8 samples",
+                         "the detail names the period");
+}
+
+MCAP_REGISTER_TEST(quiet_segments_below_their_noise_floor) {
+  std::vector<double> idle = {0.1, -0.1, 0.1, -0.1};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_rms_below(idle, 0.15, "rms");
+  expect_passed(tester, tested, "an RMS below the threshold passes");
+  tested.expect_rms_below(idle, 0.05, "rms");
+  expect_failed(tester, tested, "an RMS above the threshold fails");
+  expect_detail_contains(tester, tested, "RMS 0.1 exceeds 0.05.",
+                         "the detail names the RMS value");
+
+  tested.expect_peak_to_peak_below(idle, 0.25, "range");
+  expect_passed(tester, tested, "a range below the threshold passes");
+  tested.expect_peak_to_peak_below(idle, 0.15, "range");
+  expect_failed(tester, tested, "a range above the threshold fails");
+  expect_detail_contains(tester, tested, "Peak-to-peak 0.2 exceeds",
+                         "the detail names the range");
+
+  tested.expect_rms_below(std::vector<double>(), 1.0, "empty");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "an empty segment is a structural failure");
+}
+
+MCAP_REGISTER_TEST(resampled_series_on_different_time_bases) {
+  /* The expected ramp y = t at 1 Hz, the actual series at the midpoints. */
+  std::vector<double> expected = {0.0, 1.0, 2.0, 3.0};
+  std::vector<double> expected_times = {0.0, 1.0, 2.0, 3.0};
+  std::vector<double> actual_times = {0.5, 1.5, 2.5};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_resampled(std::vector<double>{0.5, 1.5, 2.5},
+                               actual_times, expected, expected_times, 0.1,
+                               "midpoints");
+  expect_passed(tester, tested, "samples on the interpolated ramp pass");
+  tested.expect_near_resampled(std::vector<double>{0.5, 1.5, 2.7},
+                               actual_times, expected, expected_times, 0.1,
+                               "midpoints");
+  expect_failed(tester, tested, "a sample off the interpolated ramp fails");
+  expect_detail_contains(tester, tested, "first at t = 2.5",
+                         "the detail names the time stamp");
+
+  tested.expect_near_resampled(std::vector<double>{5.0, 6.0},
+                               std::vector<double>{5.0, 6.0}, expected,
+                               expected_times, 0.1, "outside");
+  expect_detail_contains(tester, tested, "No actual sample lies within",
+                         "samples outside the expected range fail");
+  tested.expect_near_resampled(std::vector<double>{0.5, 1.5},
+                               std::vector<double>{1.5, 0.5}, expected,
+                               expected_times, 0.1, "decreasing");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "a decreasing time base is a structural failure");
+}
+
+MCAP_REGISTER_TEST(named_signals_compare_by_key) {
+  typedef std::map<std::string, std::vector<double>> Signals;
+  Signals expected = {{"speed", {1.0, 2.0}}, {"torque", {0.5}}};
+
+  MCAPTester<double> tested = quiet_tester();
+  std::unordered_map<std::string, std::vector<double>> unordered = {
+      {"torque", {0.5}}, {"speed", {1.0, 2.05}}};
+  tested.expect_near_signals(
+      unordered,
+      std::unordered_map<std::string, std::vector<double>>(expected.begin(),
+                                                           expected.end()),
+      0.1, "unordered");
+  expect_passed(tester, tested, "signals match regardless of their order");
+
+  tested.expect_near_signals(
+      Signals{{"speed", {1.0, 2.0}}, {"torque", {0.7}}}, expected, 0.1,
+      "torque");
+  expect_failed(tester, tested, "a signal outside the tolerance fails");
+  expect_detail_contains(tester, tested, "1 of 2 signals outside tolerance.",
+                         "the detail counts the failing signals");
+
+  tested.expect_near_signals(
+      Signals{{"speed", {1.0, 2.0}}, {"current", {0.5}}}, expected, 0.1,
+      "renamed");
+  expect_failed(tester, tested, "a renamed signal fails");
+  expect_detail_contains(tester, tested,
+                         "Missing signals: torque. Extra signals: current.",
+                         "the detail lists the missing and extra signals");
+}
+
+MCAP_REGISTER_TEST(signal_checks_accept_relative_tolerances) {
+  Trajectory expected(20, std::vector<double>(1, 100.0));
+  Trajectory actual(20, std::vector<double>(1, 101.0));
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_trajectory_adaptive(
+      actual, expected, Tolerance<double>::relative(0.02), "adaptive", 5);
+  expect_passed(tester, tested, "an adaptive trajectory within a relative "
+                                "tolerance passes");
+  tested.expect_near_trajectory_adaptive(
+      actual, expected, Tolerance<double>::relative(0.005), "adaptive", 5);
+  expect_failed(tester, tested, "an adaptive trajectory outside a relative "
+                                "tolerance fails");
+
+  tested.expect_pass_rate_above(std::vector<double>{10.5, 105.0},
+                                std::vector<double>{10.0, 100.0},
+                                Tolerance<double>::relative(0.05), 1.0,
+                                "rate");
+  expect_passed(tester, tested, "samples within a relative tolerance pass");
+  tested.expect_pass_rate_above(std::vector<double>{10.5, 105.0},
+                                std::vector<double>{10.0, 100.0}, 0.6, 1.0,
+                                "rate");
+  expect_failed(tester, tested, "the same samples fail an absolute "
+                                "tolerance");
+
+  /* The errors are {1, 1} and the RMS value of the expected values is 2. */
+  std::vector<double> values = {3.0, 1.0};
+  std::vector<double> references = {2.0, 2.0};
+  tested.expect_rmse_below(values, references,
+                           Tolerance<double>::relative(0.5), "rmse");
+  expect_passed(tester, tested, "an RMSE within half the RMS value passes");
+  tested.expect_rmse_below(values, references,
+                           Tolerance<double>::relative(0.4), "rmse");
+  expect_failed(tester, tested, "an RMSE above 40 % of the RMS value fails");
+  expect_detail_contains(tester, tested, "RMSE 1 exceeds 0.8.",
+                         "the relative threshold is reported as a bound");
+
+  typedef std::map<std::string, std::vector<double>> Signals;
+  tested.expect_near_signals(Signals{{"speed", {101.0}}},
+                             Signals{{"speed", {100.0}}},
+                             Tolerance<double>::relative(0.02), "signals");
+  expect_passed(tester, tested, "signals within a relative tolerance pass");
+}
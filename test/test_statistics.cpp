@@ -0,0 +1,287 @@
+/**
+ * @file test_statistics.cpp
+ * @brief Tests the statistical and timing assertions: run-to-run variance,
+ * significance, distributions, properties, scores, runtime budgets and
+ * asynchronous waits.
+ */
+
+#include <chrono>
+#include <cstddef>
+#include <functional>
+#include <future>
+#include <memory>
+#include <stdexcept>
+#include <string>
+#include <thread>
+#include <vector>
+
+#include "MCAP_tester_async.hpp"
+#include "MCAP_tester_metric_bundle.hpp"
+#include "MCAP_tester_property.hpp"
+#include "MCAP_tester_runner.hpp"
+#include "test_support.hpp"
+
+using namespace Tester;
+using TestSupport::expect_detail_contains;
+using TestSupport::expect_failed;
+using TestSupport::expect_passed;
+using TestSupport::quiet_tester;
+
+MCAP_REGISTER_TEST(run_to_run_variance) {
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_run_to_run_variance_below(
+      [] { return std::vector<double>{1.0, 2.0}; }, 3, 0.0, "deterministic");
+  expect_passed(tester, tested, "identical runs pass");
+
+  /* Element 1 alternates between 0 and 2: standard deviation 1. */
+  int calls = 0;
+  std::function<std::vector<double>()> alternating = [&calls] {
+    return std::vector<double>{1.0, calls++ % 2 == 0 ? 0.0 : 2.0};
+  };
+  tested.expect_run_to_run_variance_below(alternating, 4, 1.0, "alternating");
+  expect_passed(tester, tested, "a spread at the bound passes");
+  tested.expect_run_to_run_variance_below(alternating, 4, 0.5, "alternating");
+  expect_failed(tester, tested, "a spread beyond the bound fails");
+  expect_detail_contains(tester, tested,
+                         "Element 1 varies between runs with standard "
+                         "deviation 1 (allowed 0.5)",
+                         "the varying element is reported");
+  TestSupport::expect_equal(tester, static_cast<std::size_t>(calls), 8,
+                            "the scenario runs the requested number of "
+                            "times");
+
+  tested.expect_run_to_run_variance_below(
+      [&calls] { return std::vector<double>(calls++ % 2 + 1, 0.0); }, 2, 1e9,
+      "length");
+  expect_failed(tester, tested, "outputs of different length fail");
+  tested.expect_run_to_run_variance_below(
+      [] { return std::vector<double>(); }, 1, 1e9, "runs");
+  expect_failed(tester, tested, "a single run fails");
+}
+
+MCAP_REGISTER_TEST(runtime_budget) {
+  std::function<void()> step = [] {
+    std::this_thread::sleep_for(std::chrono::milliseconds(1));
+  };
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_runtime_below(5, 10.0, 10.0, "generous", step, 0);
+  expect_passed(tester, tested, "a step within the budget passes");
+  expect_detail_contains(tester, tested, "over 5 iterations.",
+                         "the statistics are recorded on a pass");
+
+  tested.expect_runtime_below(5, 1e-4, 10.0, "mean", step, 0);
+  expect_failed(tester, tested, "a mean above the budget fails");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Timing,
+                     "as a timing failure");
+  tested.expect_runtime_below(5, 10.0, 1e-4, "p99", step, 0);
+  expect_failed(tester, tested, "a p99 above the budget fails");
+
+  tested.expect_runtime_below(0, 10.0, 10.0, "none", step, 0);
+  expect_failed(tester, tested, "no measured iteration fails");
+}
+
+MCAP_REGISTER_TEST(runtime_baseline_regression) {
+  std::function<void()> step = [] {
+    std::this_thread::sleep_for(std::chrono::milliseconds(1));
+  };
+  TestSupport::TemporaryFile baseline("runtime.txt");
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.set_runtime_baseline_directory(".", 0.1);
+  tested.expect_runtime_below(3, 10.0, 10.0, "mcap_test_runtime", step, 0);
+  expect_passed(tester, tested, "a missing baseline is recorded");
+  FlatArray<double> recorded;
+  tester.expect_true(load_flat_array(baseline.path(), recorded) &&
+                         recorded.data.size() == 2,
+                     "the baseline holds the mean and p99");
+
+  save_flat_array(baseline.path(),
+                  make_flat_array(std::vector<double>{1e-6, 1e-6}));
+  tested.expect_runtime_below(3, 10.0, 10.0, "mcap_test_runtime", step, 0);
+  expect_failed(tester, tested, "a mean far above the baseline fails");
+  expect_detail_contains(tester, tested, "Baseline mean 1e-06 s",
+                         "the baseline is reported");
+
+  save_flat_array(baseline.path(),
+                  make_flat_array(std::vector<double>{1.0, 1.0}));
+  tested.expect_runtime_below(3, 10.0, 10.0, "mcap_test_runtime", step, 0);
+  expect_passed(tester, tested, "a mean below the baseline passes");
+}
+
+MCAP_REGISTER_TEST(property_check_holds_within_the_strategy_bounds) {
+  MCAPTester<double> tested = quiet_tester();
+  bool held = Property::check(
+      tested, "bounded",
+      Property::ArrayStrategy<double>(1, 16, -1.0, 1.0),
+      [](MCAPTester<double> &scratch, const std::vector<double> &values) {
+        scratch.expect_near(values, std::vector<double>(values.size(), 0.0),
+                            1.0, "elements");
+      });
+  tester.expect_true(held, "check returns true");
+  expect_passed(tester, tested, "a property that holds passes");
+}
+
+MCAP_REGISTER_TEST(property_check_reports_the_shrunk_input) {
+  MCAPTester<double> tested = quiet_tester();
+  bool held = Property::check(
+      tested, "short",
+      Property::ArrayStrategy<double>(0, 8, -10.0, 10.0),
+      [](MCAPTester<double> &scratch, const std::vector<double> &values) {
+        scratch.expect_true(values.size() < 3, "length");
+      },
+      100, 7);
+  tester.expect_true(!held, "check returns false");
+  expect_failed(tester, tested, "a falsified property fails");
+  expect_detail_contains(tester, tested, "(seed 7)",
+                         "the detail names the seed");
+  expect_detail_contains(tester, tested, ": [0, 0, 0]. length",
+                         "the vector shrinks to its minimum");
+
+  Property::check(
+      tested, "small", Property::MatrixStrategy<double>(1, 4, 1, 4, 1.0, 2.0),
+      [](MCAPTester<double> &scratch,
+         const std::vector<std::vector<double>> &values) {
+        scratch.expect_true(values[0][0] < 0.5, "first element");
+      });
+  expect_detail_contains(tester, tested, ": [[1]].",
+                         "the matrix shrinks to its minimum");
+
+  Property::check(tested, "throwing",
+                  Property::ArrayStrategy<double>(1, 1, 0.0, 1.0),
+                  [](MCAPTester<double> &, const std::vector<double> &) {
+                    throw std::runtime_error("generator");
+                  });
+  expect_failed(tester, tested, "a throwing property fails");
+  expect_detail_contains(tester, tested, "Uncaught exception: generator",
+                         "the detail names the exception");
+}
+
+MCAP_REGISTER_TEST(metric_bundle_grades_by_the_weighted_score) {
+  /* (2 * 0.05 / 0.1 + 60 / 50) / 3 = 0.7333... */
+  MetricBundle<double> bundle;
+  bundle.add("overshoot", 0.05, 0.1, 2.0);
+  bundle.add("effort", 60.0, 50.0);
+
+  MCAPTester<double> tested = quiet_tester();
+  bundle.expect_score_below(tested, 1.0, "grade");
+  expect_passed(tester, tested, "a score below the threshold passes");
+  bundle.expect_score_below(tested, 0.5, "grade");
+  expect_failed(tester, tested, "a score above the threshold fails");
+  expect_detail_contains(tester, tested, "effort 60 of 50 (weight 1, 1.2)",
+                         "the detail lists every normalized metric");
+
+  MetricBundle<double>().expect_score_below(tested, 1.0, "empty");
+  expect_detail_contains(tester, tested, "The bundle has no metrics.",
+                         "an empty bundle fails");
+  MetricBundle<double> unlimited;
+  unlimited.add("overshoot", 0.05, 0.0);
+  unlimited.expect_score_below(tested, 1.0, "unlimited");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "a zero limit is a structural failure");
+}
+
+MCAP_REGISTER_TEST(significance_of_a_paired_slowdown) {
+  std::vector<double> a;
+  std::vector<double> faster;
+  std::vector<double> slower;
+  for (int i = 0; i < 10; i++) {
+    a.push_back(1.0 + i);
+    faster.push_back(0.5 + i - 0.1 * (i % 2));
+    slower.push_back(2.0 + i + 0.1 * (i % 2));
+  }
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_not_significantly_worse(a, faster, 0.05, "faster");
+  expect_passed(tester, tested, "a faster candidate passes");
+  tested.expect_not_significantly_worse(a, slower, 0.05, "slower");
+  expect_failed(tester, tested, "a consistently slower candidate fails");
+  expect_detail_contains(tester, tested, "Wilcoxon test over 10 pairs",
+                         "the detail names the test");
+  tested.expect_not_significantly_worse(a, slower, 0.05, "slower",
+                                        SignificanceTest::PairedT);
+  expect_failed(tester, tested, "the paired t-test agrees");
+  expect_detail_contains(tester, tested, "Paired t-test over 10 pairs",
+                         "the detail names the paired t-test");
+
+  tested.expect_not_significantly_worse(a, slower, 1.5, "alpha");
+  expect_detail_contains(tester, tested, "Configuration error: alpha 1.5",
+                         "an alpha outside (0, 1) fails");
+  tested.expect_not_significantly_worse(std::vector<double>{1.0},
+                                        std::vector<double>{2.0}, 0.05,
+                                        "single");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "a single pair is a structural failure");
+}
+
+MCAP_REGISTER_TEST(distribution_moments_and_shape) {
+  std::vector<double> alternating = {-1.0, 1.0, -1.0, 1.0};
+
+  MCAPTester<double> tested = quiet_tester();
+  tested.expect_near_mean_var(alternating, 0.0, 4.0 / 3.0, 1e-12, 1e-12,
+                              "moments");
+  expect_passed(tester, tested, "the unbiased sample variance passes");
+  tested.expect_near_mean_var(alternating, 0.0, 1.0, 1e-12, 0.1, "moments");
+  expect_failed(tester, tested, "the population variance is not accepted");
+  expect_detail_contains(tester, tested, "Over 4 samples: mean 0",
+                         "the detail names the sample moments");
+  tested.expect_near_mean_var(std::vector<double>{1.0}, 1.0, 0.0, 1.0, 1.0,
+                              "single");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Structural,
+                     "a single sample is a structural failure");
+
+  std::vector<double> uniform;
+  std::vector<double> shifted;
+  for (int i = 0; i < 100; i++) {
+    uniform.push_back(i / 100.0);
+    shifted.push_back(0.5 + i / 100.0);
+  }
+  tested.expect_same_distribution(uniform, uniform, 0.1, "same");
+  expect_passed(tester, tested, "identical samples pass");
+  tested.expect_same_distribution(shifted, uniform, 0.1, "shifted");
+  expect_failed(tester, tested, "shifted samples fail");
+  expect_detail_contains(tester, tested, "Kolmogorov-Smirnov D 0.5",
+                         "the detail names the statistic");
+  tested.expect_same_distribution(uniform, uniform, 2.0, "threshold");
+  expect_detail_contains(tester, tested, "Configuration error: threshold 2",
+                         "a threshold outside [0, 1] fails");
+}
+
+MCAP_REGISTER_TEST(eventually_holds_or_times_out) {
+  Async::EventLoop loop;
+  MCAPTester<double> tested = quiet_tester();
+  int polls = 0;
+  std::future<bool> held = Async::expect_eventually(
+      loop.scheduler(), tested, [&polls]() { return ++polls == 3; },
+      std::chrono::milliseconds(1), std::chrono::seconds(10), "third poll");
+  loop.run();
+  tester.expect_true(held.get(), "the verdict holds");
+  expect_passed(tester, tested, "a predicate that comes true passes");
+  tester.expect_true(polls == 3, "the polls stop once the predicate holds");
+
+  bool reported = false;
+  std::future<bool> timed_out = Async::expect_eventually(
+      loop.scheduler(), tested, []() { return false; },
+      std::chrono::milliseconds(1), std::chrono::milliseconds(5), "never",
+      [&reported](bool) { reported = true; });
+  loop.run();
+  tester.expect_true(!timed_out.get(), "the verdict times out");
+  tester.expect_true(reported, "the continuation runs after the verdict");
+  expect_failed(tester, tested, "a predicate that never holds fails");
+  tester.expect_true(TestSupport::last_record(tested).category ==
+                         FailureCategory::Timing,
+                     "a timeout is a timing failure");
+}
+
+MCAP_REGISTER_ASYNC_TEST(async_tests_finish_when_done_is_called) {
+  std::shared_ptr<bool> arrived = std::make_shared<bool>(false);
+  scheduler([arrived]() { *arrived = true; }, std::chrono::milliseconds(2));
+  Async::expect_eventually(
+      scheduler, tester, [arrived]() { return *arrived; },
+      std::chrono::milliseconds(1), std::chrono::seconds(10), "arrived",
+      [done](bool) { done(); });
+}
@@ -0,0 +1,161 @@
+/**
+ * @file test_support.hpp
+ * @brief Provides helpers for the tests of MCAP_tester itself.
+ *
+ * A test checks the verdict of a tester under test, created with
+ * quiet_tester so that its expected failures print nothing, through the
+ * tester of the runner:
+ *
+ *   MCAP_REGISTER_TEST(windup_fails) {
+ *     Tester::MCAPTester<double> tested = TestSupport::quiet_tester();
+ *     tested.expect_no_integrator_windup(error, command, ...);
+ *     TestSupport::expect_failed(tester, tested, "windup fails");
+ *   }
+ */
+
+#ifndef MCAP_TESTER_TEST_SUPPORT_HPP
+#define MCAP_TESTER_TEST_SUPPORT_HPP
+
+#include <cmath>
+#include <cstddef>
+#include <cstdio>
+#include <fstream>
+#include <string>
+
+#include "MCAP_tester.hpp"
+
+namespace TestSupport {
+
+/**
+ * @brief Returns a tester that prints nothing and reads no MCAP_*
+ * environment variable.
+ */
+template <typename T = double> inline Tester::MCAPTester<T> quiet_tester() {
+  return Tester::MCAPTesterBuilder<T>()
+      .verbosity(Tester::Verbosity::Quiet)
+      .ignore_environment()
+      .build();
+}
+
+/**
+ * @brief Returns the last assertion of a tester, or an empty passing record
+ * if it made none.
+ */
+template <typename T>
+inline Tester::AssertionRecord
+last_record(const Tester::MCAPTester<T> &tested) {
+  return tested.get_records().empty() ? Tester::AssertionRecord()
+                                      : tested.get_records().back();
+}
+
+/**
+ * @brief Checks that the last assertion of a tester under test has the
+ * given status; the detail of the assertion is shown on failure.
+ */
+template <typename T>
+inline void expect_status(Tester::MCAPTester<double> &tester,
+                          const Tester::MCAPTester<T> &tested,
+                          Tester::ComparisonStatus status,
+                          const std::string &message) {
+  Tester::AssertionRecord record = last_record(tested);
+  tester.expect_true(!tested.get_records().empty() && record.status == status,
+                     message,
+                     "Expected " + Tester::Report::status_to_string(status) +
+                         ", observed " +
+                         Tester::Report::status_to_string(record.status) +
+                         ": " + record.detail);
+}
+
+template <typename T>
+inline void expect_passed(Tester::MCAPTester<double> &tester,
+                          const Tester::MCAPTester<T> &tested,
+                          const std::string &message) {
+  expect_status(tester, tested, Tester::ComparisonStatus::Pass, message);
+}
+
+template <typename T>
+inline void expect_warned(Tester::MCAPTester<double> &tester,
+                          const Tester::MCAPTester<T> &tested,
+                          const std::string &message) {
+  expect_status(tester, tested, Tester::ComparisonStatus::Warning, message);
+}
+
+template <typename T>
+inline void expect_failed(Tester::MCAPTester<double> &tester,
+                          const Tester::MCAPTester<T> &tested,
+                          const std::string &message) {
+  expect_status(tester, tested, Tester::ComparisonStatus::Failure, message);
+}
+
+/**
+ * @brief Checks that the detail of the last assertion of a tester under test
+ * contains a text.
+ */
+template <typename T>
+inline void expect_detail_contains(Tester::MCAPTester<double> &tester,
+                                   const Tester::MCAPTester<T> &tested,
+                                   const std::string &text,
+                                   const std::string &message) {
+  std::string detail = last_record(tested).detail;
+  tester.expect_true(detail.find(text) != std::string::npos, message,
+                     "\"" + text + "\" not in \"" + detail + "\"");
+}
+
+/**
+ * @brief Checks that two counts or indices are equal.
+ */
+inline void expect_equal(Tester::MCAPTester<double> &tester,
+                         std::size_t actual, std::size_t expected,
+                         const std::string &message) {
+  tester.expect_true(actual == expected, message,
+                     std::to_string(actual) + " vs " +
+                         std::to_string(expected) + ".");
+}
+
+/**
+ * @brief Checks that two values are identical, NaN matching NaN.
+ */
+inline void expect_same(Tester::MCAPTester<double> &tester, double actual,
+                        double expected, const std::string &message) {
+  bool same = (std::isnan(actual) && std::isnan(expected)) ||
+              actual == expected;
+  tester.expect_true(same, message,
+                     Tester::format_number(actual) + " vs " +
+                         Tester::format_number(expected) + ".");
+}
+
+/**
+ * @brief A file path in the working directory for the duration of a scope;
+ * the file is removed when the scope ends. Every test uses names of its
+ * own, so parallel jobs do not collide.
+ */
+class TemporaryFile {
+public:
+  explicit TemporaryFile(const std::string &name)
+      : _path("mcap_test_" + name) {
+    std::remove(this->_path.c_str());
+  }
+
+  TemporaryFile(const TemporaryFile &) = delete;
+  TemporaryFile &operator=(const TemporaryFile &) = delete;
+
+  ~TemporaryFile() { std::remove(this->_path.c_str()); }
+
+  const std::string &path() const { return this->_path; }
+
+  /**
+   * @brief Replaces the content of the file with a text or binary data.
+   */
+  bool write(const std::string &text) const {
+    std::ofstream file(this->_path.c_str(), std::ios::binary);
+    file << text;
+    return static_cast<bool>(file);
+  }
+
+protected:
+  std::string _path;
+};
+
+} // namespace TestSupport
+
+#endif // MCAP_TESTER_TEST_SUPPORT_HPP
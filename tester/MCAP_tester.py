@@ -5,105 +5,164 @@ The tester maintains an internal flag to track test failures and can raise an ex
 
 Classes:
     MCAPTester:
-        A utility class for performing assertions on numerical values and numpy arrays, supporting both scalar and 2D array comparisons with tolerance.
+        A utility class for performing assertions on numerical values and numpy arrays, supporting scalar and
+        array comparisons of any number of dimensions with tolerance.
         It provides methods to check for near-equality, handle test failure reporting, and manage the test failure state.
 """
+import warnings
+
 import numpy as np
 
 
 class MCAPTester:
+    MAX_WORST_ELEMENT_COUNT = 50
+
     def __init__(self):
         self.test_failed_flag = False
+        self.worst_element_count = 5
 
-    def expect_near(self, actual, expected, tolerance, message):
+    def set_worst_element_count(self, count):
         """
-        Asserts that two values (scalars or numpy arrays) are approximately equal within a given tolerance.
+        Sets how many of the worst elements a mismatch in expect_near_array lists.
 
         Parameters
         ----------
-        actual : int, float, or np.ndarray
-            The actual value to check.
-        expected : int, float, or np.ndarray
-            The expected value to compare against.
-        tolerance : float
-            The maximum allowed difference between actual and expected.
-        message : str
-            The message to display if the assertion fails.
-
-        Raises
-        ------
-        TypeError
-            If the types of actual and expected are not supported.
-
-        Behavior
-        --------
-        - For scalars (int or float), checks if the absolute difference is within tolerance.
-        - For numpy arrays, checks if shapes match and all elements are within tolerance using np.allclose.
-        - On failure, prints the provided message and sets self.test_failed_flag to True.
+        count : int
+            The number of worst elements to list. It is capped at
+            MAX_WORST_ELEMENT_COUNT so the output stays bounded; 0 lists no
+            elements but still reports the total number of mismatches.
         """
-        if isinstance(actual, (int, float)) and isinstance(expected, (int, float)):
-            if abs(actual - expected) <= tolerance:
-                pass  # Do Nothing
-            else:
-                print(f"FAILURE: {message}")
-                print()
-                self.test_failed_flag = True
-        elif isinstance(actual, np.ndarray) and isinstance(expected, np.ndarray):
-            if actual.shape != expected.shape:
-                print(f"FAILURE: {message} Shape mismatch.")
-                print()
-                self.test_failed_flag = True
-                return
-
-            if not np.allclose(actual, expected, atol=tolerance):
-                print(f"FAILURE: {message} Element mismatch.")
-                print()
-                self.test_failed_flag = True
-        else:
-            raise TypeError("Unsupported types for expect_near.")
+        self.worst_element_count = max(
+            0, min(int(count), self.MAX_WORST_ELEMENT_COUNT))
 
-    def expect_near_2d(self, actual, expected, tolerance, message):
+    def expect_near_array(self, actual, expected, tolerance, message,
+                          broadcast=False, relative_tolerance=0.0):
         """
-        Asserts that two 2D numpy arrays are element-wise equal within a given tolerance.
+        Asserts that two values are element-wise equal within a given tolerance, for scalars and numpy arrays of
+        any number of dimensions.
 
         Parameters
         ----------
-        actual : np.ndarray
-            The actual 2D numpy array to be tested.
-        expected : np.ndarray
-            The expected 2D numpy array to compare against.
+        actual : int, float, or np.ndarray
+            The actual value to check.
+        expected : int, float, or np.ndarray
+            The expected value to compare against.
         tolerance : float
-            The maximum allowed difference between corresponding elements.
+            The maximum allowed absolute difference between corresponding elements.
         message : str
             Message to display in case of failure.
+        broadcast : bool
+            If True, `expected` is broadcast onto the shape of `actual` following NumPy's rules, e.g. a 1xN row
+            or an Nx1 column against every row or column of a matrix.
+        relative_tolerance : float
+            Additionally allowed difference relative to the magnitude of the expected element, as the `rtol` of
+            np.allclose: an element passes if |actual - expected| <= tolerance + relative_tolerance * |expected|.
 
         Raises
         ------
         TypeError
-            If either `actual` or `expected` is not a numpy array.
+            If either `actual` or `expected` is neither a scalar nor a numpy array.
 
         Side Effects
         ------------
-        - Prints a failure message and sets `self.test_failed_flag` to True if arrays differ in shape or any element exceeds the tolerance.
-        - Returns immediately upon the first failure detected.
+        - Prints a failure message and sets `self.test_failed_flag` to True if the shapes differ or any element
+          exceeds the tolerance. A scalar is a zero-dimensional array, so it only matches a scalar or a
+          zero-dimensional array.
+        - Elements are paired by their logical index, so transposed, strided and other non-contiguous views are
+          compared correctly.
+        - A negative or NaN tolerance or relative tolerance is reported as a configuration error instead of
+          being applied.
+        - Scans the whole array, so the failure message reports the total number of mismatching elements and the
+          `worst_element_count` elements with the largest errors (NaN errors first), each with its index.
         """
-        if not isinstance(actual, np.ndarray) or not isinstance(expected, np.ndarray):
-            raise TypeError("Both actual and expected must be numpy arrays.")
+        for value in (actual, expected):
+            if not isinstance(value, (int, float, np.ndarray)):
+                raise TypeError(
+                    "actual and expected must be scalars or numpy arrays.")
+
+        if not (tolerance >= 0 and relative_tolerance >= 0):
+            invalid = tolerance if not tolerance >= 0 else relative_tolerance
+            print(f"FAILURE: {message} Configuration error: invalid "
+                  f"tolerance {invalid}; tolerances must be non-negative.")
+            print()
+            self.test_failed_flag = True
+            return
 
+        actual = np.asarray(actual)
+        expected = np.asarray(expected)
+        if broadcast and actual.shape != expected.shape:
+            try:
+                expected = np.broadcast_to(expected, actual.shape)
+            except ValueError:
+                pass
         if actual.shape != expected.shape:
             print(f"FAILURE: {message} Shape mismatch.")
             print()
             self.test_failed_flag = True
             return
 
-        for i in range(actual.shape[0]):
-            for j in range(actual.shape[1]):
-                if abs(actual[i, j] - expected[i, j]) > tolerance:
-                    print(
-                        f"FAILURE: {message} Element mismatch at ({i}, {j}).")
-                    print()
-                    self.test_failed_flag = True
-                    return
+        error = np.abs(actual - expected)
+        mismatch = ~(error <= tolerance
+                     + relative_tolerance * np.abs(expected))
+        mismatch_count = int(np.count_nonzero(mismatch))
+        if mismatch_count == 0:
+            return
+
+        indices = np.argwhere(mismatch)
+        ranking = np.where(np.isnan(error[mismatch]),
+                           np.inf, error[mismatch])
+        order = np.argsort(-ranking, kind="stable")
+        order = order[:self.worst_element_count]
+
+        detail = (f"Element mismatch. {mismatch_count} of {actual.size} "
+                  f"elements outside tolerance.")
+        worst = []
+        for k in order:
+            index = tuple(int(i) for i in indices[k])
+            position = ", ".join(str(i) for i in index)
+            worst.append(f"({position}) {actual[index]} vs {expected[index]} "
+                         f"(error {error[index]})")
+        if worst:
+            detail += " Worst: " + ", ".join(worst)
+            detail += ", ..." if mismatch_count > len(worst) else "."
+
+        print(f"FAILURE: {message} {detail}")
+        print()
+        self.test_failed_flag = True
+
+    def expect_near(self, actual, expected, tolerance, message):
+        """
+        Deprecated: use expect_near_array, which this method calls.
+
+        As before, numpy arrays are compared like np.allclose with atol=tolerance and its default rtol=1e-5,
+        scalars with the absolute tolerance only; a failure now reports the mismatching elements.
+
+        Raises
+        ------
+        TypeError
+            If the types of actual and expected are not supported.
+        """
+        warnings.warn("expect_near is deprecated; use expect_near_array.",
+                      DeprecationWarning, stacklevel=2)
+        arrays = (isinstance(actual, np.ndarray)
+                  and isinstance(expected, np.ndarray))
+        self.expect_near_array(actual, expected, tolerance, message,
+                               relative_tolerance=1e-5 if arrays else 0.0)
+
+    def expect_near_2d(self, actual, expected, tolerance, message):
+        """
+        Deprecated: use expect_near_array, which this method calls.
+
+        Raises
+        ------
+        TypeError
+            If either `actual` or `expected` is not a numpy array.
+        """
+        warnings.warn("expect_near_2d is deprecated; use expect_near_array.",
+                      DeprecationWarning, stacklevel=2)
+        if not isinstance(actual, np.ndarray) or not isinstance(expected, np.ndarray):
+            raise TypeError("Both actual and expected must be numpy arrays.")
+        self.expect_near_array(actual, expected, tolerance, message)
 
     def throw_error_if_test_failed(self):
         """